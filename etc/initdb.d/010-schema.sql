@@ -69,12 +69,24 @@ from
     join topic t on t.cluster = c.id
     join topition tp on tp.topic = t.id;
 
+create table if not exists topition_assignment (
+    id int generated always as identity primary key,
+    topition int references topition (id),
+    unique (topition),
+    leader int not null,
+    replicas int[] not null,
+    isr int[] not null,
+    last_updated timestamp default current_timestamp not null,
+    created_at timestamp default current_timestamp not null
+);
+
 create table if not exists watermark (
     id int generated always as identity primary key,
     topition int references topition (id),
     unique (topition),
     low bigint,
     high bigint,
+    record_count bigint default 0 not null,
     last_updated timestamp default current_timestamp not null,
     created_at timestamp default current_timestamp not null
 );
@@ -121,6 +133,11 @@ partition by
 
 create table if not exists record_default partition of record default;
 
+-- list offsets by timestamp, in addition to fetch and delete by offset
+-- using the primary key
+--
+create index if not exists record_timestamp on record (topition, timestamp);
+
 create
 or replace view v_record as
 select
@@ -213,6 +230,7 @@ create table if not exists consumer_offset (
     leader_epoch int,
     timestamp timestamp,
     metadata text,
+    expire_timestamp timestamp,
     last_updated timestamp default current_timestamp not null,
     created_at timestamp default current_timestamp not null
 );
@@ -227,7 +245,8 @@ select
     co.committed_offset as committed_offset,
     co.leader_epoch as leader_epoch,
     co.timestamp as timestamp,
-    co.metadata as metadata
+    co.metadata as metadata,
+    co.expire_timestamp as expire_timestamp
 from
     cluster c
     join consumer_group cg on cg.cluster = c.id
@@ -271,6 +290,7 @@ create table if not exists producer_detail (
     topition int references topition (id),
     unique (producer_epoch, topition),
     sequence int default 0 not null,
+    batches int[] default '{}' not null,
     last_updated timestamp default current_timestamp not null,
     created_at timestamp default current_timestamp not null
 );
@@ -17,19 +17,18 @@ use std::marker::PhantomData;
 
 use crate::{Error, Result};
 
-use futures::SinkExt;
+use object_store::memory::InMemory;
 use tansu_kafka_sans_io::{
     Body, ErrorCode, Frame, Header,
     fetch_request::{FetchPartition, FetchTopic},
     fetch_response::FetchableTopicResponse,
     record::inflated,
 };
-use tansu_schema_registry::{AsJsonValue, Registry};
+use tansu_schema_registry::{Registry, WireFormat};
 use tokio::{
     io::{self, AsyncReadExt, AsyncWriteExt},
     net::TcpStream,
 };
-use tokio_util::codec::{FramedWrite, LinesCodec};
 use tracing::debug;
 use url::Url;
 
@@ -39,10 +38,12 @@ pub struct Builder<B, T, P, S> {
     topic: T,
     partition: P,
     schema_registry: S,
+    wire_format: WireFormat,
     max_wait_time_ms: i32,
     min_bytes: i32,
     max_bytes: Option<i32>,
     fetch_offset: i64,
+    end_offset: Option<i64>,
     partition_max_bytes: i32,
 }
 
@@ -56,10 +57,12 @@ impl<B, T, P, S> Builder<B, T, P, S> {
             topic: self.topic,
             partition: self.partition,
             schema_registry: self.schema_registry,
+            wire_format: self.wire_format,
             max_wait_time_ms: self.max_wait_time_ms,
             min_bytes: self.min_bytes,
             max_bytes: self.max_bytes,
             fetch_offset: self.fetch_offset,
+            end_offset: self.end_offset,
             partition_max_bytes: self.partition_max_bytes,
         }
     }
@@ -70,10 +73,12 @@ impl<B, T, P, S> Builder<B, T, P, S> {
             topic: topic.into(),
             partition: self.partition,
             schema_registry: self.schema_registry,
+            wire_format: self.wire_format,
             max_wait_time_ms: self.max_wait_time_ms,
             min_bytes: self.min_bytes,
             max_bytes: self.max_bytes,
             fetch_offset: self.fetch_offset,
+            end_offset: self.end_offset,
             partition_max_bytes: self.partition_max_bytes,
         }
     }
@@ -84,10 +89,12 @@ impl<B, T, P, S> Builder<B, T, P, S> {
             topic: self.topic,
             partition,
             schema_registry: self.schema_registry,
+            wire_format: self.wire_format,
             max_wait_time_ms: self.max_wait_time_ms,
             min_bytes: self.min_bytes,
             max_bytes: self.max_bytes,
             fetch_offset: self.fetch_offset,
+            end_offset: self.end_offset,
             partition_max_bytes: self.partition_max_bytes,
         }
     }
@@ -98,14 +105,25 @@ impl<B, T, P, S> Builder<B, T, P, S> {
             topic: self.topic,
             partition: self.partition,
             schema_registry,
+            wire_format: self.wire_format,
             max_wait_time_ms: self.max_wait_time_ms,
             min_bytes: self.min_bytes,
             max_bytes: self.max_bytes,
             fetch_offset: self.fetch_offset,
+            end_offset: self.end_offset,
             partition_max_bytes: self.partition_max_bytes,
         }
     }
 
+    /// The wire format of the records, which are decoded with the
+    /// schema of the topic.
+    pub fn wire_format(self, wire_format: WireFormat) -> Self {
+        Self {
+            wire_format,
+            ..self
+        }
+    }
+
     pub fn max_wait_time_ms(self, max_wait_time_ms: i32) -> Self {
         Self {
             max_wait_time_ms,
@@ -128,6 +146,10 @@ impl<B, T, P, S> Builder<B, T, P, S> {
         }
     }
 
+    pub fn end_offset(self, end_offset: Option<i64>) -> Self {
+        Self { end_offset, ..self }
+    }
+
     pub fn partition_max_bytes(self, partition_max_bytes: i32) -> Self {
         Self {
             partition_max_bytes,
@@ -149,10 +171,12 @@ impl From<Builder<Url, String, i32, Option<Url>>> for Configuration {
             topic: builder.topic,
             partition: builder.partition,
             schema_registry: builder.schema_registry,
+            wire_format: builder.wire_format,
             max_wait_time_ms: builder.max_wait_time_ms,
             min_bytes: builder.min_bytes,
             max_bytes: builder.max_bytes,
             fetch_offset: builder.fetch_offset,
+            end_offset: builder.end_offset,
             partition_max_bytes: builder.partition_max_bytes,
         }
     }
@@ -164,17 +188,19 @@ pub struct Configuration {
     topic: String,
     partition: i32,
     schema_registry: Option<Url>,
+    wire_format: WireFormat,
     max_wait_time_ms: i32,
     min_bytes: i32,
     max_bytes: Option<i32>,
     fetch_offset: i64,
+    end_offset: Option<i64>,
     partition_max_bytes: i32,
 }
 
 #[derive(Clone, Debug)]
 pub struct Consume {
     configuration: Configuration,
-    registry: Option<Registry>,
+    registry: Registry,
 }
 
 impl TryFrom<Configuration> for Consume {
@@ -186,6 +212,9 @@ impl TryFrom<Configuration> for Consume {
             .as_ref()
             .map(Registry::try_from)
             .transpose()
+            // without a schema registry every topic is schemaless
+            .map(|registry| registry.unwrap_or_else(|| Registry::new(InMemory::new())))
+            .map(|registry| registry.wire_format(configuration.wire_format))
             .map(|registry| Self {
                 configuration,
                 registry,
@@ -195,55 +224,65 @@ impl TryFrom<Configuration> for Consume {
 }
 
 impl Consume {
+    /// Write each record from the fetch offset up to the end offset, or
+    /// the high watermark, as a line of JSON.
     pub async fn main(self) -> Result<ErrorCode> {
-        let stdout = io::stdout();
+        let mut stdout = io::stdout();
 
-        let mut writer = FramedWrite::new(stdout, LinesCodec::new());
+        let mut connection = Connection::open(&self.configuration.broker).await?;
 
-        let schema = if let Some(ref registry) = self.registry {
-            registry
-                .schema(&self.configuration.topic)
-                .await
-                .inspect(|schema| debug!(?schema))?
-        } else {
-            None
-        };
+        let mut offset = self.configuration.fetch_offset;
+        let mut end_offset = self.configuration.end_offset.unwrap_or(i64::MAX);
 
-        let mut connection = Connection::open(&self.configuration.broker).await?;
+        while offset < end_offset {
+            let mut batches = vec![];
+
+            for response in connection.consume(&self.configuration, offset).await? {
+                debug!(?response);
+
+                for partition in response.partitions.unwrap_or_default() {
+                    debug!(?partition);
 
-        for response in connection
-            .consume(
-                self.configuration.topic.as_str(),
-                self.configuration.partition,
-                self.configuration.fetch_offset,
-                self.configuration.max_wait_time_ms,
-                self.configuration.min_bytes,
-                self.configuration.max_bytes,
-            )
-            .await?
-        {
-            debug!(?response);
-
-            for partition in response.partitions.unwrap_or_default() {
-                debug!(?partition);
-
-                if let Some(frame) = partition.records {
-                    debug!(?frame);
-
-                    let frame = inflated::Frame::try_from(frame)?;
-                    for batch in frame.batches {
-                        debug!(?batch);
-
-                        if let Some(ref schema) = schema {
-                            writer
-                                .send(schema.as_json_value(&batch).map(|kv| kv.to_string())?)
-                                .await?;
-                        }
+                    if partition.error_code != i16::from(ErrorCode::None) {
+                        return Err(Error::Api(ErrorCode::try_from(partition.error_code)?));
+                    }
+
+                    if self.configuration.end_offset.is_none() {
+                        end_offset = partition.high_watermark;
+                    }
+
+                    if let Some(frame) = partition.records {
+                        batches.extend(inflated::Frame::try_from(frame)?.batches);
                     }
                 }
             }
+
+            let Some(last) = batches.last() else {
+                break;
+            };
+
+            let next = last.base_offset + i64::from(last.last_offset_delta) + 1;
+
+            let mut jsonl = vec![];
+
+            _ = self
+                .registry
+                .records_as_jsonl(
+                    self.configuration.topic.as_str(),
+                    &batches,
+                    offset,
+                    end_offset,
+                    &mut jsonl,
+                )
+                .await?;
+
+            stdout.write_all(&jsonl).await?;
+
+            offset = next;
         }
 
+        stdout.flush().await?;
+
         Ok(ErrorCode::None)
     }
 }
@@ -273,16 +312,10 @@ impl Connection {
 
     async fn consume(
         &mut self,
-        topic: &str,
-        partition: i32,
+        configuration: &Configuration,
         fetch_offset: i64,
-        max_wait_ms: i32,
-        min_bytes: i32,
-        max_bytes: Option<i32>,
     ) -> Result<Vec<FetchableTopicResponse>> {
-        let _ = (fetch_offset, max_wait_ms, min_bytes, max_bytes);
-
-        debug!(%topic, partition);
+        debug!(topic = %configuration.topic, partition = configuration.partition, fetch_offset);
 
         let api_key = 1;
         let api_version = 6;
@@ -298,24 +331,24 @@ impl Connection {
             cluster_id: None,
             replica_state: None,
             replica_id: Some(-1),
-            max_wait_ms,
-            min_bytes,
-            max_bytes,
+            max_wait_ms: configuration.max_wait_time_ms,
+            min_bytes: configuration.min_bytes,
+            max_bytes: configuration.max_bytes,
             isolation_level: Some(1),
             session_id: None,
             session_epoch: None,
             topics: Some(
                 [FetchTopic {
-                    topic: Some(topic.into()),
+                    topic: Some(configuration.topic.clone()),
                     topic_id: None,
                     partitions: Some(
                         [FetchPartition {
-                            partition: 0,
+                            partition: configuration.partition,
                             current_leader_epoch: None,
-                            fetch_offset: 0,
+                            fetch_offset,
                             last_fetched_epoch: None,
-                            log_start_offset: Some(0),
-                            partition_max_bytes: 4096,
+                            log_start_offset: Some(-1),
+                            partition_max_bytes: configuration.partition_max_bytes,
                             replica_directory_id: None,
                         }]
                         .into(),
@@ -214,6 +214,7 @@ impl Produce {
             }
 
             batch
+                .last_offset_delta(offset_delta.saturating_sub(1))
                 .build()
                 .map(|batch| inflated::Frame {
                     batches: vec![batch],
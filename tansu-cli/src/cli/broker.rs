@@ -17,10 +17,14 @@ use crate::{EnvVarExp, Error, Result};
 
 use super::DEFAULT_BROKER;
 use clap::{Parser, Subcommand};
+use std::time::Duration;
 use tansu_kafka_sans_io::ErrorCode;
 use tansu_schema_registry::lake::{self};
 use tansu_server::{NODE_ID, broker::Broker, coordinator::group::administrator::Controller, otel};
-use tansu_storage::StorageContainer;
+use tansu_storage::{
+    BatchLimit, DEFAULT_NUM_PARTITIONS, OFFSET_METADATA_MAX_BYTES, OFFSETS_RETENTION,
+    PRODUCER_ID_SEQUENCE_WINDOW, StorageContainer,
+};
 use tracing::debug;
 use url::Url;
 use uuid::Uuid;
@@ -61,6 +65,54 @@ pub(super) struct Arg {
         default_value = "tcp://[::]:9100"
     )]
     prometheus_listener_url: Option<EnvVarExp<Url>>,
+
+    /// The number of batches retained per idempotent producer and partition for deduplication
+    #[arg(long, env = "PRODUCER_ID_SEQUENCE_WINDOW", default_value_t = PRODUCER_ID_SEQUENCE_WINDOW)]
+    producer_id_sequence_window: usize,
+
+    /// The retention of committed offsets when a commit doesn't specify one
+    #[arg(long, env = "OFFSETS_RETENTION_MINUTES", default_value_t = OFFSETS_RETENTION.as_secs() / 60)]
+    offsets_retention_minutes: u64,
+
+    /// The maximum size of the metadata of a committed offset
+    #[arg(long, env = "OFFSET_METADATA_MAX_BYTES", default_value_t = OFFSET_METADATA_MAX_BYTES)]
+    offset_metadata_max_bytes: usize,
+
+    /// Create a topic when it is first produced to, only supported by the postgres storage engine
+    #[arg(long, env = "AUTO_CREATE_TOPICS_ENABLE", default_value_t = false)]
+    auto_create_topics_enable: bool,
+
+    /// The number of partitions of a topic created without specifying them
+    #[arg(long, env = "NUM_PARTITIONS", default_value_t = DEFAULT_NUM_PARTITIONS)]
+    num_partitions: i32,
+
+    /// The number of record batches cached in memory to serve fetches, currently supported by the postgres storage engine
+    #[arg(long, env = "STORAGE_CACHE_BATCHES")]
+    storage_cache_batches: Option<usize>,
+
+    /// Split fetched records into batches of at most this many records, currently supported by the postgres storage engine
+    #[arg(
+        long,
+        env = "STORAGE_FETCH_BATCH_RECORDS",
+        conflicts_with = "storage_fetch_batch_bytes"
+    )]
+    storage_fetch_batch_records: Option<usize>,
+
+    /// Split fetched records into batches of at most this many bytes of keys and values, currently supported by the postgres storage engine
+    #[arg(long, env = "STORAGE_FETCH_BATCH_BYTES")]
+    storage_fetch_batch_bytes: Option<usize>,
+
+    /// Record batches are stored as segments in this object store, examples are: s3://segments/ or memory://segments/, currently supported by the postgres storage engine
+    #[arg(long, env = "STORAGE_SEGMENTS")]
+    storage_segments: Option<EnvVarExp<Url>>,
+
+    /// Compress segments at this zstd level with a dictionary trained for their topic
+    #[arg(
+        long,
+        env = "STORAGE_SEGMENT_COMPRESSION",
+        requires = "storage_segments"
+    )]
+    storage_segment_compression: Option<i32>,
 }
 
 #[derive(Clone, Debug, Subcommand)]
@@ -156,6 +208,11 @@ impl TryFrom<Arg> for tansu_server::broker::Broker<Controller<StorageContainer>,
             })
             .transpose()?;
 
+        let fetch_batch_limit = args
+            .storage_fetch_batch_records
+            .map(BatchLimit::Records)
+            .or(args.storage_fetch_batch_bytes.map(BatchLimit::Bytes));
+
         Broker::<Controller<StorageContainer>, StorageContainer>::builder()
             .node_id(NODE_ID)
             .cluster_id(cluster_id)
@@ -165,6 +222,18 @@ impl TryFrom<Arg> for tansu_server::broker::Broker<Controller<StorageContainer>,
             .prometheus_registry(prometheus_registry)
             .schema_registry(schema)
             .lake_house(lake_house)
+            .sequence_window(args.producer_id_sequence_window)
+            .offsets_retention(Duration::from_secs(args.offsets_retention_minutes * 60))
+            .offset_metadata_max_bytes(args.offset_metadata_max_bytes)
+            .auto_create_topics_enable(args.auto_create_topics_enable)
+            .num_partitions(args.num_partitions)
+            .cache(args.storage_cache_batches)
+            .fetch_batch_limit(fetch_batch_limit)
+            .segments(
+                args.storage_segments
+                    .map(|env_var_exp| env_var_exp.into_inner()),
+            )
+            .segment_compression(args.storage_segment_compression)
             .storage(storage_engine)
             .listener(listener)
             .build()
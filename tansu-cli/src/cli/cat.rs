@@ -18,6 +18,7 @@ use crate::Result;
 use clap::Subcommand;
 use tansu_cat::Cat;
 use tansu_kafka_sans_io::ErrorCode;
+use tansu_schema_registry::WireFormat;
 use url::Url;
 
 #[derive(Clone, Debug, Subcommand)]
@@ -52,7 +53,7 @@ pub(super) enum Command {
         schema_registry: Option<Url>,
     },
 
-    #[command(about = "Consume Avro/JSON/Protobuf messages from a topic")]
+    #[command(about = "Consume messages from a topic as one line of JSON per record")]
     Consume {
         #[arg(long, default_value = DEFAULT_BROKER, env = "ADVERTISED_LISTENER_URL", help = "The URL of the broker to consume messages from")]
         broker: Url,
@@ -74,6 +75,12 @@ pub(super) enum Command {
         )]
         schema_registry: Option<Url>,
 
+        #[arg(
+            long,
+            help = "Records are framed with a magic byte and schema id, as written by Confluent serializers"
+        )]
+        confluent_wire_format: bool,
+
         #[arg(
             long,
             default_value = "5000",
@@ -98,6 +105,12 @@ pub(super) enum Command {
         #[arg(long, default_value = "0", help = "The fetch offset to start from")]
         fetch_offset: i64,
 
+        #[arg(
+            long,
+            help = "The offset to stop before, defaulting to the high watermark"
+        )]
+        end_offset: Option<i64>,
+
         #[arg(
             long,
             default_value = "1048576",
@@ -129,20 +142,28 @@ impl From<Command> for tansu_cat::Cat {
                 topic,
                 partition,
                 schema_registry,
+                confluent_wire_format,
                 max_wait_time_ms,
                 min_bytes,
                 max_bytes,
                 fetch_offset,
+                end_offset,
                 partition_max_bytes,
             } => Cat::consume()
                 .broker(broker)
                 .topic(topic)
                 .partition(partition)
                 .schema_registry(schema_registry)
+                .wire_format(if confluent_wire_format {
+                    WireFormat::Confluent
+                } else {
+                    WireFormat::Embedded
+                })
                 .max_wait_time_ms(max_wait_time_ms)
                 .min_bytes(min_bytes)
                 .max_bytes(max_bytes)
                 .fetch_offset(fetch_offset)
+                .end_offset(end_offset)
                 .partition_max_bytes(partition_max_bytes)
                 .build(),
         }
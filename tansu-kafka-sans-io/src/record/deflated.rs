@@ -227,6 +227,24 @@ impl Batch {
         self.base_offset + i64::from(self.last_offset_delta)
     }
 
+    /// Whether the CRC of this batch matches its content, from the
+    /// attributes through to the record data.
+    pub fn is_crc_valid(&self) -> Result<bool> {
+        CrcData {
+            attributes: self.attributes,
+            last_offset_delta: self.last_offset_delta,
+            base_timestamp: self.base_timestamp,
+            max_timestamp: self.max_timestamp,
+            producer_id: self.producer_id,
+            producer_epoch: self.producer_epoch,
+            base_sequence: self.base_sequence,
+            record_count: self.record_count,
+            record_data: self.record_data.clone(),
+        }
+        .crc()
+        .map(|crc| crc == self.crc)
+    }
+
     fn compression(&self) -> Result<Compression> {
         Compression::try_from(self.attributes)
     }
@@ -878,6 +896,7 @@ mod tests {
         assert_eq!(attributes, batch.attributes);
         assert_eq!(1, batch.record_count);
         assert_eq!(base_offset, batch.base_offset);
+        assert!(batch.is_crc_valid()?);
 
         Ok(())
     }
@@ -13,19 +13,29 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::{any::type_name_of_val, collections::BTreeMap, sync::Arc};
+use std::{
+    any::type_name_of_val,
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
 
-use crate::{ARROW_LIST_FIELD_NAME, AsArrow, AsJsonValue, AsKafkaRecord, Error, Result, Validator};
+use crate::{
+    ARROW_LIST_FIELD_NAME, AsArrow, AsJsonValue, AsKafkaRecord, Compatibility, Error, Result,
+    ValidateAsArrow, Validator,
+};
 use arrow::{
     array::{
-        ArrayBuilder, BooleanBuilder, Float64Builder, Int64Builder, ListBuilder, NullBuilder,
-        StringBuilder, StructBuilder,
+        ArrayBuilder, BooleanBuilder, Date32Builder, Float64Builder, Int64Builder, ListBuilder,
+        MapBuilder, NullBuilder, StringBuilder, StringDictionaryBuilder, StructBuilder,
+        Time64MicrosecondBuilder, TimestampMicrosecondBuilder, UInt64Builder,
+    },
+    datatypes::{
+        DataType, Field, FieldRef, Fields, Schema as ArrowSchema, SchemaRef, TimeUnit, UInt32Type,
     },
-    datatypes::{DataType, Field, FieldRef, Fields, Schema as ArrowSchema},
     record_batch::RecordBatch,
 };
 use bytes::Bytes;
-use chrono::{DateTime, Datelike};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike};
 use parquet::arrow::PARQUET_FIELD_ID_META_KEY;
 use serde_json::{Map, Value, json};
 use tansu_kafka_sans_io::{ErrorCode, record::inflated::Batch};
@@ -33,11 +43,103 @@ use tracing::{debug, error, warn};
 
 const NULLABLE: bool = true;
 
+const MAP_ENTRIES: &str = "entries";
+const MAP_KEYS: &str = "keys";
+const MAP_VALUES: &str = "values";
+
 #[derive(Debug, Default)]
 pub struct Schema {
     key: Option<jsonschema::Validator>,
     value: Option<jsonschema::Validator>,
+    key_schema: Option<Value>,
+    value_schema: Option<Value>,
     ids: BTreeMap<String, i32>,
+    formats: BTreeMap<String, Format>,
+    enums: BTreeSet<String>,
+    maps: BTreeSet<String>,
+    dictionary_enums: bool,
+}
+
+/// The arrow type of a string field declaring an `enum`, when dictionary
+/// encoded.
+fn enum_data_type() -> DataType {
+    DataType::Dictionary(Box::new(DataType::UInt32), Box::new(DataType::Utf8))
+}
+
+fn is_enum_data_type(data_type: &DataType) -> bool {
+    *data_type == enum_data_type()
+}
+
+/// The JSON Schema string formats that are represented as temporal
+/// arrow types.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum Format {
+    DateTime,
+    Date,
+    Time,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "date-time" => Some(Self::DateTime),
+            "date" => Some(Self::Date),
+            "time" => Some(Self::Time),
+            _ => None,
+        }
+    }
+
+    fn from_data_type(data_type: &DataType) -> Option<Self> {
+        match data_type {
+            DataType::Timestamp(TimeUnit::Microsecond, None) => Some(Self::DateTime),
+            DataType::Date32 => Some(Self::Date),
+            DataType::Time64(TimeUnit::Microsecond) => Some(Self::Time),
+            _ => None,
+        }
+    }
+
+    fn data_type(self) -> DataType {
+        match self {
+            Self::DateTime => DataType::Timestamp(TimeUnit::Microsecond, None),
+            Self::Date => DataType::Date32,
+            Self::Time => DataType::Time64(TimeUnit::Microsecond),
+        }
+    }
+
+    /// Microseconds since the epoch for a date-time, days since the epoch
+    /// for a date and microseconds since midnight (UTC) for a time.
+    fn parse(self, value: &str) -> Option<i64> {
+        match self {
+            Self::DateTime => DateTime::parse_from_rfc3339(value)
+                .ok()
+                .map(|date_time| date_time.timestamp_micros()),
+
+            Self::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .map(|date| {
+                    date.signed_duration_since(DateTime::UNIX_EPOCH.date_naive())
+                        .num_days()
+                }),
+
+            Self::Time => DateTime::parse_from_rfc3339(&format!("1970-01-01T{value}"))
+                .ok()
+                .map(|date_time| date_time.naive_utc().time())
+                .map(|time| {
+                    i64::from(time.num_seconds_from_midnight()) * 1_000_000
+                        + i64::from(time.nanosecond() / 1_000)
+                }),
+        }
+    }
+}
+
+/// A formatted string as the value of its temporal arrow type.
+fn temporal(data_type: &DataType, value: String) -> Result<i64> {
+    Format::from_data_type(data_type)
+        .and_then(|format| format.parse(&value))
+        .ok_or(Error::UnsupportedSchemaRuntimeValue(
+            data_type.to_owned(),
+            Value::String(value),
+        ))
 }
 
 #[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -57,6 +159,24 @@ impl AsRef<str> for MessageKind {
     }
 }
 
+/// Validate an already parsed instance, which is required when there
+/// is a validator.
+fn validate_instance(
+    validator: Option<&jsonschema::Validator>,
+    instance: Option<&Value>,
+) -> Result<()> {
+    validator.map_or(Ok(()), |validator| {
+        instance
+            .ok_or(Error::Api(ErrorCode::InvalidRecord))
+            .and_then(|instance| {
+                validator
+                    .validate(instance)
+                    .inspect_err(|err| warn!(?err, ?validator, %instance))
+                    .map_err(|_err| Error::Api(ErrorCode::InvalidRecord))
+            })
+    })
+}
+
 fn validate(validator: Option<&jsonschema::Validator>, encoded: Option<Bytes>) -> Result<()> {
     debug!(validator = ?validator, ?encoded);
 
@@ -69,12 +189,7 @@ fn validate(validator: Option<&jsonschema::Validator>, encoded: Option<Bytes>) -
                         Error::Api(ErrorCode::InvalidRecord)
                     })
                     .inspect(|instance| debug!(?instance))
-                    .and_then(|instance| {
-                        validator
-                            .validate(&instance)
-                            .inspect_err(|err| warn!(?err, ?validator, %instance))
-                            .map_err(|_err| Error::Api(ErrorCode::InvalidRecord))
-                    })
+                    .and_then(|instance| validate_instance(Some(validator), Some(&instance)))
             })
         })
         .inspect(|r| debug!(?r))
@@ -90,15 +205,34 @@ impl TryFrom<Bytes> for Schema {
         let mut schema =
             serde_json::from_slice::<Value>(&encoded[..]).inspect(|schema| debug!(%schema))?;
 
-        let key = schema
+        let key_schema = schema
             .get(PROPERTIES)
             .and_then(|properties| properties.get(MessageKind::Key.as_ref()))
-            .and_then(|key| jsonschema::validator_for(key).ok());
+            .cloned();
 
-        let value = schema
+        let key = key_schema
+            .as_ref()
+            .map(jsonschema::validator_for)
+            .transpose()?;
+
+        let value_schema = schema
             .get(PROPERTIES)
             .and_then(|properties| properties.get(MessageKind::Value.as_ref()))
-            .and_then(|value| jsonschema::validator_for(value).ok());
+            .cloned();
+
+        let value = value_schema
+            .as_ref()
+            .map(jsonschema::validator_for)
+            .transpose()?;
+
+        let formats = field_formats(&schema);
+        debug!(?formats);
+
+        let enums = field_enums(&schema);
+        debug!(?enums);
+
+        let maps = field_maps(&schema);
+        debug!(?maps);
 
         let meta =
             serde_json::from_slice::<Value>(&Bytes::from_static(include_bytes!("meta.json")))
@@ -114,22 +248,26 @@ impl TryFrom<Bytes> for Schema {
         let ids = field_ids(&schema);
         debug!(?ids);
 
-        Ok(Self { key, value, ids })
+        Ok(Self {
+            key,
+            value,
+            key_schema,
+            value_schema,
+            ids,
+            formats,
+            enums,
+            maps,
+            dictionary_enums: false,
+        })
     }
 }
 
 impl Validator for Schema {
-    fn validate(&self, batch: &Batch) -> Result<()> {
-        debug!(?batch);
-
-        for record in &batch.records {
-            debug!(?record);
-
-            validate(self.key.as_ref(), record.key.clone())
-                .and(validate(self.value.as_ref(), record.value.clone()))?
-        }
+    fn validate_record(&self, record: &tansu_kafka_sans_io::record::Record) -> Result<()> {
+        debug!(?record);
 
-        Ok(())
+        validate(self.key.as_ref(), record.key.clone())
+            .and(validate(self.value.as_ref(), record.value.clone()))
     }
 }
 
@@ -139,6 +277,20 @@ fn sort_dedup(mut input: Vec<DataType>) -> Vec<DataType> {
     input
 }
 
+/// Numbers widen from UInt64 through Int64 to Float64. A number is
+/// only UInt64 when it is beyond the range of Int64, so any mix of
+/// numeric types is widened to Float64.
+fn widen_numeric(data_types: &[DataType]) -> Option<DataType> {
+    (data_types.len() > 1
+        && data_types.iter().all(|data_type| {
+            matches!(
+                data_type,
+                DataType::UInt64 | DataType::Int64 | DataType::Float64
+            )
+        }))
+    .then_some(DataType::Float64)
+}
+
 struct Record {
     meta: Value,
     key: Option<Value>,
@@ -146,12 +298,80 @@ struct Record {
 }
 
 impl Schema {
+    /// Represent string fields declaring an `enum` as dictionary encoded
+    /// columns, rather than repeating each value.
+    pub fn with_dictionary_enums(self, dictionary_enums: bool) -> Self {
+        Self {
+            dictionary_enums,
+            ..self
+        }
+    }
+
+    /// The reason that this schema is incompatible with the existing
+    /// schema, where the key and value of a reader must accept every
+    /// instance that is valid against the writer.
+    pub fn incompatibility(&self, existing: &Self, compatibility: Compatibility) -> Option<String> {
+        [
+            (
+                MessageKind::Key,
+                existing.key_schema.as_ref(),
+                self.key_schema.as_ref(),
+            ),
+            (
+                MessageKind::Value,
+                existing.value_schema.as_ref(),
+                self.value_schema.as_ref(),
+            ),
+        ]
+        .into_iter()
+        .find_map(|(message_kind, existing, proposed)| {
+            compatibility.incompatibility(existing, proposed, |writer, reader| {
+                let unconstrained = Value::Bool(true);
+
+                subset_incompatibility(
+                    message_kind.as_ref(),
+                    writer.unwrap_or(&unconstrained),
+                    reader.unwrap_or(&unconstrained),
+                )
+            })
+        })
+    }
+
     fn new_list_field(&self, path: &[&str], data_type: DataType) -> Field {
         self.new_field(path, ARROW_LIST_FIELD_NAME, data_type)
     }
 
+    /// A map of string keys to values of the data type. Only the keys and
+    /// values have field ids, as a map builder doesn't carry metadata on
+    /// its entries.
+    fn new_map_data_type(&self, path: &[&str], data_type: DataType) -> DataType {
+        let inside = append_path(path, MAP_ENTRIES);
+
+        DataType::Map(
+            FieldRef::new(Field::new(
+                MAP_ENTRIES,
+                DataType::Struct(Fields::from_iter([
+                    self.new_nullable_field(&inside[..], MAP_KEYS, DataType::Utf8, !NULLABLE),
+                    self.new_field(&inside[..], MAP_VALUES, data_type),
+                ])),
+                !NULLABLE,
+            )),
+            false,
+        )
+    }
+
     fn new_field(&self, path: &[&str], name: &str, data_type: DataType) -> Field {
-        debug!(?path, name, ?data_type, ids = ?self.ids);
+        self.new_nullable_field(path, name, data_type, NULLABLE)
+    }
+
+    fn new_nullable_field(
+        &self,
+        path: &[&str],
+        name: &str,
+        data_type: DataType,
+        nullable: bool,
+    ) -> Field {
+        debug!(?path, name, ?data_type, nullable, ids = ?self.ids);
 
         let path = {
             let mut path = Vec::from(path);
@@ -159,7 +379,7 @@ impl Schema {
             path.join(".")
         };
 
-        Field::new(name.to_owned(), data_type, NULLABLE).with_metadata(
+        Field::new(name.to_owned(), data_type, nullable).with_metadata(
             self.ids
                 .get(path.as_str())
                 .inspect(|field_id| debug!(?path, field_id))
@@ -176,19 +396,36 @@ impl Schema {
             Value::Bool(_) => Ok(DataType::Boolean),
 
             Value::Number(value) => {
-                if value.is_i64() | value.is_u64() {
+                if value.is_i64() {
                     Ok(DataType::Int64)
+                } else if value.is_u64() {
+                    Ok(DataType::UInt64)
                 } else {
                     Ok(DataType::Float64)
                 }
             }
 
-            Value::String(_) => Ok(DataType::Utf8),
+            Value::String(_) if self.dictionary_enums && self.enums.contains(&path.join(".")) => {
+                Ok(enum_data_type())
+            }
+
+            Value::String(value) => Ok(self
+                .formats
+                .get(&path.join("."))
+                .filter(|format| format.parse(value).is_some())
+                .map_or(DataType::Utf8, |format| format.data_type())),
 
             Value::Array(values) => self.common_data_type(path, values).map(|data_type| {
                 DataType::List(FieldRef::new(self.new_list_field(path, data_type)))
             }),
 
+            Value::Object(object) if self.maps.contains(&path.join(".")) => self
+                .common_data_type(
+                    &map_values_path(path)[..],
+                    &object.values().cloned().collect::<Vec<_>>()[..],
+                )
+                .map(|data_type| self.new_map_data_type(path, data_type)),
+
             Value::Object(object) => object
                 .iter()
                 .map(|(k, v)| {
@@ -219,29 +456,199 @@ impl Schema {
             .collect::<Result<Vec<_>>>()
             .map(sort_dedup)
             .inspect(|data_types| debug!(?data_types))
-            .and_then(|mut data_types| {
-                if data_types.len() > 1 {
-                    Err(Error::NoCommonType(data_types))
-                } else if let Some(data_type) = data_types.pop() {
-                    Ok(data_type)
-                } else {
-                    Ok(DataType::Null)
-                }
-            })
+            .and_then(|data_types| self.merge_data_types(path, data_types))
             .inspect(|data_type| debug!(?path, ?values, ?data_type))
             .inspect_err(|err| error!(?err, ?values))
     }
 
-    fn data_type_builder(&self, path: &[&str], data_type: &DataType) -> Box<dyn ArrayBuilder> {
+    fn merge_data_types(&self, path: &[&str], mut data_types: Vec<DataType>) -> Result<DataType> {
+        if data_types.len() > 1
+            && data_types
+                .iter()
+                .all(|data_type| matches!(data_type, DataType::Struct(_)))
+        {
+            self.merge_struct_fields(path, &data_types)
+                .map(DataType::Struct)
+        } else if data_types.len() > 1
+            && data_types
+                .iter()
+                .all(|data_type| matches!(data_type, DataType::List(_)))
+        {
+            self.merge_list_elements(path, &data_types)
+                .map(|data_type| {
+                    DataType::List(FieldRef::new(self.new_list_field(path, data_type)))
+                })
+        } else if data_types.len() > 1
+            && data_types
+                .iter()
+                .all(|data_type| matches!(data_type, DataType::Map(_, _)))
+        {
+            self.merge_map_values(path, &data_types)
+                .map(|data_type| self.new_map_data_type(path, data_type))
+        } else if data_types.contains(&DataType::Utf8)
+            && data_types.iter().all(|data_type| {
+                *data_type == DataType::Utf8 || Format::from_data_type(data_type).is_some()
+            })
+        {
+            // a formatted string that doesn't parse
+            Ok(DataType::Utf8)
+        } else if let Some(data_type) = widen_numeric(&data_types) {
+            Ok(data_type)
+        } else if data_types.len() > 1 {
+            Err(Error::NoCommonType(data_types))
+        } else if let Some(data_type) = data_types.pop() {
+            Ok(data_type)
+        } else {
+            Ok(DataType::Null)
+        }
+    }
+
+    /// Arrays from different records share the common type of all their
+    /// elements, where an empty array takes the type of any other.
+    fn merge_list_elements(&self, path: &[&str], data_types: &[DataType]) -> Result<DataType> {
+        let elements = sort_dedup(
+            data_types
+                .iter()
+                .filter_map(|data_type| {
+                    if let DataType::List(element) = data_type {
+                        Some(element.data_type().to_owned())
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+        );
+
+        let present = elements
+            .iter()
+            .filter(|data_type| **data_type != DataType::Null)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        self.merge_data_types(
+            path,
+            if present.is_empty() {
+                elements
+            } else {
+                present
+            },
+        )
+    }
+
+    /// Maps from different records share the common type of all their
+    /// values, where an empty map takes the type of any other.
+    fn merge_map_values(&self, path: &[&str], data_types: &[DataType]) -> Result<DataType> {
+        let values = sort_dedup(
+            data_types
+                .iter()
+                .filter_map(|data_type| match data_type {
+                    DataType::Map(entries, _) => match entries.data_type() {
+                        DataType::Struct(fields) => fields
+                            .find(MAP_VALUES)
+                            .map(|(_, field)| field.data_type().to_owned()),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect(),
+        );
+
+        let present = values
+            .iter()
+            .filter(|data_type| **data_type != DataType::Null)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        self.merge_data_types(
+            &map_values_path(path)[..],
+            if present.is_empty() { values } else { present },
+        )
+    }
+
+    /// Objects omitting different optional fields share a struct of all
+    /// their fields, where a null field takes the type of any present value.
+    /// Fields are ordered as declared by the schema, including the `items`
+    /// of an array, so that the struct is the same whichever object is seen
+    /// first.
+    fn merge_struct_fields(&self, path: &[&str], data_types: &[DataType]) -> Result<Fields> {
+        let mut names = vec![];
+        let mut candidates: BTreeMap<&str, Vec<DataType>> = BTreeMap::new();
+
+        for data_type in data_types {
+            if let DataType::Struct(fields) = data_type {
+                for field in fields {
+                    candidates
+                        .entry(field.name().as_str())
+                        .or_insert_with(|| {
+                            names.push(field.name().as_str());
+                            vec![]
+                        })
+                        .push(field.data_type().to_owned());
+                }
+            }
+        }
+
+        let declared = |name: &str| {
+            self.ids
+                .get(&append_path(path, name).join("."))
+                .or_else(|| {
+                    self.ids.get(
+                        &append_path(&append_path(path, ARROW_LIST_FIELD_NAME)[..], name).join("."),
+                    )
+                })
+                .copied()
+                .unwrap_or(i32::MAX)
+        };
+
+        names.sort_by_key(|name| declared(name));
+
+        names
+            .into_iter()
+            .map(|name| {
+                let data_types = sort_dedup(candidates.remove(name).unwrap_or_default());
+
+                let present = data_types
+                    .iter()
+                    .filter(|data_type| **data_type != DataType::Null)
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                self.merge_data_types(
+                    &append_path(path, name)[..],
+                    if present.is_empty() {
+                        data_types
+                    } else {
+                        present
+                    },
+                )
+                .map(|data_type| self.new_field(path, name, data_type))
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Fields::from)
+    }
+
+    fn data_type_builder(
+        &self,
+        path: &[&str],
+        data_type: &DataType,
+    ) -> Result<Box<dyn ArrayBuilder>> {
         debug!(path = path.join("."), ?data_type);
 
-        match data_type {
+        Ok(match data_type {
             DataType::Null => Box::new(NullBuilder::new()),
             DataType::Boolean => Box::new(BooleanBuilder::new()),
-            DataType::UInt64 => Box::new(Int64Builder::new()),
+            DataType::UInt64 => Box::new(UInt64Builder::new()),
             DataType::Int64 => Box::new(Int64Builder::new()),
             DataType::Float64 => Box::new(Float64Builder::new()),
             DataType::Utf8 => Box::new(StringBuilder::new()),
+            data_type if is_enum_data_type(data_type) => {
+                Box::new(StringDictionaryBuilder::<UInt32Type>::new())
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, None) => {
+                Box::new(TimestampMicrosecondBuilder::new())
+            }
+            DataType::Date32 => Box::new(Date32Builder::new()),
+            DataType::Time64(TimeUnit::Microsecond) => Box::new(Time64MicrosecondBuilder::new()),
 
             DataType::List(element) => {
                 debug!(?element);
@@ -250,11 +657,33 @@ impl Schema {
                     ListBuilder::new(self.data_type_builder(
                         &append_path(path, ARROW_LIST_FIELD_NAME)[..],
                         element.data_type(),
-                    ))
+                    )?)
                     .with_field(self.new_list_field(path, element.data_type().to_owned())),
                 ) as Box<dyn ArrayBuilder>
             }
 
+            DataType::Map(entries, _) => {
+                debug!(?entries);
+
+                match entries.data_type() {
+                    DataType::Struct(fields) if fields.len() == 2 => Box::new(
+                        MapBuilder::new(
+                            None,
+                            Box::new(StringBuilder::new()) as Box<dyn ArrayBuilder>,
+                            self.data_type_builder(
+                                &map_values_path(path)[..],
+                                fields[1].data_type(),
+                            )?,
+                        )
+                        .with_keys_field(fields[0].to_owned())
+                        .with_values_field(fields[1].to_owned()),
+                    )
+                        as Box<dyn ArrayBuilder>,
+
+                    _ => return Err(Error::UnexpectedMapEntries(entries.data_type().to_owned())),
+                }
+            }
+
             DataType::Struct(fields) => {
                 debug!(?fields);
 
@@ -268,12 +697,12 @@ impl Schema {
                                 field.data_type(),
                             )
                         })
-                        .collect::<Vec<_>>(),
+                        .collect::<Result<Vec<_>>>()?,
                 ))
             }
 
             _ => unimplemented!("unexpected: {}", type_name_of_val(data_type)),
-        }
+        })
     }
 }
 
@@ -283,6 +712,10 @@ fn append_path<'a>(path: &[&'a str], name: &'a str) -> Vec<&'a str> {
     path
 }
 
+fn map_values_path<'a>(path: &[&'a str]) -> Vec<&'a str> {
+    append_path(&append_path(path, MAP_ENTRIES)[..], MAP_VALUES)
+}
+
 fn append_list_builder(
     element: Arc<Field>,
     items: Vec<Value>,
@@ -326,7 +759,19 @@ fn append_list_builder(
                 })
                 .inspect_err(|err| error!(?value, ?err))?,
 
-            (DataType::Float64, Value::Number(value)) if value.is_f64() => values
+            (DataType::UInt64, Value::Number(value)) => values
+                .downcast_mut::<UInt64Builder>()
+                .ok_or(Error::Downcast)
+                .map(|builder| {
+                    if let Some(value) = value.as_u64() {
+                        builder.append_value(value)
+                    } else {
+                        builder.append_null()
+                    }
+                })
+                .inspect_err(|err| error!(?value, ?err))?,
+
+            (DataType::Float64, Value::Number(value)) => values
                 .downcast_mut::<Float64Builder>()
                 .ok_or(Error::Downcast)
                 .map(|builder| {
@@ -338,6 +783,12 @@ fn append_list_builder(
                 })
                 .inspect_err(|err| error!(?value, ?err))?,
 
+            (data_type, Value::String(value)) if is_enum_data_type(data_type) => values
+                .downcast_mut::<StringDictionaryBuilder<UInt32Type>>()
+                .ok_or(Error::Downcast)
+                .and_then(|builder| builder.append(value).map_err(Into::into))
+                .map(|_key| ())?,
+
             (_, Value::String(value)) => values
                 .downcast_mut::<StringBuilder>()
                 .ok_or(Error::Downcast)
@@ -349,6 +800,12 @@ fn append_list_builder(
                 .inspect_err(|err| error!(?err, ?element, ?items))
                 .and_then(|builder| append_list_builder(element.to_owned(), items, builder))?,
 
+            (DataType::Map(entries, _), Value::Object(object)) => values
+                .downcast_mut::<MapBuilder<Box<dyn ArrayBuilder>, Box<dyn ArrayBuilder>>>()
+                .ok_or(Error::Downcast)
+                .inspect_err(|err| error!(?err, ?entries, ?object))
+                .and_then(|builder| append_map_builder(entries, object, builder))?,
+
             (DataType::Struct(fields), Value::Object(object)) => values
                 .downcast_mut::<StructBuilder>()
                 .ok_or(Error::Downcast)
@@ -377,11 +834,9 @@ fn append_struct_builder(
     for (index, field) in fields.iter().enumerate() {
         if let Some(value) = object.remove(field.name()) {
             match (field.data_type(), value) {
-                (_, Value::Null) => builder
-                    .field_builder::<NullBuilder>(index)
-                    .ok_or(Error::Downcast)
-                    .map(|builder| builder.append_null())
-                    .inspect_err(|err| error!(?err))?,
+                (_, Value::Null) => {
+                    append_null_field(fields, index, builder).inspect_err(|err| error!(?err))?
+                }
 
                 (_, Value::Bool(value)) => builder
                     .field_builder::<BooleanBuilder>(index)
@@ -412,7 +867,18 @@ fn append_struct_builder(
                         }
                     })?,
 
-                (DataType::Float64, Value::Number(value)) if value.is_f64() => builder
+                (DataType::UInt64, Value::Number(value)) => builder
+                    .field_builder::<UInt64Builder>(index)
+                    .ok_or(Error::Downcast)
+                    .map(|builder| {
+                        if let Some(value) = value.as_u64() {
+                            builder.append_value(value)
+                        } else {
+                            builder.append_null()
+                        }
+                    })?,
+
+                (DataType::Float64, Value::Number(value)) => builder
                     .field_builder::<Float64Builder>(index)
                     .ok_or(Error::Downcast)
                     .map(|builder| {
@@ -429,12 +895,60 @@ fn append_struct_builder(
                     .map(|builder| builder.append_value(value))
                     .inspect_err(|err| error!(?err))?,
 
+                (data_type, Value::String(value)) if is_enum_data_type(data_type) => builder
+                    .field_builder::<StringDictionaryBuilder<UInt32Type>>(index)
+                    .ok_or(Error::Downcast)
+                    .and_then(|builder| builder.append(value).map_err(Into::into))
+                    .map(|_key| ())
+                    .inspect_err(|err| error!(?err))?,
+
+                (
+                    data_type @ DataType::Timestamp(TimeUnit::Microsecond, None),
+                    Value::String(value),
+                ) => temporal(data_type, value)
+                    .and_then(|micros| {
+                        builder
+                            .field_builder::<TimestampMicrosecondBuilder>(index)
+                            .ok_or(Error::Downcast)
+                            .map(|builder| builder.append_value(micros))
+                    })
+                    .inspect_err(|err| error!(?err))?,
+
+                (data_type @ DataType::Date32, Value::String(value)) => temporal(data_type, value)
+                    .and_then(|days| i32::try_from(days).map_err(Into::into))
+                    .and_then(|days| {
+                        builder
+                            .field_builder::<Date32Builder>(index)
+                            .ok_or(Error::Downcast)
+                            .map(|builder| builder.append_value(days))
+                    })
+                    .inspect_err(|err| error!(?err))?,
+
+                (data_type @ DataType::Time64(TimeUnit::Microsecond), Value::String(value)) => {
+                    temporal(data_type, value)
+                        .and_then(|micros| {
+                            builder
+                                .field_builder::<Time64MicrosecondBuilder>(index)
+                                .ok_or(Error::Downcast)
+                                .map(|builder| builder.append_value(micros))
+                        })
+                        .inspect_err(|err| error!(?err))?
+                }
+
                 (DataType::List(element), Value::Array(items)) => builder
                     .field_builder::<ListBuilder<Box<dyn ArrayBuilder>>>(index)
                     .ok_or(Error::Downcast)
                     .and_then(|builder| append_list_builder(element.to_owned(), items, builder))
                     .inspect_err(|err| error!(?err))?,
 
+                (DataType::Map(entries, _), Value::Object(object)) => builder
+                    .field_builder::<MapBuilder<Box<dyn ArrayBuilder>, Box<dyn ArrayBuilder>>>(
+                        index,
+                    )
+                    .ok_or(Error::Downcast)
+                    .and_then(|builder| append_map_builder(entries, object, builder))
+                    .inspect_err(|err| error!(?err))?,
+
                 (DataType::Struct(fields), Value::Object(object)) => builder
                     .field_builder::<StructBuilder>(index)
                     .ok_or(Error::Downcast)
@@ -446,6 +960,8 @@ fn append_struct_builder(
                     value,
                 ))?,
             }
+        } else {
+            append_null_field(fields, index, builder).inspect_err(|err| error!(?err))?
         }
     }
 
@@ -454,6 +970,119 @@ fn append_struct_builder(
     Ok(())
 }
 
+fn append_map_builder(
+    entries: &FieldRef,
+    object: Map<String, Value>,
+    builder: &mut MapBuilder<Box<dyn ArrayBuilder>, Box<dyn ArrayBuilder>>,
+) -> Result<()> {
+    debug!(?entries, ?object);
+
+    let DataType::Struct(fields) = entries.data_type() else {
+        return Err(Error::UnsupportedSchemaRuntimeValue(
+            entries.data_type().to_owned(),
+            Value::Object(object),
+        ));
+    };
+
+    let (_, values) = fields.find(MAP_VALUES).ok_or(Error::Downcast)?;
+
+    for (key, value) in object {
+        builder
+            .keys()
+            .as_any_mut()
+            .downcast_mut::<StringBuilder>()
+            .ok_or(Error::Downcast)
+            .map(|keys| keys.append_value(key))?;
+
+        append(values, value, builder.values().as_mut())?;
+    }
+
+    builder.append(true).map_err(Into::into)
+}
+
+/// Keep every child builder of a struct the same length, appending a
+/// null for a field that is absent from an object.
+fn append_null_field(fields: &Fields, index: usize, builder: &mut StructBuilder) -> Result<()> {
+    match fields[index].data_type() {
+        DataType::Null => builder
+            .field_builder::<NullBuilder>(index)
+            .ok_or(Error::Downcast)
+            .map(|builder| builder.append_null()),
+
+        DataType::Boolean => builder
+            .field_builder::<BooleanBuilder>(index)
+            .ok_or(Error::Downcast)
+            .map(|builder| builder.append_null()),
+
+        DataType::Int64 => builder
+            .field_builder::<Int64Builder>(index)
+            .ok_or(Error::Downcast)
+            .map(|builder| builder.append_null()),
+
+        DataType::UInt64 => builder
+            .field_builder::<UInt64Builder>(index)
+            .ok_or(Error::Downcast)
+            .map(|builder| builder.append_null()),
+
+        DataType::Float64 => builder
+            .field_builder::<Float64Builder>(index)
+            .ok_or(Error::Downcast)
+            .map(|builder| builder.append_null()),
+
+        DataType::Utf8 => builder
+            .field_builder::<StringBuilder>(index)
+            .ok_or(Error::Downcast)
+            .map(|builder| builder.append_null()),
+
+        data_type if is_enum_data_type(data_type) => builder
+            .field_builder::<StringDictionaryBuilder<UInt32Type>>(index)
+            .ok_or(Error::Downcast)
+            .map(|builder| builder.append_null()),
+
+        DataType::Timestamp(TimeUnit::Microsecond, None) => builder
+            .field_builder::<TimestampMicrosecondBuilder>(index)
+            .ok_or(Error::Downcast)
+            .map(|builder| builder.append_null()),
+
+        DataType::Date32 => builder
+            .field_builder::<Date32Builder>(index)
+            .ok_or(Error::Downcast)
+            .map(|builder| builder.append_null()),
+
+        DataType::Time64(TimeUnit::Microsecond) => builder
+            .field_builder::<Time64MicrosecondBuilder>(index)
+            .ok_or(Error::Downcast)
+            .map(|builder| builder.append_null()),
+
+        DataType::List(_) => builder
+            .field_builder::<ListBuilder<Box<dyn ArrayBuilder>>>(index)
+            .ok_or(Error::Downcast)
+            .map(|builder| builder.append_null()),
+
+        DataType::Map(_, _) => builder
+            .field_builder::<MapBuilder<Box<dyn ArrayBuilder>, Box<dyn ArrayBuilder>>>(index)
+            .ok_or(Error::Downcast)
+            .and_then(|builder| builder.append(false).map_err(Into::into)),
+
+        DataType::Struct(children) => builder
+            .field_builder::<StructBuilder>(index)
+            .ok_or(Error::Downcast)
+            .and_then(|builder| {
+                for index in 0..children.len() {
+                    append_null_field(children, index, builder)?;
+                }
+
+                builder.append_null();
+                Ok(())
+            }),
+
+        data_type => Err(Error::UnsupportedSchemaRuntimeValue(
+            data_type.to_owned(),
+            Value::Null,
+        )),
+    }
+}
+
 fn append(field: &Field, value: Value, builder: &mut dyn ArrayBuilder) -> Result<()> {
     debug!(?field, ?value, builder = type_name_of_val(builder));
 
@@ -495,24 +1124,73 @@ fn append(field: &Field, value: Value, builder: &mut dyn ArrayBuilder) -> Result
                 }
             }),
 
-        (DataType::Float64, Value::Number(value)) if value.is_f64() => builder
+        (DataType::UInt64, Value::Number(value)) => builder
             .as_any_mut()
-            .downcast_mut::<Float64Builder>()
+            .downcast_mut::<UInt64Builder>()
             .ok_or(Error::Downcast)
             .map(|builder| {
-                if let Some(value) = value.as_f64() {
+                if let Some(value) = value.as_u64() {
                     builder.append_value(value)
                 } else {
                     builder.append_null()
                 }
             }),
 
-        (DataType::Utf8, Value::String(value)) => builder
+        (DataType::Float64, Value::Number(value)) => builder
             .as_any_mut()
-            .downcast_mut::<StringBuilder>()
+            .downcast_mut::<Float64Builder>()
             .ok_or(Error::Downcast)
-            .map(|builder| builder.append_value(value)),
-
+            .map(|builder| {
+                if let Some(value) = value.as_f64() {
+                    builder.append_value(value)
+                } else {
+                    builder.append_null()
+                }
+            }),
+
+        (DataType::Utf8, Value::String(value)) => builder
+            .as_any_mut()
+            .downcast_mut::<StringBuilder>()
+            .ok_or(Error::Downcast)
+            .map(|builder| builder.append_value(value)),
+
+        (data_type, Value::String(value)) if is_enum_data_type(data_type) => builder
+            .as_any_mut()
+            .downcast_mut::<StringDictionaryBuilder<UInt32Type>>()
+            .ok_or(Error::Downcast)
+            .and_then(|builder| builder.append(value).map_err(Into::into))
+            .map(|_key| ()),
+
+        (data_type @ DataType::Timestamp(TimeUnit::Microsecond, None), Value::String(value)) => {
+            temporal(data_type, value).and_then(|micros| {
+                builder
+                    .as_any_mut()
+                    .downcast_mut::<TimestampMicrosecondBuilder>()
+                    .ok_or(Error::Downcast)
+                    .map(|builder| builder.append_value(micros))
+            })
+        }
+
+        (data_type @ DataType::Date32, Value::String(value)) => temporal(data_type, value)
+            .and_then(|days| i32::try_from(days).map_err(Into::into))
+            .and_then(|days| {
+                builder
+                    .as_any_mut()
+                    .downcast_mut::<Date32Builder>()
+                    .ok_or(Error::Downcast)
+                    .map(|builder| builder.append_value(days))
+            }),
+
+        (data_type @ DataType::Time64(TimeUnit::Microsecond), Value::String(value)) => {
+            temporal(data_type, value).and_then(|micros| {
+                builder
+                    .as_any_mut()
+                    .downcast_mut::<Time64MicrosecondBuilder>()
+                    .ok_or(Error::Downcast)
+                    .map(|builder| builder.append_value(micros))
+            })
+        }
+
         (DataType::List(element), Value::Array(items)) => builder
             .as_any_mut()
             .downcast_mut::<ListBuilder<Box<dyn ArrayBuilder>>>()
@@ -520,6 +1198,13 @@ fn append(field: &Field, value: Value, builder: &mut dyn ArrayBuilder) -> Result
             .inspect_err(|err| error!(?err, ?element, ?items))
             .and_then(|builder| append_list_builder(element.to_owned(), items, builder)),
 
+        (DataType::Map(entries, _), Value::Object(object)) => builder
+            .as_any_mut()
+            .downcast_mut::<MapBuilder<Box<dyn ArrayBuilder>, Box<dyn ArrayBuilder>>>()
+            .ok_or(Error::Downcast)
+            .inspect_err(|err| error!(?err, ?entries, ?object))
+            .and_then(|builder| append_map_builder(entries, object, builder)),
+
         (DataType::Struct(fields), Value::Object(object)) => builder
             .as_any_mut()
             .downcast_mut::<StructBuilder>()
@@ -536,8 +1221,90 @@ fn append(field: &Field, value: Value, builder: &mut dyn ArrayBuilder) -> Result
 
 impl AsArrow for Schema {
     fn as_arrow(&self, partition: i32, batch: &Batch) -> Result<RecordBatch> {
+        self.as_arrow_stream(partition, batch, usize::MAX)
+            .and_then(|mut record_batches| record_batches.pop().ok_or(Error::BuilderExhausted))
+    }
+
+    /// The column types are inferred from the whole batch, so that every
+    /// emitted record batch has the same schema.
+    fn as_arrow_stream(
+        &self,
+        partition: i32,
+        batch: &Batch,
+        max_rows: usize,
+    ) -> Result<Vec<RecordBatch>> {
+        debug!(?batch, max_rows);
+
+        self.records(partition, batch, false)
+            .and_then(|records| self.record_batches(partition, batch, records, max_rows))
+    }
+}
+
+impl ValidateAsArrow for Schema {
+    fn validate_as_arrow(&self, partition: i32, batch: &Batch) -> Result<RecordBatch> {
         debug!(?batch);
 
+        self.records(partition, batch, true)
+            .and_then(|records| self.record_batches(partition, batch, records, usize::MAX))
+            .and_then(|mut record_batches| record_batches.pop().ok_or(Error::BuilderExhausted))
+    }
+}
+
+impl Schema {
+    /// Parse the key and value of each record once, optionally
+    /// validating them against the schema.
+    fn records(&self, partition: i32, batch: &Batch, validating: bool) -> Result<Vec<Record>> {
+        let parse = |encoded: Option<&Bytes>| {
+            encoded
+                .map(|encoded| serde_json::from_slice::<Value>(&encoded[..]))
+                .transpose()
+                .map_err(|err| {
+                    if validating {
+                        warn!(?err, ?encoded);
+                        Error::Api(ErrorCode::InvalidRecord)
+                    } else {
+                        err.into()
+                    }
+                })
+        };
+
+        batch
+            .records
+            .iter()
+            .map(|record| {
+                let key = parse(record.key.as_ref())?;
+                let value = parse(record.value.as_ref())?;
+
+                if validating {
+                    validate_instance(self.key.as_ref(), key.as_ref())
+                        .and(validate_instance(self.value.as_ref(), value.as_ref()))?;
+                }
+
+                let meta =
+                    DateTime::from_timestamp_millis(batch.base_timestamp + record.timestamp_delta)
+                        .as_ref()
+                        .map(|date_time| {
+                            json!({
+                            "partition": partition,
+                            "timestamp": date_time.to_rfc3339(),
+                            "year": date_time.date_naive().year(),
+                            "month": date_time.date_naive().month(),
+                            "day": date_time.date_naive().day()})
+                        })
+                        .unwrap_or(json!({"partition": partition}));
+
+                Ok(Record { meta, key, value })
+            })
+            .collect::<Result<Vec<_>>>()
+    }
+
+    fn record_batches(
+        &self,
+        partition: i32,
+        batch: &Batch,
+        records: Vec<Record>,
+        max_rows: usize,
+    ) -> Result<Vec<RecordBatch>> {
         let mut builders = vec![];
         let mut fields = vec![];
 
@@ -557,99 +1324,44 @@ impl AsArrow for Schema {
             let data_type = self.common_data_type(&[MessageKind::Meta.as_ref()], &[meta][..])?;
 
             debug!(?data_type);
-            builders.push(self.data_type_builder(&[MessageKind::Meta.as_ref()], &data_type));
+            builders.push(self.data_type_builder(&[MessageKind::Meta.as_ref()], &data_type)?);
             fields.push(self.new_field(&[], MessageKind::Meta.as_ref(), data_type))
         }
 
-        if let Some(data_type) = batch
-            .records
-            .iter()
-            .map(|record| {
-                record.key.clone().map_or(Ok(None), |encoded| {
-                    serde_json::from_slice::<Value>(&encoded[..])
-                        .map(Some)
-                        .map_err(Into::into)
-                })
-            })
-            .collect::<Result<Vec<_>>>()
-            .map(|values| values.into_iter().flatten().collect::<Vec<_>>())
-            .and_then(|values| {
-                if values.is_empty() {
-                    Ok(None)
-                } else {
-                    self.common_data_type(&[MessageKind::Key.as_ref()], values.as_slice())
-                        .map(Some)
-                }
-            })
-            .inspect(|data_type| debug!(?data_type))?
-        {
-            builders.push(self.data_type_builder(&[MessageKind::Key.as_ref()], &data_type));
-            fields.push(self.new_field(&[], MessageKind::Key.as_ref(), data_type))
-        };
+        for (message_kind, values) in [
+            (
+                MessageKind::Key,
+                records
+                    .iter()
+                    .filter_map(|record| record.key.clone())
+                    .collect::<Vec<_>>(),
+            ),
+            (
+                MessageKind::Value,
+                records
+                    .iter()
+                    .filter_map(|record| record.value.clone())
+                    .collect::<Vec<_>>(),
+            ),
+        ] {
+            if values.is_empty() {
+                continue;
+            }
 
-        if let Some(data_type) = batch
-            .records
-            .iter()
-            .map(|record| {
-                record.value.clone().map_or(Ok(None), |encoded| {
-                    serde_json::from_slice::<Value>(&encoded[..])
-                        .map(Some)
-                        .map_err(Into::into)
-                })
-            })
-            .collect::<Result<Vec<_>>>()
-            .map(|values| values.into_iter().flatten().collect::<Vec<_>>())
-            .and_then(|values| {
-                if values.is_empty() {
-                    Ok(None)
-                } else {
-                    self.common_data_type(&[MessageKind::Value.as_ref()], values.as_slice())
-                        .map(Some)
-                }
-            })
-            .inspect(|data_type| debug!(?data_type))?
-        {
-            builders.push(self.data_type_builder(&[MessageKind::Value.as_ref()], &data_type));
-            fields.push(self.new_field(&[], MessageKind::Value.as_ref(), data_type))
-        };
+            let data_type = self
+                .common_data_type(&[message_kind.as_ref()], values.as_slice())
+                .inspect(|data_type| debug!(?data_type))?;
 
-        for kv in batch
-            .records
-            .iter()
-            .map(|record| {
-                record
-                    .key
-                    .as_ref()
-                    .map(|encoded| serde_json::from_slice::<Value>(&encoded[..]))
-                    .transpose()
-                    .map_err(Into::into)
-                    .and_then(|key| {
-                        let meta = DateTime::from_timestamp_millis(
-                            batch.base_timestamp + record.timestamp_delta,
-                        )
-                        .as_ref()
-                        .map(|date_time| {
-                            json!({
-                            "partition": partition,
-                            "timestamp": date_time.to_rfc3339(),
-                            "year": date_time.date_naive().year(),
-                            "month": date_time.date_naive().month(),
-                            "day": date_time.date_naive().day()})
-                        })
-                        .unwrap_or(json!({"partition": partition}));
+            builders.push(self.data_type_builder(&[message_kind.as_ref()], &data_type)?);
+            fields.push(self.new_field(&[], message_kind.as_ref(), data_type))
+        }
 
-                        record
-                            .value
-                            .as_ref()
-                            .map(|encoded| serde_json::from_slice::<Value>(&encoded[..]))
-                            .transpose()
-                            .map_err(Into::into)
-                            .map(|value| Record { meta, key, value })
-                    })
-            })
-            .collect::<Result<Vec<_>>>()?
-        {
-            let mut i = fields.iter().zip(builders.iter_mut());
+        let schema = SchemaRef::new(ArrowSchema::new(Fields::from(fields)));
+        let mut record_batches = vec![];
+        let mut rows = 0;
+
+        for kv in records {
+            let mut i = schema.fields().iter().zip(builders.iter_mut());
 
             let (field, builder) = i.next().unwrap();
             debug!(meta = %kv.meta, ?field);
@@ -666,21 +1378,36 @@ impl AsArrow for Schema {
                 debug!(%value, ?field);
                 append(field, value, builder)?;
             }
+
+            rows += 1;
+
+            if rows >= max_rows {
+                record_batches.push(finish(schema.clone(), &mut builders)?);
+                rows = 0;
+            }
         }
 
-        debug!(len = ?builders.iter().map(|builder|builder.len()).collect::<Vec<_>>());
+        if rows > 0 || record_batches.is_empty() {
+            record_batches.push(finish(schema, &mut builders)?);
+        }
 
-        RecordBatch::try_new(
-            Arc::new(ArrowSchema::new(Fields::from(fields))),
-            builders
-                .iter_mut()
-                .map(|builder| builder.finish())
-                .collect(),
-        )
-        .map_err(Into::into)
+        Ok(record_batches)
     }
 }
 
+fn finish(schema: SchemaRef, builders: &mut [Box<dyn ArrayBuilder>]) -> Result<RecordBatch> {
+    debug!(len = ?builders.iter().map(|builder|builder.len()).collect::<Vec<_>>());
+
+    RecordBatch::try_new(
+        schema,
+        builders
+            .iter_mut()
+            .map(|builder| builder.finish())
+            .collect(),
+    )
+    .map_err(Into::into)
+}
+
 impl AsKafkaRecord for Schema {
     fn as_kafka_record(&self, value: &Value) -> Result<tansu_kafka_sans_io::record::Builder> {
         let mut builder = tansu_kafka_sans_io::record::Record::builder();
@@ -707,20 +1434,81 @@ impl AsKafkaRecord for Schema {
 }
 
 impl AsJsonValue for Schema {
-    fn as_json_value(&self, batch: &Batch) -> Result<Value> {
-        let _ = batch;
-        todo!()
+    fn record_as_json_value(&self, record: &tansu_kafka_sans_io::record::Record) -> Result<Value> {
+        debug!(?record);
+
+        let key = record
+            .key
+            .as_deref()
+            .map(serde_json::from_slice::<Value>)
+            .transpose()?
+            .unwrap_or(Value::Null);
+
+        let value = record
+            .value
+            .as_deref()
+            .map(serde_json::from_slice::<Value>)
+            .transpose()?
+            .unwrap_or(Value::Null);
+
+        Ok(json!({
+            MessageKind::Key.as_ref(): key,
+            MessageKind::Value.as_ref(): value,
+        }))
     }
 }
 
-fn field_ids(schema: &Value) -> BTreeMap<String, i32> {
-    debug!(%schema);
+/// The declared format of string properties of the key and value,
+/// keyed by their path.
+fn field_formats(schema: &Value) -> BTreeMap<String, Format> {
+    fn field_formats_with_path(
+        path: &[&str],
+        schema: &Value,
+        formats: &mut BTreeMap<String, Format>,
+    ) {
+        match schema.get("type").and_then(|r#type| r#type.as_str()) {
+            Some("object") => {
+                if let Some(properties) = schema
+                    .get("properties")
+                    .and_then(|properties| properties.as_object())
+                {
+                    for (k, v) in properties {
+                        field_formats_with_path(&append_path(path, k)[..], v, formats)
+                    }
+                }
+            }
 
-    fn field_ids_with_path(path: &[&str], schema: &Value, id: &mut i32) -> BTreeMap<String, i32> {
-        debug!(?path, %schema, id);
+            Some("string") => {
+                if let Some(format) = schema
+                    .get("format")
+                    .and_then(|format| format.as_str())
+                    .and_then(Format::from_name)
+                {
+                    _ = formats.insert(path.join("."), format);
+                }
+            }
 
-        let mut ids = BTreeMap::new();
+            None | Some(_) => (),
+        }
+    }
+
+    let mut formats = BTreeMap::new();
+
+    for kind in [MessageKind::Key, MessageKind::Value] {
+        if let Some(schema) = schema
+            .get("properties")
+            .and_then(|schema| schema.get(kind.as_ref()))
+        {
+            field_formats_with_path(&[kind.as_ref()], schema, &mut formats)
+        }
+    }
+
+    formats
+}
 
+/// The paths of the string fields that declare an `enum` of values.
+fn field_enums(schema: &Value) -> BTreeSet<String> {
+    fn field_enums_with_path(path: &[&str], schema: &Value, enums: &mut BTreeSet<String>) {
         match schema.get("type").and_then(|r#type| r#type.as_str()) {
             Some("object") => {
                 if let Some(properties) = schema
@@ -728,110 +1516,432 @@ fn field_ids(schema: &Value) -> BTreeMap<String, i32> {
                     .and_then(|properties| properties.as_object())
                 {
                     for (k, v) in properties {
-                        let mut path = Vec::from(path);
-                        path.push(k);
-
-                        ids.insert(path.join("."), *id);
-                        *id += 1;
-
-                        ids.extend(field_ids_with_path(&path[..], v, id))
+                        field_enums_with_path(&append_path(path, k)[..], v, enums)
                     }
                 }
             }
 
-            Some("array") => {
-                let mut path = Vec::from(path);
-                path.push(ARROW_LIST_FIELD_NAME);
-                ids.insert(path.join("."), *id);
-                *id += 1;
-
-                if let Some(items) = schema.get("items") {
-                    debug!(?items);
-
-                    ids.extend(field_ids_with_path(&path[..], items, id))
+            Some("string") => {
+                if schema
+                    .get("enum")
+                    .and_then(|symbols| symbols.as_array())
+                    .is_some_and(|symbols| symbols.iter().all(Value::is_string))
+                {
+                    _ = enums.insert(path.join("."));
                 }
             }
 
             None | Some(_) => (),
         }
-
-        ids
     }
 
-    let mut ids = BTreeMap::new();
-    let mut id = 1;
-    let kinds = [MessageKind::Meta, MessageKind::Key, MessageKind::Value];
+    let mut enums = BTreeSet::new();
 
-    for kind in kinds {
-        if schema
+    for kind in [MessageKind::Key, MessageKind::Value] {
+        if let Some(schema) = schema
             .get("properties")
             .and_then(|schema| schema.get(kind.as_ref()))
-            .inspect(|schema| debug!(?kind, ?schema))
-            .is_some()
         {
-            ids.insert(kind.as_ref().into(), id);
-            id += 1;
+            field_enums_with_path(&[kind.as_ref()], schema, &mut enums)
         }
     }
 
-    for kind in kinds {
+    enums
+}
+
+/// The schema of the values of an object without `properties`, whose
+/// `additionalProperties` are typed, as a map of dynamic keys.
+fn map_values(schema: &Value) -> Option<&Value> {
+    schema
+        .get("additionalProperties")
+        .filter(|values| values.is_object() && schema.get("properties").is_none())
+}
+
+/// The paths of the objects that are represented as maps.
+fn field_maps(schema: &Value) -> BTreeSet<String> {
+    fn field_maps_with_path(path: &[&str], schema: &Value, maps: &mut BTreeSet<String>) {
+        match schema.get("type").and_then(|r#type| r#type.as_str()) {
+            Some("object") => {
+                if let Some(values) = map_values(schema) {
+                    _ = maps.insert(path.join("."));
+                    field_maps_with_path(&map_values_path(path)[..], values, maps)
+                } else if let Some(properties) = schema
+                    .get("properties")
+                    .and_then(|properties| properties.as_object())
+                {
+                    for (k, v) in properties {
+                        field_maps_with_path(&append_path(path, k)[..], v, maps)
+                    }
+                }
+            }
+
+            Some("array") => {
+                if let Some(items) = schema.get("items") {
+                    field_maps_with_path(&append_path(path, ARROW_LIST_FIELD_NAME)[..], items, maps)
+                }
+            }
+
+            None | Some(_) => (),
+        }
+    }
+
+    let mut maps = BTreeSet::new();
+
+    for kind in [MessageKind::Key, MessageKind::Value] {
         if let Some(schema) = schema
             .get("properties")
             .and_then(|schema| schema.get(kind.as_ref()))
-            .inspect(|schema| debug!(?kind, ?schema))
         {
-            ids.extend(field_ids_with_path(&[kind.as_ref()], schema, &mut id));
+            field_maps_with_path(&[kind.as_ref()], schema, &mut maps)
         }
     }
 
-    debug!(?ids);
-
-    ids
+    maps
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::Registry;
+/// The keywords that only annotate a schema, without constraining an
+/// instance.
+const ANNOTATIONS: [&str; 6] = [
+    "$id",
+    "$schema",
+    "default",
+    "description",
+    "examples",
+    "title",
+];
+
+/// The reason, with the path where it was found, that an instance valid
+/// against the writer might not be valid against the reader. Keywords
+/// that are not understood must be identical in both schemas.
+fn subset_incompatibility(path: &str, writer: &Value, reader: &Value) -> Option<String> {
+    debug!(path, %writer, %reader);
+
+    if writer == reader || writer == &Value::Bool(false) {
+        return None;
+    }
 
-    use super::*;
-    use arrow::util::pretty::pretty_format_batches;
-    use datafusion::prelude::*;
-    use iceberg::{
-        io::FileIOBuilder,
-        spec::{
-            DataFile, DataFileFormat::Parquet, Schema as IcebergSchema,
-            SchemaRef as IcebergSchemaRef,
-        },
-        writer::{
-            IcebergWriter, IcebergWriterBuilder,
-            base_writer::data_file_writer::DataFileWriterBuilder,
-            file_writer::{
-                ParquetWriterBuilder,
-                location_generator::{DefaultFileNameGenerator, LocationGenerator},
-            },
-        },
+    let unconstrained = Map::new();
+
+    let reader = match reader {
+        Value::Bool(true) => &unconstrained,
+        Value::Object(reader) => reader,
+        _ => return Some(format!("{path}: rejects every instance")),
     };
-    use jsonschema::BasicOutput;
-    use object_store::{ObjectStore, PutPayload, memory::InMemory, path::Path};
-    use parquet::file::properties::WriterProperties;
-    use serde_json::json;
-    use std::{collections::VecDeque, fs::File, ops::Deref, sync::Arc, thread};
-    use tansu_kafka_sans_io::record::Record;
-    use tracing::subscriber::DefaultGuard;
-    use tracing_subscriber::EnvFilter;
 
-    fn init_tracing() -> Result<DefaultGuard> {
-        Ok(tracing::subscriber::set_default(
-            tracing_subscriber::fmt()
-                .with_level(true)
-                .with_line_number(true)
-                .with_thread_names(false)
-                .with_env_filter(
-                    EnvFilter::from_default_env()
-                        .add_directive(format!("{}=debug", env!("CARGO_CRATE_NAME")).parse()?),
-                )
-                .with_writer(
-                    thread::current()
+    let writer = match writer {
+        Value::Bool(true) => &unconstrained,
+        Value::Object(writer) => writer,
+        _ => return Some(format!("{path}: has an invalid schema")),
+    };
+
+    fn types(schema: &Map<String, Value>) -> Option<BTreeSet<&str>> {
+        match schema.get("type") {
+            Some(Value::String(r#type)) => Some(BTreeSet::from([r#type.as_str()])),
+            Some(Value::Array(types)) => Some(types.iter().filter_map(Value::as_str).collect()),
+            _ => None,
+        }
+    }
+
+    if let Some(reader_types) = types(reader) {
+        let Some(writer_types) = types(writer) else {
+            return Some(format!(
+                "{path}: type is now restricted to: {reader_types:?}"
+            ));
+        };
+
+        if let Some(r#type) = writer_types.iter().find(|r#type| {
+            !(reader_types.contains(*r#type)
+                || (**r#type == "integer" && reader_types.contains("number")))
+        }) {
+            return Some(format!(
+                "{path}: type: {} is not one of: {reader_types:?}",
+                r#type
+            ));
+        }
+    }
+
+    if let Some(reader_enum) = reader.get("enum").and_then(Value::as_array) {
+        let Some(writer_enum) = writer.get("enum").and_then(Value::as_array) else {
+            return Some(format!("{path}: is now restricted to: {reader_enum:?}"));
+        };
+
+        if let Some(symbol) = writer_enum
+            .iter()
+            .find(|symbol| !reader_enum.contains(symbol))
+        {
+            return Some(format!("{path}: enum: {symbol} has been removed"));
+        }
+    }
+
+    fn required(schema: &Map<String, Value>) -> BTreeSet<&str> {
+        schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    let writer_required = required(writer);
+
+    if let Some(property) = required(reader)
+        .into_iter()
+        .find(|property| !writer_required.contains(property))
+    {
+        return Some(format!("{path}: {property} is now required"));
+    }
+
+    let properties = |schema: &Map<String, Value>| {
+        schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    let additional = |schema: &Map<String, Value>| {
+        schema
+            .get("additionalProperties")
+            .cloned()
+            .unwrap_or(Value::Bool(true))
+    };
+
+    let writer_properties = properties(writer);
+    let reader_properties = properties(reader);
+    let writer_additional = additional(writer);
+    let reader_additional = additional(reader);
+
+    // each property is checked against its schema in the reader, or
+    // the additional properties of the reader when it isn't declared
+    //
+    for (name, writer_property) in &writer_properties {
+        let reader_property = reader_properties.get(name).unwrap_or(&reader_additional);
+
+        if let Some(reason) =
+            subset_incompatibility(&format!("{path}.{name}"), writer_property, reader_property)
+        {
+            return Some(reason);
+        }
+    }
+
+    for (name, reader_property) in &reader_properties {
+        if writer_properties.contains_key(name) {
+            continue;
+        }
+
+        if let Some(reason) = subset_incompatibility(
+            &format!("{path}.{name}"),
+            &writer_additional,
+            reader_property,
+        ) {
+            return Some(reason);
+        }
+    }
+
+    if let Some(reason) = subset_incompatibility(
+        &format!("{path}.additionalProperties"),
+        &writer_additional,
+        &reader_additional,
+    ) {
+        return Some(reason);
+    }
+
+    if let Some(reader_items) = reader.get("items") {
+        if let Some(reason) = subset_incompatibility(
+            &format!("{path}.items"),
+            writer.get("items").unwrap_or(&Value::Bool(true)),
+            reader_items,
+        ) {
+            return Some(reason);
+        }
+    }
+
+    for (keyword, looser) in [
+        ("minimum", f64::lt as fn(&f64, &f64) -> bool),
+        ("exclusiveMinimum", f64::lt),
+        ("minLength", f64::lt),
+        ("minItems", f64::lt),
+        ("minProperties", f64::lt),
+        ("maximum", f64::gt),
+        ("exclusiveMaximum", f64::gt),
+        ("maxLength", f64::gt),
+        ("maxItems", f64::gt),
+        ("maxProperties", f64::gt),
+    ] {
+        let Some(reader_bound) = reader.get(keyword).and_then(Value::as_f64) else {
+            continue;
+        };
+
+        match writer.get(keyword).and_then(Value::as_f64) {
+            Some(writer_bound) if !looser(&writer_bound, &reader_bound) => (),
+
+            _ => return Some(format!("{path}: {keyword} is now: {reader_bound}")),
+        }
+    }
+
+    const UNDERSTOOD: [&str; 16] = [
+        "additionalProperties",
+        "enum",
+        "exclusiveMaximum",
+        "exclusiveMinimum",
+        "items",
+        "maxItems",
+        "maxLength",
+        "maxProperties",
+        "maximum",
+        "minItems",
+        "minLength",
+        "minProperties",
+        "minimum",
+        "properties",
+        "required",
+        "type",
+    ];
+
+    reader
+        .iter()
+        .filter(|(keyword, _)| {
+            !ANNOTATIONS.contains(&keyword.as_str()) && !UNDERSTOOD.contains(&keyword.as_str())
+        })
+        .find(|(keyword, constraint)| writer.get(keyword.as_str()) != Some(constraint))
+        .map(|(keyword, _)| format!("{path}: {keyword} has changed"))
+}
+
+fn field_ids(schema: &Value) -> BTreeMap<String, i32> {
+    debug!(%schema);
+
+    fn field_ids_with_path(path: &[&str], schema: &Value, id: &mut i32) -> BTreeMap<String, i32> {
+        debug!(?path, %schema, id);
+
+        let mut ids = BTreeMap::new();
+
+        match schema.get("type").and_then(|r#type| r#type.as_str()) {
+            Some("object") => {
+                if let Some(values) = map_values(schema) {
+                    let path = append_path(path, MAP_ENTRIES);
+
+                    ids.insert(append_path(&path[..], MAP_KEYS).join("."), *id);
+                    *id += 1;
+
+                    let path = append_path(&path[..], MAP_VALUES);
+                    ids.insert(path.join("."), *id);
+                    *id += 1;
+
+                    ids.extend(field_ids_with_path(&path[..], values, id))
+                } else if let Some(properties) = schema
+                    .get("properties")
+                    .and_then(|properties| properties.as_object())
+                {
+                    for (k, v) in properties {
+                        let mut path = Vec::from(path);
+                        path.push(k);
+
+                        ids.insert(path.join("."), *id);
+                        *id += 1;
+
+                        ids.extend(field_ids_with_path(&path[..], v, id))
+                    }
+                }
+            }
+
+            Some("array") => {
+                let mut path = Vec::from(path);
+                path.push(ARROW_LIST_FIELD_NAME);
+                ids.insert(path.join("."), *id);
+                *id += 1;
+
+                if let Some(items) = schema.get("items") {
+                    debug!(?items);
+
+                    ids.extend(field_ids_with_path(&path[..], items, id))
+                }
+            }
+
+            None | Some(_) => (),
+        }
+
+        ids
+    }
+
+    let mut ids = BTreeMap::new();
+    let mut id = 1;
+    let kinds = [MessageKind::Meta, MessageKind::Key, MessageKind::Value];
+
+    for kind in kinds {
+        if schema
+            .get("properties")
+            .and_then(|schema| schema.get(kind.as_ref()))
+            .inspect(|schema| debug!(?kind, ?schema))
+            .is_some()
+        {
+            ids.insert(kind.as_ref().into(), id);
+            id += 1;
+        }
+    }
+
+    for kind in kinds {
+        if let Some(schema) = schema
+            .get("properties")
+            .and_then(|schema| schema.get(kind.as_ref()))
+            .inspect(|schema| debug!(?kind, ?schema))
+        {
+            ids.extend(field_ids_with_path(&[kind.as_ref()], schema, &mut id));
+        }
+    }
+
+    debug!(?ids);
+
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AsNdJson, AsParquet, Registry};
+
+    use super::*;
+    use arrow::{
+        array::{AsArray, Int64Array},
+        util::pretty::pretty_format_batches,
+    };
+    use datafusion::prelude::*;
+    use iceberg::{
+        io::FileIOBuilder,
+        spec::{
+            DataFile, DataFileFormat::Parquet, Schema as IcebergSchema,
+            SchemaRef as IcebergSchemaRef,
+        },
+        writer::{
+            IcebergWriter, IcebergWriterBuilder,
+            base_writer::data_file_writer::DataFileWriterBuilder,
+            file_writer::{
+                ParquetWriterBuilder,
+                location_generator::{DefaultFileNameGenerator, LocationGenerator},
+            },
+        },
+    };
+    use jsonschema::BasicOutput;
+    use object_store::{ObjectStore, PutPayload, memory::InMemory, path::Path};
+    use parquet::{
+        arrow::arrow_reader::ParquetRecordBatchReaderBuilder, basic::Compression,
+        file::properties::WriterProperties,
+    };
+    use serde_json::json;
+    use std::{collections::VecDeque, fs::File, ops::Deref, sync::Arc, thread};
+    use tansu_kafka_sans_io::record::Record;
+    use tracing::subscriber::DefaultGuard;
+    use tracing_subscriber::EnvFilter;
+
+    fn init_tracing() -> Result<DefaultGuard> {
+        Ok(tracing::subscriber::set_default(
+            tracing_subscriber::fmt()
+                .with_level(true)
+                .with_line_number(true)
+                .with_thread_names(false)
+                .with_env_filter(
+                    EnvFilter::from_default_env()
+                        .add_directive(format!("{}=debug", env!("CARGO_CRATE_NAME")).parse()?),
+                )
+                .with_writer(
+                    thread::current()
                         .name()
                         .ok_or(Error::Message(String::from("unnamed thread")))
                         .and_then(|name| {
@@ -850,6 +1960,8 @@ mod tests {
             .inspect(|schema| debug!(?schema))
             .inspect_err(|err| debug!(?err))?;
 
+        let record_batch = crate::lake::berg::conform(record_batch, &iceberg_schema)?;
+
         let memory = FileIOBuilder::new("memory").build()?;
 
         #[derive(Clone)]
@@ -1045,6 +2157,68 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn validate_collect_every_invalid_record() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let schema = serde_json::to_vec(&json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "number",
+                    "multipleOf": 10
+                }
+            }
+        }))
+        .map(Bytes::from)
+        .map_err(Into::into)
+        .and_then(Schema::try_from)?;
+
+        let keys = [
+            Some(Bytes::from_static(b"5450")),
+            Some(Bytes::from_static(b"545")),
+            Some(Bytes::from_static(b"6760")),
+            None,
+            Some(Bytes::from_static(b"not json")),
+        ];
+
+        let batch = {
+            let mut batch = Batch::builder();
+
+            for (offset_delta, key) in keys.into_iter().enumerate() {
+                batch = batch.record(
+                    Record::builder()
+                        .offset_delta(i32::try_from(offset_delta)?)
+                        .key(key.into()),
+                );
+            }
+
+            batch.build()?
+        };
+
+        assert!(matches!(
+            schema.validate(&batch),
+            Err(Error::Api(ErrorCode::InvalidRecord))
+        ));
+
+        let Err(errors) = schema.validate_collect(&batch) else {
+            panic!("expected every invalid record to be reported");
+        };
+
+        assert_eq!(
+            vec![1, 3, 4],
+            errors.iter().map(|(index, _)| *index).collect::<Vec<_>>()
+        );
+
+        assert!(
+            errors
+                .iter()
+                .all(|(_, err)| matches!(err, Error::Api(ErrorCode::InvalidRecord)))
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn key_and_value() -> Result<()> {
         let _guard = init_tracing()?;
@@ -1410,7 +2584,622 @@ mod tests {
             "type": "object",
             "properties": {
                 "value": {
-                    "type": "array",
+                    "type": "array",
+                }
+            }
+        }))
+        .map_err(Into::into)
+        .map(Bytes::from)
+        .and_then(Schema::try_from)?;
+
+        assert_eq!(
+            BasicOutput::Valid(VecDeque::new()),
+            schema
+                .value
+                .as_ref()
+                .unwrap()
+                .apply(&json!([1, 2, 3, 4, 5]))
+                .basic()
+        );
+
+        assert_eq!(
+            BasicOutput::Valid(VecDeque::new()),
+            schema
+                .value
+                .as_ref()
+                .unwrap()
+                .apply(&json!([3, "different", { "types": "of values" }]))
+                .basic()
+        );
+
+        assert!(matches!(
+            schema
+                .value
+                .as_ref()
+                .unwrap()
+                .apply(&json!({"Not": "an array"}))
+                .basic(),
+            BasicOutput::Invalid(_)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn schema_basic_output() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let schema = serde_json::to_vec(&json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "number"
+                },
+                "value": {
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                        },
+                        "email": {
+                            "type": "string",
+                            "format": "email"
+                        }
+                    }
+                }
+            }
+        }))
+        .map_err(Into::into)
+        .map(Bytes::from)
+        .and_then(Schema::try_from)?;
+
+        debug!(?schema);
+
+        assert_eq!(
+            BasicOutput::Valid(VecDeque::new()),
+            schema.key.as_ref().unwrap().apply(&json!(12321)).basic()
+        );
+
+        match schema
+            .value
+            .as_ref()
+            .unwrap()
+            .apply(&json!({"name": "alice", "email": "alice@example.com"}))
+            .basic()
+        {
+            BasicOutput::Valid(annotations) => {
+                debug!(?annotations);
+                assert_eq!(1, annotations.len());
+                assert_eq!(
+                    &Value::Array(vec![
+                        Value::String("email".into()),
+                        Value::String("name".into())
+                    ]),
+                    annotations[0].value().deref()
+                );
+
+                for annotation in annotations {
+                    debug!(
+                        "value: {} at path {}",
+                        annotation.value(),
+                        annotation.instance_location()
+                    )
+                }
+            }
+            BasicOutput::Invalid(errors) => {
+                debug!(?errors);
+                panic!()
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn key_and_value_as_arrow() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let topic = "def";
+
+        let schema = serde_json::to_vec(&json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "number"
+                },
+                "value": {
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                        },
+                        "email": {
+                            "type": "string",
+                            "format": "email"
+                        }
+                    }
+                }
+            }
+        }))
+        .map_err(Into::into)
+        .map(Bytes::from)
+        .and_then(Schema::try_from)?;
+
+        let kv = [
+            (
+                json!(12321),
+                json!({"name": "alice", "email": "alice@example.com"}),
+            ),
+            (
+                json!(32123),
+                json!({"name": "bob", "email": "bob@example.com"}),
+            ),
+        ];
+
+        let batch = {
+            let mut batch = Batch::builder().base_timestamp(1_234_567_890 * 1_000);
+
+            for (ref key, ref value) in kv {
+                batch = batch.record(
+                    Record::builder()
+                        .key(serde_json::to_vec(key).map(Bytes::from).map(Into::into)?)
+                        .value(serde_json::to_vec(value).map(Bytes::from).map(Into::into)?),
+                );
+            }
+
+            batch.build()?
+        };
+
+        let record_batch = schema.as_arrow(0, &batch)?;
+        let data_files = iceberg_write(record_batch.clone()).await?;
+        assert_eq!(1, data_files.len());
+        assert_eq!(2, data_files[0].record_count());
+
+        let ctx = SessionContext::new();
+
+        _ = ctx.register_batch(topic, record_batch)?;
+        let df = ctx.sql(format!("select * from {topic}").as_str()).await?;
+        let results = df.collect().await?;
+
+        let pretty_results = pretty_format_batches(&results)?.to_string();
+
+        let expected = vec![
+            "+-------------------------------------------------------------------------------------+-------+-----------------------------------------+",
+            "| meta                                                                                | key   | value                                   |",
+            "+-------------------------------------------------------------------------------------+-------+-----------------------------------------+",
+            "| {day: 13, month: 2, partition: 0, timestamp: 2009-02-13T23:31:30+00:00, year: 2009} | 12321 | {email: alice@example.com, name: alice} |",
+            "| {day: 13, month: 2, partition: 0, timestamp: 2009-02-13T23:31:30+00:00, year: 2009} | 32123 | {email: bob@example.com, name: bob}     |",
+            "+-------------------------------------------------------------------------------------+-------+-----------------------------------------+",
+        ];
+
+        assert_eq!(pretty_results.trim().lines().collect::<Vec<_>>(), expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn as_arrow_stream_max_rows() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let schema = serde_json::to_vec(&json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "number"
+                },
+                "value": {
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                        },
+                        "email": {
+                            "type": "string",
+                            "format": "email"
+                        }
+                    }
+                }
+            }
+        }))
+        .map_err(Into::into)
+        .map(Bytes::from)
+        .and_then(Schema::try_from)?;
+
+        let batch = {
+            let mut batch = Batch::builder().base_timestamp(1_234_567_890 * 1_000);
+
+            for offset_delta in 0..5_000 {
+                let key = json!(offset_delta);
+                let value = json!({"name": format!("name-{offset_delta}"), "email": format!("{offset_delta}@example.com")});
+
+                batch = batch.record(
+                    Record::builder()
+                        .offset_delta(offset_delta)
+                        .key(serde_json::to_vec(&key).map(Bytes::from).map(Into::into)?)
+                        .value(
+                            serde_json::to_vec(&value)
+                                .map(Bytes::from)
+                                .map(Into::into)?,
+                        ),
+                );
+            }
+
+            batch.build()?
+        };
+
+        let record_batches = schema.as_arrow_stream(0, &batch, 1_000)?;
+        assert_eq!(5, record_batches.len());
+
+        for record_batch in &record_batches {
+            assert_eq!(1_000, record_batch.num_rows());
+            assert_eq!(record_batches[0].schema(), record_batch.schema());
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn absent_optional_fields_as_arrow() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let topic = "def";
+
+        let schema = serde_json::to_vec(&json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "number"
+                },
+                "value": {
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                        },
+                        "email": {
+                            "type": "string",
+                            "format": "email"
+                        }
+                    }
+                }
+            }
+        }))
+        .map_err(Into::into)
+        .map(Bytes::from)
+        .and_then(Schema::try_from)?;
+
+        let kv = [
+            (
+                json!(12321),
+                json!({"name": "alice", "email": "alice@example.com"}),
+            ),
+            (json!(32123), json!({"name": "bob"})),
+            (json!(45654), json!({"email": "carol@example.com"})),
+        ];
+
+        let batch = {
+            let mut batch = Batch::builder().base_timestamp(1_234_567_890 * 1_000);
+
+            for (ref key, ref value) in kv {
+                batch = batch.record(
+                    Record::builder()
+                        .key(serde_json::to_vec(key).map(Bytes::from).map(Into::into)?)
+                        .value(serde_json::to_vec(value).map(Bytes::from).map(Into::into)?),
+                );
+            }
+
+            batch.build()?
+        };
+
+        let record_batch = schema.as_arrow(0, &batch)?;
+        assert_eq!(3, record_batch.num_rows());
+
+        let ctx = SessionContext::new();
+
+        _ = ctx.register_batch(topic, record_batch)?;
+        let df = ctx
+            .sql(
+                format!(
+                    "select key, value['name'] as name, value['email'] as email from {topic} order by key"
+                )
+                .as_str(),
+            )
+            .await?;
+        let results = df.collect().await?;
+
+        let pretty_results = pretty_format_batches(&results)?.to_string();
+
+        let expected = vec![
+            "+-------+-------+-------------------+",
+            "| key   | name  | email             |",
+            "+-------+-------+-------------------+",
+            "| 12321 | alice | alice@example.com |",
+            "| 32123 | bob   |                   |",
+            "| 45654 |       | carol@example.com |",
+            "+-------+-------+-------------------+",
+        ];
+
+        assert_eq!(pretty_results.trim().lines().collect::<Vec<_>>(), expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn array_of_objects_omitting_fields() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let schema = serde_json::to_vec(&json!({
+            "type": "object",
+            "properties": {
+                "value": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "quantity": {
+                                "type": "integer",
+                            },
+                            "location": {
+                                "type": "string",
+                            }
+                        }
+                    }
+                }
+            }
+        }))
+        .map_err(Into::into)
+        .map(Bytes::from)
+        .and_then(Schema::try_from)?;
+
+        let values = [
+            json!([{"quantity": 6, "location": "abc"}, {"quantity": 11}]),
+            json!([{"location": "xyz"}]),
+            json!([]),
+        ];
+
+        let batch = {
+            let mut batch = Batch::builder().base_timestamp(1_234_567_890 * 1_000);
+
+            for ref value in values {
+                batch = batch.record(
+                    Record::builder()
+                        .value(serde_json::to_vec(value).map(Bytes::from).map(Into::into)?),
+                );
+            }
+
+            batch.build()?
+        };
+
+        let record_batch = schema.as_arrow(0, &batch)?;
+        assert_eq!(3, record_batch.num_rows());
+
+        let lists = record_batch
+            .column_by_name(MessageKind::Value.as_ref())
+            .map(|column| column.as_list::<i32>())
+            .ok_or(Error::Downcast)?;
+
+        assert_eq!(&[0, 2, 3, 3], lists.value_offsets());
+
+        let elements = lists.values().as_struct();
+
+        let quantity = elements
+            .column_by_name("quantity")
+            .and_then(|column| column.as_any().downcast_ref::<Int64Array>())
+            .ok_or(Error::Downcast)?;
+        assert_eq!(
+            vec![Some(6), Some(11), None],
+            quantity.iter().collect::<Vec<_>>()
+        );
+
+        let location = elements
+            .column_by_name("location")
+            .map(|column| column.as_string::<i32>())
+            .ok_or(Error::Downcast)?;
+        assert_eq!(
+            vec![Some("abc"), None, Some("xyz")],
+            location.iter().collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn date_time_format_as_timestamp() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let topic = "def";
+
+        let schema = serde_json::to_vec(&json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "number"
+                },
+                "value": {
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                        },
+                        "created": {
+                            "type": "string",
+                            "format": "date-time"
+                        }
+                    }
+                }
+            }
+        }))
+        .map_err(Into::into)
+        .map(Bytes::from)
+        .and_then(Schema::try_from)?;
+
+        let kv = [
+            (
+                json!(12321),
+                json!({"name": "alice", "created": "2009-02-13T23:31:30Z"}),
+            ),
+            (
+                json!(32123),
+                json!({"name": "bob", "created": "2009-02-14T01:31:30+02:00"}),
+            ),
+        ];
+
+        let batch = {
+            let mut batch = Batch::builder().base_timestamp(1_234_567_890 * 1_000);
+
+            for (ref key, ref value) in kv {
+                batch = batch.record(
+                    Record::builder()
+                        .key(serde_json::to_vec(key).map(Bytes::from).map(Into::into)?)
+                        .value(serde_json::to_vec(value).map(Bytes::from).map(Into::into)?),
+                );
+            }
+
+            batch.build()?
+        };
+
+        let record_batch = schema.as_arrow(0, &batch)?;
+
+        let ctx = SessionContext::new();
+
+        _ = ctx.register_batch(topic, record_batch)?;
+        let df = ctx
+            .sql(
+                format!(
+                    "select key, arrow_typeof(value['created']) as t, value['created'] as created from {topic} order by key"
+                )
+                .as_str(),
+            )
+            .await?;
+        let results = df.collect().await?;
+
+        let pretty_results = pretty_format_batches(&results)?.to_string();
+
+        let expected = vec![
+            "+-------+------------------------------+---------------------+",
+            "| key   | t                            | created             |",
+            "+-------+------------------------------+---------------------+",
+            "| 12321 | Timestamp(Microsecond, None) | 2009-02-13T23:31:30 |",
+            "| 32123 | Timestamp(Microsecond, None) | 2009-02-13T23:31:30 |",
+            "+-------+------------------------------+---------------------+",
+        ];
+
+        assert_eq!(pretty_results.trim().lines().collect::<Vec<_>>(), expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn enum_as_dictionary() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let topic = "def";
+
+        let encoded = serde_json::to_vec(&json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "number"
+                },
+                "value": {
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                        },
+                        "status": {
+                            "type": "string",
+                            "enum": ["active", "suspended"]
+                        }
+                    }
+                }
+            }
+        }))
+        .map(Bytes::from)?;
+
+        let kv = [
+            (json!(12321), json!({"name": "alice", "status": "active"})),
+            (json!(32123), json!({"name": "bob", "status": "suspended"})),
+            (json!(45654), json!({"name": "carol", "status": "active"})),
+        ];
+
+        let batch = {
+            let mut batch = Batch::builder();
+
+            for (ref key, ref value) in kv {
+                batch = batch.record(
+                    Record::builder()
+                        .key(serde_json::to_vec(key).map(Bytes::from).map(Into::into)?)
+                        .value(serde_json::to_vec(value).map(Bytes::from).map(Into::into)?),
+                );
+            }
+
+            batch.build()?
+        };
+
+        // plain strings unless enabled
+        //
+        let record_batch = Schema::try_from(encoded.clone())?.as_arrow(0, &batch)?;
+
+        assert!(matches!(
+            record_batch.column_by_name("value").map(|value| value.data_type()),
+            Some(DataType::Struct(fields))
+                if fields.find("status").is_some_and(|(_, field)| *field.data_type() == DataType::Utf8)
+        ));
+
+        let record_batch = Schema::try_from(encoded)?
+            .with_dictionary_enums(true)
+            .as_arrow(0, &batch)?;
+
+        let ctx = SessionContext::new();
+
+        _ = ctx.register_batch(topic, record_batch)?;
+        let df = ctx
+            .sql(
+                format!(
+                    "select key, arrow_typeof(value['status']) as t, value['status'] as status from {topic} order by key"
+                )
+                .as_str(),
+            )
+            .await?;
+        let results = df.collect().await?;
+
+        let pretty_results = pretty_format_batches(&results)?.to_string();
+
+        let expected = vec![
+            "+-------+--------------------------+-----------+",
+            "| key   | t                        | status    |",
+            "+-------+--------------------------+-----------+",
+            "| 12321 | Dictionary(UInt32, Utf8) | active    |",
+            "| 32123 | Dictionary(UInt32, Utf8) | suspended |",
+            "| 45654 | Dictionary(UInt32, Utf8) | active    |",
+            "+-------+--------------------------+-----------+",
+        ];
+
+        assert_eq!(pretty_results.trim().lines().collect::<Vec<_>>(), expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn integer_and_float_widen_to_float() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let topic = "def";
+
+        let schema = serde_json::to_vec(&json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "number"
+                },
+                "value": {
+                    "type": "object",
+                    "properties": {
+                        "amount": {
+                            "type": "number",
+                        }
+                    }
                 }
             }
         }))
@@ -1418,41 +3207,94 @@ mod tests {
         .map(Bytes::from)
         .and_then(Schema::try_from)?;
 
+        let kv = [
+            (json!(12321), json!({"amount": 1})),
+            (json!(32123), json!({"amount": -2})),
+            (json!(45654), json!({"amount": 3.5})),
+        ];
+
+        let batch = {
+            let mut batch = Batch::builder();
+
+            for (ref key, ref value) in kv {
+                batch = batch.record(
+                    Record::builder()
+                        .key(serde_json::to_vec(key).map(Bytes::from).map(Into::into)?)
+                        .value(serde_json::to_vec(value).map(Bytes::from).map(Into::into)?),
+                );
+            }
+
+            batch.build()?
+        };
+
+        let record_batch = schema.as_arrow(0, &batch)?;
+
+        let ctx = SessionContext::new();
+
+        _ = ctx.register_batch(topic, record_batch)?;
+        let df = ctx
+            .sql(
+                format!(
+                    "select key, arrow_typeof(value['amount']) as t, value['amount'] as amount from {topic} order by key"
+                )
+                .as_str(),
+            )
+            .await?;
+        let results = df.collect().await?;
+
+        let pretty_results = pretty_format_batches(&results)?.to_string();
+
+        let expected = vec![
+            "+-------+---------+--------+",
+            "| key   | t       | amount |",
+            "+-------+---------+--------+",
+            "| 12321 | Float64 | 1.0    |",
+            "| 32123 | Float64 | -2.0   |",
+            "| 45654 | Float64 | 3.5    |",
+            "+-------+---------+--------+",
+        ];
+
+        assert_eq!(pretty_results.trim().lines().collect::<Vec<_>>(), expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn signed_and_unsigned_widen_to_float() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let schema = Schema::try_from(Bytes::from_static(b"{}"))?;
+
         assert_eq!(
-            BasicOutput::Valid(VecDeque::new()),
-            schema
-                .value
-                .as_ref()
-                .unwrap()
-                .apply(&json!([1, 2, 3, 4, 5]))
-                .basic()
+            DataType::Int64,
+            schema.common_data_type(&["value"], &[json!(1), json!(-2)])?
         );
 
         assert_eq!(
-            BasicOutput::Valid(VecDeque::new()),
-            schema
-                .value
-                .as_ref()
-                .unwrap()
-                .apply(&json!([3, "different", { "types": "of values" }]))
-                .basic()
+            DataType::UInt64,
+            schema.common_data_type(&["value"], &[json!(u64::MAX)])?
+        );
+
+        assert_eq!(
+            DataType::Float64,
+            schema.common_data_type(&["value"], &[json!(u64::MAX), json!(-2)])?
+        );
+
+        assert_eq!(
+            DataType::Float64,
+            schema.common_data_type(&["value"], &[json!(u64::MAX), json!(3.5)])?
         );
 
         assert!(matches!(
-            schema
-                .value
-                .as_ref()
-                .unwrap()
-                .apply(&json!({"Not": "an array"}))
-                .basic(),
-            BasicOutput::Invalid(_)
+            schema.common_data_type(&["value"], &[json!(1), json!("abc")]),
+            Err(Error::NoCommonType(_))
         ));
 
         Ok(())
     }
 
     #[test]
-    fn schema_basic_output() -> Result<()> {
+    fn batches_as_parquet() -> Result<()> {
         let _guard = init_tracing()?;
 
         let schema = serde_json::to_vec(&json!({
@@ -1479,54 +3321,86 @@ mod tests {
         .map(Bytes::from)
         .and_then(Schema::try_from)?;
 
-        debug!(?schema);
+        let batch = |kv: &[(Value, Value)]| -> Result<Batch> {
+            kv.iter()
+                .try_fold(Batch::builder(), |batch, (key, value)| {
+                    Ok::<_, Error>(
+                        batch.record(
+                            Record::builder()
+                                .key(serde_json::to_vec(key).map(Bytes::from).map(Into::into)?)
+                                .value(serde_json::to_vec(value).map(Bytes::from).map(Into::into)?),
+                        ),
+                    )
+                })?
+                .build()
+                .map_err(Into::into)
+        };
 
-        assert_eq!(
-            BasicOutput::Valid(VecDeque::new()),
-            schema.key.as_ref().unwrap().apply(&json!(12321)).basic()
-        );
+        let batches = [
+            batch(&[
+                (
+                    json!(12321),
+                    json!({"name": "alice", "email": "alice@example.com"}),
+                ),
+                (
+                    json!(32123),
+                    json!({"name": "bob", "email": "bob@example.com"}),
+                ),
+            ])?,
+            batch(&[(
+                json!(45654),
+                json!({"name": "carol", "email": "carol@example.com"}),
+            )])?,
+        ];
 
-        match schema
-            .value
-            .as_ref()
-            .unwrap()
-            .apply(&json!({"name": "alice", "email": "alice@example.com"}))
-            .basic()
-        {
-            BasicOutput::Valid(annotations) => {
-                debug!(?annotations);
-                assert_eq!(1, annotations.len());
-                assert_eq!(
-                    &Value::Array(vec![
-                        Value::String("email".into()),
-                        Value::String("name".into())
-                    ]),
-                    annotations[0].value().deref()
-                );
+        let written = schema.as_parquet(0, &batches[..], Compression::SNAPPY, Vec::new())?;
 
-                for annotation in annotations {
-                    debug!(
-                        "value: {} at path {}",
-                        annotation.value(),
-                        annotation.instance_location()
-                    )
-                }
-            }
-            BasicOutput::Invalid(errors) => {
-                debug!(?errors);
-                panic!()
-            }
-        }
+        let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(written))?;
+
+        assert_eq!(3, builder.metadata().file_metadata().num_rows());
+        assert!(builder.metadata().row_groups().iter().all(|row_group| {
+            row_group
+                .columns()
+                .iter()
+                .all(|column| column.compression() == Compression::SNAPPY)
+        }));
+
+        let expected = schema
+            .as_arrow(0, &batches[0])?
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().to_owned())
+            .collect::<Vec<_>>();
+
+        let read = builder
+            .build()?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(Error::from)?;
+
+        assert!(read.iter().all(|record_batch| {
+            record_batch
+                .schema()
+                .fields()
+                .iter()
+                .map(|field| field.name().to_owned())
+                .collect::<Vec<_>>()
+                == expected
+        }));
+        assert_eq!(
+            3,
+            read.iter()
+                .map(|record_batch| record_batch.num_rows())
+                .sum::<usize>()
+        );
 
         Ok(())
     }
 
-    #[tokio::test]
-    async fn key_and_value_as_arrow() -> Result<()> {
+    #[test]
+    fn batch_as_ndjson() -> Result<()> {
         let _guard = init_tracing()?;
 
-        let topic = "def";
-
         let schema = serde_json::to_vec(&json!({
             "type": "object",
             "properties": {
@@ -1538,10 +3412,6 @@ mod tests {
                     "properties": {
                         "name": {
                             "type": "string",
-                        },
-                        "email": {
-                            "type": "string",
-                            "format": "email"
                         }
                     }
                 }
@@ -1552,53 +3422,37 @@ mod tests {
         .and_then(Schema::try_from)?;
 
         let kv = [
-            (
-                json!(12321),
-                json!({"name": "alice", "email": "alice@example.com"}),
-            ),
-            (
-                json!(32123),
-                json!({"name": "bob", "email": "bob@example.com"}),
-            ),
+            (json!(12321), json!({"name": "alice"})),
+            (json!(32123), json!({"name": "bob"})),
+            (json!(45654), json!({"name": "carol"})),
         ];
 
-        let batch = {
-            let mut batch = Batch::builder().base_timestamp(1_234_567_890 * 1_000);
-
-            for (ref key, ref value) in kv {
-                batch = batch.record(
-                    Record::builder()
-                        .key(serde_json::to_vec(key).map(Bytes::from).map(Into::into)?)
-                        .value(serde_json::to_vec(value).map(Bytes::from).map(Into::into)?),
-                );
-            }
-
-            batch.build()?
-        };
-
-        let record_batch = schema.as_arrow(0, &batch)?;
-        let data_files = iceberg_write(record_batch.clone()).await?;
-        assert_eq!(1, data_files.len());
-        assert_eq!(2, data_files[0].record_count());
-
-        let ctx = SessionContext::new();
+        let batch = kv
+            .iter()
+            .try_fold(Batch::builder(), |batch, (key, value)| {
+                Ok::<_, Error>(
+                    batch.record(
+                        Record::builder()
+                            .key(serde_json::to_vec(key).map(Bytes::from).map(Into::into)?)
+                            .value(serde_json::to_vec(value).map(Bytes::from).map(Into::into)?),
+                    ),
+                )
+            })?
+            .build()?;
 
-        _ = ctx.register_batch(topic, record_batch)?;
-        let df = ctx.sql(format!("select * from {topic}").as_str()).await?;
-        let results = df.collect().await?;
+        let mut ndjson = Vec::new();
+        schema.as_ndjson(&batch, &mut ndjson)?;
 
-        let pretty_results = pretty_format_batches(&results)?.to_string();
+        let lines = String::from_utf8(ndjson)?
+            .lines()
+            .map(serde_json::from_str::<Value>)
+            .collect::<Result<Vec<_>, _>>()?;
 
-        let expected = vec![
-            "+-------------------------------------------------------------------------------------+-------+-----------------------------------------+",
-            "| meta                                                                                | key   | value                                   |",
-            "+-------------------------------------------------------------------------------------+-------+-----------------------------------------+",
-            "| {day: 13, month: 2, partition: 0, timestamp: 2009-02-13T23:31:30+00:00, year: 2009} | 12321 | {email: alice@example.com, name: alice} |",
-            "| {day: 13, month: 2, partition: 0, timestamp: 2009-02-13T23:31:30+00:00, year: 2009} | 32123 | {email: bob@example.com, name: bob}     |",
-            "+-------------------------------------------------------------------------------------+-------+-----------------------------------------+",
-        ];
+        assert_eq!(batch.records.len(), lines.len());
 
-        assert_eq!(pretty_results.trim().lines().collect::<Vec<_>>(), expected);
+        for ((key, value), line) in kv.iter().zip(lines.iter()) {
+            assert_eq!(&json!({"key": key, "value": value}), line);
+        }
 
         Ok(())
     }
@@ -2052,4 +3906,110 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn additional_properties_as_map() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let topic = "def";
+
+        let schema = serde_json::to_vec(&json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "number"
+                },
+                "value": {
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                        },
+                        "scores": {
+                            "type": "object",
+                            "additionalProperties": {
+                                "type": "number"
+                            }
+                        }
+                    }
+                }
+            }
+        }))
+        .map_err(Into::into)
+        .map(Bytes::from)
+        .and_then(Schema::try_from)?;
+
+        let kv = [
+            (
+                json!(12321),
+                json!({"name": "alice", "scores": {"maths": 87, "physics": 91.5}}),
+            ),
+            (
+                json!(32123),
+                json!({"name": "bob", "scores": {"history": 72}}),
+            ),
+            (json!(45654), json!({"name": "carol", "scores": {}})),
+        ];
+
+        let batch = {
+            let mut batch = Batch::builder().base_timestamp(1_234_567_890 * 1_000);
+
+            for (ref key, ref value) in kv {
+                batch = batch.record(
+                    Record::builder()
+                        .key(serde_json::to_vec(key).map(Bytes::from).map(Into::into)?)
+                        .value(serde_json::to_vec(value).map(Bytes::from).map(Into::into)?),
+                );
+            }
+
+            batch.build()?
+        };
+
+        let record_batch = schema.as_arrow(0, &batch)?;
+
+        // the keys are dynamic, with a common value type
+        //
+        assert!(matches!(
+            record_batch.column_by_name("value").map(|value| value.data_type()),
+            Some(DataType::Struct(fields))
+                if fields.find("scores").is_some_and(|(_, field)| matches!(
+                    field.data_type(),
+                    DataType::Map(entries, false)
+                        if matches!(
+                            entries.data_type(),
+                            DataType::Struct(fields)
+                                if fields.iter().map(|field| field.data_type()).collect::<Vec<_>>()
+                                    == [&DataType::Utf8, &DataType::Float64]
+                        )
+                ))
+        ));
+
+        let data_files = iceberg_write(record_batch.clone()).await?;
+        assert_eq!(1, data_files.len());
+        assert_eq!(3, data_files[0].record_count());
+
+        let ctx = SessionContext::new();
+
+        _ = ctx.register_batch(topic, record_batch)?;
+        let df = ctx
+            .sql(format!("select key, value from {topic} order by key").as_str())
+            .await?;
+        let results = df.collect().await?;
+
+        let pretty_results = pretty_format_batches(&results)?.to_string();
+
+        let expected = vec![
+            "+-------+-----------------------------------------------------+",
+            "| key   | value                                               |",
+            "+-------+-----------------------------------------------------+",
+            "| 12321 | {name: alice, scores: {maths: 87.0, physics: 91.5}} |",
+            "| 32123 | {name: bob, scores: {history: 72.0}}                |",
+            "| 45654 | {name: carol, scores: {}}                           |",
+            "+-------+-----------------------------------------------------+",
+        ];
+
+        assert_eq!(pretty_results.trim().lines().collect::<Vec<_>>(), expected);
+
+        Ok(())
+    }
 }
@@ -21,10 +21,11 @@ use std::{
 };
 
 use crate::{Error, Result, lake::LakeHouse};
-use arrow::array::RecordBatch;
+use arrow::{array::RecordBatch, compute::cast};
 use async_trait::async_trait;
 use iceberg::{
     Catalog, NamespaceIdent, TableCreation, TableIdent,
+    arrow::schema_to_arrow_schema,
     io::{FileIOBuilder, S3_ACCESS_KEY_ID, S3_ENDPOINT, S3_REGION, S3_SECRET_ACCESS_KEY},
     spec::{DataFileFormat, Schema},
     table::Table,
@@ -48,6 +49,21 @@ use uuid::Uuid;
 
 use super::House;
 
+/// Cast the columns of a record batch to the arrow schema of a table,
+/// dropping any field metadata that is not part of the iceberg schema,
+/// e.g., the avro fullname of a named type.
+pub(crate) fn conform(record_batch: RecordBatch, schema: &Schema) -> Result<RecordBatch> {
+    let schema = schema_to_arrow_schema(schema).map(Arc::new)?;
+
+    record_batch
+        .columns()
+        .iter()
+        .zip(schema.fields())
+        .map(|(column, field)| cast(column, field.data_type()).map_err(Into::into))
+        .collect::<Result<Vec<_>>>()
+        .and_then(|columns| RecordBatch::try_new(schema, columns).map_err(Into::into))
+}
+
 fn env_mapping(k: &str) -> Option<&str> {
     match k {
         "AWS_ACCESS_KEY_ID" => Some(S3_ACCESS_KEY_ID),
@@ -273,7 +289,7 @@ impl LakeHouse for Iceberg {
             .inspect_err(|err| error!(?err))?;
 
         data_file_writer
-            .write(record_batch)
+            .write(conform(record_batch, table.metadata().current_schema())?)
             .await
             .inspect_err(|err| debug!(?err))?;
 
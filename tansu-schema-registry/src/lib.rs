@@ -14,20 +14,30 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     env::{self},
-    io,
+    io::{self, Write},
     num::TryFromIntError,
     result,
     string::FromUtf8Error,
-    sync::{Arc, LazyLock, Mutex, PoisonError},
+    sync::{
+        Arc, LazyLock, Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
     time::SystemTime,
 };
 
-use arrow::{datatypes::DataType, error::ArrowError, record_batch::RecordBatch};
+use arrow::{
+    array::{ArrayRef, BinaryBuilder, ListBuilder, StringBuilder, StructBuilder},
+    datatypes::{DataType, Field, Fields, Schema as ArrowSchema},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
 use bytes::Bytes;
 use datafusion::error::DataFusionError;
 use deltalake::DeltaTableError;
+use futures::TryStreamExt;
 use iceberg::spec::DataFileBuilderError;
 use jsonschema::ValidationError;
 use object_store::{
@@ -39,10 +49,18 @@ use opentelemetry::{
     metrics::{Counter, Histogram, Meter},
 };
 use opentelemetry_semantic_conventions::SCHEMA_URL;
-use parquet::errors::ParquetError;
-use serde_json::Value;
-use tansu_kafka_sans_io::{ErrorCode, record::inflated::Batch};
-use tracing::{debug, error};
+use parquet::{
+    arrow::{ArrowWriter, PARQUET_FIELD_ID_META_KEY},
+    basic::Compression,
+    errors::ParquetError,
+    file::properties::WriterProperties,
+};
+use serde_json::{Value, json};
+use tansu_kafka_sans_io::{
+    ErrorCode,
+    record::{Record, inflated::Batch},
+};
+use tracing::{debug, error, warn};
 use tracing_subscriber::filter::ParseError;
 use url::Url;
 
@@ -54,6 +72,9 @@ pub(crate) mod sql;
 
 pub(crate) const ARROW_LIST_FIELD_NAME: &str = "element";
 
+/// The name of the optional column of Kafka record headers.
+pub const HEADERS_COLUMN_NAME: &str = "headers";
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("{:?}", self)]
@@ -74,12 +95,18 @@ pub enum Error {
     #[error("{:?}", self)]
     BadDowncast { field: String },
 
+    #[error("{:?}", self)]
+    Base64(#[from] base64::DecodeError),
+
     #[error("{:?}", self)]
     BuilderExhausted,
 
     #[error("{:?}", self)]
     ChronoParse(#[from] chrono::ParseError),
 
+    #[error("{:?}", self)]
+    ConflictingSchema(Vec<Path>),
+
     #[error("{:?}", self)]
     DataFileBuilder(#[from] DataFileBuilderError),
 
@@ -98,6 +125,9 @@ pub enum Error {
     #[error("{:?}", self)]
     Iceberg(#[from] ::iceberg::Error),
 
+    #[error("topic: {topic}, incompatible schema: {reason}")]
+    IncompatibleSchema { topic: String, reason: String },
+
     #[error("{:?}", self)]
     InvalidValue(apache_avro::types::Value),
 
@@ -114,9 +144,19 @@ pub enum Error {
         field: String,
     },
 
+    #[error("unknown fields: {fields:?}, in: {value} with schema: {schema}")]
+    JsonToAvroUnknownFields {
+        schema: Box<apache_avro::Schema>,
+        value: Box<serde_json::Value>,
+        fields: Vec<String>,
+    },
+
     #[error("{:?}", self)]
     KafkaSansIo(#[from] tansu_kafka_sans_io::Error),
 
+    #[error("field: {field}, is nested beyond the maximum depth")]
+    MaxDepthExceeded { field: String },
+
     #[error("{:?}", self)]
     Message(String),
 
@@ -150,6 +190,12 @@ pub enum Error {
     #[error("{:?}", self)]
     ProtobufFileDescriptorMissing(Bytes),
 
+    #[error("topic: {topic}, has no schema")]
+    SchemaNotFound { topic: String },
+
+    #[error("topic: {topic}, schema: {source}")]
+    SchemaParse { topic: String, source: Box<Error> },
+
     #[error("{:?}", self)]
     SchemaValidation,
 
@@ -162,6 +208,9 @@ pub enum Error {
     #[error("{:?}", self)]
     TryFromInt(#[from] TryFromIntError),
 
+    #[error("{:?}", self)]
+    UnexpectedMapEntries(DataType),
+
     #[error("{:?}", self)]
     UnsupportedIcebergCatalogUrl(Url),
 
@@ -199,11 +248,55 @@ impl From<ValidationError<'_>> for Error {
 pub type Result<T, E = Error> = result::Result<T, E>;
 
 pub trait Validator {
-    fn validate(&self, batch: &Batch) -> Result<()>;
+    /// Validate the records of a batch, failing on the first record
+    /// that is invalid.
+    fn validate(&self, batch: &Batch) -> Result<()> {
+        batch
+            .records
+            .iter()
+            .try_for_each(|record| self.validate_record(record))
+    }
+
+    /// Validate every record of a batch, collecting the index and error
+    /// of each record that is invalid.
+    fn validate_collect(&self, batch: &Batch) -> Result<(), Vec<(usize, Error)>> {
+        let errors = batch
+            .records
+            .iter()
+            .enumerate()
+            .filter_map(|(index, record)| {
+                self.validate_record(record).err().map(|err| (index, err))
+            })
+            .collect::<Vec<_>>();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate a single record.
+    fn validate_record(&self, record: &Record) -> Result<()>;
 }
 
 pub trait AsArrow {
     fn as_arrow(&self, partition: i32, batch: &Batch) -> Result<RecordBatch>;
+
+    /// As arrow, emitting record batches of at most `max_rows` rows
+    /// that share the same schema.
+    fn as_arrow_stream(
+        &self,
+        partition: i32,
+        batch: &Batch,
+        max_rows: usize,
+    ) -> Result<Vec<RecordBatch>>;
+}
+
+pub trait ValidateAsArrow {
+    /// Validate a batch while converting it into Arrow, decoding each
+    /// record once, with an invalid record failing the whole batch.
+    fn validate_as_arrow(&self, partition: i32, batch: &Batch) -> Result<RecordBatch>;
 }
 
 pub trait AsKafkaRecord {
@@ -211,7 +304,151 @@ pub trait AsKafkaRecord {
 }
 
 pub trait AsJsonValue {
-    fn as_json_value(&self, batch: &Batch) -> Result<Value>;
+    /// The records of a batch as a JSON array.
+    fn as_json_value(&self, batch: &Batch) -> Result<Value> {
+        batch
+            .records
+            .iter()
+            .map(|record| self.record_as_json_value(record))
+            .collect::<Result<Vec<_>>>()
+            .map(Value::Array)
+    }
+
+    /// A single record as a JSON object.
+    fn record_as_json_value(&self, record: &Record) -> Result<Value>;
+}
+
+pub trait AsNdJson {
+    /// Write the records of a batch as newline delimited JSON, one
+    /// object per line, without buffering the whole batch.
+    fn as_ndjson(&self, batch: &Batch, w: impl Write) -> Result<()>;
+}
+
+impl<T> AsNdJson for T
+where
+    T: AsJsonValue,
+{
+    fn as_ndjson(&self, batch: &Batch, mut w: impl Write) -> Result<()> {
+        debug!(records = batch.records.len());
+
+        for record in &batch.records {
+            self.record_as_json_value(record)
+                .and_then(|value| serde_json::to_writer(&mut w, &value).map_err(Into::into))
+                .and_then(|()| w.write_all(b"\n").map_err(Into::into))?;
+        }
+
+        w.flush().map_err(Into::into)
+    }
+}
+
+pub trait AsParquet {
+    /// Write batches as Parquet, appending each batch as arrow to the
+    /// same file, returning the writer once the file is complete.
+    fn as_parquet<W>(
+        &self,
+        partition: i32,
+        batches: &[Batch],
+        compression: Compression,
+        writer: W,
+    ) -> Result<W>
+    where
+        W: Write + Send;
+}
+
+impl<T> AsParquet for T
+where
+    T: AsArrow,
+{
+    fn as_parquet<W>(
+        &self,
+        partition: i32,
+        batches: &[Batch],
+        compression: Compression,
+        writer: W,
+    ) -> Result<W>
+    where
+        W: Write + Send,
+    {
+        debug!(partition, batches = batches.len(), ?compression);
+
+        let mut batches = batches.iter();
+
+        let first = batches
+            .next()
+            .ok_or(Error::BuilderExhausted)
+            .and_then(|batch| self.as_arrow(partition, batch))?;
+
+        let properties = WriterProperties::builder()
+            .set_compression(compression)
+            .build();
+
+        let mut writer = ArrowWriter::try_new(writer, first.schema(), Some(properties))?;
+        writer.write(&first)?;
+
+        for batch in batches {
+            self.as_arrow(partition, batch)
+                .and_then(|record_batch| writer.write(&record_batch).map_err(Into::into))?;
+        }
+
+        writer.into_inner().map_err(Into::into)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum WireFormat {
+    #[default]
+    Embedded,
+    Confluent,
+}
+
+pub(crate) const CONFLUENT_MAGIC: u8 = 0;
+
+/// Split a Confluent framed datum into the schema id of the frame and
+/// the remaining datum.
+pub(crate) fn confluent_wire_format(encoded: &[u8]) -> Result<(u32, &[u8])> {
+    match encoded {
+        [CONFLUENT_MAGIC, a, b, c, d, datum @ ..] => {
+            Ok((u32::from_be_bytes([*a, *b, *c, *d]), datum))
+        }
+
+        otherwise => {
+            debug!(?otherwise);
+            Err(Error::Api(ErrorCode::InvalidRecord))
+        }
+    }
+}
+
+/// The compatibility required between a new schema for a topic and its
+/// existing schema.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Compatibility {
+    /// data written with the existing schema can be read with the new
+    #[default]
+    Backward,
+
+    /// data written with the new schema can be read with the existing
+    Forward,
+
+    /// both backward and forward
+    Full,
+}
+
+impl Compatibility {
+    /// The first incompatibility found by checking whether data written
+    /// with one schema can be read by another, in each direction
+    /// required.
+    pub(crate) fn incompatibility<T: Copy>(
+        self,
+        existing: T,
+        proposed: T,
+        can_read: impl Fn(T, T) -> Option<String>,
+    ) -> Option<String> {
+        match self {
+            Self::Backward => can_read(existing, proposed),
+            Self::Forward => can_read(proposed, existing),
+            Self::Full => can_read(existing, proposed).or_else(|| can_read(proposed, existing)),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -221,6 +458,42 @@ pub enum Schema {
     Proto(Box<proto::Schema>),
 }
 
+impl Schema {
+    /// The reason that this schema is incompatible with the existing
+    /// schema, Protocol Buffers are not checked.
+    pub fn incompatibility(
+        &self,
+        existing: &Schema,
+        compatibility: Compatibility,
+    ) -> Option<String> {
+        match (existing, self) {
+            (Self::Avro(existing), Self::Avro(proposed)) => {
+                proposed.incompatibility(existing, compatibility)
+            }
+
+            (Self::Json(existing), Self::Json(proposed)) => {
+                proposed.incompatibility(existing, compatibility)
+            }
+
+            (Self::Proto(_), Self::Proto(_)) => None,
+
+            (existing, proposed) => Some(format!(
+                "format changed from: {} to: {}",
+                existing.format(),
+                proposed.format()
+            )),
+        }
+    }
+
+    fn format(&self) -> &str {
+        match self {
+            Self::Avro(_) => "avro",
+            Self::Json(_) => "json",
+            Self::Proto(_) => "proto",
+        }
+    }
+}
+
 impl AsKafkaRecord for Schema {
     fn as_kafka_record(&self, value: &Value) -> Result<tansu_kafka_sans_io::record::Builder> {
         debug!(?value);
@@ -234,13 +507,13 @@ impl AsKafkaRecord for Schema {
 }
 
 impl Validator for Schema {
-    fn validate(&self, batch: &Batch) -> Result<()> {
-        debug!(?batch);
+    fn validate_record(&self, record: &Record) -> Result<()> {
+        debug!(?record);
 
         match self {
-            Self::Avro(schema) => schema.validate(batch),
-            Self::Json(schema) => schema.validate(batch),
-            Self::Proto(schema) => schema.validate(batch),
+            Self::Avro(schema) => schema.validate_record(record),
+            Self::Json(schema) => schema.validate_record(record),
+            Self::Proto(schema) => schema.validate_record(record),
         }
     }
 }
@@ -255,6 +528,33 @@ impl AsArrow for Schema {
             Self::Proto(schema) => schema.as_arrow(partition, batch),
         }
     }
+
+    fn as_arrow_stream(
+        &self,
+        partition: i32,
+        batch: &Batch,
+        max_rows: usize,
+    ) -> Result<Vec<RecordBatch>> {
+        debug!(?batch, max_rows);
+
+        match self {
+            Self::Avro(schema) => schema.as_arrow_stream(partition, batch, max_rows),
+            Self::Json(schema) => schema.as_arrow_stream(partition, batch, max_rows),
+            Self::Proto(schema) => schema.as_arrow_stream(partition, batch, max_rows),
+        }
+    }
+}
+
+impl ValidateAsArrow for Schema {
+    fn validate_as_arrow(&self, partition: i32, batch: &Batch) -> Result<RecordBatch> {
+        debug!(?batch);
+
+        match self {
+            Self::Avro(schema) => schema.validate_as_arrow(partition, batch),
+            Self::Json(schema) => schema.validate_as_arrow(partition, batch),
+            Self::Proto(schema) => schema.validate_as_arrow(partition, batch),
+        }
+    }
 }
 
 impl AsJsonValue for Schema {
@@ -267,12 +567,39 @@ impl AsJsonValue for Schema {
             Self::Proto(schema) => schema.as_json_value(batch),
         }
     }
+
+    fn record_as_json_value(&self, record: &Record) -> Result<Value> {
+        debug!(?record);
+
+        match self {
+            Self::Avro(schema) => schema.record_as_json_value(record),
+            Self::Json(schema) => schema.record_as_json_value(record),
+            Self::Proto(schema) => schema.record_as_json_value(record),
+        }
+    }
+}
+
+/// The location of the Avro schema for a topic.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) enum AvroSchemaPaths {
+    /// `topic.avsc`: a record with optional `key` and `value` fields
+    Combined(Path),
+
+    /// `topic/key.avsc` and/or `topic/value.avsc`: each containing
+    /// only the schema of the key or value
+    Separate {
+        key: Option<Path>,
+        value: Option<Path>,
+    },
 }
 
 #[derive(Clone, Debug)]
 pub struct Registry {
     object_store: Arc<DynObjectStore>,
     schemas: Arc<Mutex<BTreeMap<String, Schema>>>,
+    fetches: Arc<AtomicU64>,
+    wire_format: WireFormat,
+    headers: bool,
     validation_duration: Histogram<u64>,
     validation_error: Counter<u64>,
     as_arrow_duration: Histogram<u64>,
@@ -292,6 +619,9 @@ impl Registry {
         Self {
             object_store: Arc::new(storage),
             schemas: Arc::new(Mutex::new(BTreeMap::new())),
+            fetches: Arc::new(AtomicU64::new(0)),
+            wire_format: WireFormat::default(),
+            headers: false,
             validation_duration: METER
                 .u64_histogram("registry_validation_duration")
                 .with_unit("ms")
@@ -309,6 +639,19 @@ impl Registry {
         }
     }
 
+    pub fn wire_format(self, wire_format: WireFormat) -> Self {
+        Self {
+            wire_format,
+            ..self
+        }
+    }
+
+    /// Include the Kafka record headers as an additional column of the
+    /// Arrow output, which is off by default.
+    pub fn headers(self, headers: bool) -> Self {
+        Self { headers, ..self }
+    }
+
     pub fn as_arrow(
         &self,
         topic: &str,
@@ -328,6 +671,15 @@ impl Registry {
                     .map(|schema| schema.as_arrow(partition, batch))
                     .transpose()
             })
+            .and_then(|record_batch| {
+                if self.headers {
+                    record_batch
+                        .map(|record_batch| with_headers(record_batch, batch))
+                        .transpose()
+                } else {
+                    Ok(record_batch)
+                }
+            })
             .inspect(|record_batch| {
                 debug!(?record_batch);
 
@@ -341,12 +693,70 @@ impl Registry {
             .inspect_err(|err| debug!(?err))
     }
 
+    /// Validate a batch and convert it into Arrow in a single pass,
+    /// rather than decoding each record in both [`Registry::validate`]
+    /// and [`Registry::as_arrow`].
+    pub async fn validate_and_arrow(
+        &self,
+        topic: &str,
+        partition: i32,
+        batch: &Batch,
+    ) -> Result<RecordBatch> {
+        debug!(topic, partition, ?batch);
+
+        let start = SystemTime::now();
+
+        self.required_schema(topic)
+            .await?
+            .validate_as_arrow(partition, batch)
+            .and_then(|record_batch| {
+                if self.headers {
+                    with_headers(record_batch, batch)
+                } else {
+                    Ok(record_batch)
+                }
+            })
+            .inspect(|_| {
+                let elapsed = start
+                    .elapsed()
+                    .map_or(0, |duration| duration.as_millis() as u64);
+
+                self.validation_duration
+                    .record(elapsed, &[KeyValue::new("topic", topic.to_owned())]);
+
+                self.as_arrow_duration
+                    .record(elapsed, &[KeyValue::new("topic", topic.to_owned())]);
+            })
+            .inspect_err(|err| {
+                if matches!(err, Error::Api(ErrorCode::InvalidRecord)) {
+                    self.validation_error.add(
+                        1,
+                        &[
+                            KeyValue::new("topic", topic.to_owned()),
+                            KeyValue::new("reason", err.to_string()),
+                        ],
+                    )
+                }
+            })
+    }
+
+    /// The number of schemas fetched, whether or not from the cache.
+    pub fn fetches(&self) -> u64 {
+        self.fetches.load(Ordering::Relaxed)
+    }
+
     pub async fn schema(&self, topic: &str) -> Result<Option<Schema>> {
         debug!(?topic);
 
+        _ = self.fetches.fetch_add(1, Ordering::Relaxed);
+
         let proto = Path::from(format!("{topic}.proto"));
         let json = Path::from(format!("{topic}.json"));
-        let avro = Path::from(format!("{topic}.avsc"));
+
+        let schema_parse = |source| Error::SchemaParse {
+            topic: topic.to_owned(),
+            source: Box::new(source),
+        };
 
         if let Some(schema) = self.schemas.lock().map(|guard| guard.get(topic).cloned())? {
             Ok(Some(schema))
@@ -361,7 +771,8 @@ impl Registry {
                 .bytes()
                 .await
                 .map_err(Into::into)
-                .and_then(proto::Schema::try_from)
+                .and_then(|encoded| proto::Schema::try_from(encoded).map_err(schema_parse))
+                .map(|schema| schema.with_wire_format(self.wire_format))
                 .map(Box::new)
                 .map(Schema::Proto)
                 .and_then(|schema| {
@@ -376,7 +787,7 @@ impl Registry {
                 .bytes()
                 .await
                 .map_err(Into::into)
-                .and_then(json::Schema::try_from)
+                .and_then(|encoded| json::Schema::try_from(encoded).map_err(schema_parse))
                 .map(Arc::new)
                 .map(Schema::Json)
                 .and_then(|schema| {
@@ -386,12 +797,21 @@ impl Registry {
                         .map(|mut guard| guard.insert(topic.to_owned(), schema.clone()))
                         .and(Ok(Some(schema)))
                 })
-        } else if let Ok(get_result) = self.object_store.get(&avro).await {
-            get_result
-                .bytes()
+        } else if let Some(paths) = self.resolve_schema_paths(topic).await? {
+            let writer = self.avro_writer_schema(topic).await?;
+            let writer_ids = self.avro_writer_ids(topic).await?;
+
+            self.avro_schema(topic, paths)
                 .await
-                .map_err(Into::into)
-                .and_then(avro::Schema::try_from)
+                .map(|schema| {
+                    if let Some(writer) = writer {
+                        schema.with_writer(writer)
+                    } else {
+                        schema
+                    }
+                })
+                .map(|schema| schema.with_writer_ids(writer_ids))
+                .map(|schema| schema.with_wire_format(self.wire_format))
                 .map(Box::new)
                 .map(Schema::Avro)
                 .and_then(|schema| {
@@ -406,6 +826,194 @@ impl Registry {
         }
     }
 
+    /// Load and parse the schema of each topic into the cache, so that
+    /// the first produce to a topic doesn't pay for it. Every topic is
+    /// warmed, with the first schema that could not be parsed reported.
+    pub async fn warm(&self, topics: &[String]) -> Result<()> {
+        debug!(?topics);
+
+        let mut first = None;
+
+        for topic in topics {
+            if let Err(err) = self.schema(topic).await {
+                warn!(topic, ?err);
+                _ = first.get_or_insert(err);
+            }
+        }
+
+        first.map_or(Ok(()), Err)
+    }
+
+    /// Resolve the location of the Avro schema for a topic, in order of
+    /// precedence:
+    ///
+    /// 1. a combined `topic.avsc` record with `key` and `value` fields;
+    /// 2. separate `topic/key.avsc` and/or `topic/value.avsc` files.
+    ///
+    /// Defining a topic with both layouts is a conflict.
+    pub(crate) async fn resolve_schema_paths(
+        &self,
+        topic: &str,
+    ) -> Result<Option<AvroSchemaPaths>> {
+        let combined = self.exists(Path::from(format!("{topic}.avsc"))).await?;
+        let key = self.exists(Path::from(format!("{topic}/key.avsc"))).await?;
+        let value = self
+            .exists(Path::from(format!("{topic}/value.avsc")))
+            .await?;
+
+        debug!(topic, ?combined, ?key, ?value);
+
+        match (combined, key, value) {
+            (None, None, None) => Ok(None),
+
+            (Some(combined), None, None) => Ok(Some(AvroSchemaPaths::Combined(combined))),
+
+            (None, key, value) => Ok(Some(AvroSchemaPaths::Separate { key, value })),
+
+            (Some(combined), key, value) => Err(Error::ConflictingSchema(
+                [Some(combined), key, value].into_iter().flatten().collect(),
+            )),
+        }
+    }
+
+    async fn exists(&self, location: Path) -> Result<Option<Path>> {
+        match self.object_store.head(&location).await {
+            Ok(_) => Ok(Some(location)),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn avro_schema(&self, topic: &str, paths: AvroSchemaPaths) -> Result<avro::Schema> {
+        let schema_parse = |source| Error::SchemaParse {
+            topic: topic.to_owned(),
+            source: Box::new(source),
+        };
+
+        match paths {
+            AvroSchemaPaths::Combined(location) => {
+                let encoded = self.object_store.get(&location).await?.bytes().await?;
+                avro::Schema::try_from(encoded).map_err(schema_parse)
+            }
+
+            AvroSchemaPaths::Separate { key, value } => {
+                let mut fields = vec![];
+
+                for (name, location) in [("key", key), ("value", value)] {
+                    if let Some(location) = location {
+                        let encoded = self.object_store.get(&location).await?.bytes().await?;
+
+                        fields.push(json!({
+                            "name": name,
+                            "type": serde_json::from_slice::<Value>(&encoded[..])
+                                .map_err(Into::into)
+                                .map_err(schema_parse)?,
+                        }));
+                    }
+                }
+
+                serde_json::to_vec(&json!({
+                    "type": "record",
+                    "name": "topic",
+                    "fields": fields,
+                }))
+                .map(Bytes::from)
+                .map_err(Into::into)
+                .and_then(|encoded| avro::Schema::try_from(encoded).map_err(schema_parse))
+            }
+        }
+    }
+
+    /// The optional `topic/writer.avsc` schema that data was written
+    /// with, a record with `key` and/or `value` fields, that is
+    /// resolved against the reader schema of the topic when decoding.
+    async fn avro_writer_schema(&self, topic: &str) -> Result<Option<avro::Schema>> {
+        let Some(location) = self
+            .exists(Path::from(format!("{topic}/writer.avsc")))
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let encoded = self.object_store.get(&location).await?.bytes().await?;
+
+        avro::Schema::try_from(encoded)
+            .map(Some)
+            .map_err(|source| Error::SchemaParse {
+                topic: topic.to_owned(),
+                source: Box::new(source),
+            })
+    }
+
+    /// The optional `topic/ids/<id>.avsc` schemas that Confluent framed
+    /// data was written with, by the schema id of the frame, each a
+    /// record with `key` and/or `value` fields.
+    async fn avro_writer_ids(&self, topic: &str) -> Result<BTreeMap<u32, avro::Schema>> {
+        let prefix = Path::from(format!("{topic}/ids"));
+
+        let mut writer_ids = BTreeMap::new();
+        let mut listing = self.object_store.list(Some(&prefix));
+
+        while let Some(meta) = listing.try_next().await? {
+            let Some(id) = meta
+                .location
+                .filename()
+                .and_then(|filename| filename.strip_suffix(".avsc"))
+                .and_then(|id| id.parse::<u32>().ok())
+            else {
+                debug!(location = %meta.location);
+                continue;
+            };
+
+            let encoded = self.object_store.get(&meta.location).await?.bytes().await?;
+
+            _ = avro::Schema::try_from(encoded)
+                .map(|writer| writer_ids.insert(id, writer))
+                .map_err(|source| Error::SchemaParse {
+                    topic: topic.to_owned(),
+                    source: Box::new(source),
+                })?;
+        }
+
+        Ok(writer_ids)
+    }
+
+    /// Check that a new schema for a topic is compatible with its
+    /// existing schema, with any schema being compatible with a topic
+    /// that doesn't have one.
+    pub async fn check_compatibility(
+        &self,
+        topic: &str,
+        new_schema: &Schema,
+        compatibility: Compatibility,
+    ) -> Result<()> {
+        debug!(topic, ?compatibility);
+
+        let Some(existing) = self.schema(topic).await? else {
+            return Ok(());
+        };
+
+        new_schema
+            .incompatibility(&existing, compatibility)
+            .inspect(|reason| debug!(topic, reason))
+            .map_or(Ok(()), |reason| {
+                Err(Error::IncompatibleSchema {
+                    topic: topic.to_owned(),
+                    reason,
+                })
+            })
+    }
+
+    /// The schema for a topic, distinguishing a topic without a schema
+    /// from one with a schema that could not be parsed.
+    pub async fn required_schema(&self, topic: &str) -> Result<Schema> {
+        self.schema(topic)
+            .await?
+            .ok_or_else(|| Error::SchemaNotFound {
+                topic: topic.to_owned(),
+            })
+    }
+
     pub async fn validate(&self, topic: &str, batch: &Batch) -> Result<()> {
         debug!(%topic, ?batch);
 
@@ -436,6 +1044,192 @@ impl Registry {
                 )
             })
     }
+
+    pub async fn records_as_jsonl(
+        &self,
+        topic: &str,
+        batches: &[Batch],
+        from: i64,
+        to: i64,
+        mut writer: impl Write,
+    ) -> Result<usize> {
+        debug!(topic, from, to);
+
+        let schema = self.schema(topic).await?;
+
+        let mut lines = 0;
+
+        for batch in batches {
+            let decoded = schema
+                .as_ref()
+                .map(|schema| schema.as_json_value(batch))
+                .transpose()?;
+
+            for (index, record) in batch.records.iter().enumerate() {
+                let offset = batch.base_offset + i64::from(record.offset_delta);
+
+                if offset < from || offset >= to {
+                    continue;
+                }
+
+                let decoded = decoded.as_ref().and_then(|decoded| decoded.get(index));
+
+                let headers = record
+                    .headers
+                    .iter()
+                    .map(|header| {
+                        json!({
+                            "key": header
+                                .key
+                                .as_deref()
+                                .map(|key| String::from_utf8_lossy(key).into_owned()),
+                            "value": encoded_as_json_value(header.value.as_deref()),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                let line = json!({
+                    "offset": offset,
+                    "timestamp": batch.base_timestamp + record.timestamp_delta,
+                    "headers": headers,
+                    "key": decoded_as_json_value(decoded, "key")
+                        .unwrap_or_else(|| encoded_as_json_value(record.key.as_deref())),
+                    "value": decoded_as_json_value(decoded, "value")
+                        .unwrap_or_else(|| encoded_as_json_value(record.value.as_deref())),
+                });
+
+                debug!(%line);
+
+                serde_json::to_writer(&mut writer, &line)?;
+                writer.write_all(b"\n")?;
+                lines += 1;
+            }
+        }
+
+        writer.flush().map(|()| lines).map_err(Into::into)
+    }
+}
+
+/// Append the Kafka record headers of a batch to its Arrow output as a
+/// `List<Struct<key: Utf8, value: Binary>>` column, preserving the order
+/// and any repetition of keys, with field ids following those in use.
+pub fn with_headers(record_batch: RecordBatch, batch: &Batch) -> Result<RecordBatch> {
+    fn max_field_id(fields: &Fields) -> i32 {
+        fields
+            .iter()
+            .map(|field| {
+                let id = field
+                    .metadata()
+                    .get(PARQUET_FIELD_ID_META_KEY)
+                    .and_then(|id| id.parse::<i32>().ok())
+                    .unwrap_or_default();
+
+                let inner = match field.data_type() {
+                    DataType::Struct(fields) => max_field_id(fields),
+
+                    DataType::List(field)
+                    | DataType::LargeList(field)
+                    | DataType::FixedSizeList(field, _)
+                    | DataType::Map(field, _) => max_field_id(&Fields::from(vec![field.clone()])),
+
+                    _ => 0,
+                };
+
+                id.max(inner)
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    let id = max_field_id(record_batch.schema().fields());
+
+    let with_id = |field: Field, id: i32| {
+        field.with_metadata(HashMap::from([(
+            PARQUET_FIELD_ID_META_KEY.to_string(),
+            id.to_string(),
+        )]))
+    };
+
+    let fields = Fields::from(vec![
+        with_id(Field::new("key", DataType::Utf8, true), id + 3),
+        with_id(Field::new("value", DataType::Binary, true), id + 4),
+    ]);
+
+    let mut builder = ListBuilder::new(StructBuilder::new(
+        fields.clone(),
+        vec![
+            Box::new(StringBuilder::new()),
+            Box::new(BinaryBuilder::new()),
+        ],
+    ))
+    .with_field(Arc::new(with_id(
+        Field::new(ARROW_LIST_FIELD_NAME, DataType::Struct(fields), true),
+        id + 2,
+    )));
+
+    for record in &batch.records {
+        let values = builder.values();
+
+        for header in &record.headers {
+            values
+                .field_builder::<StringBuilder>(0)
+                .ok_or(Error::BadDowncast {
+                    field: String::from("key"),
+                })?
+                .append_option(
+                    header
+                        .key
+                        .as_deref()
+                        .map(|key| String::from_utf8_lossy(key).into_owned()),
+                );
+
+            values
+                .field_builder::<BinaryBuilder>(1)
+                .ok_or(Error::BadDowncast {
+                    field: String::from("value"),
+                })?
+                .append_option(header.value.as_deref());
+
+            values.append(true);
+        }
+
+        builder.append(true);
+    }
+
+    let headers = Arc::new(builder.finish()) as ArrayRef;
+
+    let mut fields = record_batch.schema().fields().to_vec();
+    fields.push(Arc::new(with_id(
+        Field::new(HEADERS_COLUMN_NAME, headers.data_type().to_owned(), false),
+        id + 1,
+    )));
+
+    let mut columns = record_batch.columns().to_vec();
+    columns.push(headers);
+
+    RecordBatch::try_new(
+        Arc::new(ArrowSchema::new_with_metadata(
+            fields,
+            record_batch.schema().metadata().to_owned(),
+        )),
+        columns,
+    )
+    .map_err(Into::into)
+}
+
+fn encoded_as_json_value(encoded: Option<&[u8]>) -> Value {
+    encoded.map_or(Value::Null, |encoded| {
+        Value::String(STANDARD.encode(encoded))
+    })
+}
+
+fn decoded_as_json_value(decoded: Option<&Value>, name: &str) -> Option<Value> {
+    decoded.and_then(Value::as_object).map(|decoded| {
+        decoded
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map_or(Value::Null, |(_, value)| value.to_owned())
+    })
 }
 
 impl TryFrom<Url> for Registry {
@@ -494,7 +1288,9 @@ impl TryFrom<&Url> for Registry {
 mod tests {
     use super::*;
     use crate::Result;
+    use arrow::util::pretty::pretty_format_batches;
     use bytes::Bytes;
+    use datafusion::prelude::*;
     use object_store::PutPayload;
     use serde_json::json;
     use std::{fs::File, sync::Arc, thread};
@@ -636,4 +1432,573 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn validate_and_arrow() -> Result<()> {
+        let _guard = init_tracing()?;
+        let registry = populate().await?;
+
+        let location = Path::from("stu/value.avsc");
+        let payload = serde_json::to_vec(&json!({
+            "type": "record",
+            "name": "value",
+            "fields": [
+                {"name": "name", "type": "string"},
+                {"name": "age", "type": "int"}
+            ]
+        }))
+        .map(Bytes::from)
+        .map(PutPayload::from)?;
+
+        _ = registry.object_store.put(&location, payload).await?;
+
+        let stu = registry.required_schema("stu").await?;
+
+        for (topic, valid, invalid) in [
+            (
+                "abc",
+                Batch::builder()
+                    .record(Record::builder().key(Bytes::from_static(b"5450").into()))
+                    .record(
+                        Record::builder()
+                            .offset_delta(1)
+                            .key(Bytes::from_static(b"6760").into()),
+                    )
+                    .last_offset_delta(1)
+                    .build()?,
+                Batch::builder()
+                    .record(Record::builder().key(Bytes::from_static(b"5450").into()))
+                    .record(
+                        Record::builder()
+                            .offset_delta(1)
+                            .key(Bytes::from_static(b"545").into()),
+                    )
+                    .last_offset_delta(1)
+                    .build()?,
+            ),
+            (
+                "stu",
+                Batch::builder()
+                    .record(stu.as_kafka_record(&json!({"value": {"name": "alice", "age": 32}}))?)
+                    .record(
+                        stu.as_kafka_record(&json!({"value": {"name": "bob", "age": 23}}))?
+                            .offset_delta(1),
+                    )
+                    .last_offset_delta(1)
+                    .build()?,
+                Batch::builder()
+                    .record(stu.as_kafka_record(&json!({"value": {"name": "alice", "age": 32}}))?)
+                    .record(
+                        Record::builder()
+                            .offset_delta(1)
+                            .value(Bytes::from_static(b"\xff").into()),
+                    )
+                    .last_offset_delta(1)
+                    .build()?,
+            ),
+        ] {
+            _ = registry.required_schema(topic).await?;
+
+            registry.validate(topic, &valid).await?;
+
+            assert_eq!(
+                registry.as_arrow(topic, 0, &valid)?,
+                Some(registry.validate_and_arrow(topic, 0, &valid).await?),
+                "{topic}"
+            );
+
+            assert!(
+                matches!(
+                    registry.validate(topic, &invalid).await,
+                    Err(Error::Api(ErrorCode::InvalidRecord))
+                ),
+                "{topic}"
+            );
+
+            assert!(
+                matches!(
+                    registry.validate_and_arrow(topic, 0, &invalid).await,
+                    Err(Error::Api(ErrorCode::InvalidRecord))
+                ),
+                "{topic}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn malformed_avro_schema() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let object_store = InMemory::new();
+
+        for (topic, encoded) in [
+            (
+                "truncated",
+                Bytes::from_static(br#"{"type": "record", "name": "#),
+            ),
+            (
+                "unknown_type",
+                serde_json::to_vec(&json!({
+                    "type": "record",
+                    "name": "test",
+                    "fields": [{"name": "key", "type": "integer"}]
+                }))
+                .map(Bytes::from)?,
+            ),
+        ] {
+            let location = Path::from(format!("{topic}.avsc"));
+            _ = object_store
+                .put(&location, PutPayload::from(encoded))
+                .await?;
+        }
+
+        let registry = Registry::new(object_store);
+
+        let batch = Batch::builder()
+            .record(Record::builder().key(Bytes::from_static(b"5450").into()))
+            .build()?;
+
+        for topic in ["truncated", "unknown_type"] {
+            assert!(matches!(
+                registry.schema(topic).await,
+                Err(Error::SchemaParse { topic: ref parsed, .. }) if parsed == topic
+            ));
+
+            // a broken schema must not let records through unvalidated
+            //
+            assert!(matches!(
+                registry.validate(topic, &batch).await,
+                Err(Error::SchemaParse { .. })
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn warm() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let object_store = InMemory::new();
+
+        for (location, encoded) in [
+            (
+                "valid.avsc",
+                serde_json::to_vec(&json!({
+                    "type": "record",
+                    "name": "test",
+                    "fields": [{"name": "key", "type": "int"}]
+                }))
+                .map(Bytes::from)?,
+            ),
+            (
+                "invalid.avsc",
+                Bytes::from_static(br#"{"type": "record", "name": "#),
+            ),
+        ] {
+            _ = object_store
+                .put(&Path::from(location), PutPayload::from(encoded))
+                .await?;
+        }
+
+        let registry = Registry::new(object_store);
+
+        registry
+            .warm(&["valid".into(), "without_schema".into()])
+            .await?;
+
+        assert!(registry.schemas.lock()?.contains_key("valid"));
+        assert!(!registry.schemas.lock()?.contains_key("without_schema"));
+
+        assert!(matches!(
+            registry
+                .warm(&["invalid".into(), "valid".into()])
+                .await,
+            Err(Error::SchemaParse { ref topic, .. }) if topic == "invalid"
+        ));
+
+        assert!(!registry.schemas.lock()?.contains_key("invalid"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn missing_schema() -> Result<()> {
+        let _guard = init_tracing()?;
+        let registry = populate().await?;
+
+        assert!(registry.schema("xyz").await?.is_none());
+
+        assert!(matches!(
+            registry.required_schema("xyz").await,
+            Err(Error::SchemaNotFound { ref topic }) if topic == "xyz"
+        ));
+
+        let batch = Batch::builder()
+            .record(Record::builder().key(Bytes::from_static(b"5450").into()))
+            .build()?;
+
+        registry.validate("xyz", &batch).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn as_arrow_with_headers() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let header = |key: &[u8], value: &[u8]| {
+            tansu_kafka_sans_io::record::header::Header::builder()
+                .key(key.to_vec())
+                .value(value.to_vec())
+        };
+
+        let batch = Batch::builder()
+            .base_timestamp(0)
+            .record(
+                Record::builder()
+                    .key(Bytes::from_static(b"5450").into())
+                    .header(header(b"trace", b"abc"))
+                    .header(header(b"span", b"def")),
+            )
+            .record(
+                Record::builder()
+                    .offset_delta(1)
+                    .key(Bytes::from_static(b"6760").into()),
+            )
+            .record(
+                Record::builder()
+                    .offset_delta(2)
+                    .key(Bytes::from_static(b"7890").into())
+                    .header(header(b"trace", b"ghi")),
+            )
+            .last_offset_delta(2)
+            .build()?;
+
+        let registry = populate().await?;
+        _ = registry.schema("abc").await?;
+
+        let record_batch = registry.as_arrow("abc", 0, &batch)?.expect("record batch");
+        assert!(
+            record_batch
+                .schema()
+                .column_with_name(HEADERS_COLUMN_NAME)
+                .is_none()
+        );
+
+        let registry = registry.headers(true);
+
+        let record_batch = registry.as_arrow("abc", 0, &batch)?.expect("record batch");
+
+        let ctx = SessionContext::new();
+        _ = ctx.register_batch("t", record_batch)?;
+
+        let results = ctx
+            .sql("select key, headers, headers[1]['key'] as first from t")
+            .await?
+            .collect()
+            .await?;
+
+        let pretty_results = pretty_format_batches(&results).map(|pretty| pretty.to_string())?;
+
+        let expected = vec![
+            "+------+-----------------------------------------------------------+-------+",
+            "| key  | headers                                                   | first |",
+            "+------+-----------------------------------------------------------+-------+",
+            "| 5450 | [{key: trace, value: 616263}, {key: span, value: 646566}] | trace |",
+            "| 6760 | []                                                        |       |",
+            "| 7890 | [{key: trace, value: 676869}]                             | trace |",
+            "+------+-----------------------------------------------------------+-------+",
+        ];
+
+        assert_eq!(pretty_results.trim().lines().collect::<Vec<_>>(), expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn records_as_jsonl_schemaless() -> Result<()> {
+        let _guard = init_tracing()?;
+        let registry = populate().await?;
+
+        let batch = Batch::builder()
+            .base_offset(32123)
+            .base_timestamp(1_000)
+            .record(
+                Record::builder()
+                    .key(Bytes::from_static(b"k1").into())
+                    .value(Bytes::from_static(b"v1").into())
+                    .header(
+                        tansu_kafka_sans_io::record::header::Header::builder()
+                            .key(b"h".to_vec())
+                            .value(b"hv".to_vec()),
+                    ),
+            )
+            .record(
+                Record::builder()
+                    .offset_delta(1)
+                    .timestamp_delta(1)
+                    .key(Bytes::from_static(b"k2").into()),
+            )
+            .record(
+                Record::builder()
+                    .offset_delta(2)
+                    .timestamp_delta(2)
+                    .key(Bytes::from_static(b"k3").into())
+                    .value(Bytes::from_static(b"v3").into()),
+            )
+            .last_offset_delta(2)
+            .build()?;
+
+        let mut jsonl = Vec::new();
+
+        assert_eq!(
+            2,
+            registry
+                .records_as_jsonl("xyz", &[batch], 32123, 32125, &mut jsonl)
+                .await?
+        );
+
+        assert_eq!(
+            vec![
+                json!({
+                    "offset": 32123,
+                    "timestamp": 1000,
+                    "headers": [{"key": "h", "value": "aHY="}],
+                    "key": "azE=",
+                    "value": "djE=",
+                }),
+                json!({
+                    "offset": 32124,
+                    "timestamp": 1001,
+                    "headers": [],
+                    "key": "azI=",
+                    "value": null,
+                }),
+            ],
+            String::from_utf8(jsonl)?
+                .lines()
+                .map(serde_json::from_str::<Value>)
+                .collect::<Result<Vec<_>, _>>()?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn records_as_jsonl_with_schema() -> Result<()> {
+        let _guard = init_tracing()?;
+        let registry = populate().await?;
+
+        let batch = Batch::builder()
+            .base_timestamp(0)
+            .record(Record::builder().key(Bytes::from_static(b"5450").into()))
+            .record(
+                Record::builder()
+                    .offset_delta(1)
+                    .key(Bytes::from_static(b"6760").into()),
+            )
+            .last_offset_delta(1)
+            .build()?;
+
+        let mut jsonl = Vec::new();
+
+        assert_eq!(
+            2,
+            registry
+                .records_as_jsonl("abc", &[batch], 0, i64::MAX, &mut jsonl)
+                .await?
+        );
+
+        assert_eq!(
+            vec![
+                json!({"offset": 0, "timestamp": 0, "headers": [], "key": 5450, "value": null}),
+                json!({"offset": 1, "timestamp": 0, "headers": [], "key": 6760, "value": null}),
+            ],
+            String::from_utf8(jsonl)?
+                .lines()
+                .map(serde_json::from_str::<Value>)
+                .collect::<Result<Vec<_>, _>>()?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn avro_backward_compatibility() -> Result<()> {
+        let _guard = init_tracing()?;
+        let registry = populate().await?;
+
+        let value = |fields| {
+            serde_json::to_vec(&json!({
+                "type": "record",
+                "name": "topic",
+                "fields": [{
+                    "name": "value",
+                    "type": {
+                        "type": "record",
+                        "name": "value",
+                        "fields": fields
+                    }
+                }]
+            }))
+            .map(Bytes::from)
+            .map_err(Into::into)
+            .and_then(avro::Schema::try_from)
+            .map(Box::new)
+            .map(Schema::Avro)
+        };
+
+        let location = Path::from("stu/value.avsc");
+        let payload = serde_json::to_vec(&json!({
+            "type": "record",
+            "name": "value",
+            "fields": [{"name": "name", "type": "string"}]
+        }))
+        .map(Bytes::from)
+        .map(PutPayload::from)?;
+
+        _ = registry.object_store.put(&location, payload).await?;
+
+        // a new field with a default can read data without it
+        //
+        registry
+            .check_compatibility(
+                "stu",
+                &value(json!([
+                    {"name": "name", "type": "string"},
+                    {"name": "age", "type": "int", "default": 0}
+                ]))?,
+                Compatibility::Backward,
+            )
+            .await?;
+
+        // ...but data written with the new field can't be read by a
+        // field without a default
+        //
+        assert!(matches!(
+            registry
+                .check_compatibility(
+                    "stu",
+                    &value(json!([
+                        {"name": "name", "type": "string"},
+                        {"name": "age", "type": "int"}
+                    ]))?,
+                    Compatibility::Backward,
+                )
+                .await,
+            Err(Error::IncompatibleSchema { topic, .. }) if topic == "stu"
+        ));
+
+        assert!(matches!(
+            registry
+                .check_compatibility(
+                    "stu",
+                    &value(json!([{"name": "name", "type": "int"}]))?,
+                    Compatibility::Backward,
+                )
+                .await,
+            Err(Error::IncompatibleSchema { topic, reason })
+                if topic == "stu" && reason.starts_with("value: ")
+        ));
+
+        // any schema is compatible with a topic without one
+        //
+        registry
+            .check_compatibility(
+                "without_schema",
+                &value(json!([{"name": "name", "type": "int"}]))?,
+                Compatibility::Full,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn json_compatibility() -> Result<()> {
+        let _guard = init_tracing()?;
+        let registry = populate().await?;
+
+        let schema = |value| {
+            serde_json::to_vec(&json!({
+                "type": "object",
+                "properties": {
+                    "value": value
+                }
+            }))
+            .map(Bytes::from)
+            .map_err(Into::into)
+            .and_then(json::Schema::try_from)
+            .map(Arc::new)
+            .map(Schema::Json)
+        };
+
+        let location = Path::from("xyz.json");
+        let payload = serde_json::to_vec(&json!({
+            "type": "object",
+            "properties": {
+                "value": {
+                    "type": "object",
+                    "properties": {"name": {"type": "string"}},
+                    "required": ["name"],
+                    "additionalProperties": false
+                }
+            }
+        }))
+        .map(Bytes::from)
+        .map(PutPayload::from)?;
+
+        _ = registry.object_store.put(&location, payload).await?;
+
+        let added = schema(json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "age": {"type": "integer"}
+            },
+            "required": ["name"],
+            "additionalProperties": false
+        }))?;
+
+        registry
+            .check_compatibility("xyz", &added, Compatibility::Backward)
+            .await?;
+
+        // data with an age can't be read by the existing schema
+        //
+        assert!(matches!(
+            registry
+                .check_compatibility("xyz", &added, Compatibility::Full)
+                .await,
+            Err(Error::IncompatibleSchema { reason, .. }) if reason.starts_with("value.age: ")
+        ));
+
+        assert!(matches!(
+            registry
+                .check_compatibility(
+                    "xyz",
+                    &schema(json!({
+                        "type": "object",
+                        "properties": {"name": {"type": "integer"}},
+                        "required": ["name"],
+                        "additionalProperties": false
+                    }))?,
+                    Compatibility::Backward,
+                )
+                .await,
+            Err(Error::IncompatibleSchema { reason, .. }) if reason.starts_with("value.name: type")
+        ));
+
+        assert!(matches!(
+            registry
+                .check_compatibility("xyz", &registry.required_schema("pqr").await?, Compatibility::Backward)
+                .await,
+            Err(Error::IncompatibleSchema { reason, .. }) if reason == "format changed from: json to: avro"
+        ));
+
+        Ok(())
+    }
 }
@@ -15,14 +15,17 @@
 
 use std::{collections::BTreeMap, io::Write, ops::Deref, sync::LazyLock};
 
-use crate::{ARROW_LIST_FIELD_NAME, AsArrow, AsJsonValue, AsKafkaRecord, Error, Result, Validator};
+use crate::{
+    ARROW_LIST_FIELD_NAME, AsArrow, AsJsonValue, AsKafkaRecord, Error, Result, ValidateAsArrow,
+    Validator, WireFormat, confluent_wire_format,
+};
 use arrow::{
     array::{
         ArrayBuilder, BooleanBuilder, Float32Builder, Float64Builder, Int32Builder, Int64Builder,
         LargeBinaryBuilder, ListBuilder, MapBuilder, StringBuilder, StructBuilder,
         TimestampMicrosecondBuilder,
     },
-    datatypes::{DataType, Field, FieldRef, Fields, Schema as ArrowSchema, TimeUnit},
+    datatypes::{DataType, Field, FieldRef, Fields, Schema as ArrowSchema, SchemaRef, TimeUnit},
     record_batch::RecordBatch,
 };
 use bytes::{BufMut, Bytes, BytesMut};
@@ -85,9 +88,66 @@ struct RecordBuilder {
 pub struct Schema {
     file_descriptors: Vec<FileDescriptor>,
     ids: BTreeMap<String, i32>,
+    wire_format: WireFormat,
 }
 
 impl Schema {
+    pub fn with_wire_format(self, wire_format: WireFormat) -> Self {
+        Self {
+            wire_format,
+            ..self
+        }
+    }
+
+    /// Strip the frame of a Confluent framed message, which follows the
+    /// schema id with the indexes of the message within its file, that
+    /// must be those of the message expected as the key or value.
+    fn unframed(&self, message_kind: MessageKind, encoded: Option<Bytes>) -> Result<Option<Bytes>> {
+        let (WireFormat::Confluent, Some(expected), Some(encoded)) = (
+            self.wire_format,
+            self.message_by_package_relative_name(message_kind),
+            encoded.clone(),
+        ) else {
+            return Ok(encoded);
+        };
+
+        let (id, datum) = confluent_wire_format(&encoded[..])?;
+
+        let mut input = CodedInputStream::from_bytes(datum);
+
+        let indexes = input
+            .read_sint32()
+            .and_then(|count| {
+                if count == 0 {
+                    Ok(vec![0])
+                } else {
+                    (0..count).map(|_| input.read_sint32()).collect()
+                }
+            })
+            .inspect_err(|err| debug!(?err))
+            .map_err(|_| Error::Api(ErrorCode::InvalidRecord))?;
+
+        let offset = encoded.len() - datum.len() + usize::try_from(input.pos())?;
+
+        debug!(id, ?indexes, offset);
+
+        let mut indexes = indexes.into_iter().map(usize::try_from);
+
+        let message = indexes
+            .next()
+            .transpose()?
+            .and_then(|index| expected.file_descriptor().messages().nth(index));
+
+        indexes
+            .try_fold(message, |message, index| {
+                index.map(|index| message.and_then(|message| message.nested_messages().nth(index)))
+            })?
+            .filter(|message| *message == expected)
+            .ok_or(Error::Api(ErrorCode::InvalidRecord))
+            .inspect_err(|err| debug!(?err, id, ?message_kind))
+            .map(|_| Some(encoded.slice(offset..)))
+    }
+
     fn new_list_field(&self, path: &[&str], data_type: DataType) -> Field {
         self.new_field(path, ARROW_LIST_FIELD_NAME, data_type)
     }
@@ -718,24 +778,18 @@ fn validate(message_descriptor: Option<MessageDescriptor>, encoded: Option<Bytes
 }
 
 impl Validator for Schema {
-    fn validate(&self, batch: &Batch) -> Result<()> {
-        debug!(?batch);
-
-        for record in &batch.records {
-            debug!(?record);
-
-            validate(
-                self.message_by_package_relative_name(MessageKind::Key),
-                record.key.clone(),
-            )
-            .and(validate(
-                self.message_by_package_relative_name(MessageKind::Value),
-                record.value.clone(),
-            ))
-            .inspect_err(|err| error!(?err))?
-        }
+    fn validate_record(&self, record: &tansu_kafka_sans_io::record::Record) -> Result<()> {
+        debug!(?record);
 
-        Ok(())
+        validate(
+            self.message_by_package_relative_name(MessageKind::Key),
+            self.unframed(MessageKind::Key, record.key.clone())?,
+        )
+        .and(validate(
+            self.message_by_package_relative_name(MessageKind::Value),
+            self.unframed(MessageKind::Value, record.value.clone())?,
+        ))
+        .inspect_err(|err| error!(?err))
     }
 }
 
@@ -776,6 +830,7 @@ impl TryFrom<Bytes> for Schema {
             .map(|file_descriptors| Self {
                 ids: field_ids(&file_descriptors),
                 file_descriptors,
+                wire_format: WireFormat::default(),
             })
     }
 }
@@ -1411,14 +1466,33 @@ where
         .inspect_err(|err| debug!(?err))
 }
 
+impl ValidateAsArrow for Schema {
+    /// Decoding a message against the descriptor is its validation.
+    fn validate_as_arrow(&self, partition: i32, batch: &Batch) -> Result<RecordBatch> {
+        self.as_arrow(partition, batch)
+    }
+}
+
 impl AsArrow for Schema {
     fn as_arrow(&self, partition: i32, batch: &Batch) -> Result<RecordBatch> {
-        debug!(?batch);
+        self.as_arrow_stream(partition, batch, usize::MAX)
+            .and_then(|mut record_batches| record_batches.pop().ok_or(Error::BuilderExhausted))
+    }
+
+    fn as_arrow_stream(
+        &self,
+        partition: i32,
+        batch: &Batch,
+        max_rows: usize,
+    ) -> Result<Vec<RecordBatch>> {
+        debug!(?batch, max_rows);
 
-        let schema = ArrowSchema::from(self);
+        let schema = SchemaRef::new(ArrowSchema::from(self));
         debug!(?schema);
 
         let mut record_builder = RecordBuilder::from(self);
+        let mut record_batches = vec![];
+        let mut rows = 0;
 
         for record in batch.records.iter() {
             debug!(?record);
@@ -1444,44 +1518,50 @@ impl AsArrow for Schema {
 
             process_message_descriptor(
                 self.message_by_package_relative_name(MessageKind::Key),
-                record.key(),
+                self.unframed(MessageKind::Key, record.key())?,
                 &mut record_builder.key.iter_mut(),
             )
             .inspect_err(|err| debug!(?err))?;
 
             process_message_descriptor(
                 self.message_by_package_relative_name(MessageKind::Value),
-                record.value(),
+                self.unframed(MessageKind::Value, record.value())?,
                 &mut record_builder.value.iter_mut(),
             )
             .inspect_err(|err| debug!(?err))?;
-        }
-
-        debug!(
-            meta_rows = ?record_builder.meta.iter().map(|rows| rows.len()).collect::<Vec<_>>(),
-            key_rows = ?record_builder.key.iter().map(|rows| rows.len()).collect::<Vec<_>>(),
-            value_rows = ?record_builder.value.iter().map(|rows| rows.len()).collect::<Vec<_>>()
-        );
 
-        let mut columns = vec![];
+            rows += 1;
 
-        if let Some(meta) = record_builder.meta {
-            columns.push(meta);
+            if rows >= max_rows {
+                record_batches.push(record_builder.finish(schema.clone())?);
+                rows = 0;
+            }
         }
 
-        if let Some(key) = record_builder.key {
-            columns.push(key);
+        if rows > 0 || record_batches.is_empty() {
+            record_batches.push(record_builder.finish(schema)?);
         }
 
-        if let Some(value) = record_builder.value {
-            columns.push(value);
-        }
+        Ok(record_batches)
+    }
+}
 
-        debug!(columns = columns.len(), ?schema);
+impl RecordBuilder {
+    fn finish(&mut self, schema: SchemaRef) -> Result<RecordBatch> {
+        debug!(
+            meta_rows = ?self.meta.iter().map(|rows| rows.len()).collect::<Vec<_>>(),
+            key_rows = ?self.key.iter().map(|rows| rows.len()).collect::<Vec<_>>(),
+            value_rows = ?self.value.iter().map(|rows| rows.len()).collect::<Vec<_>>()
+        );
 
         RecordBatch::try_new(
-            schema.into(),
-            columns.iter_mut().map(|builder| builder.finish()).collect(),
+            schema,
+            self.meta
+                .iter_mut()
+                .chain(self.key.iter_mut())
+                .chain(self.value.iter_mut())
+                .map(|builder| builder.finish())
+                .collect(),
         )
         .inspect_err(|err| debug!(?err))
         .map_err(Into::into)
@@ -1494,7 +1574,10 @@ impl Schema {
         message_kind: MessageKind,
         encoded: Option<Bytes>,
     ) -> Result<(String, Value)> {
-        decode(self.message_by_package_relative_name(message_kind), encoded)
+        self.unframed(message_kind, encoded)
+            .and_then(|encoded| {
+                decode(self.message_by_package_relative_name(message_kind), encoded)
+            })
             .inspect(|decoded| debug!(?decoded))
             .and_then(|decoded| {
                 decoded.map_or(
@@ -1513,27 +1596,20 @@ impl Schema {
 }
 
 impl AsJsonValue for Schema {
-    fn as_json_value(&self, batch: &Batch) -> Result<Value> {
-        Ok(Value::Array(
-            batch
-                .records
-                .iter()
-                .inspect(|record| debug!(?record))
-                .map(|record| {
-                    Value::Object(Map::from_iter(
-                        self.to_json_value(MessageKind::Key, record.key.clone())
-                            .into_iter()
-                            .chain(self.to_json_value(MessageKind::Value, record.value.clone())),
-                    ))
-                })
-                .collect::<Vec<_>>(),
-        ))
+    fn record_as_json_value(&self, record: &tansu_kafka_sans_io::record::Record) -> Result<Value> {
+        debug!(?record);
+
+        Ok(Value::Object(Map::from_iter(
+            self.to_json_value(MessageKind::Key, record.key.clone())
+                .into_iter()
+                .chain(self.to_json_value(MessageKind::Value, record.value.clone())),
+        )))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Registry;
+    use crate::{CONFLUENT_MAGIC, Registry};
 
     use super::*;
     use arrow::util::pretty::pretty_format_batches;
@@ -1728,6 +1804,106 @@ mod tests {
         registry.validate(topic, &batch).await
     }
 
+    fn confluent_framed(schema_id: u32, indexes: &[u8], encoded: Bytes) -> Bytes {
+        let mut framed = vec![CONFLUENT_MAGIC];
+        framed.extend_from_slice(&schema_id.to_be_bytes());
+        framed.extend_from_slice(indexes);
+        framed.extend(encoded);
+        Bytes::from(framed)
+    }
+
+    #[tokio::test]
+    async fn confluent_wire_format() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let topic = "def";
+
+        let proto = Bytes::from_static(
+            br#"
+                syntax = 'proto3';
+
+                message Key {
+                  int32 id = 1;
+                }
+
+                message Value {
+                  string name = 1;
+                  string email = 2;
+                }
+                "#,
+        );
+
+        let object_store = InMemory::new();
+        let location = Path::from(format!("{topic}.proto"));
+        let payload = PutPayload::from(proto.clone());
+        _ = object_store.put(&location, payload).await?;
+
+        let registry = Registry::new(object_store).wire_format(WireFormat::Confluent);
+
+        let schema = Schema::try_from(proto.clone())?;
+
+        let key = schema.encode_from_value(MessageKind::Key, &json!({"id": 12321}))?;
+        let value = schema.encode_from_value(
+            MessageKind::Value,
+            &json!({
+                "name": "alice",
+                "email": "alice@example.com"
+            }),
+        )?;
+
+        // the first message of the file is the single zero byte, other
+        // messages are a zig zag encoded count followed by each index
+        //
+        let batch = Batch::builder()
+            .record(
+                Record::builder()
+                    .key(confluent_framed(1, &[0], key.clone()).into())
+                    .value(confluent_framed(2, &[2, 2], value.clone()).into()),
+            )
+            .build()?;
+
+        registry.validate(topic, &batch).await?;
+
+        let record_batch = registry
+            .as_arrow(topic, 0, &batch)?
+            .ok_or(Error::Message(format!("no record batch for: {topic}")))?;
+
+        let ctx = SessionContext::new();
+
+        _ = ctx.register_batch("t", record_batch)?;
+        let df = ctx.sql("select key, value from t").await?;
+        let results = df.collect().await?;
+
+        let pretty_results = pretty_format_batches(&results)?.to_string();
+
+        let expected = vec![
+            "+-------------+-----------------------------------------+",
+            "| key         | value                                   |",
+            "+-------------+-----------------------------------------+",
+            "| {id: 12321} | {name: alice, email: alice@example.com} |",
+            "+-------------+-----------------------------------------+",
+        ];
+
+        assert_eq!(pretty_results.trim().lines().collect::<Vec<_>>(), expected);
+
+        // a value framed with the index of the key message is invalid
+        //
+        let batch = Batch::builder()
+            .record(
+                Record::builder()
+                    .key(confluent_framed(1, &[0], key).into())
+                    .value(confluent_framed(2, &[0], value).into()),
+            )
+            .build()?;
+
+        assert!(matches!(
+            registry.validate(topic, &batch).await,
+            Err(Error::Api(ErrorCode::InvalidRecord))
+        ));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn enumeration() -> Result<()> {
         let _guard = init_tracing()?;
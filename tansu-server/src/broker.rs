@@ -47,10 +47,6 @@ use init_producer_id::InitProducerIdRequest;
 use list_offsets::ListOffsetsRequest;
 use list_partition_reassignments::ListPartitionReassignmentsRequest;
 use metadata::MetadataRequest;
-use object_store::{
-    aws::{AmazonS3Builder, S3ConditionalPut},
-    memory::InMemory,
-};
 use opentelemetry::{
     KeyValue,
     metrics::{Counter, Histogram},
@@ -70,8 +66,9 @@ use tansu_kafka_sans_io::{
 };
 use tansu_schema_registry::{Registry, lake::House};
 use tansu_storage::{
-    BrokerRegistrationRequest, Storage, StorageContainer, TopicId, dynostore::DynoStore,
-    pg::Postgres,
+    BatchLimit, BrokerRegistrationRequest, DEFAULT_NUM_PARTITIONS, OFFSET_METADATA_MAX_BYTES,
+    OFFSETS_RETENTION, PRODUCER_ID_SEQUENCE_WINDOW, Storage, StorageContainer, TopicDetail,
+    TopicId, Topition, redact_dsn,
 };
 use telemetry::GetTelemetrySubscriptionsRequest;
 use tokio::{
@@ -82,11 +79,36 @@ use tokio::{
     task::JoinSet,
     time::{self, sleep},
 };
-use tracing::{Instrument, Level, Span, debug, debug_span, error, info, span};
+use tracing::{Instrument, Level, Span, debug, debug_span, error, info, span, warn};
 use txn::{add_offsets::AddOffsets, add_partitions::AddPartitions};
 use url::Url;
 use uuid::Uuid;
 
+/// Compact every partition of the topics with a `compact` cleanup policy,
+/// continuing past any partition that fails to compact.
+async fn compact<S>(storage: &mut S) -> Result<()>
+where
+    S: Storage,
+{
+    for topic in storage
+        .list_topics()
+        .await?
+        .into_iter()
+        .filter(TopicDetail::is_compacted)
+    {
+        for partition in 0..topic.creatable_topic().num_partitions {
+            let topition = Topition::new(topic.name(), partition);
+
+            match storage.compact(&topition).await {
+                Ok(removed) => debug!(?topition, removed),
+                Err(err) => warn!(?err, ?topition),
+            }
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Clone, Debug)]
 pub struct Broker<G, S> {
     node_id: i32,
@@ -99,6 +121,8 @@ pub struct Broker<G, S> {
     metron: Metron,
     prometheus_listener_url: Option<Url>,
     prometheus_registry: Option<PromRegistry>,
+    schemas: Option<Registry>,
+    num_partitions: i32,
 }
 
 impl<G, S> Broker<G, S>
@@ -127,6 +151,8 @@ where
             metron: Metron::new(cluster_id, incarnation_id),
             prometheus_listener_url: None,
             prometheus_registry: None,
+            schemas: None,
+            num_partitions: DEFAULT_NUM_PARTITIONS,
         }
     }
 
@@ -159,6 +185,8 @@ where
         let mut terminate_signal = signal(SignalKind::terminate()).unwrap();
         debug!(?terminate_signal);
 
+        let mut storage = self.storage.clone();
+
         set.spawn(async move {
             self.serve(receiver)
                 .await
@@ -216,14 +244,40 @@ where
             }
         }
 
+        storage.close().await.inspect_err(|err| error!(?err))?;
+
         Ok(ErrorCode::None)
     }
 
     pub async fn serve(&mut self, interrupts: Receiver<CancelKind>) -> Result<()> {
         self.register().await?;
+        self.warm_schemas().await?;
         self.listen(interrupts).await
     }
 
+    /// Parse the schema of every topic before accepting any produce,
+    /// reporting a broken schema now rather than on its first produce.
+    async fn warm_schemas(&mut self) -> Result<()> {
+        let Some(ref schemas) = self.schemas else {
+            return Ok(());
+        };
+
+        let topics = self
+            .storage
+            .list_topics()
+            .await?
+            .into_iter()
+            .map(|topic| topic.name().to_owned())
+            .collect::<Vec<_>>();
+
+        _ = schemas
+            .warm(&topics)
+            .await
+            .inspect_err(|err| error!(?err, "schema warm up"));
+
+        Ok(())
+    }
+
     pub async fn register(&mut self) -> Result<()> {
         self.storage
             .register_broker(BrokerRegistrationRequest {
@@ -298,7 +352,7 @@ where
                 }
 
                 _ = interval.tick() => {
-                    let storage = self.storage.clone();
+                    let mut storage = self.storage.clone();
 
 
                     let handle = set.spawn(async move {
@@ -306,6 +360,8 @@ where
 
                         async move {
                             _ = storage.maintain().await.inspect(|maintain|debug!(?maintain)).inspect_err(|err|debug!(?err)).ok();
+                            _ = compact(&mut storage).await.inspect_err(|err|warn!(?err)).ok();
+                            _ = storage.expire_offsets().await.inspect(|expired|debug!(expired)).inspect_err(|err|debug!(?err)).ok();
 
                         }.instrument(span).await
 
@@ -511,6 +567,7 @@ where
             } => {
                 debug!(?validate_only, ?topics);
                 CreateTopic::with_storage(self.storage.clone())
+                    .num_partitions(self.num_partitions)
                     .response(topics, validate_only.unwrap_or(false))
                     .await
                     .map(Some)
@@ -903,6 +960,7 @@ where
             } => {
                 debug!(?transactional_id, ?acks, ?timeout_ms, ?topic_data);
                 ProduceRequest::with_storage(self.storage.clone())
+                    .schemas(self.schemas.clone())
                     .response(transactional_id, acks, timeout_ms, topic_data)
                     .await
                     .map(|response| Body::ProduceResponse {
@@ -1295,6 +1353,15 @@ pub struct Builder<N, C, I, A, S, L> {
     prometheus_registry: Option<PromRegistry>,
     schema_registry: Option<Url>,
     lake_house: Option<House>,
+    sequence_window: Option<usize>,
+    offsets_retention: Option<Duration>,
+    offset_metadata_max_bytes: Option<usize>,
+    auto_create_topics_enable: bool,
+    num_partitions: Option<i32>,
+    cache: Option<usize>,
+    fetch_batch_limit: Option<BatchLimit>,
+    segments: Option<Url>,
+    segment_compression: Option<i32>,
 }
 
 type PhantomBuilder = Builder<
@@ -1319,6 +1386,15 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             prometheus_registry: self.prometheus_registry,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
+            sequence_window: self.sequence_window,
+            offsets_retention: self.offsets_retention,
+            offset_metadata_max_bytes: self.offset_metadata_max_bytes,
+            auto_create_topics_enable: self.auto_create_topics_enable,
+            num_partitions: self.num_partitions,
+            cache: self.cache,
+            fetch_batch_limit: self.fetch_batch_limit,
+            segments: self.segments,
+            segment_compression: self.segment_compression,
         }
     }
 
@@ -1334,6 +1410,15 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             prometheus_registry: self.prometheus_registry,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
+            sequence_window: self.sequence_window,
+            offsets_retention: self.offsets_retention,
+            offset_metadata_max_bytes: self.offset_metadata_max_bytes,
+            auto_create_topics_enable: self.auto_create_topics_enable,
+            num_partitions: self.num_partitions,
+            cache: self.cache,
+            fetch_batch_limit: self.fetch_batch_limit,
+            segments: self.segments,
+            segment_compression: self.segment_compression,
         }
     }
 
@@ -1349,6 +1434,15 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             prometheus_registry: self.prometheus_registry,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
+            sequence_window: self.sequence_window,
+            offsets_retention: self.offsets_retention,
+            offset_metadata_max_bytes: self.offset_metadata_max_bytes,
+            auto_create_topics_enable: self.auto_create_topics_enable,
+            num_partitions: self.num_partitions,
+            cache: self.cache,
+            fetch_batch_limit: self.fetch_batch_limit,
+            segments: self.segments,
+            segment_compression: self.segment_compression,
         }
     }
 
@@ -1367,11 +1461,20 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             prometheus_registry: self.prometheus_registry,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
+            sequence_window: self.sequence_window,
+            offsets_retention: self.offsets_retention,
+            offset_metadata_max_bytes: self.offset_metadata_max_bytes,
+            auto_create_topics_enable: self.auto_create_topics_enable,
+            num_partitions: self.num_partitions,
+            cache: self.cache,
+            fetch_batch_limit: self.fetch_batch_limit,
+            segments: self.segments,
+            segment_compression: self.segment_compression,
         }
     }
 
     pub fn storage(self, storage: Url) -> Builder<N, C, I, A, Url, L> {
-        debug!(%storage);
+        debug!(storage = redact_dsn(storage.as_str()));
 
         Builder {
             node_id: self.node_id,
@@ -1384,6 +1487,15 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             prometheus_registry: self.prometheus_registry,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
+            sequence_window: self.sequence_window,
+            offsets_retention: self.offsets_retention,
+            offset_metadata_max_bytes: self.offset_metadata_max_bytes,
+            auto_create_topics_enable: self.auto_create_topics_enable,
+            num_partitions: self.num_partitions,
+            cache: self.cache,
+            fetch_batch_limit: self.fetch_batch_limit,
+            segments: self.segments,
+            segment_compression: self.segment_compression,
         }
     }
 
@@ -1401,6 +1513,15 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             prometheus_registry: self.prometheus_registry,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
+            sequence_window: self.sequence_window,
+            offsets_retention: self.offsets_retention,
+            offset_metadata_max_bytes: self.offset_metadata_max_bytes,
+            auto_create_topics_enable: self.auto_create_topics_enable,
+            num_partitions: self.num_partitions,
+            cache: self.cache,
+            fetch_batch_limit: self.fetch_batch_limit,
+            segments: self.segments,
+            segment_compression: self.segment_compression,
         }
     }
 
@@ -1420,6 +1541,15 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             prometheus_registry: self.prometheus_registry,
             schema_registry,
             lake_house: self.lake_house,
+            sequence_window: self.sequence_window,
+            offsets_retention: self.offsets_retention,
+            offset_metadata_max_bytes: self.offset_metadata_max_bytes,
+            auto_create_topics_enable: self.auto_create_topics_enable,
+            num_partitions: self.num_partitions,
+            cache: self.cache,
+            fetch_batch_limit: self.fetch_batch_limit,
+            segments: self.segments,
+            segment_compression: self.segment_compression,
         }
     }
 
@@ -1439,6 +1569,102 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             prometheus_registry: self.prometheus_registry,
             schema_registry: self.schema_registry,
             lake_house,
+            sequence_window: self.sequence_window,
+            offsets_retention: self.offsets_retention,
+            offset_metadata_max_bytes: self.offset_metadata_max_bytes,
+            auto_create_topics_enable: self.auto_create_topics_enable,
+            num_partitions: self.num_partitions,
+            cache: self.cache,
+            fetch_batch_limit: self.fetch_batch_limit,
+            segments: self.segments,
+            segment_compression: self.segment_compression,
+        }
+    }
+
+    /// The number of batches retained per producer and topition when
+    /// deduplicating idempotent produces (`producer.id.sequence.window`).
+    pub fn sequence_window(self, sequence_window: usize) -> Builder<N, C, I, A, S, L> {
+        Builder {
+            sequence_window: Some(sequence_window),
+            ..self
+        }
+    }
+
+    /// The retention of committed offsets when a commit doesn't
+    /// specify one (`offsets.retention.minutes`).
+    pub fn offsets_retention(self, offsets_retention: Duration) -> Builder<N, C, I, A, S, L> {
+        Builder {
+            offsets_retention: Some(offsets_retention),
+            ..self
+        }
+    }
+
+    /// The maximum size of the metadata of a committed offset
+    /// (`offset.metadata.max.bytes`).
+    pub fn offset_metadata_max_bytes(
+        self,
+        offset_metadata_max_bytes: usize,
+    ) -> Builder<N, C, I, A, S, L> {
+        Builder {
+            offset_metadata_max_bytes: Some(offset_metadata_max_bytes),
+            ..self
+        }
+    }
+
+    /// Create a topic with the default number of partitions and
+    /// replication factor when it is first produced to
+    /// (`auto.create.topics.enable`).
+    pub fn auto_create_topics_enable(
+        self,
+        auto_create_topics_enable: bool,
+    ) -> Builder<N, C, I, A, S, L> {
+        Builder {
+            auto_create_topics_enable,
+            ..self
+        }
+    }
+
+    /// The number of partitions of a topic created with `-1`, or on its
+    /// first produce (`num.partitions`).
+    pub fn num_partitions(self, num_partitions: i32) -> Builder<N, C, I, A, S, L> {
+        Builder {
+            num_partitions: Some(num_partitions),
+            ..self
+        }
+    }
+
+    /// The number of record batches cached in memory across all
+    /// topitions, serving tail fetches without reading storage.
+    pub fn cache(self, cache: Option<usize>) -> Builder<N, C, I, A, S, L> {
+        Builder { cache, ..self }
+    }
+
+    /// Split the records of a fetch into batches of at most this size,
+    /// rather than a single batch.
+    pub fn fetch_batch_limit(
+        self,
+        fetch_batch_limit: Option<BatchLimit>,
+    ) -> Builder<N, C, I, A, S, L> {
+        Builder {
+            fetch_batch_limit,
+            ..self
+        }
+    }
+
+    /// Store record batches as segments in this object store.
+    pub fn segments(self, segments: Option<Url>) -> Builder<N, C, I, A, S, L> {
+        Builder { segments, ..self }
+    }
+
+    /// Compress segments at this zstd level with a dictionary trained
+    /// for their topic.
+    pub fn segment_compression(
+        self,
+        segment_compression: Option<i32>,
+    ) -> Builder<N, C, I, A, S, L> {
+        Builder {
+            segment_compression,
+            ..self
         }
     }
 
@@ -1457,6 +1683,15 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             prometheus_registry: self.prometheus_registry,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
+            sequence_window: self.sequence_window,
+            offsets_retention: self.offsets_retention,
+            offset_metadata_max_bytes: self.offset_metadata_max_bytes,
+            auto_create_topics_enable: self.auto_create_topics_enable,
+            num_partitions: self.num_partitions,
+            cache: self.cache,
+            fetch_batch_limit: self.fetch_batch_limit,
+            segments: self.segments,
+            segment_compression: self.segment_compression,
         }
     }
 
@@ -1475,58 +1710,51 @@ impl<N, C, I, A, S, L> Builder<N, C, I, A, S, L> {
             prometheus_registry,
             schema_registry: self.schema_registry,
             lake_house: self.lake_house,
+            sequence_window: self.sequence_window,
+            offsets_retention: self.offsets_retention,
+            offset_metadata_max_bytes: self.offset_metadata_max_bytes,
+            auto_create_topics_enable: self.auto_create_topics_enable,
+            num_partitions: self.num_partitions,
+            cache: self.cache,
+            fetch_batch_limit: self.fetch_batch_limit,
+            segments: self.segments,
+            segment_compression: self.segment_compression,
         }
     }
 }
 
 impl Builder<i32, String, Uuid, Url, Url, Url> {
-    fn storage_engine(&self) -> Result<StorageContainer> {
+    fn storage_engine(&self, schemas: Option<Registry>) -> Result<StorageContainer> {
+        StorageContainer::builder()
+            .cluster(self.cluster_id.as_str())
+            .node(self.node_id)
+            .advertised_listener(self.advertised_listener.clone())
+            .storage(self.storage.clone())
+            .schemas(schemas)
+            .lake(self.lake_house.clone())
+            .sequence_window(self.sequence_window.unwrap_or(PRODUCER_ID_SEQUENCE_WINDOW))
+            .offsets_retention(self.offsets_retention.unwrap_or(OFFSETS_RETENTION))
+            .offset_metadata_max_bytes(
+                self.offset_metadata_max_bytes
+                    .unwrap_or(OFFSET_METADATA_MAX_BYTES),
+            )
+            .auto_create_topics(self.auto_create_topics_enable)
+            .num_partitions(self.num_partitions.unwrap_or(DEFAULT_NUM_PARTITIONS))
+            .cache(self.cache)
+            .fetch_batch_limit(self.fetch_batch_limit)
+            .segments(self.segments.clone())
+            .segment_compression(self.segment_compression)
+            .build()
+            .map_err(Into::into)
+    }
+
+    pub fn build(self) -> Result<Broker<Controller<StorageContainer>, StorageContainer>> {
         let schemas = self
             .schema_registry
             .as_ref()
             .map_or(Ok(None), |schema| Registry::try_from(schema).map(Some))?;
 
-        match self.storage.scheme() {
-            "postgres" | "postgresql" => Postgres::builder(self.storage.to_string().as_str())
-                .map(|builder| builder.cluster(self.cluster_id.as_str()))
-                .map(|builder| builder.node(self.node_id))
-                .map(|builder| builder.advertised_listener(self.advertised_listener.clone()))
-                .map(|builder| builder.schemas(schemas))
-                .map(|builder| builder.lake(self.lake_house.clone()))
-                .map(|builder| builder.build())
-                .map(StorageContainer::Postgres)
-                .map_err(Into::into),
-
-            "s3" => {
-                let bucket_name = self.storage.host_str().unwrap_or("tansu");
-
-                AmazonS3Builder::from_env()
-                    .with_bucket_name(bucket_name)
-                    .with_conditional_put(S3ConditionalPut::ETagMatch)
-                    .build()
-                    .map(|object_store| {
-                        DynoStore::new(self.cluster_id.as_str(), self.node_id, object_store)
-                            .advertised_listener(self.advertised_listener.clone())
-                            .schemas(schemas)
-                            .lake(self.lake_house.clone())
-                    })
-                    .map(StorageContainer::DynoStore)
-                    .map_err(Into::into)
-            }
-
-            "memory" => Ok(StorageContainer::DynoStore(
-                DynoStore::new(self.cluster_id.as_str(), self.node_id, InMemory::new())
-                    .advertised_listener(self.advertised_listener.clone())
-                    .schemas(schemas)
-                    .lake(self.lake_house.clone()),
-            )),
-
-            _unsupported => Err(Error::UnsupportedStorageUrl(self.storage.clone())),
-        }
-    }
-
-    pub fn build(self) -> Result<Broker<Controller<StorageContainer>, StorageContainer>> {
-        let storage = self.storage_engine()?;
+        let storage = self.storage_engine(schemas.clone())?;
         let groups = Controller::with_storage(storage.clone())?;
         let metron = Metron::new(self.cluster_id.as_str(), self.incarnation_id);
 
@@ -1541,6 +1769,8 @@ impl Builder<i32, String, Uuid, Url, Url, Url> {
             metron,
             prometheus_listener_url: self.prometheus_listener_url,
             prometheus_registry: self.prometheus_registry,
+            schemas,
+            num_partitions: self.num_partitions.unwrap_or(DEFAULT_NUM_PARTITIONS),
         })
     }
 }
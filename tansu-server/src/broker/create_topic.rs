@@ -17,12 +17,13 @@ use crate::Result;
 use tansu_kafka_sans_io::{
     ErrorCode, create_topics_request::CreatableTopic, create_topics_response::CreatableTopicResult,
 };
-use tansu_storage::Storage;
+use tansu_storage::{DEFAULT_NUM_PARTITIONS, DEFAULT_REPLICATION_FACTOR, Storage};
 use tracing::debug;
 
 #[derive(Clone, Debug)]
 pub struct CreateTopic<S> {
     storage: S,
+    num_partitions: i32,
 }
 
 impl<S> CreateTopic<S>
@@ -30,7 +31,18 @@ where
     S: Storage,
 {
     pub fn with_storage(storage: S) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            num_partitions: DEFAULT_NUM_PARTITIONS,
+        }
+    }
+
+    /// The number of partitions of a topic created with `-1`.
+    pub fn num_partitions(self, num_partitions: i32) -> Self {
+        Self {
+            num_partitions,
+            ..self
+        }
     }
 
     async fn create_topic(
@@ -38,14 +50,12 @@ where
         mut topic: CreatableTopic,
         validate_only: bool,
     ) -> CreatableTopicResult {
-        let _ = validate_only;
-
         if topic.num_partitions == -1 {
-            topic.num_partitions = 1;
+            topic.num_partitions = self.num_partitions;
         }
 
         if topic.replication_factor == -1 {
-            topic.replication_factor = 3
+            topic.replication_factor = DEFAULT_REPLICATION_FACTOR
         }
 
         let name = topic.name.clone();
@@ -135,7 +145,7 @@ mod tests {
 
         let name = "pqr";
         let num_partitions = 5;
-        let replication_factor = 3;
+        let replication_factor = 1;
         let assignments = Some([].into());
         let configs = Some([].into());
         let validate_only = false;
@@ -157,7 +167,7 @@ mod tests {
         assert_eq!(name, r[0].name.as_str());
         assert_ne!(Some(NULL_TOPIC_ID), r[0].topic_id);
         assert_eq!(Some(5), r[0].num_partitions);
-        assert_eq!(Some(3), r[0].replication_factor);
+        assert_eq!(Some(1), r[0].replication_factor);
         assert_eq!(ErrorCode::None, ErrorCode::try_from(r[0].error_code)?);
 
         Ok(())
@@ -196,12 +206,88 @@ mod tests {
         assert_eq!(name, r[0].name.as_str());
         assert_ne!(Some(NULL_TOPIC_ID), r[0].topic_id);
         assert_eq!(Some(1), r[0].num_partitions);
-        assert_eq!(Some(3), r[0].replication_factor);
+        assert_eq!(Some(1), r[0].replication_factor);
         assert_eq!(ErrorCode::None, ErrorCode::try_from(r[0].error_code)?);
 
         Ok(())
     }
 
+    #[tokio::test]
+    async fn create_with_configured_default() -> Result<()> {
+        let cluster = "abc";
+        let node = 12321;
+
+        let mut storage = DynoStore::new(cluster, node, InMemory::new());
+
+        let mut create_topic = CreateTopic::with_storage(storage.clone()).num_partitions(3);
+
+        let name = "pqr";
+
+        let r = create_topic
+            .response(
+                Some(vec![CreatableTopic {
+                    name: name.into(),
+                    num_partitions: -1,
+                    replication_factor: -1,
+                    assignments: Some([].into()),
+                    configs: Some([].into()),
+                }]),
+                false,
+            )
+            .await?;
+
+        assert_eq!(1, r.len());
+        assert_eq!(ErrorCode::None, ErrorCode::try_from(r[0].error_code)?);
+        assert_eq!(Some(3), r[0].num_partitions);
+
+        assert_eq!(
+            Some(3),
+            storage
+                .list_topics()
+                .await?
+                .into_iter()
+                .find(|detail| detail.name() == name)
+                .map(|detail| detail.creatable_topic().num_partitions)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn storage_with_configured_default() -> Result<()> {
+        let cluster = "abc";
+        let node = 12321;
+
+        let mut storage = DynoStore::new(cluster, node, InMemory::new()).num_partitions(4);
+
+        let name = "pqr";
+
+        _ = storage
+            .create_topic(
+                CreatableTopic {
+                    name: name.into(),
+                    num_partitions: -1,
+                    replication_factor: -1,
+                    assignments: Some([].into()),
+                    configs: Some([].into()),
+                },
+                false,
+            )
+            .await?;
+
+        assert_eq!(
+            Some(4),
+            storage
+                .list_topics()
+                .await?
+                .into_iter()
+                .find(|detail| detail.name() == name)
+                .map(|detail| detail.creatable_topic().num_partitions)
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn duplicate() -> Result<()> {
         let cluster = "abc";
@@ -213,7 +299,7 @@ mod tests {
 
         let name = "pqr";
         let num_partitions = 5;
-        let replication_factor = 3;
+        let replication_factor = 1;
         let assignments = Some([].into());
         let configs = Some([].into());
         let validate_only = false;
@@ -235,7 +321,7 @@ mod tests {
         assert_eq!(name, r[0].name.as_str());
         assert_ne!(Some(NULL_TOPIC_ID), r[0].topic_id);
         assert_eq!(Some(5), r[0].num_partitions);
-        assert_eq!(Some(3), r[0].replication_factor);
+        assert_eq!(Some(1), r[0].replication_factor);
         assert_eq!(ErrorCode::None, ErrorCode::try_from(r[0].error_code)?);
 
         let r = create_topic
@@ -255,7 +341,7 @@ mod tests {
         assert_eq!(name, r[0].name.as_str());
         assert_eq!(Some(NULL_TOPIC_ID), r[0].topic_id);
         assert_eq!(Some(5), r[0].num_partitions);
-        assert_eq!(Some(3), r[0].replication_factor);
+        assert_eq!(Some(1), r[0].replication_factor);
         assert_eq!(
             ErrorCode::TopicAlreadyExists,
             ErrorCode::try_from(r[0].error_code)?
@@ -17,7 +17,7 @@ use crate::Result;
 use tansu_kafka_sans_io::{
     delete_topics_request::DeleteTopicState, delete_topics_response::DeletableTopicResult,
 };
-use tansu_storage::Storage;
+use tansu_storage::{Storage, TopicId};
 use tracing::debug;
 
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -40,33 +40,37 @@ where
     ) -> Result<Vec<DeletableTopicResult>> {
         debug!(?topics, ?topic_names);
 
-        let mut responses = vec![];
+        let mut requested = vec![];
+
+        for topic in topics.unwrap_or_default() {
+            requested.push((
+                topic.name.clone(),
+                Some(topic.topic_id),
+                TopicId::from(topic),
+            ));
+        }
 
-        if let Some(topics) = topics {
-            for topic in topics {
-                let error_code = self.storage.delete_topic(&topic.clone().into()).await?;
-
-                responses.push(DeletableTopicResult {
-                    name: topic.name.clone(),
-                    topic_id: Some(topic.topic_id),
-                    error_code: i16::from(error_code),
-                    error_message: Some(error_code.to_string()),
-                });
-            }
+        for name in topic_names.unwrap_or_default() {
+            requested.push((Some(name.clone()), None, TopicId::from(name)));
         }
 
-        if let Some(topic_names) = topic_names {
-            for name in topic_names {
-                let topic_id = name.clone().into();
-                let error_code = self.storage.delete_topic(&topic_id).await?;
-
-                responses.push(DeletableTopicResult {
-                    name: Some(name),
-                    topic_id: None,
-                    error_code: i16::from(error_code),
-                    error_message: Some(error_code.to_string()),
-                });
-            }
+        let topic_ids = requested
+            .iter()
+            .map(|(_, _, topic_id)| topic_id.to_owned())
+            .collect::<Vec<_>>();
+
+        let mut responses = vec![];
+
+        for ((name, topic_id, _), (_, error_code)) in requested
+            .into_iter()
+            .zip(self.storage.delete_topics(&topic_ids).await?)
+        {
+            responses.push(DeletableTopicResult {
+                name,
+                topic_id,
+                error_code: i16::from(error_code),
+                error_message: Some(error_code.to_string()),
+            });
         }
 
         Ok(responses)
@@ -126,7 +130,7 @@ mod tests {
 
         let name = "pqr";
         let num_partitions = 5;
-        let replication_factor = 3;
+        let replication_factor = 1;
         let assignments = Some([].into());
         let configs = Some([].into());
         let validate_only = false;
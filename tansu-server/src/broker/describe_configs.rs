@@ -46,16 +46,23 @@ where
 
         if let Some(resources) = resources {
             for resource in resources {
-                results.push(
-                    self.storage
-                        .describe_config(
-                            resource.resource_name.as_str(),
-                            ConfigResource::from(resource.resource_type),
-                            resource.configuration_keys.as_deref(),
-                        )
-                        .await
-                        .inspect_err(|err| error!(?err))?,
-                );
+                let mut result = self
+                    .storage
+                    .describe_config(
+                        resource.resource_name.as_str(),
+                        ConfigResource::from(resource.resource_type),
+                        resource.configuration_keys.as_deref(),
+                    )
+                    .await
+                    .inspect_err(|err| error!(?err))?;
+
+                if !include_synonyms.unwrap_or_default() {
+                    for config in result.configs.as_deref_mut().unwrap_or_default() {
+                        config.synonyms = Some([].into());
+                    }
+                }
+
+                results.push(result);
             }
         }
 
@@ -24,8 +24,7 @@ use tansu_kafka_sans_io::{
     metadata_response::MetadataResponseTopic,
     record::{deflated::Batch, deflated::Frame},
 };
-use tansu_storage::{Storage, Topition};
-use tokio::time::sleep;
+use tansu_storage::{Storage, TopicId, Topition};
 use tracing::{debug, error};
 
 use crate::Result;
@@ -74,12 +73,20 @@ where
 
             debug!(offset);
 
-            let mut fetched = self
+            let mut fetched = match self
                 .storage
                 .fetch(&tp, offset, min_bytes, *max_bytes, isolation)
                 .await
-                .inspect(|r| debug!(?tp, ?offset, ?r))
-                .inspect_err(|error| error!(?tp, ?error))?;
+            {
+                Err(tansu_storage::Error::Api(error_code)) => {
+                    debug!(?tp, ?offset, ?error_code);
+                    return Ok(Self::error_partition(partition_index, error_code));
+                }
+
+                otherwise => otherwise
+                    .inspect(|r| debug!(?tp, ?offset, ?r))
+                    .inspect_err(|error| error!(?tp, ?error))?,
+            };
 
             *max_bytes =
                 u32::try_from(fetched.byte_size()).map(|bytes| max_bytes.saturating_sub(bytes))?;
@@ -124,6 +131,22 @@ where
         .inspect(|r| debug!(?r))
     }
 
+    fn error_partition(partition_index: i32, error_code: ErrorCode) -> PartitionData {
+        PartitionData {
+            partition_index,
+            error_code: error_code.into(),
+            high_watermark: -1,
+            last_stable_offset: Some(-1),
+            log_start_offset: Some(-1),
+            diverging_epoch: None,
+            current_leader: None,
+            snapshot_id: None,
+            aborted_transactions: Some([].into()),
+            preferred_read_replica: Some(-1),
+            records: None,
+        }
+    }
+
     fn unknown_topic_response(&self, fetch: &FetchTopic) -> Result<FetchableTopicResponse> {
         Ok(FetchableTopicResponse {
             topic: fetch.topic.clone(),
@@ -167,10 +190,13 @@ where
         isolation: IsolationLevel,
         fetch: &FetchTopic,
         _is_first: bool,
+        topitions: &mut Vec<Topition>,
     ) -> Result<FetchableTopicResponse> {
         debug!(?max_wait_ms, ?min_bytes, ?isolation, ?fetch);
 
-        let metadata = self.storage.metadata(Some(&[fetch.into()])).await?;
+        let topic_id = TopicId::try_from(fetch).inspect_err(|err| error!(?err, ?fetch))?;
+
+        let metadata = self.storage.metadata(Some(&[topic_id])).await?;
 
         if let Some(MetadataResponseTopic {
             topic_id,
@@ -192,6 +218,7 @@ where
                     )
                     .await?;
 
+                topitions.push(Topition::new(name.to_owned(), fetch_partition.partition));
                 partitions.push(partition);
             }
 
@@ -223,22 +250,40 @@ where
             let mut iteration = 0;
             let mut elapsed = Duration::from_millis(0);
             let mut bytes = 0;
+            let mut topitions = vec![];
 
             while elapsed < max_wait && bytes < min_bytes {
                 debug!(?elapsed, ?max_wait, ?bytes, ?min_bytes);
 
+                // watch the topitions of the previous fetch before fetching
+                // again, so that a batch produced meanwhile isn't missed
+                //
+                let watched = !topitions.is_empty();
+                let watch = self.storage.produce_watch(&topitions)?;
+                let produced = watch.produced();
+
                 let enumerate = topics.iter().enumerate();
                 responses.clear();
+                topitions.clear();
 
                 for (i, fetch) in enumerate {
                     let fetch_response = self
-                        .fetch_topic(max_wait, min_bytes, max_bytes, isolation, fetch, i == 0)
+                        .fetch_topic(
+                            max_wait,
+                            min_bytes,
+                            max_bytes,
+                            isolation,
+                            fetch,
+                            i == 0,
+                            &mut topitions,
+                        )
                         .await?;
 
                     responses.push(fetch_response);
                 }
 
-                bytes += u32::try_from(responses.byte_size())?;
+                // each fetch rebuilds the whole response
+                bytes = u32::try_from(responses.byte_size())?;
 
                 let now = Instant::now();
                 elapsed = now.duration_since(start);
@@ -253,12 +298,18 @@ where
                     ?min_bytes
                 );
 
-                sleep(if remaining.as_millis() >= 250 {
-                    remaining / 2
-                } else {
-                    remaining
-                })
-                .await;
+                // without a watch on these topitions, fetch again straight
+                // away with one in place
+                //
+                if bytes < min_bytes && (watched || topitions.is_empty()) {
+                    produced
+                        .wait(if remaining.as_millis() >= 250 {
+                            remaining / 2
+                        } else {
+                            remaining
+                        })
+                        .await;
+                }
 
                 iteration += 1;
             }
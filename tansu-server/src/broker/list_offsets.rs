@@ -64,7 +64,7 @@ where
 
             Some(
                 self.storage
-                    .list_offsets(isolation_level, offsets.deref())
+                    .list_offsets_batch(isolation_level, offsets.deref())
                     .await
                     .inspect(|r| debug!(?r, ?offsets))
                     .inspect_err(|err| error!(?err, ?offsets))
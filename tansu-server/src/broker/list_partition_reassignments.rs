@@ -15,13 +15,9 @@
 
 use crate::Result;
 use tansu_kafka_sans_io::{
-    Body, ErrorCode,
-    list_partition_reassignments_request::ListPartitionReassignmentsTopics,
-    list_partition_reassignments_response::{
-        OngoingPartitionReassignment, OngoingTopicReassignment,
-    },
+    Body, ErrorCode, list_partition_reassignments_request::ListPartitionReassignmentsTopics,
 };
-use tansu_storage::{Storage, TopicId};
+use tansu_storage::Storage;
 
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct ListPartitionReassignmentsRequest<S> {
@@ -40,35 +36,7 @@ where
         &mut self,
         topics: Option<&[ListPartitionReassignmentsTopics]>,
     ) -> Result<Body> {
-        let topics = topics.map(|topics| {
-            topics
-                .iter()
-                .map(|topic| TopicId::Name(topic.name.as_str().into()))
-                .collect::<Vec<_>>()
-        });
-
-        let metadata = self.storage.metadata(topics.as_deref()).await?;
-
-        let mut ongoing = vec![];
-
-        for topic in metadata.topics() {
-            let Some(ref name) = topic.name else { continue };
-
-            ongoing.push(OngoingTopicReassignment {
-                name: name.into(),
-                partitions: topic.partitions.as_ref().map(|partitions| {
-                    partitions
-                        .iter()
-                        .map(|partition| OngoingPartitionReassignment {
-                            partition_index: partition.partition_index,
-                            replicas: partition.replica_nodes.clone(),
-                            adding_replicas: Some(vec![]),
-                            removing_replicas: Some(vec![]),
-                        })
-                        .collect::<Vec<_>>()
-                }),
-            });
-        }
+        let ongoing = self.storage.list_partition_reassignments(topics).await?;
 
         Ok(Body::ListPartitionReassignmentsResponse {
             throttle_time_ms: 0,
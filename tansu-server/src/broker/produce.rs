@@ -13,18 +13,25 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{Error, Result};
+use std::collections::BTreeSet;
+
+use crate::{Error, Result, partitioner::Partitioner};
 use tansu_kafka_sans_io::{
-    ErrorCode,
+    BatchAttribute, ConfigResource, ErrorCode,
     produce_request::{PartitionProduceData, TopicProduceData},
     produce_response::{NodeEndpoint, PartitionProduceResponse, TopicProduceResponse},
+    record::{deflated, inflated},
 };
-use tansu_storage::{Storage, Topition};
+use tansu_schema_registry::{Registry, Schema, Validator};
+use tansu_storage::{Storage, TopicId, Topition};
 use tracing::{debug, error, warn};
 
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub const SCHEMA_VALIDATION_CONFIG: &str = "confluent.value.schema.validation";
+
+#[derive(Clone, Debug, Default)]
 pub struct ProduceRequest<S> {
     storage: S,
+    schemas: Option<Registry>,
 }
 
 #[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -39,7 +46,139 @@ where
     S: Storage,
 {
     pub fn with_storage(storage: S) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            schemas: None,
+        }
+    }
+
+    /// Validate produced records against the schema registry, for
+    /// topics that have opted in with `confluent.value.schema.validation`.
+    pub fn schemas(self, schemas: Option<Registry>) -> Self {
+        Self { schemas, ..self }
+    }
+
+    async fn validation(&self, name: &str) -> bool {
+        if self.schemas.is_none() {
+            return false;
+        }
+
+        self.storage
+            .describe_config(name, ConfigResource::Topic, None)
+            .await
+            .inspect_err(|err| debug!(name, ?err))
+            .is_ok_and(|topic| {
+                topic
+                    .configs
+                    .unwrap_or_default()
+                    .iter()
+                    .find(|config| config.name == SCHEMA_VALIDATION_CONFIG)
+                    .and_then(|config| config.value.as_deref())
+                    .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+            })
+    }
+
+    /// Validate the records of every partition of a topic in one pass
+    /// before anything is written, fetching the topic schema once,
+    /// returning the indexes of the partitions with invalid records.
+    async fn validate(&self, name: &str, partition_data: &[PartitionProduceData]) -> BTreeSet<i32> {
+        let Some(ref schemas) = self.schemas else {
+            return BTreeSet::new();
+        };
+
+        if !self.validation(name).await {
+            return BTreeSet::new();
+        }
+
+        let schema = match schemas.schema(name).await {
+            Ok(Some(schema)) => schema,
+
+            Ok(None) => return BTreeSet::new(),
+
+            Err(err) => {
+                debug!(name, ?err);
+
+                return partition_data
+                    .iter()
+                    .map(|partition| partition.index)
+                    .collect();
+            }
+        };
+
+        partition_data
+            .iter()
+            .filter(|partition| {
+                partition
+                    .records
+                    .as_ref()
+                    .map_or(&[][..], |records| &records.batches[..])
+                    .iter()
+                    .any(|batch| {
+                        validate(&schema, batch)
+                            .inspect_err(|err| debug!(name, partition.index, ?err))
+                            .is_err()
+                    })
+            })
+            .map(|partition| partition.index)
+            .collect()
+    }
+
+    /// The partitions with keyed records that belong to another
+    /// partition, for topics that have opted in with `tansu.partitioner`.
+    async fn misrouted(
+        &mut self,
+        name: &str,
+        partition_data: &[PartitionProduceData],
+    ) -> Result<BTreeSet<i32>> {
+        let Some(partitioner) = self
+            .storage
+            .describe_config(name, ConfigResource::Topic, None)
+            .await
+            .inspect_err(|err| debug!(name, ?err))
+            .ok()
+            .as_ref()
+            .and_then(Partitioner::configured)
+        else {
+            return Ok(BTreeSet::new());
+        };
+
+        let partitions = self
+            .storage
+            .describe_topic_partitions(Some(&[TopicId::from(name)]), i32::MAX, None)
+            .await?
+            .first()
+            .and_then(|topic| topic.partitions.as_ref())
+            .map_or(0, |partitions| partitions.len());
+
+        let partitions = i32::try_from(partitions)?;
+
+        let mut misrouted = BTreeSet::new();
+
+        for partition in partition_data {
+            for batch in partition
+                .records
+                .as_ref()
+                .map_or(&[][..], |records| &records.batches[..])
+            {
+                if BatchAttribute::try_from(batch.attributes)?.control {
+                    continue;
+                }
+
+                for record in inflated::Batch::try_from(batch)?.records {
+                    // keyless records may be produced to any partition
+                    //
+                    if partitioner
+                        .partition_for_key(record.key.as_deref(), partitions)?
+                        .is_some_and(|keyed| keyed != partition.index)
+                    {
+                        debug!(name, partition.index, ?record.key, ?partitioner);
+                        _ = misrouted.insert(partition.index);
+                    }
+                }
+            }
+        }
+
+        Ok(misrouted)
     }
 
     fn error(&self, index: i32, error_code: ErrorCode) -> PartitionProduceResponse {
@@ -110,8 +249,28 @@ where
         let mut partitions = vec![];
 
         if let Some(partition_data) = topic.partition_data {
+            // rejected before anything is persisted
+            //
+            let mut invalid = self.validate(&topic.name, &partition_data).await;
+
+            invalid.extend(
+                self.misrouted(&topic.name, &partition_data)
+                    .await
+                    .inspect_err(|err| error!(?err))
+                    .unwrap_or_else(|_| {
+                        partition_data
+                            .iter()
+                            .map(|partition| partition.index)
+                            .collect()
+                    }),
+            );
+
             for partition in partition_data {
-                partitions.push(self.partition(transaction_id, &topic.name, partition).await)
+                if invalid.contains(&partition.index) {
+                    partitions.push(self.error(partition.index, ErrorCode::InvalidRecord));
+                } else {
+                    partitions.push(self.partition(transaction_id, &topic.name, partition).await)
+                }
             }
         }
 
@@ -149,6 +308,16 @@ where
     }
 }
 
+fn validate(schema: &Schema, batch: &deflated::Batch) -> Result<()> {
+    if BatchAttribute::try_from(batch.attributes)?.control {
+        return Ok(());
+    }
+
+    inflated::Batch::try_from(batch)
+        .map_err(Into::into)
+        .and_then(|inflated| schema.validate(&inflated).map_err(Into::into))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
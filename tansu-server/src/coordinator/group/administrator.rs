@@ -1567,8 +1567,11 @@ where
     }
 
     async fn commit_offset(&mut self, detail: &OffsetCommit<'_>) -> Result<Body> {
+        // a retention of -1 (or absent) uses the broker default
+        //
         let retention_time_ms = detail
             .retention_time_ms
+            .filter(|ms| *ms >= 0)
             .map_or(Ok(None), |ms| {
                 u64::try_from(ms).map_err(Error::from).map(Some)
             })?
@@ -0,0 +1,271 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+
+use tansu_kafka_sans_io::{ErrorCode, describe_configs_response::DescribeConfigsResult};
+use tracing::debug;
+
+use crate::{Error, Result};
+
+pub const PARTITIONER_CONFIG: &str = "tansu.partitioner";
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Partitioner {
+    #[default]
+    Murmur2,
+    ConsistentHash,
+}
+
+impl Partitioner {
+    pub fn partition(&self, key: &[u8], partitions: i32) -> Result<i32> {
+        if partitions < 1 {
+            return Err(Error::Api(ErrorCode::InvalidPartitions));
+        }
+
+        let hash = to_positive(murmur2(key));
+
+        match self {
+            Self::Murmur2 => Ok(hash % partitions),
+            Self::ConsistentHash => Ok(jump_consistent_hash(hash as u64, partitions)),
+        }
+        .inspect(|partition| debug!(?self, partitions, ?partition))
+    }
+
+    /// The partition of a keyed record, or none for a keyless record
+    /// where the producer is free to choose the partition.
+    pub fn partition_for_key(&self, key: Option<&[u8]>, partitions: i32) -> Result<Option<i32>> {
+        key.map(|key| self.partition(key, partitions)).transpose()
+    }
+}
+
+/// The partition of a key chosen by the default partitioner of the
+/// Java client: `toPositive(murmur2(key)) % partitions`.
+pub fn default_partition(key: &[u8], partitions: i32) -> Result<i32> {
+    Partitioner::Murmur2.partition(key, partitions)
+}
+
+impl FromStr for Partitioner {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "murmur2" => Ok(Self::Murmur2),
+            "consistent" => Ok(Self::ConsistentHash),
+            otherwise => Err(Error::Custom(format!("unknown partitioner: {otherwise}"))),
+        }
+    }
+}
+
+impl Partitioner {
+    /// The partitioner of a topic that has opted in with `tansu.partitioner`.
+    pub fn configured(topic: &DescribeConfigsResult) -> Option<Self> {
+        topic
+            .configs
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .find(|config| config.name == PARTITIONER_CONFIG)
+            .and_then(|config| config.value.as_deref())
+            .and_then(|value| Self::from_str(value).ok())
+    }
+}
+
+impl From<&DescribeConfigsResult> for Partitioner {
+    fn from(topic: &DescribeConfigsResult) -> Self {
+        Self::configured(topic).unwrap_or_default()
+    }
+}
+
+fn to_positive(hash: i32) -> i32 {
+    hash & 0x7fffffff
+}
+
+/// The murmur2 hash used by the Java client's default partitioner.
+fn murmur2(data: &[u8]) -> i32 {
+    const SEED: u32 = 0x9747b28c;
+    const M: u32 = 0x5bd1e995;
+    const R: u32 = 24;
+
+    let mut h = SEED ^ data.len() as u32;
+
+    let chunks = data.chunks_exact(4);
+    let remainder = chunks.remainder();
+
+    for chunk in chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+
+        h = h.wrapping_mul(M);
+        h ^= k;
+    }
+
+    if remainder.len() >= 3 {
+        h ^= u32::from(remainder[2]) << 16;
+    }
+
+    if remainder.len() >= 2 {
+        h ^= u32::from(remainder[1]) << 8;
+    }
+
+    if !remainder.is_empty() {
+        h ^= u32::from(remainder[0]);
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^= h >> 15;
+
+    h as i32
+}
+
+/// Lamping and Veach jump consistent hash: growing from n to n + 1
+/// buckets only moves keys into the new bucket.
+fn jump_consistent_hash(mut key: u64, buckets: i32) -> i32 {
+    let mut b = -1i64;
+    let mut j = 0i64;
+
+    while j < i64::from(buckets) {
+        b = j;
+        key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+        j = ((b + 1) as f64 * ((1i64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+
+    b as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tansu_kafka_sans_io::{
+        ConfigResource, describe_configs_response::DescribeConfigsResourceResult,
+    };
+
+    #[test]
+    fn murmur2_java_compatible() {
+        assert_eq!(-973932308, murmur2(b"21"));
+        assert_eq!(-790332482, murmur2(b"foobar"));
+        assert_eq!(-985981536, murmur2(b"a-little-bit-long-string"));
+        assert_eq!(-1486304829, murmur2(b"a-little-bit-longer-string"));
+        assert_eq!(
+            -58897971,
+            murmur2(b"lkjh234lh9fiuh90y23oiuhsafujhadof229phr9h19h89h8")
+        );
+        assert_eq!(479470107, murmur2(b"abc"));
+    }
+
+    #[test]
+    fn default_partition_java_compatible() -> Result<()> {
+        for (key, expected) in [
+            (&b"21"[..], [0, 0, 0, 0, 40]),
+            (b"foobar", [0, 0, 0, 6, 66]),
+            (b"a-little-bit-long-string", [0, 2, 2, 8, 12]),
+            (b"abc", [0, 0, 3, 3, 7]),
+        ] {
+            for (partitions, expected) in [1, 3, 6, 12, 100].into_iter().zip(expected) {
+                assert_eq!(
+                    expected,
+                    default_partition(key, partitions)?,
+                    "{key:?}, partitions: {partitions}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn keyless_records_are_not_partitioned() -> Result<()> {
+        assert_eq!(None, Partitioner::Murmur2.partition_for_key(None, 6)?);
+        assert_eq!(
+            Some(3),
+            Partitioner::Murmur2.partition_for_key(Some(b"abc"), 6)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_partitions() {
+        assert!(matches!(
+            Partitioner::ConsistentHash.partition(b"abc", 0),
+            Err(Error::Api(ErrorCode::InvalidPartitions))
+        ));
+    }
+
+    #[test]
+    fn consistent_hash_stable_when_adding_partitions() -> Result<()> {
+        let keys = (0..10_000)
+            .map(|key| format!("key-{key}"))
+            .collect::<Vec<_>>();
+
+        let before = 12;
+        let after = 16;
+
+        let mut unmoved = 0;
+
+        for key in &keys {
+            let original = Partitioner::ConsistentHash.partition(key.as_bytes(), before)?;
+            let current = Partitioner::ConsistentHash.partition(key.as_bytes(), after)?;
+
+            if original == current {
+                unmoved += 1;
+            } else {
+                assert!(current >= before, "{key}: {original} -> {current}");
+            }
+        }
+
+        assert!(unmoved * 100 / keys.len() >= 70, "unmoved: {unmoved}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn selectable_per_topic() {
+        let topic = |value: Option<&str>| DescribeConfigsResult {
+            error_code: ErrorCode::None.into(),
+            error_message: None,
+            resource_type: i8::from(ConfigResource::Topic),
+            resource_name: "pqr".into(),
+            configs: value.map(|value| {
+                vec![DescribeConfigsResourceResult {
+                    name: PARTITIONER_CONFIG.into(),
+                    value: Some(value.into()),
+                    read_only: false,
+                    is_default: None,
+                    config_source: None,
+                    is_sensitive: false,
+                    synonyms: None,
+                    config_type: None,
+                    documentation: None,
+                }]
+            }),
+        };
+
+        assert_eq!(None, Partitioner::configured(&topic(None)));
+        assert_eq!(Partitioner::Murmur2, Partitioner::from(&topic(None)));
+        assert_eq!(
+            Partitioner::ConsistentHash,
+            Partitioner::from(&topic(Some("consistent")))
+        );
+        assert_eq!(
+            Partitioner::Murmur2,
+            Partitioner::from(&topic(Some("murmur2")))
+        );
+    }
+}
@@ -35,7 +35,7 @@ pub async fn offset_commit(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -102,7 +102,7 @@ pub async fn topic_delete_cascade_to_offset_commit(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -169,7 +169,7 @@ pub async fn consumer_group_delete_cascade_to_offset_commit(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
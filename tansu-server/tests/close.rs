@@ -0,0 +1,137 @@
+// Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use common::{StorageType, alphanumeric_string, init_tracing, register_broker};
+use rand::{prelude::*, rng};
+use tansu_kafka_sans_io::create_topics_request::CreatableTopic;
+use tansu_server::Result;
+use tansu_storage::{Error, Storage, StorageContainer, Topition};
+use tracing::debug;
+use url::Url;
+use uuid::Uuid;
+
+pub mod common;
+
+pub async fn idempotent(cluster_id: Uuid, broker_id: i32, mut sc: StorageContainer) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions: 1,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    sc.close().await?;
+    sc.close().await?;
+
+    assert!(matches!(sc.brokers().await, Err(Error::Closed)));
+
+    assert!(matches!(
+        sc.offset_stage(&Topition::new(topic_name.clone(), 0)).await,
+        Err(Error::Closed)
+    ));
+
+    assert!(matches!(
+        sc.create_topic(
+            CreatableTopic {
+                name: alphanumeric_string(15),
+                num_partitions: 1,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await,
+        Err(Error::Closed)
+    ));
+
+    Ok(())
+}
+mod pg {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::Postgres,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn idempotent() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::idempotent(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
+
+mod in_memory {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::InMemory,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn idempotent() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::idempotent(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
@@ -22,7 +22,7 @@ use rand::{
     rng,
 };
 use tansu_kafka_sans_io::{
-    Body, ErrorCode,
+    Body, ErrorCode, IsolationLevel,
     fetch_response::{FetchableTopicResponse, NodeEndpoint},
     join_group_request::JoinGroupRequestProtocol,
     join_group_response::JoinGroupResponseMember,
@@ -30,6 +30,7 @@ use tansu_kafka_sans_io::{
     leave_group_response::MemberResponse,
     offset_fetch_request::OffsetFetchRequestTopic,
     offset_fetch_response::OffsetFetchResponseTopic,
+    record::{Record, deflated, inflated},
     sync_group_request::SyncGroupRequestAssignment,
 };
 use tansu_schema_registry::Registry;
@@ -38,7 +39,8 @@ use tansu_server::{
     coordinator::group::{Coordinator, administrator::Controller},
 };
 use tansu_storage::{
-    BrokerRegistrationRequest, Storage, StorageContainer, dynostore::DynoStore, pg::Postgres,
+    BrokerRegistrationRequest, Storage, StorageContainer, Topition, dynostore::DynoStore,
+    pg::Postgres,
 };
 use tracing::{debug, subscriber::DefaultGuard};
 use tracing_subscriber::EnvFilter;
@@ -540,6 +542,48 @@ pub(crate) async fn register_broker(
         .map_err(Into::into)
 }
 
+/// A batch of a single record with value.
+pub(crate) fn value_batch(value: &str) -> Result<deflated::Batch> {
+    inflated::Batch::builder()
+        .record(Record::builder().value(Bytes::copy_from_slice(value.as_bytes()).into()))
+        .build()
+        .and_then(TryInto::try_into)
+        .inspect(|deflated| debug!(value, ?deflated))
+        .map_err(Into::into)
+}
+
+/// The offset and value of every record fetched from offset.
+pub(crate) async fn fetch_values(
+    sc: &mut StorageContainer,
+    topition: &Topition,
+    offset: i64,
+) -> Result<Vec<(i64, Bytes)>> {
+    let mut values = vec![];
+
+    for batch in sc
+        .fetch(
+            topition,
+            offset,
+            0,
+            1_048_576,
+            IsolationLevel::ReadUncommitted,
+        )
+        .await?
+    {
+        let inflated = inflated::Batch::try_from(batch)?;
+
+        for record in inflated.records {
+            let offset = inflated.base_offset + i64::from(record.offset_delta);
+
+            if let Some(value) = record.value {
+                values.push((offset, value));
+            }
+        }
+    }
+
+    Ok(values)
+}
+
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub(crate) struct FetchResponse {
     error_code: ErrorCode,
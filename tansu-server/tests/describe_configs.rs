@@ -16,10 +16,12 @@
 use common::{alphanumeric_string, register_broker};
 use rand::{prelude::*, rng};
 use tansu_kafka_sans_io::{
-    ConfigResource, ConfigSource, ErrorCode, OpType,
+    ConfigResource, ConfigSource, ConfigType, ErrorCode, OpType,
     create_topics_request::{CreatableTopic, CreatableTopicConfig},
     describe_configs_request::DescribeConfigsResource,
-    describe_configs_response::{DescribeConfigsResourceResult, DescribeConfigsResult},
+    describe_configs_response::{
+        DescribeConfigsResourceResult, DescribeConfigsResult, DescribeConfigsSynonym,
+    },
     incremental_alter_configs_request::{AlterConfigsResource, AlterableConfig},
 };
 use tansu_server::{Result, broker::describe_configs::DescribeConfigsRequest};
@@ -42,7 +44,7 @@ pub async fn single_topic(
     let compact = "compact";
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some(
         [CreatableTopicConfig {
@@ -68,7 +70,7 @@ pub async fn single_topic(
     let resources = [DescribeConfigsResource {
         resource_type: ConfigResource::Topic.into(),
         resource_name: topic_name.clone(),
-        configuration_keys: None,
+        configuration_keys: Some([cleanup_policy.into()].into()),
     }];
 
     let include_synonyms = Some(false);
@@ -95,11 +97,11 @@ pub async fn single_topic(
                     name: cleanup_policy.into(),
                     value: Some(compact.into()),
                     read_only: false,
-                    is_default: None,
-                    config_source: Some(ConfigSource::DefaultConfig.into()),
+                    is_default: Some(false),
+                    config_source: Some(ConfigSource::DynamicTopicConfig.into()),
                     is_sensitive: false,
                     synonyms: Some([].into()),
-                    config_type: Some(ConfigResource::Topic.into()),
+                    config_type: Some(ConfigType::List.into()),
                     documentation: Some("".into()),
                 }]
                 .into(),
@@ -126,7 +128,7 @@ pub async fn alter_single_topic(
     let delete = "delete";
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
 
     let topic_id = sc
         .create_topic(
@@ -144,7 +146,7 @@ pub async fn alter_single_topic(
     let resources = [DescribeConfigsResource {
         resource_type: ConfigResource::Topic.into(),
         resource_name: topic_name.clone(),
-        configuration_keys: None,
+        configuration_keys: Some([cleanup_policy.into()].into()),
     }];
 
     let include_synonyms = Some(false);
@@ -168,7 +170,20 @@ pub async fn alter_single_topic(
             error_message: Some(none.to_string()),
             resource_type: ConfigResource::Topic.into(),
             resource_name: topic_name.clone(),
-            configs: Some([].into(),),
+            configs: Some(
+                [DescribeConfigsResourceResult {
+                    name: cleanup_policy.into(),
+                    value: Some(delete.into()),
+                    read_only: false,
+                    is_default: Some(true),
+                    config_source: Some(ConfigSource::DefaultConfig.into()),
+                    is_sensitive: false,
+                    synonyms: Some([].into()),
+                    config_type: Some(ConfigType::List.into()),
+                    documentation: Some("".into()),
+                }]
+                .into(),
+            ),
         }],
     );
 
@@ -209,11 +224,11 @@ pub async fn alter_single_topic(
                     name: cleanup_policy.into(),
                     value: Some(compact.into()),
                     read_only: false,
-                    is_default: None,
-                    config_source: Some(ConfigSource::DefaultConfig.into()),
+                    is_default: Some(false),
+                    config_source: Some(ConfigSource::DynamicTopicConfig.into()),
                     is_sensitive: false,
                     synonyms: Some([].into()),
-                    config_type: Some(ConfigResource::Topic.into()),
+                    config_type: Some(ConfigType::List.into()),
                     documentation: Some("".into()),
                 }]
                 .into(),
@@ -258,11 +273,11 @@ pub async fn alter_single_topic(
                     name: cleanup_policy.into(),
                     value: Some(delete.into()),
                     read_only: false,
-                    is_default: None,
-                    config_source: Some(ConfigSource::DefaultConfig.into()),
+                    is_default: Some(false),
+                    config_source: Some(ConfigSource::DynamicTopicConfig.into()),
                     is_sensitive: false,
                     synonyms: Some([].into()),
-                    config_type: Some(ConfigResource::Topic.into()),
+                    config_type: Some(ConfigType::List.into()),
                     documentation: Some("".into()),
                 }]
                 .into(),
@@ -302,7 +317,20 @@ pub async fn alter_single_topic(
             error_message: Some(none.to_string()),
             resource_type: ConfigResource::Topic.into(),
             resource_name: topic_name.clone(),
-            configs: Some([].into(),),
+            configs: Some(
+                [DescribeConfigsResourceResult {
+                    name: cleanup_policy.into(),
+                    value: Some(delete.into()),
+                    read_only: false,
+                    is_default: Some(true),
+                    config_source: Some(ConfigSource::DefaultConfig.into()),
+                    is_sensitive: false,
+                    synonyms: Some([].into()),
+                    config_type: Some(ConfigType::List.into()),
+                    documentation: Some("".into()),
+                }]
+                .into(),
+            ),
         }],
     );
 
@@ -310,6 +338,198 @@ pub async fn alter_single_topic(
     Ok(())
 }
 
+pub async fn retention_override_and_default(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let retention_ms = "retention.ms";
+    let one_hour = "3600000";
+    let one_week = "604800000";
+
+    let overridden: String = alphanumeric_string(15);
+    let inherited: String = alphanumeric_string(15);
+    debug!(?overridden, ?inherited);
+
+    for (topic_name, configs) in [
+        (
+            &overridden,
+            vec![CreatableTopicConfig {
+                name: retention_ms.into(),
+                value: Some(one_hour.into()),
+            }],
+        ),
+        (&inherited, vec![]),
+    ] {
+        _ = sc
+            .create_topic(
+                CreatableTopic {
+                    name: topic_name.clone(),
+                    num_partitions: 1,
+                    replication_factor: 1,
+                    assignments: Some([].into()),
+                    configs: Some(configs),
+                },
+                false,
+            )
+            .await?;
+    }
+
+    let resources = [&overridden, &inherited].map(|topic_name| DescribeConfigsResource {
+        resource_type: ConfigResource::Topic.into(),
+        resource_name: topic_name.clone(),
+        configuration_keys: Some([retention_ms.into()].into()),
+    });
+
+    let results = DescribeConfigsRequest::with_storage(sc)
+        .response(Some(&resources[..]), Some(true), Some(false))
+        .await
+        .inspect(|results| debug!(?results))?;
+
+    let broker_default = DescribeConfigsSynonym {
+        name: "log.retention.ms".into(),
+        value: Some(one_week.into()),
+        source: ConfigSource::DefaultConfig.into(),
+    };
+
+    assert_eq!(
+        results,
+        vec![
+            DescribeConfigsResult {
+                error_code: ErrorCode::None.into(),
+                error_message: Some(ErrorCode::None.to_string()),
+                resource_type: ConfigResource::Topic.into(),
+                resource_name: overridden,
+                configs: Some(
+                    [DescribeConfigsResourceResult {
+                        name: retention_ms.into(),
+                        value: Some(one_hour.into()),
+                        read_only: false,
+                        is_default: Some(false),
+                        config_source: Some(ConfigSource::DynamicTopicConfig.into()),
+                        is_sensitive: false,
+                        synonyms: Some(
+                            [
+                                DescribeConfigsSynonym {
+                                    name: retention_ms.into(),
+                                    value: Some(one_hour.into()),
+                                    source: ConfigSource::DynamicTopicConfig.into(),
+                                },
+                                broker_default.clone(),
+                            ]
+                            .into(),
+                        ),
+                        config_type: Some(ConfigType::Long.into()),
+                        documentation: Some("".into()),
+                    }]
+                    .into(),
+                ),
+            },
+            DescribeConfigsResult {
+                error_code: ErrorCode::None.into(),
+                error_message: Some(ErrorCode::None.to_string()),
+                resource_type: ConfigResource::Topic.into(),
+                resource_name: inherited,
+                configs: Some(
+                    [DescribeConfigsResourceResult {
+                        name: retention_ms.into(),
+                        value: Some(one_week.into()),
+                        read_only: false,
+                        is_default: Some(true),
+                        config_source: Some(ConfigSource::DefaultConfig.into()),
+                        is_sensitive: false,
+                        synonyms: Some([broker_default].into()),
+                        config_type: Some(ConfigType::Long.into()),
+                        documentation: Some("".into()),
+                    }]
+                    .into(),
+                ),
+            },
+        ],
+    );
+
+    Ok(())
+}
+
+pub async fn broker_defaults(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let resources = [
+        DescribeConfigsResource {
+            resource_type: ConfigResource::Broker.into(),
+            resource_name: broker_id.to_string(),
+            configuration_keys: None,
+        },
+        DescribeConfigsResource {
+            resource_type: ConfigResource::Broker.into(),
+            resource_name: broker_id.to_string(),
+            configuration_keys: Some(["offsets.retention.minutes".into()].into()),
+        },
+        DescribeConfigsResource {
+            resource_type: ConfigResource::Broker.into(),
+            resource_name: broker_id.wrapping_add(1).to_string(),
+            configuration_keys: None,
+        },
+    ];
+
+    let results = DescribeConfigsRequest::with_storage(sc)
+        .response(Some(&resources[..]), Some(false), Some(false))
+        .await
+        .inspect(|results| debug!(?results))?;
+
+    assert_eq!(3, results.len());
+
+    let offsets_retention = DescribeConfigsResourceResult {
+        name: "offsets.retention.minutes".into(),
+        value: Some("10080".into()),
+        read_only: true,
+        is_default: Some(true),
+        config_source: Some(ConfigSource::DefaultConfig.into()),
+        is_sensitive: false,
+        synonyms: Some([].into()),
+        config_type: Some(ConfigType::Int.into()),
+        documentation: Some("".into()),
+    };
+
+    assert_eq!(i16::from(ErrorCode::None), results[0].error_code);
+    assert_eq!(i8::from(ConfigResource::Broker), results[0].resource_type);
+    assert_eq!(broker_id.to_string(), results[0].resource_name);
+
+    let configs = results[0].configs.as_deref().unwrap_or_default();
+    assert!(configs.contains(&offsets_retention));
+    assert!(
+        configs
+            .iter()
+            .any(|config| config.name == "offset.metadata.max.bytes"
+                && config.value.as_deref() == Some("4096")
+                && config.is_default == Some(true))
+    );
+    assert!(
+        configs
+            .iter()
+            .any(|config| config.name == "log.message.timestamp.type"
+                && config.value.as_deref() == Some("CreateTime")
+                && config.is_default == Some(true))
+    );
+    assert!(configs.iter().any(|config| config.name == "broker.id"
+        && config.value == Some(broker_id.to_string())
+        && config.config_source == Some(ConfigSource::StaticBrokerConfig.into())));
+
+    assert_eq!(i16::from(ErrorCode::None), results[1].error_code);
+    assert_eq!(Some(vec![offsets_retention]), results[1].configs);
+
+    assert_eq!(i16::from(ErrorCode::InvalidRequest), results[2].error_code);
+    assert_eq!(Some([].into()), results[2].configs);
+
+    Ok(())
+}
+
 mod pg {
     use common::{StorageType, init_tracing};
     use url::Url;
@@ -345,6 +565,36 @@ mod pg {
         .await
     }
 
+    #[tokio::test]
+    async fn broker_defaults() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::broker_defaults(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn retention_override_and_default() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::retention_override_and_default(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
     #[tokio::test]
     async fn single_topic() -> Result<()> {
         let _guard = init_tracing()?;
@@ -396,6 +646,36 @@ mod in_memory {
         .await
     }
 
+    #[tokio::test]
+    async fn broker_defaults() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::broker_defaults(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn retention_override_and_default() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::retention_override_and_default(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
     #[tokio::test]
     async fn single_topic() -> Result<()> {
         let _guard = init_tracing()?;
@@ -21,6 +21,7 @@ use rand::{prelude::*, rng};
 use tansu_kafka_sans_io::{
     ErrorCode, IsolationLevel, NULL_TOPIC_ID,
     create_topics_request::CreatableTopic,
+    delete_records_request::{DeleteRecordsPartition, DeleteRecordsTopic},
     fetch_request::{FetchPartition, FetchTopic},
     record::{Record, inflated},
 };
@@ -39,7 +40,7 @@ pub async fn empty_topic(cluster_id: Uuid, broker_id: i32, mut sc: StorageContai
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -142,7 +143,7 @@ pub async fn simple_non_txn(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -296,6 +297,139 @@ pub async fn simple_non_txn(
     Ok(())
 }
 
+pub async fn after_delete_records(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let num_partitions = 6;
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let topition = Topition::new(topic_name.clone(), rng().random_range(0..num_partitions));
+
+    for offset in 0..5 {
+        let batch = inflated::Batch::builder()
+            .record(Record::builder().value(Bytes::from(alphanumeric_string(15)).into()))
+            .build()
+            .and_then(TryInto::try_into)?;
+
+        assert_eq!(offset, sc.produce(None, &topition, batch).await?);
+    }
+
+    let log_start = 3;
+
+    let deleted = sc
+        .delete_records(&[DeleteRecordsTopic {
+            name: topition.topic().into(),
+            partitions: Some(
+                [DeleteRecordsPartition {
+                    partition_index: topition.partition(),
+                    offset: log_start,
+                }]
+                .into(),
+            ),
+        }])
+        .await?;
+    debug!(?deleted);
+
+    assert_eq!(log_start, sc.offset_stage(&topition).await?.log_start());
+
+    // below the log start is out of range, rather than fetching
+    // whatever happens to remain
+    //
+    for offset in 0..log_start {
+        assert!(matches!(
+            offsets(&mut sc, &topition, offset).await,
+            Err(tansu_storage::Error::Api(ErrorCode::OffsetOutOfRange))
+        ));
+    }
+
+    assert_eq!(vec![3, 4], offsets(&mut sc, &topition, log_start).await?);
+    assert_eq!(vec![4], offsets(&mut sc, &topition, log_start + 1).await?);
+
+    // which the broker reports as an error of the partition
+    //
+    let topics = [FetchTopic {
+        topic: Some(topition.topic().to_string()),
+        topic_id: Some(NULL_TOPIC_ID),
+        partitions: Some(vec![FetchPartition {
+            partition: topition.partition(),
+            current_leader_epoch: Some(-1),
+            fetch_offset: log_start - 1,
+            last_fetched_epoch: Some(-1),
+            log_start_offset: Some(-1),
+            partition_max_bytes: 50 * 1024,
+            replica_directory_id: None,
+        }]),
+    }];
+
+    let fetch: FetchResponse = FetchRequest::with_storage(sc.clone())
+        .response(
+            500,
+            1,
+            Some(50 * 1024),
+            Some((&IsolationLevel::ReadUncommitted).into()),
+            Some(&topics[..]),
+        )
+        .await
+        .and_then(TryInto::try_into)?;
+
+    assert_eq!(
+        vec![i16::from(ErrorCode::OffsetOutOfRange)],
+        fetch
+            .responses()
+            .iter()
+            .flat_map(|response| response.partitions.as_deref().unwrap_or(&[]))
+            .map(|partition| partition.error_code)
+            .collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
+/// The base offset of each batch fetched from offset.
+/// The offsets fetched, whether the storage keeps the produced batches
+/// or builds batches of its own from the stored records.
+async fn offsets(
+    sc: &mut StorageContainer,
+    topition: &Topition,
+    offset: i64,
+) -> tansu_storage::Result<Vec<i64>> {
+    sc.fetch(
+        topition,
+        offset,
+        0,
+        50 * 1024,
+        IsolationLevel::ReadUncommitted,
+    )
+    .await
+    .map(|batches| {
+        batches
+            .iter()
+            .flat_map(|batch| {
+                batch.base_offset..=batch.base_offset + i64::from(batch.last_offset_delta)
+            })
+            .collect()
+    })
+}
+
 mod pg {
     use super::*;
 
@@ -342,6 +476,21 @@ mod pg {
         )
         .await
     }
+
+    #[tokio::test]
+    async fn after_delete_records() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::after_delete_records(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
 }
 
 mod in_memory {
@@ -390,4 +539,19 @@ mod in_memory {
         )
         .await
     }
+
+    #[tokio::test]
+    async fn after_delete_records() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::after_delete_records(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
 }
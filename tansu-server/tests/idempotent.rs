@@ -34,7 +34,7 @@ use tansu_server::{
         produce::{ProduceRequest, ProduceResponse},
     },
 };
-use tansu_storage::{Storage, StorageContainer};
+use tansu_storage::{ProducerState, Storage, StorageContainer, Topition};
 use url::Url;
 use uuid::Uuid;
 
@@ -504,6 +504,169 @@ async fn non_txn_idempotent_sequence_out_of_order(
     Ok(())
 }
 
+async fn produce_error_code(
+    request: &mut ProduceRequest<StorageContainer>,
+    topic: &str,
+    index: i32,
+    producer_id: i64,
+    base_sequence: i32,
+) -> Result<Option<i16>> {
+    request
+        .response(
+            None,
+            0,
+            0,
+            topic_data(
+                topic,
+                index,
+                inflated::Batch::builder()
+                    .record(Record::builder().value(Bytes::from_static(b"consectetur").into()))
+                    .base_sequence(base_sequence)
+                    .producer_id(producer_id),
+            )?,
+        )
+        .await
+        .map(|response| {
+            response
+                .responses
+                .unwrap_or_default()
+                .into_iter()
+                .flat_map(|topic| topic.partition_responses.unwrap_or_default())
+                .map(|partition| partition.error_code)
+                .next()
+        })
+}
+
+async fn non_txn_idempotent_sequence_window(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic = alphanumeric_string(10);
+    let index = 0;
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic.clone(),
+                num_partitions: 3,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let producer = InitProducerIdRequest::with_storage(sc.clone())
+        .response(None, 0, Some(-1), Some(-1))
+        .await?;
+
+    let mut request = ProduceRequest::with_storage(sc.clone());
+
+    // one more batch than the window, evicting the first
+    //
+    for base_sequence in 0..=5 {
+        assert_eq!(
+            Some(ErrorCode::None.into()),
+            produce_error_code(&mut request, &topic, index, producer.id, base_sequence).await?
+        );
+    }
+
+    assert_eq!(
+        Some(ErrorCode::DuplicateSequenceNumber.into()),
+        produce_error_code(&mut request, &topic, index, producer.id, 1).await?
+    );
+
+    assert_eq!(
+        Some(ErrorCode::OutOfOrderSequenceNumber.into()),
+        produce_error_code(&mut request, &topic, index, producer.id, 0).await?
+    );
+
+    Ok(())
+}
+
+async fn describe_producers(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic = alphanumeric_string(10);
+    let index = 0;
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic.clone(),
+                num_partitions: 3,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let alpha = InitProducerIdRequest::with_storage(sc.clone())
+        .response(None, 0, Some(-1), Some(-1))
+        .await?;
+
+    let beta = InitProducerIdRequest::with_storage(sc.clone())
+        .response(None, 0, Some(-1), Some(-1))
+        .await?;
+
+    let mut request = ProduceRequest::with_storage(sc.clone());
+
+    for base_sequence in 0..3 {
+        assert_eq!(
+            Some(ErrorCode::None.into()),
+            produce_error_code(&mut request, &topic, index, alpha.id, base_sequence).await?
+        );
+    }
+
+    assert_eq!(
+        Some(ErrorCode::None.into()),
+        produce_error_code(&mut request, &topic, index, beta.id, 0).await?
+    );
+
+    let mut producers = sc
+        .describe_producers(&Topition::new(topic.clone(), index))
+        .await?;
+    producers.sort_by_key(|producer| producer.producer_id);
+
+    assert_eq!(
+        vec![
+            ProducerState {
+                producer_id: alpha.id,
+                producer_epoch: alpha.epoch,
+                last_sequence: 2,
+                transaction_id: None,
+            },
+            ProducerState {
+                producer_id: beta.id,
+                producer_epoch: beta.epoch,
+                last_sequence: 0,
+                transaction_id: None,
+            },
+        ],
+        producers
+    );
+
+    // neither producer has produced to another partition
+    //
+    assert!(
+        sc.describe_producers(&Topition::new(topic, index + 1))
+            .await?
+            .is_empty()
+    );
+
+    Ok(())
+}
+
 mod pg {
     use super::*;
 
@@ -579,6 +742,36 @@ mod pg {
         )
         .await
     }
+
+    #[tokio::test]
+    async fn non_txn_idempotent_sequence_window() -> Result<()> {
+        let _guard = common::init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::non_txn_idempotent_sequence_window(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn describe_producers() -> Result<()> {
+        let _guard = common::init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::describe_producers(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
 }
 
 mod in_memory {
@@ -657,4 +850,34 @@ mod in_memory {
         )
         .await
     }
+
+    #[tokio::test]
+    async fn non_txn_idempotent_sequence_window() -> Result<()> {
+        let _guard = common::init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::non_txn_idempotent_sequence_window(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn describe_producers() -> Result<()> {
+        let _guard = common::init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::describe_producers(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
 }
@@ -19,7 +19,9 @@ use rand::{prelude::*, rng};
 use tansu_kafka_sans_io::{
     IsolationLevel,
     create_topics_request::CreatableTopic,
+    delete_records_request::{DeleteRecordsPartition, DeleteRecordsTopic},
     record::{Record, inflated},
+    to_system_time,
 };
 use tansu_server::Result;
 use tansu_storage::{ListOffsetRequest, Storage, StorageContainer, Topition};
@@ -36,7 +38,7 @@ pub async fn new_topic(cluster_id: Uuid, broker_id: i32, mut sc: StorageContaine
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -85,7 +87,7 @@ pub async fn single_record(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -133,6 +135,349 @@ pub async fn single_record(
     Ok(())
 }
 
+async fn timestamp_offset(
+    sc: &mut StorageContainer,
+    topition: &Topition,
+    timestamp: i64,
+) -> Result<Option<i64>> {
+    let offsets = [(
+        topition.clone(),
+        ListOffsetRequest::Timestamp(to_system_time(timestamp)?),
+    )];
+
+    sc.list_offsets(IsolationLevel::ReadUncommitted, &offsets[..])
+        .await
+        .inspect(|responses| debug!(timestamp, ?responses))
+        .map(|responses| responses[0].1.offset)
+        .map_err(Into::into)
+}
+
+pub async fn timestamp(cluster_id: Uuid, broker_id: i32, mut sc: StorageContainer) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let num_partitions = 6;
+    let replication_factor = 1;
+    let assignments = Some([].into());
+    let configs = Some([].into());
+
+    let topic_id = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions,
+                replication_factor,
+                assignments: assignments.clone(),
+                configs: configs.clone(),
+            },
+            false,
+        )
+        .await?;
+    debug!(?topic_id);
+
+    let partition_index = rng().random_range(0..num_partitions);
+    let topition = Topition::new(topic_name.clone(), partition_index);
+
+    let base_timestamp = 1_700_000_000_000;
+
+    for offset in 0..4 {
+        let batch = inflated::Batch::builder()
+            .base_timestamp(base_timestamp + (offset * 1_000))
+            .record(
+                Record::builder()
+                    .value(Bytes::copy_from_slice(alphanumeric_string(15).as_bytes()).into()),
+            )
+            .build()
+            .and_then(TryInto::try_into)
+            .inspect(|deflated| debug!(?deflated))?;
+
+        assert_eq!(offset, sc.produce(None, &topition, batch).await?);
+    }
+
+    // before, within and after the retained range
+    //
+    assert_eq!(
+        Some(0),
+        timestamp_offset(&mut sc, &topition, base_timestamp - 1_000).await?
+    );
+    assert_eq!(
+        Some(2),
+        timestamp_offset(&mut sc, &topition, base_timestamp + 2_000).await?
+    );
+    assert_eq!(
+        Some(2),
+        timestamp_offset(&mut sc, &topition, base_timestamp + 1_500).await?
+    );
+    assert_eq!(
+        Some(4),
+        timestamp_offset(&mut sc, &topition, base_timestamp + 4_000).await?
+    );
+
+    let deleted = sc
+        .delete_records(&[DeleteRecordsTopic {
+            name: topic_name.clone(),
+            partitions: Some(
+                [DeleteRecordsPartition {
+                    partition_index,
+                    offset: 2,
+                }]
+                .into(),
+            ),
+        }])
+        .await?;
+    debug!(?deleted);
+
+    // once deleted, a timestamp before the log start is the log start
+    //
+    assert_eq!(
+        Some(2),
+        timestamp_offset(&mut sc, &topition, base_timestamp - 1_000).await?
+    );
+    assert_eq!(
+        Some(2),
+        timestamp_offset(&mut sc, &topition, base_timestamp).await?
+    );
+    assert_eq!(
+        Some(3),
+        timestamp_offset(&mut sc, &topition, base_timestamp + 3_000).await?
+    );
+    assert_eq!(
+        Some(4),
+        timestamp_offset(&mut sc, &topition, base_timestamp + 4_000).await?
+    );
+
+    Ok(())
+}
+
+pub async fn fetch_from_timestamp(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let num_partitions = 6;
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let partition_index = rng().random_range(0..num_partitions);
+    let topition = Topition::new(topic_name.clone(), partition_index);
+
+    let base_timestamp = 1_700_000_000_000;
+
+    let mut values = vec![];
+
+    for offset in 0..4 {
+        let value = Bytes::copy_from_slice(alphanumeric_string(15).as_bytes());
+        values.push(value.clone());
+
+        let batch = inflated::Batch::builder()
+            .base_timestamp(base_timestamp + (offset * 1_000))
+            .record(Record::builder().value(value.into()))
+            .build()
+            .and_then(TryInto::try_into)
+            .inspect(|deflated| debug!(?deflated))?;
+
+        assert_eq!(offset, sc.produce(None, &topition, batch).await?);
+    }
+
+    // the first batch at or after a mid-range timestamp
+    //
+    let batch = sc
+        .fetch_from_timestamp(&topition, to_system_time(base_timestamp + 1_500)?, 50_000)
+        .await
+        .map(inflated::Batch::try_from)??;
+
+    assert_eq!(2, batch.base_offset);
+    assert_eq!(base_timestamp + 2_000, batch.base_timestamp);
+    assert_eq!(Some(values[2].clone()), batch.records[0].value);
+
+    Ok(())
+}
+
+pub async fn delete_all_records(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let num_partitions = 6;
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let partition_index = rng().random_range(0..num_partitions);
+    let topition = Topition::new(topic_name.clone(), partition_index);
+
+    let produce = |offset: i64| {
+        inflated::Batch::builder()
+            .record(
+                Record::builder()
+                    .value(Bytes::copy_from_slice(alphanumeric_string(15).as_bytes()).into()),
+            )
+            .build()
+            .and_then(TryInto::try_into)
+            .inspect(|deflated| debug!(offset, ?deflated))
+    };
+
+    for offset in 0..3 {
+        assert_eq!(offset, sc.produce(None, &topition, produce(offset)?).await?);
+    }
+
+    let deleted = sc
+        .delete_records(&[DeleteRecordsTopic {
+            name: topic_name.clone(),
+            partitions: Some(
+                [DeleteRecordsPartition {
+                    partition_index,
+                    offset: -1,
+                }]
+                .into(),
+            ),
+        }])
+        .await?;
+    debug!(?deleted);
+
+    let offset_stage = sc.offset_stage(&topition).await?;
+    assert_eq!(3, offset_stage.high_watermark());
+
+    // deleting everything moves the low watermark to the log end offset
+    //
+    assert_eq!(
+        Some(offset_stage.high_watermark()),
+        deleted[0]
+            .partitions
+            .as_deref()
+            .and_then(|partitions| partitions.first())
+            .map(|partition| partition.low_watermark)
+    );
+    assert_eq!(offset_stage.high_watermark(), offset_stage.log_start());
+
+    let offsets = [
+        (topition.clone(), ListOffsetRequest::Earliest),
+        (topition.clone(), ListOffsetRequest::Latest),
+    ];
+
+    for (_topition, response) in sc
+        .list_offsets(IsolationLevel::ReadUncommitted, &offsets[..])
+        .await?
+    {
+        assert_eq!(Some(offset_stage.high_watermark()), response.offset);
+    }
+
+    assert_eq!(3, sc.produce(None, &topition, produce(3)?).await?);
+
+    Ok(())
+}
+
+pub async fn batch_matches_per_partition(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let num_partitions = 50;
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let base_timestamp = 1_700_000_000_000;
+
+    // a varying number of records in each partition, leaving some empty
+    //
+    for partition in 0..num_partitions {
+        let topition = Topition::new(topic_name.clone(), partition);
+
+        for offset in 0..i64::from(partition % 4) {
+            let batch = inflated::Batch::builder()
+                .base_timestamp(base_timestamp + (offset * 1_000))
+                .record(
+                    Record::builder()
+                        .value(Bytes::copy_from_slice(alphanumeric_string(15).as_bytes()).into()),
+                )
+                .build()
+                .and_then(TryInto::try_into)
+                .inspect(|deflated| debug!(?deflated))?;
+
+            assert_eq!(offset, sc.produce(None, &topition, batch).await?);
+        }
+    }
+
+    let offsets = (0..num_partitions)
+        .map(|partition| {
+            let request = match partition % 3 {
+                0 => Ok(ListOffsetRequest::Earliest),
+                1 => Ok(ListOffsetRequest::Latest),
+                _ => to_system_time(base_timestamp + 1_000).map(ListOffsetRequest::Timestamp),
+            };
+
+            request.map(|request| (Topition::new(topic_name.clone(), partition), request))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for isolation_level in [
+        IsolationLevel::ReadUncommitted,
+        IsolationLevel::ReadCommitted,
+    ] {
+        let batched = sc.list_offsets_batch(isolation_level, &offsets[..]).await?;
+        debug!(?isolation_level, ?batched);
+
+        let mut per_partition = vec![];
+
+        for offset in &offsets {
+            per_partition.extend(sc.list_offsets(isolation_level, &[offset.clone()]).await?);
+        }
+
+        assert_eq!(per_partition, batched);
+    }
+
+    Ok(())
+}
+
 mod pg {
     use super::*;
 
@@ -179,6 +524,66 @@ mod pg {
         )
         .await
     }
+
+    #[tokio::test]
+    async fn timestamp() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::timestamp(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn fetch_from_timestamp() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::fetch_from_timestamp(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn delete_all_records() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::delete_all_records(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn batch_matches_per_partition() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::batch_matches_per_partition(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
 }
 
 mod in_memory {
@@ -227,4 +632,34 @@ mod in_memory {
         )
         .await
     }
+
+    #[tokio::test]
+    async fn delete_all_records() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::delete_all_records(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn batch_matches_per_partition() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::batch_matches_per_partition(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
 }
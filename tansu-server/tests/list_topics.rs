@@ -0,0 +1,170 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use common::{StorageType, alphanumeric_string, init_tracing, register_broker};
+use rand::{prelude::*, rng};
+use tansu_kafka_sans_io::create_topics_request::{
+    CreatableReplicaAssignment, CreatableTopic, CreatableTopicConfig,
+};
+use tansu_server::Result;
+use tansu_storage::{Storage, StorageContainer, TopicDetail};
+use tracing::debug;
+use url::Url;
+use uuid::Uuid;
+
+pub mod common;
+
+pub async fn two_topics(cluster_id: Uuid, broker_id: i32, mut sc: StorageContainer) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let replicated = CreatableTopic {
+        name: alphanumeric_string(15),
+        num_partitions: 3,
+        replication_factor: 1,
+        assignments: Some([].into()),
+        configs: Some(
+            [CreatableTopicConfig {
+                name: "cleanup.policy".into(),
+                value: Some("compact".into()),
+            }]
+            .into(),
+        ),
+    };
+
+    let internal = CreatableTopic {
+        name: format!("__{}", alphanumeric_string(15)),
+        num_partitions: 1,
+        replication_factor: 1,
+        assignments: Some([].into()),
+        configs: Some([].into()),
+    };
+
+    let replicated_id = sc.create_topic(replicated.clone(), false).await?;
+    let internal_id = sc.create_topic(internal.clone(), false).await?;
+
+    let topics = sc.list_topics().await?;
+    debug!(?topics);
+
+    let detail = |name: &str| topics.iter().find(|topic| topic.name() == name).cloned();
+
+    assert_eq!(
+        Some(
+            TopicDetail::new(
+                replicated_id.into_bytes(),
+                CreatableTopic {
+                    assignments: Some(
+                        (0..3)
+                            .map(|partition_index| CreatableReplicaAssignment {
+                                partition_index,
+                                broker_ids: Some(vec![broker_id]),
+                            })
+                            .collect(),
+                    ),
+                    ..replicated.clone()
+                },
+            )
+            .internal(false)
+        ),
+        detail(&replicated.name)
+    );
+
+    assert_eq!(
+        Some(
+            TopicDetail::new(
+                internal_id.into_bytes(),
+                CreatableTopic {
+                    assignments: Some(
+                        [CreatableReplicaAssignment {
+                            partition_index: 0,
+                            broker_ids: Some(vec![broker_id]),
+                        }]
+                        .into(),
+                    ),
+                    ..internal.clone()
+                },
+            )
+            .internal(true)
+        ),
+        detail(&internal.name)
+    );
+
+    Ok(())
+}
+
+mod pg {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::Postgres,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn two_topics() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::two_topics(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
+
+mod in_memory {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::InMemory,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn two_topics() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::two_topics(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use bytes::Bytes;
+use common::{StorageType, alphanumeric_string, init_tracing, register_broker};
+use rand::{prelude::*, rng};
+use tansu_kafka_sans_io::{
+    create_topics_request::CreatableTopic,
+    delete_records_request::{DeleteRecordsPartition, DeleteRecordsTopic},
+    record::{Record, inflated},
+};
+use tansu_server::Result;
+use tansu_storage::{Storage, StorageContainer, Topition};
+use tracing::debug;
+use url::Url;
+use uuid::Uuid;
+
+pub mod common;
+
+/// The topition, log start, log end, record count and size of each log dir.
+async fn log_dirs(sc: &mut StorageContainer) -> Result<Vec<(Topition, i64, i64, i64, i64)>> {
+    sc.log_dirs()
+        .await
+        .map(|log_dirs| {
+            log_dirs
+                .into_iter()
+                .map(|log_dir| {
+                    (
+                        log_dir.topition().to_owned(),
+                        log_dir.log_start(),
+                        log_dir.log_end(),
+                        log_dir.record_count(),
+                        log_dir.size(),
+                    )
+                })
+                .collect()
+        })
+        .inspect(|log_dirs| debug!(?log_dirs))
+        .map_err(Into::into)
+}
+
+pub async fn produce_and_delete(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let num_partitions = 3;
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let partition_index = rng().random_range(0..num_partitions);
+    let topition = Topition::new(topic_name.clone(), partition_index);
+
+    let expected = |log_start: i64, log_end: i64, size: i64| {
+        (0..num_partitions)
+            .map(|partition| {
+                if partition == partition_index {
+                    (
+                        Topition::new(topic_name.clone(), partition),
+                        log_start,
+                        log_end,
+                        log_end - log_start,
+                        size,
+                    )
+                } else {
+                    (Topition::new(topic_name.clone(), partition), 0, 0, 0, 0)
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(expected(0, 0, 0), log_dirs(&mut sc).await?);
+
+    // each record has a 5 byte key and a 15 byte value
+    //
+    for records in [2, 3, 4] {
+        let mut batch = inflated::Batch::builder().last_offset_delta(records - 1);
+
+        for offset_delta in 0..records {
+            batch = batch.record(
+                Record::builder()
+                    .offset_delta(offset_delta)
+                    .key(Bytes::copy_from_slice(alphanumeric_string(5).as_bytes()).into())
+                    .value(Bytes::copy_from_slice(alphanumeric_string(15).as_bytes()).into()),
+            );
+        }
+
+        let batch = batch
+            .build()
+            .and_then(TryInto::try_into)
+            .inspect(|deflated| debug!(?deflated))?;
+
+        _ = sc
+            .produce(None, &topition, batch)
+            .await
+            .inspect(|offset| debug!(?offset))?;
+    }
+
+    assert_eq!(expected(0, 9, 9 * 20), log_dirs(&mut sc).await?);
+
+    _ = sc
+        .delete_records(&[DeleteRecordsTopic {
+            name: topic_name.clone(),
+            partitions: Some(
+                [DeleteRecordsPartition {
+                    partition_index,
+                    offset: 5,
+                }]
+                .into(),
+            ),
+        }])
+        .await?;
+
+    assert_eq!(expected(5, 9, 4 * 20), log_dirs(&mut sc).await?);
+
+    Ok(())
+}
+mod pg {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::Postgres,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn produce_and_delete() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::produce_and_delete(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
+
+mod in_memory {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::InMemory,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn produce_and_delete() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::produce_and_delete(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
@@ -36,7 +36,7 @@ pub async fn topics_none(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 3;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -146,7 +146,7 @@ pub async fn topics_some_empty(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 3;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -256,7 +256,7 @@ pub async fn topics_some_matching_by_name(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 3;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -405,7 +405,7 @@ pub async fn topics_some_matching_by_id(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 3;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -540,6 +540,91 @@ pub async fn topics_some_not_matching_by_id(
     Ok(())
 }
 
+pub async fn leadership_stable(
+    cluster_id: Uuid,
+    broker_id: i32,
+    advertised_listener: Url,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    debug!(%cluster_id, broker_id, %advertised_listener);
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let num_partitions = 6;
+    let replication_factor = 1;
+    let assignments = Some([].into());
+    let configs = Some([].into());
+
+    let id = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions,
+                replication_factor,
+                assignments: assignments.clone(),
+                configs: configs.clone(),
+            },
+            false,
+        )
+        .await
+        .inspect(|topic_id| debug!(?topic_id))?;
+
+    let topics = [TopicId::Name(topic_name.clone())];
+
+    let first = sc
+        .metadata(Some(&topics))
+        .await
+        .inspect(|metadata| debug!(?metadata))?;
+
+    let second = sc
+        .metadata(Some(&topics))
+        .await
+        .inspect(|metadata| debug!(?metadata))?;
+
+    assert_eq!(1, first.topics().len());
+    assert_eq!(Some(id.as_bytes()), first.topics()[0].topic_id.as_ref());
+    assert_eq!(
+        num_partitions,
+        first.topics()[0]
+            .partitions
+            .as_deref()
+            .unwrap_or_default()
+            .len() as i32
+    );
+
+    assert_eq!(first.topics()[0].partitions, second.topics()[0].partitions);
+
+    // added partitions are assigned to the registered brokers
+    //
+    assert_eq!(
+        ErrorCode::None,
+        sc.create_partitions(&topics[0], num_partitions + 3, None)
+            .await?
+    );
+
+    let third = sc
+        .metadata(Some(&topics))
+        .await
+        .inspect(|metadata| debug!(?metadata))?;
+
+    let partitions = third.topics()[0].partitions.as_deref().unwrap_or_default();
+
+    assert_eq!(num_partitions + 3, partitions.len() as i32);
+    assert_eq!(
+        first.topics()[0].partitions.as_deref().unwrap_or_default(),
+        &partitions[..num_partitions as usize]
+    );
+
+    for partition in &partitions[num_partitions as usize..] {
+        assert_eq!(broker_id, partition.leader_id);
+        assert_eq!(Some(&[broker_id][..]), partition.replica_nodes.as_deref());
+    }
+
+    Ok(())
+}
+
 mod pg {
     use common::{StorageType, init_tracing};
     use rand::{prelude::*, rng};
@@ -661,6 +746,23 @@ mod pg {
         )
         .await
     }
+
+    #[tokio::test]
+    async fn leadership_stable() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster = Uuid::now_v7();
+        let node = rng().random_range(0..i32::MAX);
+        let advertised_listener = Url::parse("tcp://example.com:9092/")?;
+
+        super::leadership_stable(
+            cluster,
+            node,
+            advertised_listener.clone(),
+            storage_container(cluster, node, advertised_listener)?,
+        )
+        .await
+    }
 }
 
 mod in_memory {
@@ -0,0 +1,189 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use common::{StorageType, alphanumeric_string, init_tracing, register_broker};
+use rand::{prelude::*, rng};
+use tansu_kafka_sans_io::{ErrorCode, create_topics_request::CreatableTopic};
+use tansu_server::Result;
+use tansu_storage::{
+    OFFSET_METADATA_MAX_BYTES, OffsetCommitRequest, Storage, StorageContainer, Topition,
+};
+use tracing::debug;
+use url::Url;
+use uuid::Uuid;
+
+pub mod common;
+
+pub async fn mixed_partitions(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions: 2,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let group_id: String = alphanumeric_string(15);
+    debug!(?group_id);
+
+    let valid = Topition::new(topic_name.clone(), 0);
+    let too_large = Topition::new(topic_name.clone(), 1);
+    let unknown_partition = Topition::new(topic_name.clone(), 2);
+    let unknown_topic = Topition::new(alphanumeric_string(15), 0);
+
+    let offsets = [
+        (
+            valid.clone(),
+            OffsetCommitRequest::default()
+                .offset(32123)
+                .metadata(Some("valid".into())),
+        ),
+        (
+            too_large.clone(),
+            OffsetCommitRequest::default()
+                .offset(12321)
+                .metadata(Some("x".repeat(OFFSET_METADATA_MAX_BYTES + 1))),
+        ),
+        (
+            unknown_partition.clone(),
+            OffsetCommitRequest::default().offset(45654),
+        ),
+        (
+            unknown_topic.clone(),
+            OffsetCommitRequest::default().offset(78987),
+        ),
+    ];
+
+    assert_eq!(
+        vec![
+            (valid.clone(), ErrorCode::None),
+            (too_large.clone(), ErrorCode::OffsetMetadataTooLarge),
+            (unknown_partition, ErrorCode::UnknownTopicOrPartition),
+            (unknown_topic, ErrorCode::UnknownTopicOrPartition),
+        ],
+        sc.offset_commit(&group_id, None, &offsets[..]).await?
+    );
+
+    // only the valid offset was committed
+    //
+    let committed = sc.committed_offsets(&group_id).await?;
+    debug!(?committed);
+
+    assert_eq!(1, committed.len());
+    assert_eq!(32123, committed[&valid].offset);
+
+    // metadata at the limit is accepted
+    //
+    let offsets = [(
+        too_large.clone(),
+        OffsetCommitRequest::default()
+            .offset(12321)
+            .metadata(Some("x".repeat(OFFSET_METADATA_MAX_BYTES))),
+    )];
+
+    assert_eq!(
+        vec![(too_large.clone(), ErrorCode::None)],
+        sc.offset_commit(&group_id, None, &offsets[..]).await?
+    );
+
+    let committed = sc.committed_offsets(&group_id).await?;
+    debug!(?committed);
+
+    assert_eq!(2, committed.len());
+    assert_eq!(32123, committed[&valid].offset);
+    assert_eq!(12321, committed[&too_large].offset);
+
+    Ok(())
+}
+
+mod pg {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::Postgres,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn mixed_partitions() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::mixed_partitions(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
+
+mod in_memory {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::InMemory,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn mixed_partitions() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::mixed_partitions(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
@@ -0,0 +1,314 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::{Duration, SystemTime};
+
+use bytes::Bytes;
+use common::{StorageType, alphanumeric_string, init_tracing, register_broker};
+use rand::{prelude::*, rng};
+use tansu_kafka_sans_io::{
+    ErrorCode, create_topics_request::CreatableTopic, join_group_response::JoinGroupResponseMember,
+};
+use tansu_server::Result;
+use tansu_storage::{
+    CommittedOffset, GroupDetail, GroupMember, OFFSETS_RETENTION, OffsetCommitRequest, Storage,
+    StorageContainer, Topition,
+};
+use tracing::debug;
+use url::Url;
+use uuid::Uuid;
+
+pub mod common;
+
+fn assert_expires_within(
+    committed: &CommittedOffset,
+    retention: Duration,
+    before: SystemTime,
+    after: SystemTime,
+) {
+    // allow for the precision of the stored timestamp
+    //
+    let tolerance = Duration::from_secs(1);
+
+    let expire_timestamp = committed
+        .expire_timestamp
+        .expect("committed offset has an expiry");
+
+    assert!(expire_timestamp >= before + retention - tolerance);
+    assert!(expire_timestamp <= after + retention + tolerance);
+}
+
+pub async fn default_and_requested_retention(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions: 2,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let group_id: String = alphanumeric_string(15);
+    debug!(?group_id);
+
+    let default = Topition::new(topic_name.clone(), 0);
+    let requested = Topition::new(topic_name.clone(), 1);
+    let retention = Duration::from_secs(60);
+
+    let before = SystemTime::now();
+
+    for (topition, retention) in [(&default, None), (&requested, Some(retention))] {
+        let offsets = [(
+            topition.clone(),
+            OffsetCommitRequest::default().offset(32123),
+        )];
+
+        assert_eq!(
+            vec![(topition.clone(), ErrorCode::None)],
+            sc.offset_commit(&group_id, retention, &offsets[..]).await?
+        );
+    }
+
+    let after = SystemTime::now();
+
+    let committed = sc.committed_offsets(&group_id).await?;
+    debug!(?committed);
+
+    assert_eq!(2, committed.len());
+
+    assert_eq!(32123, committed[&default].offset);
+    assert_expires_within(&committed[&default], OFFSETS_RETENTION, before, after);
+
+    assert_eq!(32123, committed[&requested].offset);
+    assert_expires_within(&committed[&requested], retention, before, after);
+
+    Ok(())
+}
+
+pub async fn expire_offsets(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions: 2,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let stale = Topition::new(topic_name.clone(), 0);
+    let fresh = Topition::new(topic_name.clone(), 1);
+
+    let retention = Duration::from_secs(60);
+    let committed_at = SystemTime::now() - Duration::from_secs(3_600);
+
+    let abandoned: String = alphanumeric_string(15);
+    let active: String = alphanumeric_string(15);
+
+    for group_id in [&abandoned, &active] {
+        debug!(?group_id);
+
+        let offsets = [(
+            stale.clone(),
+            OffsetCommitRequest::default()
+                .offset(12321)
+                .timestamp(committed_at),
+        )];
+
+        assert_eq!(
+            vec![(stale.clone(), ErrorCode::None)],
+            sc.offset_commit(group_id, Some(retention), &offsets[..])
+                .await?
+        );
+
+        let offsets = [(fresh.clone(), OffsetCommitRequest::default().offset(32123))];
+
+        assert_eq!(
+            vec![(fresh.clone(), ErrorCode::None)],
+            sc.offset_commit(group_id, Some(retention), &offsets[..])
+                .await?
+        );
+    }
+
+    let member_id: String = alphanumeric_string(10);
+
+    assert!(
+        sc.update_group(
+            &active,
+            GroupDetail {
+                members: [(
+                    member_id.clone(),
+                    GroupMember {
+                        join_response: JoinGroupResponseMember {
+                            member_id,
+                            group_instance_id: None,
+                            metadata: Bytes::new(),
+                        },
+                        last_contact: Some(SystemTime::now()),
+                    },
+                )]
+                .into(),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .is_ok()
+    );
+
+    assert_eq!(1, sc.expire_offsets().await?);
+
+    // only the stale offset of the group without members has expired
+    //
+    let committed = sc.committed_offsets(&abandoned).await?;
+    debug!(?committed);
+
+    assert_eq!(1, committed.len());
+    assert_eq!(32123, committed[&fresh].offset);
+
+    let committed = sc.committed_offsets(&active).await?;
+    debug!(?committed);
+
+    assert_eq!(2, committed.len());
+    assert_eq!(12321, committed[&stale].offset);
+    assert_eq!(32123, committed[&fresh].offset);
+
+    // nothing further to expire
+    //
+    assert_eq!(0, sc.expire_offsets().await?);
+
+    Ok(())
+}
+
+mod pg {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::Postgres,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn default_and_requested_retention() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::default_and_requested_retention(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn expire_offsets() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::expire_offsets(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
+
+mod in_memory {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::InMemory,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn default_and_requested_retention() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::default_and_requested_retention(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn expire_offsets() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::expire_offsets(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
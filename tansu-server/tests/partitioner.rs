@@ -0,0 +1,244 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use bytes::Bytes;
+use common::{StorageType, alphanumeric_string, init_tracing, register_broker};
+use rand::{prelude::*, rng};
+use tansu_kafka_sans_io::{
+    ErrorCode,
+    create_topics_request::{CreatableTopic, CreatableTopicConfig},
+    produce_request::{PartitionProduceData, TopicProduceData},
+    record::{
+        Record,
+        deflated::{self, Frame},
+        inflated::Batch,
+    },
+};
+use tansu_server::{
+    Result,
+    broker::produce::ProduceRequest,
+    partitioner::{PARTITIONER_CONFIG, Partitioner},
+};
+use tansu_storage::{Storage, StorageContainer, Topition};
+use tracing::debug;
+use url::Url;
+use uuid::Uuid;
+
+pub mod common;
+
+async fn produce_error_code(
+    request: &mut ProduceRequest<StorageContainer>,
+    topition: &Topition,
+    key: Option<&'static [u8]>,
+) -> Result<Option<i16>> {
+    let value = Bytes::copy_from_slice(alphanumeric_string(15).as_bytes());
+
+    let batch = Batch::builder()
+        .record(
+            Record::builder()
+                .key(key.map(Bytes::from_static).into())
+                .value(value.into()),
+        )
+        .build()
+        .and_then(deflated::Batch::try_from)?;
+
+    let topic_data = Some(vec![TopicProduceData {
+        name: topition.topic().into(),
+        partition_data: Some(vec![PartitionProduceData {
+            index: topition.partition(),
+            records: Some(Frame {
+                batches: vec![batch],
+            }),
+        }]),
+    }]);
+
+    request
+        .response(None, 0, 0, topic_data)
+        .await
+        .map(|response| {
+            response
+                .responses
+                .unwrap_or_default()
+                .into_iter()
+                .flat_map(|topic| topic.partition_responses.unwrap_or_default())
+                .map(|partition| partition.error_code)
+                .next()
+        })
+}
+
+pub async fn keyed_records_on_partition(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+    partitioner: &str,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name = alphanumeric_string(15);
+    debug!(?topic_name, partitioner);
+
+    let num_partitions = 6;
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some(
+                    [CreatableTopicConfig {
+                        name: PARTITIONER_CONFIG.into(),
+                        value: Some(partitioner.into()),
+                    }]
+                    .into(),
+                ),
+            },
+            false,
+        )
+        .await?;
+
+    let key: &[u8] = b"abc";
+    let partition = partitioner
+        .parse::<Partitioner>()
+        .and_then(|partitioner| partitioner.partition(key, num_partitions))?;
+
+    let keyed = Topition::new(topic_name.clone(), partition);
+    let other = Topition::new(topic_name.clone(), (partition + 1) % num_partitions);
+
+    let mut request = ProduceRequest::with_storage(sc.clone());
+
+    assert_eq!(
+        Some(ErrorCode::None.into()),
+        produce_error_code(&mut request, &keyed, Some(key)).await?
+    );
+
+    assert_eq!(
+        Some(ErrorCode::InvalidRecord.into()),
+        produce_error_code(&mut request, &other, Some(key)).await?
+    );
+
+    // keyless records may be produced to any partition
+    //
+    assert_eq!(
+        Some(ErrorCode::None.into()),
+        produce_error_code(&mut request, &other, None).await?
+    );
+
+    assert_eq!(1, sc.offset_stage(&keyed).await?.high_watermark());
+    assert_eq!(1, sc.offset_stage(&other).await?.high_watermark());
+
+    Ok(())
+}
+
+mod pg {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::Postgres,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn murmur2() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::keyed_records_on_partition(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+            "murmur2",
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn consistent() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::keyed_records_on_partition(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+            "consistent",
+        )
+        .await
+    }
+}
+
+mod in_memory {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::InMemory,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn murmur2() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::keyed_records_on_partition(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+            "murmur2",
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn consistent() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::keyed_records_on_partition(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+            "consistent",
+        )
+        .await
+    }
+}
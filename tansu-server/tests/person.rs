@@ -19,10 +19,19 @@ use rand::{prelude::*, rng};
 use serde_json::json;
 use tansu_kafka_sans_io::{
     ErrorCode,
-    create_topics_request::CreatableTopic,
-    record::{Record, inflated::Batch},
+    create_topics_request::{CreatableTopic, CreatableTopicConfig},
+    produce_request::{PartitionProduceData, TopicProduceData},
+    record::{
+        Record,
+        deflated::{self, Frame},
+        inflated::Batch,
+    },
+};
+use tansu_schema_registry::Registry;
+use tansu_server::{
+    Result,
+    broker::produce::{ProduceRequest, SCHEMA_VALIDATION_CONFIG},
 };
-use tansu_server::Result;
 use tansu_storage::{Error, Storage, StorageContainer, Topition};
 use tracing::{debug, error};
 use uuid::Uuid;
@@ -40,7 +49,7 @@ pub async fn person_valid(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -99,7 +108,7 @@ pub async fn person_invalid(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -150,11 +159,219 @@ pub async fn person_invalid(
     Ok(())
 }
 
+async fn produce_error_code(
+    request: &mut ProduceRequest<StorageContainer>,
+    topition: &Topition,
+    value: serde_json::Value,
+) -> Result<Option<i16>> {
+    let key = serde_json::to_vec(&json!("345-67-6543")).map(Bytes::from)?;
+    let value = serde_json::to_vec(&value).map(Bytes::from)?;
+
+    let batch = Batch::builder()
+        .record(Record::builder().key(key.into()).value(value.into()))
+        .build()
+        .and_then(deflated::Batch::try_from)?;
+
+    let topic_data = Some(vec![TopicProduceData {
+        name: topition.topic().into(),
+        partition_data: Some(vec![PartitionProduceData {
+            index: topition.partition(),
+            records: Some(Frame {
+                batches: vec![batch],
+            }),
+        }]),
+    }]);
+
+    request
+        .response(None, 0, 0, topic_data)
+        .await
+        .map(|response| {
+            response
+                .responses
+                .unwrap_or_default()
+                .into_iter()
+                .flat_map(|topic| topic.partition_responses.unwrap_or_default())
+                .map(|partition| partition.error_code)
+                .next()
+        })
+}
+
+pub async fn person_produce_validation(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+    schemas: Registry,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name = "person";
+    let num_partitions = 6;
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.into(),
+                num_partitions,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some(
+                    [CreatableTopicConfig {
+                        name: SCHEMA_VALIDATION_CONFIG.into(),
+                        value: Some("true".into()),
+                    }]
+                    .into(),
+                ),
+            },
+            false,
+        )
+        .await?;
+
+    let topition = Topition::new(topic_name, rng().random_range(0..num_partitions));
+
+    let mut request = ProduceRequest::with_storage(sc.clone()).schemas(Some(schemas));
+
+    assert_eq!(
+        Some(ErrorCode::None.into()),
+        produce_error_code(
+            &mut request,
+            &topition,
+            json!({"firstName": "John", "lastName": "Doe", "age": 21}),
+        )
+        .await?
+    );
+
+    assert_eq!(
+        Some(ErrorCode::InvalidRecord.into()),
+        produce_error_code(
+            &mut request,
+            &topition,
+            json!({"firstName": "John", "lastName": "Doe", "age": -1}),
+        )
+        .await?
+    );
+
+    // the invalid produce is not persisted
+    //
+    assert_eq!(1, sc.offset_stage(&topition).await?.high_watermark());
+
+    Ok(())
+}
+
+pub async fn person_multi_partition_produce_validation(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+    schemas: Registry,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name = "person";
+    let num_partitions = 10;
+    let invalid = 3;
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.into(),
+                num_partitions,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some(
+                    [CreatableTopicConfig {
+                        name: SCHEMA_VALIDATION_CONFIG.into(),
+                        value: Some("true".into()),
+                    }]
+                    .into(),
+                ),
+            },
+            false,
+        )
+        .await?;
+
+    let key = serde_json::to_vec(&json!("345-67-6543")).map(Bytes::from)?;
+
+    let partition_data = (0..num_partitions)
+        .map(|index| {
+            let age = if index == invalid { -1 } else { 21 };
+
+            serde_json::to_vec(&json!({"firstName": "John", "lastName": "Doe", "age": age}))
+                .map(Bytes::from)
+                .map_err(Into::into)
+                .and_then(|value| {
+                    Batch::builder()
+                        .record(
+                            Record::builder()
+                                .key(key.clone().into())
+                                .value(value.into()),
+                        )
+                        .build()
+                        .and_then(deflated::Batch::try_from)
+                        .map_err(Into::into)
+                })
+                .map(|batch| PartitionProduceData {
+                    index,
+                    records: Some(Frame {
+                        batches: vec![batch],
+                    }),
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let fetches = schemas.fetches();
+
+    let response = ProduceRequest::with_storage(sc.clone())
+        .schemas(Some(schemas.clone()))
+        .response(
+            None,
+            0,
+            0,
+            Some(vec![TopicProduceData {
+                name: topic_name.into(),
+                partition_data: Some(partition_data),
+            }]),
+        )
+        .await?;
+
+    // the schema is fetched once for all partitions of the topic
+    //
+    assert_eq!(fetches + 1, schemas.fetches());
+
+    let error_codes = response
+        .responses
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|topic| topic.partition_responses.unwrap_or_default())
+        .map(|partition| (partition.index, partition.error_code))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        (0..num_partitions)
+            .map(|index| (
+                index,
+                if index == invalid {
+                    ErrorCode::InvalidRecord.into()
+                } else {
+                    ErrorCode::None.into()
+                }
+            ))
+            .collect::<Vec<_>>(),
+        error_codes
+    );
+
+    assert_eq!(
+        0,
+        sc.offset_stage(&Topition::new(topic_name, invalid))
+            .await?
+            .high_watermark()
+    );
+
+    Ok(())
+}
+
 mod pg {
     use std::env;
 
     use common::{StorageType, init_tracing};
-    use tansu_schema_registry::Registry;
     use tansu_server::Error;
     use url::Url;
 
@@ -211,13 +428,64 @@ mod pg {
         )
         .await
     }
+
+    #[tokio::test]
+    async fn person_produce_validation() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        let schemas = Url::parse("file://../etc/schema")
+            .map_err(Error::from)
+            .and_then(|url| Registry::try_from(url).map_err(Into::into))?;
+
+        let sc = Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::Postgres,
+                    cluster_id,
+                    broker_id,
+                    advertised_listener,
+                    None,
+                )
+            })?;
+
+        super::person_produce_validation(cluster_id, broker_id, sc, schemas).await
+    }
+
+    #[tokio::test]
+    async fn person_multi_partition_produce_validation() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        let schemas = Url::parse("file://../etc/schema")
+            .map_err(Error::from)
+            .and_then(|url| Registry::try_from(url).map_err(Into::into))?;
+
+        let sc = Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::Postgres,
+                    cluster_id,
+                    broker_id,
+                    advertised_listener,
+                    None,
+                )
+            })?;
+
+        super::person_multi_partition_produce_validation(cluster_id, broker_id, sc, schemas).await
+    }
 }
 
 mod in_memory {
     use std::env;
 
     use common::{StorageType, init_tracing};
-    use tansu_schema_registry::Registry;
     use tansu_server::Error;
     use url::Url;
 
@@ -274,4 +542,56 @@ mod in_memory {
         )
         .await
     }
+
+    #[tokio::test]
+    async fn person_produce_validation() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        let schemas = Url::parse("file://../etc/schema")
+            .map_err(Error::from)
+            .and_then(|url| Registry::try_from(url).map_err(Into::into))?;
+
+        let sc = Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::InMemory,
+                    cluster_id,
+                    broker_id,
+                    advertised_listener,
+                    None,
+                )
+            })?;
+
+        super::person_produce_validation(cluster_id, broker_id, sc, schemas).await
+    }
+
+    #[tokio::test]
+    async fn person_multi_partition_produce_validation() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        let schemas = Url::parse("file://../etc/schema")
+            .map_err(Error::from)
+            .and_then(|url| Registry::try_from(url).map_err(Into::into))?;
+
+        let sc = Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::InMemory,
+                    cluster_id,
+                    broker_id,
+                    advertised_listener,
+                    None,
+                )
+            })?;
+
+        super::person_multi_partition_produce_validation(cluster_id, broker_id, sc, schemas).await
+    }
 }
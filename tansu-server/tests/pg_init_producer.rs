@@ -15,6 +15,7 @@
 
 use common::{StorageType, alphanumeric_string, init_tracing, register_broker, storage_container};
 use rand::{prelude::*, rng};
+use tansu_kafka_sans_io::ErrorCode;
 use tansu_server::Result;
 use tansu_storage::Storage;
 use tracing::debug;
@@ -77,3 +78,47 @@ async fn with_txn() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn without_txn() -> Result<()> {
+    let _guard = init_tracing()?;
+
+    let mut rng = rng();
+
+    let cluster_id = Uuid::now_v7();
+    let broker_id = rng.random_range(0..i32::MAX);
+    let mut sc = Url::parse("tcp://127.0.0.1/")
+        .map_err(Into::into)
+        .and_then(|advertised_listener| {
+            storage_container(
+                StorageType::Postgres,
+                cluster_id,
+                broker_id,
+                advertised_listener,
+                None,
+            )
+        })?;
+
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let transaction_timeout_ms = 10_000;
+
+    let first = sc
+        .init_producer(None, transaction_timeout_ms, Some(-1), Some(-1))
+        .await?;
+    debug!(?first);
+
+    assert_eq!(ErrorCode::None, first.error);
+    assert_eq!(0, first.epoch);
+
+    let second = sc
+        .init_producer(None, transaction_timeout_ms, Some(-1), Some(-1))
+        .await?;
+    debug!(?second);
+
+    assert_eq!(ErrorCode::None, second.error);
+    assert_eq!(0, second.epoch);
+    assert!(second.id > first.id);
+
+    Ok(())
+}
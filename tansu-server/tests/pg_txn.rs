@@ -70,7 +70,7 @@ async fn simple_txn_commit() -> Result<()> {
     debug!(?input_topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -46,7 +46,7 @@ pub async fn simple_non_txn(
     debug!(?input_topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -163,7 +163,7 @@ pub async fn with_txn(cluster_id: Uuid, broker_id: i32, mut sc: StorageContainer
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -397,7 +397,7 @@ pub async fn with_multiple_txn(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
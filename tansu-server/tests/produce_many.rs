@@ -0,0 +1,236 @@
+// Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use bytes::Bytes;
+use common::{
+    StorageType, alphanumeric_string, fetch_values, init_tracing, register_broker, value_batch,
+};
+use rand::{prelude::*, rng};
+use tansu_kafka_sans_io::{ErrorCode, create_topics_request::CreatableTopic};
+use tansu_server::Result;
+use tansu_storage::{Storage, StorageContainer, Topition};
+use tracing::debug;
+use url::Url;
+use uuid::Uuid;
+
+pub mod common;
+
+const NUM_PARTITIONS: i32 = 3;
+
+async fn topic(sc: &mut StorageContainer) -> Result<String> {
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions: NUM_PARTITIONS,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    Ok(topic_name)
+}
+
+pub async fn all_partitions(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic = topic(&mut sc).await?;
+
+    let values = (0..NUM_PARTITIONS)
+        .map(|_| alphanumeric_string(15))
+        .collect::<Vec<_>>();
+
+    let mut writes = vec![];
+
+    for (partition, value) in values.iter().enumerate() {
+        writes.push((
+            Topition::new(topic.clone(), i32::try_from(partition)?),
+            value_batch(value)?,
+        ));
+    }
+
+    assert_eq!(
+        writes
+            .iter()
+            .map(|(topition, _)| (topition.to_owned(), 0))
+            .collect::<Vec<_>>(),
+        sc.produce_many(&writes).await?
+    );
+
+    // a second write follows on from the first in each topition
+    //
+    assert_eq!(
+        writes
+            .iter()
+            .map(|(topition, _)| (topition.to_owned(), 1))
+            .collect::<Vec<_>>(),
+        sc.produce_many(&writes).await?
+    );
+
+    for ((topition, _), value) in writes.iter().zip(values.iter()) {
+        let offset_stage = sc.offset_stage(topition).await?;
+        assert_eq!(2, offset_stage.high_watermark());
+
+        let value = Bytes::copy_from_slice(value.as_bytes());
+
+        assert_eq!(
+            vec![(0, value.clone()), (1, value)],
+            fetch_values(&mut sc, topition, 0).await?
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn unknown_partition(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic = topic(&mut sc).await?;
+
+    let known = Topition::new(topic.clone(), 0);
+    let unknown = Topition::new(topic.clone(), NUM_PARTITIONS);
+
+    assert!(matches!(
+        sc.produce_many(&[
+            (known.clone(), value_batch(&alphanumeric_string(15))?),
+            (unknown, value_batch(&alphanumeric_string(15))?),
+        ])
+        .await,
+        Err(tansu_storage::Error::Api(
+            ErrorCode::UnknownTopicOrPartition
+        ))
+    ));
+
+    // the write to the known partition was not persisted
+    //
+    let offset_stage = sc.offset_stage(&known).await?;
+    assert_eq!(0, offset_stage.high_watermark());
+    assert!(fetch_values(&mut sc, &known, 0).await?.is_empty());
+
+    Ok(())
+}
+
+pub async fn unsupported(cluster_id: Uuid, broker_id: i32, mut sc: StorageContainer) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic = topic(&mut sc).await?;
+    let topition = Topition::new(topic.clone(), 0);
+
+    assert!(matches!(
+        sc.produce_many(&[(topition.clone(), value_batch(&alphanumeric_string(15))?)])
+            .await,
+        Err(tansu_storage::Error::UnsupportedOperation("produce_many"))
+    ));
+
+    let offset_stage = sc.offset_stage(&topition).await?;
+    assert_eq!(0, offset_stage.high_watermark());
+
+    Ok(())
+}
+
+mod pg {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::Postgres,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn all_partitions() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::all_partitions(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn unknown_partition() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::unknown_partition(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
+
+mod in_memory {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::InMemory,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn unsupported() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::unsupported(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use bytes::Bytes;
+use common::{StorageType, alphanumeric_string, init_tracing, register_broker};
+use rand::{prelude::*, rng};
+use tansu_kafka_sans_io::{
+    ErrorCode,
+    create_topics_request::CreatableTopic,
+    delete_records_request::{DeleteRecordsPartition, DeleteRecordsTopic},
+    delete_records_response::{DeleteRecordsPartitionResult, DeleteRecordsTopicResult},
+    record::{Record, inflated},
+};
+use tansu_server::Result;
+use tansu_storage::{Storage, StorageContainer, TopicId, Topition};
+use tracing::debug;
+use url::Url;
+use uuid::Uuid;
+
+pub mod common;
+
+pub async fn produce_and_delete(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let num_partitions = 6;
+    let replication_factor = 1;
+    let assignments = Some([].into());
+    let configs = Some([].into());
+
+    let topic_id = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions,
+                replication_factor,
+                assignments: assignments.clone(),
+                configs: configs.clone(),
+            },
+            false,
+        )
+        .await?;
+    debug!(?topic_id);
+
+    let partition_index = rng().random_range(0..num_partitions);
+    let topition = Topition::new(topic_name.clone(), partition_index);
+
+    let record_counts = |count: i64| {
+        (0..num_partitions)
+            .map(|partition| {
+                (
+                    Topition::new(topic_name.clone(), partition),
+                    if partition == partition_index {
+                        count
+                    } else {
+                        0
+                    },
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        record_counts(0),
+        sc.record_counts(&[TopicId::from(topic_id)]).await?
+    );
+
+    for records in [2, 3, 4] {
+        let mut batch = inflated::Batch::builder().last_offset_delta(records - 1);
+
+        for offset_delta in 0..records {
+            batch = batch.record(
+                Record::builder()
+                    .offset_delta(offset_delta)
+                    .value(Bytes::copy_from_slice(alphanumeric_string(15).as_bytes()).into()),
+            );
+        }
+
+        let batch = batch
+            .build()
+            .and_then(TryInto::try_into)
+            .inspect(|deflated| debug!(?deflated))?;
+
+        _ = sc
+            .produce(None, &topition, batch)
+            .await
+            .inspect(|offset| debug!(?offset))?;
+    }
+
+    assert_eq!(
+        record_counts(9),
+        sc.record_counts(&[TopicId::from(topic_name.as_str())])
+            .await?
+    );
+
+    let delete_records = |offset: i64| {
+        [DeleteRecordsTopic {
+            name: topic_name.clone(),
+            partitions: Some(
+                [DeleteRecordsPartition {
+                    partition_index,
+                    offset,
+                }]
+                .into(),
+            ),
+        }]
+    };
+
+    let deleted = |low_watermark: i64| {
+        vec![DeleteRecordsTopicResult {
+            name: topic_name.clone(),
+            partitions: Some(
+                [DeleteRecordsPartitionResult {
+                    partition_index,
+                    low_watermark,
+                    error_code: ErrorCode::None.into(),
+                }]
+                .into(),
+            ),
+        }]
+    };
+
+    assert_eq!(deleted(5), sc.delete_records(&delete_records(5)).await?);
+
+    assert_eq!(
+        record_counts(4),
+        sc.record_counts(&[TopicId::from(topic_id)]).await?
+    );
+
+    assert_eq!(deleted(9), sc.delete_records(&delete_records(-1)).await?);
+
+    assert_eq!(
+        record_counts(0),
+        sc.record_counts(&[TopicId::from(topic_id)]).await?
+    );
+
+    assert_eq!(
+        ErrorCode::None,
+        sc.delete_topic(&TopicId::from(topic_id)).await?
+    );
+
+    Ok(())
+}
+
+mod pg {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::Postgres,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn produce_and_delete() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::produce_and_delete(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
+
+mod in_memory {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::InMemory,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn produce_and_delete() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::produce_and_delete(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
@@ -0,0 +1,289 @@
+// Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use common::{StorageType, alphanumeric_string, init_tracing, register_broker};
+use rand::{prelude::*, rng};
+use tansu_kafka_sans_io::{
+    BatchAttribute, IsolationLevel, TimestampType,
+    create_topics_request::{CreatableTopic, CreatableTopicConfig},
+    record::{Record, inflated},
+    to_system_time, to_timestamp,
+};
+use tansu_server::Result;
+use tansu_storage::{
+    ListOffsetRequest, MESSAGE_TIMESTAMP_TYPE, Storage, StorageContainer, Topition,
+};
+use tracing::debug;
+use url::Url;
+use uuid::Uuid;
+
+pub mod common;
+
+const CREATE_TIMESTAMP: i64 = 1_700_000_000_000;
+
+async fn topition(sc: &mut StorageContainer, timestamp_type: Option<&str>) -> Result<Topition> {
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name, ?timestamp_type);
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions: 1,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some(
+                    timestamp_type
+                        .map(|value| CreatableTopicConfig {
+                            name: MESSAGE_TIMESTAMP_TYPE.into(),
+                            value: Some(value.into()),
+                        })
+                        .into_iter()
+                        .collect(),
+                ),
+            },
+            false,
+        )
+        .await?;
+
+    Ok(Topition::new(topic_name, 0))
+}
+
+async fn produce_fetch(sc: &mut StorageContainer, topition: &Topition) -> Result<inflated::Batch> {
+    let batch = inflated::Batch::builder()
+        .base_timestamp(CREATE_TIMESTAMP)
+        .max_timestamp(CREATE_TIMESTAMP)
+        .record(
+            Record::builder()
+                .value(Bytes::copy_from_slice(alphanumeric_string(15).as_bytes()).into()),
+        )
+        .build()
+        .and_then(TryInto::try_into)
+        .inspect(|deflated| debug!(?deflated))?;
+
+    assert_eq!(0, sc.produce(None, topition, batch).await?);
+
+    let mut fetched = sc
+        .fetch(topition, 0, 0, 50 * 1_024, IsolationLevel::ReadUncommitted)
+        .await?;
+    assert_eq!(1, fetched.len());
+
+    inflated::Batch::try_from(fetched.remove(0))
+        .inspect(|inflated| debug!(?inflated))
+        .map_err(Into::into)
+}
+
+pub async fn create_time(cluster_id: Uuid, broker_id: i32, mut sc: StorageContainer) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    for timestamp_type in [None, Some("CreateTime")] {
+        let topition = topition(&mut sc, timestamp_type).await?;
+
+        let fetched = produce_fetch(&mut sc, &topition).await?;
+
+        assert_eq!(
+            TimestampType::CreateTime,
+            BatchAttribute::try_from(fetched.attributes)?.timestamp
+        );
+        assert_eq!(CREATE_TIMESTAMP, fetched.base_timestamp);
+        assert_eq!(CREATE_TIMESTAMP, fetched.max_timestamp);
+    }
+
+    Ok(())
+}
+
+pub async fn log_append_time(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topition = topition(&mut sc, Some("LogAppendTime")).await?;
+
+    let before = to_timestamp(SystemTime::now())?;
+    let fetched = produce_fetch(&mut sc, &topition).await?;
+    let after = to_timestamp(SystemTime::now())?;
+
+    assert_eq!(
+        TimestampType::LogAppendTime,
+        BatchAttribute::try_from(fetched.attributes)?.timestamp
+    );
+    assert!(fetched.max_timestamp >= before);
+    assert!(fetched.max_timestamp <= after);
+
+    Ok(())
+}
+
+pub async fn list_offsets_by_timestamp(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let create_time = topition(&mut sc, Some("CreateTime")).await?;
+    let log_append_time = topition(&mut sc, Some("LogAppendTime")).await?;
+
+    let before = SystemTime::now();
+
+    for topition in [&create_time, &log_append_time] {
+        _ = produce_fetch(&mut sc, topition).await?;
+    }
+
+    let offsets = [
+        (
+            create_time.clone(),
+            ListOffsetRequest::Timestamp(to_system_time(CREATE_TIMESTAMP)?),
+        ),
+        (create_time.clone(), ListOffsetRequest::Timestamp(before)),
+        (
+            log_append_time.clone(),
+            ListOffsetRequest::Timestamp(to_system_time(CREATE_TIMESTAMP)?),
+        ),
+        (
+            log_append_time.clone(),
+            ListOffsetRequest::Timestamp(before),
+        ),
+    ];
+
+    let listed = sc
+        .list_offsets(IsolationLevel::ReadUncommitted, &offsets[..])
+        .await?
+        .into_iter()
+        .map(|(_, response)| response.offset)
+        .collect::<Vec<_>>();
+
+    // a create time record is only found by its producer's timestamp,
+    // while a log append time record is stamped on arrival
+    //
+    assert_eq!(vec![Some(0), Some(1), Some(0), Some(0)], listed);
+
+    Ok(())
+}
+
+mod pg {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::Postgres,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn create_time() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::create_time(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn log_append_time() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::log_append_time(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn list_offsets_by_timestamp() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::list_offsets_by_timestamp(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
+
+mod in_memory {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::InMemory,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn create_time() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::create_time(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn log_append_time() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::log_append_time(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
@@ -13,13 +13,16 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use bytes::Bytes;
 use common::{alphanumeric_string, register_broker};
 use tansu_kafka_sans_io::{
     ErrorCode, NULL_TOPIC_ID,
-    create_topics_request::{CreatableTopic, CreatableTopicConfig},
+    create_topics_request::{CreatableReplicaAssignment, CreatableTopic, CreatableTopicConfig},
+    list_partition_reassignments_request::ListPartitionReassignmentsTopics,
+    record::{Record, inflated},
 };
 use tansu_server::Result;
-use tansu_storage::{Storage, StorageContainer, TopicId};
+use tansu_storage::{DEFAULT_NUM_PARTITIONS, Storage, StorageContainer, TopicId, Topition};
 use tracing::debug;
 use uuid::Uuid;
 
@@ -36,7 +39,7 @@ pub async fn create_delete(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
 
     let assignments = Some([].into());
     let configs = Some([].into());
@@ -74,7 +77,7 @@ pub async fn create_describe_topic_partitions_by_id(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 3;
+    let replication_factor = 1;
 
     let assignments = Some([].into());
     let configs = Some([].into());
@@ -146,7 +149,7 @@ pub async fn create_describe_topic_partitions_by_name(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 3;
+    let replication_factor = 1;
 
     let assignments = Some([].into());
     let configs = Some([].into());
@@ -253,7 +256,7 @@ pub async fn create_with_config_delete(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
 
     let assignments = Some([].into());
     let configs = Some(
@@ -286,6 +289,531 @@ pub async fn create_with_config_delete(
     Ok(())
 }
 
+pub async fn delete_topics_by_id_and_name(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let num_partitions = 6;
+    let replication_factor = 1;
+
+    let mut create = async |name: &str| {
+        sc.create_topic(
+            CreatableTopic {
+                name: name.into(),
+                num_partitions,
+                replication_factor,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await
+        .inspect(|topic_id| debug!(name, ?topic_id))
+    };
+
+    let by_name: String = alphanumeric_string(15);
+    _ = create(&by_name).await?;
+
+    let by_id: String = alphanumeric_string(15);
+    let topic_id = create(&by_id).await?;
+
+    let topics = [
+        TopicId::Name(by_name),
+        TopicId::Name(alphanumeric_string(15)),
+        TopicId::Id(topic_id),
+        TopicId::Id(Uuid::now_v7()),
+    ];
+
+    let deleted = sc.delete_topics(&topics).await?;
+
+    assert_eq!(
+        topics
+            .into_iter()
+            .zip([
+                ErrorCode::None,
+                ErrorCode::UnknownTopicOrPartition,
+                ErrorCode::None,
+                ErrorCode::UnknownTopicOrPartition,
+            ])
+            .collect::<Vec<_>>(),
+        deleted
+    );
+
+    assert_eq!(
+        ErrorCode::UnknownTopicOrPartition,
+        sc.delete_topic(&TopicId::Id(topic_id)).await?
+    );
+
+    Ok(())
+}
+
+pub async fn create_partitions(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let topic_id = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions: 2,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    assert_eq!(
+        ErrorCode::None,
+        sc.create_partitions(&TopicId::Id(topic_id), 4, None)
+            .await?
+    );
+
+    assert_eq!(
+        ErrorCode::InvalidPartitions,
+        sc.create_partitions(&TopicId::Name(topic_name.clone()), 3, None)
+            .await?
+    );
+
+    assert_eq!(
+        ErrorCode::UnknownTopicOrPartition,
+        sc.create_partitions(&TopicId::Name(alphanumeric_string(15)), 4, None)
+            .await?
+    );
+
+    let metadata = sc
+        .metadata(Some([TopicId::Name(topic_name.clone())].as_slice()))
+        .await
+        .inspect(|metadata| debug!(?metadata))?;
+
+    assert_eq!(1, metadata.topics().len());
+    assert_eq!(
+        Some(4),
+        metadata.topics()[0]
+            .partitions
+            .as_ref()
+            .map(|partitions| partitions.len())
+    );
+
+    for partition in 2..4 {
+        let topition = Topition::new(topic_name.clone(), partition);
+
+        let batch = inflated::Batch::builder()
+            .record(Record::builder().value(Bytes::from_static(b"abc").into()))
+            .build()
+            .and_then(TryInto::try_into)?;
+
+        assert_eq!(0, sc.produce(None, &topition, batch).await?);
+    }
+
+    Ok(())
+}
+
+pub async fn validate_only(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic = CreatableTopic {
+        name: alphanumeric_string(15),
+        num_partitions: 3,
+        replication_factor: 1,
+        assignments: Some([].into()),
+        configs: Some([].into()),
+    };
+
+    assert_eq!(Uuid::nil(), sc.create_topic(topic.clone(), true).await?);
+
+    // nothing is created when only validating
+    //
+    assert!(
+        sc.list_topics()
+            .await?
+            .iter()
+            .all(|detail| detail.name() != topic.name)
+    );
+
+    _ = sc.create_topic(topic.clone(), false).await?;
+
+    assert!(matches!(
+        sc.create_topic(topic.clone(), true).await,
+        Err(tansu_storage::Error::Api(ErrorCode::TopicAlreadyExists))
+    ));
+
+    for (invalid, error_code) in [
+        (
+            CreatableTopic {
+                name: "not a valid name".into(),
+                ..topic.clone()
+            },
+            ErrorCode::InvalidTopicException,
+        ),
+        (
+            CreatableTopic {
+                name: alphanumeric_string(15),
+                num_partitions: 0,
+                ..topic.clone()
+            },
+            ErrorCode::InvalidPartitions,
+        ),
+        (
+            CreatableTopic {
+                name: alphanumeric_string(15),
+                configs: Some(
+                    [CreatableTopicConfig {
+                        name: "retention.ms".into(),
+                        value: Some("forever".into()),
+                    }]
+                    .into(),
+                ),
+                ..topic.clone()
+            },
+            ErrorCode::InvalidConfig,
+        ),
+        (
+            CreatableTopic {
+                name: alphanumeric_string(15),
+                configs: Some(
+                    [
+                        CreatableTopicConfig {
+                            name: "cleanup.policy".into(),
+                            value: Some("compact".into()),
+                        },
+                        CreatableTopicConfig {
+                            name: "delete.retention.ms".into(),
+                            value: Some("-1".into()),
+                        },
+                    ]
+                    .into(),
+                ),
+                ..topic.clone()
+            },
+            ErrorCode::InvalidConfig,
+        ),
+        (
+            CreatableTopic {
+                name: alphanumeric_string(15),
+                configs: Some(
+                    [CreatableTopicConfig {
+                        name: "tansu.partitioner".into(),
+                        value: Some("round-robin".into()),
+                    }]
+                    .into(),
+                ),
+                ..topic.clone()
+            },
+            ErrorCode::InvalidConfig,
+        ),
+    ] {
+        assert!(matches!(
+            sc.create_topic(invalid, true).await,
+            Err(tansu_storage::Error::Api(reported)) if reported == error_code
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn create_with_replica_assignment(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let assignments = (0..3)
+        .map(|partition_index| CreatableReplicaAssignment {
+            partition_index,
+            broker_ids: Some(vec![broker_id]),
+        })
+        .collect::<Vec<_>>();
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions: -1,
+                replication_factor: -1,
+                assignments: Some(assignments.clone()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let metadata = sc
+        .metadata(Some([TopicId::Name(topic_name.clone())].as_slice()))
+        .await
+        .inspect(|metadata| debug!(?metadata))?;
+
+    assert_eq!(1, metadata.topics().len());
+
+    let partitions = metadata.topics()[0]
+        .partitions
+        .as_deref()
+        .unwrap_or_default();
+    assert_eq!(3, partitions.len());
+
+    for partition in partitions {
+        assert_eq!(broker_id, partition.leader_id);
+        assert_eq!(Some(vec![broker_id]), partition.replica_nodes);
+    }
+
+    let detail = sc
+        .list_topics()
+        .await?
+        .into_iter()
+        .find(|detail| detail.name() == topic_name)
+        .expect("created topic");
+
+    assert_eq!(3, detail.creatable_topic().num_partitions);
+    assert_eq!(1, detail.creatable_topic().replication_factor);
+
+    let topic = |assignments: Vec<CreatableReplicaAssignment>| CreatableTopic {
+        name: alphanumeric_string(15),
+        num_partitions: -1,
+        replication_factor: -1,
+        assignments: Some(assignments),
+        configs: Some([].into()),
+    };
+
+    for invalid in [
+        // an unknown broker
+        //
+        topic(vec![CreatableReplicaAssignment {
+            partition_index: 0,
+            broker_ids: Some(vec![broker_id.wrapping_add(1)]),
+        }]),
+        // partition 1 isn't assigned
+        //
+        topic(vec![
+            CreatableReplicaAssignment {
+                partition_index: 0,
+                broker_ids: Some(vec![broker_id]),
+            },
+            CreatableReplicaAssignment {
+                partition_index: 2,
+                broker_ids: Some(vec![broker_id]),
+            },
+        ]),
+        // without any replicas
+        //
+        topic(vec![CreatableReplicaAssignment {
+            partition_index: 0,
+            broker_ids: Some(vec![]),
+        }]),
+        // a partition count that differs from the assignment
+        //
+        CreatableTopic {
+            num_partitions: 2,
+            ..topic(assignments[..1].to_vec())
+        },
+    ] {
+        assert!(matches!(
+            sc.create_topic(invalid, false).await,
+            Err(tansu_storage::Error::Api(
+                ErrorCode::InvalidReplicaAssignment
+            ))
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn idle_partition_reassignments(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions: 3,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let ongoing = sc
+        .list_partition_reassignments(Some(
+            &[ListPartitionReassignmentsTopics {
+                name: topic_name.clone(),
+                partition_indexes: Some([0, 2].into()),
+            }][..],
+        ))
+        .await
+        .inspect(|ongoing| debug!(?ongoing))?;
+
+    assert_eq!(1, ongoing.len());
+    assert_eq!(topic_name, ongoing[0].name);
+
+    let partitions = ongoing[0].partitions.as_deref().unwrap_or_default();
+
+    assert_eq!(
+        vec![0, 2],
+        partitions
+            .iter()
+            .map(|partition| partition.partition_index)
+            .collect::<Vec<_>>()
+    );
+
+    // nothing is being reassigned
+    //
+    for partition in partitions {
+        assert_eq!(Some([].into()), partition.adding_replicas);
+        assert_eq!(Some([].into()), partition.removing_replicas);
+    }
+
+    // every topic is listed when none are requested
+    //
+    assert!(
+        sc.list_partition_reassignments(None)
+            .await?
+            .iter()
+            .any(|topic| topic.name == topic_name
+                && topic
+                    .partitions
+                    .as_ref()
+                    .is_some_and(|partitions| partitions.len() == 3))
+    );
+
+    Ok(())
+}
+
+pub async fn replication_factor(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic = |replication_factor| CreatableTopic {
+        name: alphanumeric_string(15),
+        num_partitions: 3,
+        replication_factor,
+        assignments: Some([].into()),
+        configs: Some([].into()),
+    };
+
+    let replication_factor_of = async |sc: &mut StorageContainer, name: &str| {
+        sc.list_topics().await.map(|topics| {
+            topics
+                .into_iter()
+                .find(|detail| detail.name() == name)
+                .map(|detail| detail.creatable_topic().replication_factor)
+        })
+    };
+
+    // a single broker can hold a single replica
+    //
+    let valid = topic(1);
+    _ = sc.create_topic(valid.clone(), false).await?;
+    assert_eq!(Some(1), replication_factor_of(&mut sc, &valid.name).await?);
+
+    // the default replication factor
+    //
+    let default = topic(-1);
+    _ = sc.create_topic(default.clone(), false).await?;
+    assert_eq!(
+        Some(1),
+        replication_factor_of(&mut sc, &default.name).await?
+    );
+
+    // more replicas than brokers, no replicas or an undefined factor
+    //
+    for invalid in [topic(5), topic(0), topic(-2)] {
+        assert!(matches!(
+            sc.create_topic(invalid.clone(), false).await,
+            Err(tansu_storage::Error::Api(
+                ErrorCode::InvalidReplicationFactor
+            ))
+        ));
+
+        assert!(matches!(
+            sc.create_topic(invalid.clone(), true).await,
+            Err(tansu_storage::Error::Api(
+                ErrorCode::InvalidReplicationFactor
+            ))
+        ));
+
+        assert_eq!(None, replication_factor_of(&mut sc, &invalid.name).await?);
+    }
+
+    Ok(())
+}
+
+pub async fn default_num_partitions(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let name = alphanumeric_string(15);
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: name.clone(),
+                num_partitions: -1,
+                replication_factor: -1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let created = sc
+        .list_topics()
+        .await?
+        .into_iter()
+        .find(|detail| detail.name() == name)
+        .map(|detail| detail.creatable_topic().to_owned());
+    debug!(?created);
+
+    assert_eq!(
+        Some(DEFAULT_NUM_PARTITIONS),
+        created.as_ref().map(|topic| topic.num_partitions)
+    );
+    assert_eq!(Some(1), created.map(|topic| topic.replication_factor));
+
+    for partition in 0..DEFAULT_NUM_PARTITIONS {
+        assert_eq!(
+            0,
+            sc.offset_stage(&Topition::new(name.as_str(), partition))
+                .await?
+                .high_watermark()
+        );
+    }
+
+    Ok(())
+}
+
 mod pg {
     use common::{StorageType, init_tracing};
     use rand::{prelude::*, rng};
@@ -381,6 +909,111 @@ mod pg {
         )
         .await
     }
+
+    #[tokio::test]
+    async fn delete_topics_by_id_and_name() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::delete_topics_by_id_and_name(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn create_partitions() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::create_partitions(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn validate_only() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::validate_only(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn create_with_replica_assignment() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::create_with_replica_assignment(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn idle_partition_reassignments() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::idle_partition_reassignments(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn replication_factor() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::replication_factor(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn default_num_partitions() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::default_num_partitions(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
 }
 
 mod in_memory {
@@ -478,4 +1111,109 @@ mod in_memory {
         )
         .await
     }
+
+    #[tokio::test]
+    async fn delete_topics_by_id_and_name() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::delete_topics_by_id_and_name(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn create_partitions() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::create_partitions(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn validate_only() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::validate_only(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn create_with_replica_assignment() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::create_with_replica_assignment(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn idle_partition_reassignments() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::idle_partition_reassignments(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn replication_factor() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::replication_factor(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn default_num_partitions() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::default_num_partitions(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
 }
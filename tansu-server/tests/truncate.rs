@@ -0,0 +1,307 @@
+// Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use bytes::Bytes;
+use common::{
+    StorageType, alphanumeric_string, fetch_values, init_tracing, register_broker, value_batch,
+};
+use rand::{prelude::*, rng};
+use tansu_kafka_sans_io::{
+    ErrorCode,
+    create_topics_request::CreatableTopic,
+    delete_records_request::{DeleteRecordsPartition, DeleteRecordsTopic},
+};
+use tansu_server::Result;
+use tansu_storage::{Storage, StorageContainer, Topition};
+use tracing::debug;
+use url::Url;
+use uuid::Uuid;
+
+pub mod common;
+
+async fn topition(sc: &mut StorageContainer) -> Result<Topition> {
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let num_partitions = 6;
+
+    _ = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    Ok(Topition::new(
+        topic_name,
+        rng().random_range(0..num_partitions),
+    ))
+}
+
+pub async fn mid_offset(cluster_id: Uuid, broker_id: i32, mut sc: StorageContainer) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topition = topition(&mut sc).await?;
+
+    let values = (0..5).map(|_| alphanumeric_string(15)).collect::<Vec<_>>();
+
+    for (offset, value) in values.iter().enumerate() {
+        assert_eq!(
+            i64::try_from(offset)?,
+            sc.produce(None, &topition, value_batch(value)?).await?
+        );
+    }
+
+    assert_eq!(3, sc.truncate(&topition, 3).await?);
+
+    let offset_stage = sc.offset_stage(&topition).await?;
+    assert_eq!(0, offset_stage.log_start());
+    assert_eq!(3, offset_stage.high_watermark());
+    assert_eq!(3, offset_stage.last_stable());
+
+    assert_eq!(
+        values[..3]
+            .iter()
+            .enumerate()
+            .map(|(offset, value)| (offset as i64, Bytes::copy_from_slice(value.as_bytes())))
+            .collect::<Vec<_>>(),
+        fetch_values(&mut sc, &topition, 0).await?
+    );
+
+    // produce resumes from the truncated offset
+    //
+    let resumed = alphanumeric_string(15);
+    assert_eq!(
+        3,
+        sc.produce(None, &topition, value_batch(&resumed)?).await?
+    );
+
+    assert_eq!(
+        vec![(3, Bytes::copy_from_slice(resumed.as_bytes()))],
+        fetch_values(&mut sc, &topition, 3).await?
+    );
+
+    let offset_stage = sc.offset_stage(&topition).await?;
+    assert_eq!(4, offset_stage.high_watermark());
+
+    // truncating at the high watermark leaves the topition unchanged
+    //
+    assert_eq!(4, sc.truncate(&topition, 4).await?);
+
+    Ok(())
+}
+
+pub async fn out_of_range(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topition = topition(&mut sc).await?;
+
+    for offset in 0..4 {
+        assert_eq!(
+            offset,
+            sc.produce(None, &topition, value_batch(&alphanumeric_string(15))?)
+                .await?
+        );
+    }
+
+    let deleted = sc
+        .delete_records(&[DeleteRecordsTopic {
+            name: topition.topic().into(),
+            partitions: Some(
+                [DeleteRecordsPartition {
+                    partition_index: topition.partition(),
+                    offset: 2,
+                }]
+                .into(),
+            ),
+        }])
+        .await?;
+    debug!(?deleted);
+
+    assert!(matches!(
+        sc.truncate(&topition, 1).await,
+        Err(tansu_storage::Error::Api(ErrorCode::OffsetOutOfRange))
+    ));
+
+    assert!(matches!(
+        sc.truncate(&topition, 5).await,
+        Err(tansu_storage::Error::Api(ErrorCode::OffsetOutOfRange))
+    ));
+
+    let offset_stage = sc.offset_stage(&topition).await?;
+    assert_eq!(2, offset_stage.log_start());
+    assert_eq!(4, offset_stage.high_watermark());
+
+    // truncating to the low watermark removes every record
+    //
+    assert_eq!(2, sc.truncate(&topition, 2).await?);
+    assert!(fetch_values(&mut sc, &topition, 2).await?.is_empty());
+
+    Ok(())
+}
+
+pub async fn unknown_topition(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    assert!(matches!(
+        sc.truncate(&Topition::new(alphanumeric_string(15), 0), 0)
+            .await,
+        Err(tansu_storage::Error::Api(
+            ErrorCode::UnknownTopicOrPartition
+        ))
+    ));
+
+    Ok(())
+}
+
+mod pg {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::Postgres,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn mid_offset() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::mid_offset(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn out_of_range() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::out_of_range(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn unknown_topition() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::unknown_topition(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
+
+mod in_memory {
+    use super::*;
+
+    fn storage_container(cluster: impl Into<String>, node: i32) -> Result<StorageContainer> {
+        Url::parse("tcp://127.0.0.1/")
+            .map_err(Into::into)
+            .and_then(|advertised_listener| {
+                common::storage_container(
+                    StorageType::InMemory,
+                    cluster,
+                    node,
+                    advertised_listener,
+                    None,
+                )
+            })
+    }
+
+    #[tokio::test]
+    async fn mid_offset() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::mid_offset(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn out_of_range() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::out_of_range(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn unknown_topition() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::unknown_topition(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+}
@@ -27,11 +27,12 @@ use tansu_kafka_sans_io::{
     create_topics_request::CreatableTopic,
     record::{Record, inflated},
     txn_offset_commit_request::{TxnOffsetCommitRequestPartition, TxnOffsetCommitRequestTopic},
+    txn_offset_commit_response::{TxnOffsetCommitResponsePartition, TxnOffsetCommitResponseTopic},
 };
 use tansu_server::Result;
 use tansu_storage::{
-    ListOffsetRequest, Storage, StorageContainer, TopicId, Topition, TxnAddPartitionsRequest,
-    TxnOffsetCommitRequest,
+    Error, ListOffsetRequest, Storage, StorageContainer, TopicId, Topition,
+    TxnAddPartitionsRequest, TxnOffsetCommitRequest,
 };
 use tracing::{debug, error};
 use url::Url;
@@ -50,7 +51,7 @@ pub async fn simple_txn_commit_offset_commit(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
 
     let assignments = Some([].into());
     let configs = Some([].into());
@@ -172,7 +173,7 @@ pub async fn simple_txn_commit_offset_abort(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
 
     let assignments = Some([].into());
     let configs = Some([].into());
@@ -294,7 +295,7 @@ pub async fn simple_txn_produce_commit(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
 
     let assignments = Some([].into());
     let configs = Some([].into());
@@ -582,7 +583,7 @@ pub async fn simple_txn_produce_abort(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
 
     let assignments = Some([].into());
     let configs = Some([].into());
@@ -871,7 +872,7 @@ pub async fn with_overlap(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
 
     let assignments = Some([].into());
     let configs = Some([].into());
@@ -1239,7 +1240,7 @@ pub async fn init_producer_twice(
     debug!(?topic_name);
 
     let num_partitions = 6;
-    let replication_factor = 0;
+    let replication_factor = 1;
 
     let assignments = Some([].into());
     let configs = Some([].into());
@@ -1547,6 +1548,548 @@ pub async fn init_producer_twice(
     Ok(())
 }
 
+pub async fn txn_produce_gated_at_last_stable(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let num_partitions = 6;
+    let replication_factor = 1;
+
+    let topic_id = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions,
+                replication_factor,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+    debug!(?topic_id);
+
+    let partition_index = rng().random_range(0..num_partitions);
+    let topition = Topition::new(topic_name.clone(), partition_index);
+
+    // a produce without a transactional id is immediately stable
+    //
+    {
+        let batch = inflated::Batch::builder()
+            .record(
+                Record::builder()
+                    .key(Bytes::copy_from_slice(alphanumeric_string(15).as_bytes()).into())
+                    .value(Bytes::copy_from_slice(alphanumeric_string(15).as_bytes()).into()),
+            )
+            .build()
+            .and_then(TryInto::try_into)
+            .inspect(|deflated| debug!(?deflated))
+            .inspect_err(|err| error!(?err))?;
+
+        assert_eq!(0, sc.produce(None, &topition, batch).await?);
+
+        let offset_stage = sc.offset_stage(&topition).await?;
+        assert_eq!(1, offset_stage.high_watermark());
+        assert_eq!(1, offset_stage.last_stable());
+    }
+
+    let transaction_id = alphanumeric_string(10);
+    let transaction_timeout_ms = 10_000;
+
+    let producer = sc
+        .init_producer(
+            Some(transaction_id.as_str()),
+            transaction_timeout_ms,
+            Some(-1),
+            Some(-1),
+        )
+        .await?;
+
+    let add_partitions = sc
+        .txn_add_partitions(TxnAddPartitionsRequest::VersionZeroToThree {
+            transaction_id: transaction_id.clone(),
+            producer_id: producer.id,
+            producer_epoch: producer.epoch,
+            topics: [AddPartitionsToTxnTopic {
+                name: topic_name.clone(),
+                partitions: Some([partition_index].into()),
+            }]
+            .into(),
+        })
+        .await?;
+    debug!(?add_partitions);
+
+    // a produce with a transactional id is gated at the last stable offset
+    //
+    {
+        let batch = inflated::Batch::builder()
+            .record(
+                Record::builder()
+                    .key(Bytes::copy_from_slice(alphanumeric_string(15).as_bytes()).into())
+                    .value(Bytes::copy_from_slice(alphanumeric_string(15).as_bytes()).into()),
+            )
+            .attributes(BatchAttribute::default().transaction(true).into())
+            .producer_id(producer.id)
+            .producer_epoch(producer.epoch)
+            .base_sequence(0)
+            .build()
+            .and_then(TryInto::try_into)
+            .inspect(|deflated| debug!(?deflated))
+            .inspect_err(|err| error!(?err))?;
+
+        assert_eq!(
+            1,
+            sc.produce(Some(transaction_id.as_str()), &topition, batch)
+                .await?
+        );
+
+        let offset_stage = sc.offset_stage(&topition).await?;
+        assert_eq!(2, offset_stage.high_watermark());
+        assert_eq!(1, offset_stage.last_stable());
+    }
+
+    assert_eq!(
+        ErrorCode::None,
+        sc.txn_end(transaction_id.as_str(), producer.id, producer.epoch, true)
+            .await?
+    );
+
+    // the end transaction marker is stable once committed
+    //
+    {
+        let offset_stage = sc.offset_stage(&topition).await?;
+        assert_eq!(3, offset_stage.high_watermark());
+        assert_eq!(3, offset_stage.last_stable());
+    }
+
+    assert_eq!(
+        ErrorCode::None,
+        sc.delete_topic(&TopicId::from(topic_id)).await?
+    );
+
+    Ok(())
+}
+
+pub async fn producer_restart_fences_previous_epoch(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let num_partitions = 6;
+
+    let topic_id = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+    debug!(?topic_id);
+
+    let transaction_timeout_ms = 10_000;
+    let transaction_id: String = alphanumeric_string(10);
+
+    let partition_index = rng().random_range(0..num_partitions);
+    let topition = Topition::new(topic_name.clone(), partition_index);
+
+    let add_partitions =
+        |producer_id, producer_epoch| TxnAddPartitionsRequest::VersionZeroToThree {
+            transaction_id: transaction_id.clone(),
+            producer_id,
+            producer_epoch,
+            topics: [AddPartitionsToTxnTopic {
+                name: topic_name.clone(),
+                partitions: Some([partition_index].into()),
+            }]
+            .into(),
+        };
+
+    let added = |error_code: ErrorCode| {
+        [AddPartitionsToTxnTopicResult {
+            name: topic_name.clone(),
+            results_by_partition: Some(
+                [AddPartitionsToTxnPartitionResult {
+                    partition_index,
+                    partition_error_code: error_code.into(),
+                }]
+                .into(),
+            ),
+        }]
+    };
+
+    let batch = |producer_id, producer_epoch, base_sequence| {
+        inflated::Batch::builder()
+            .record(Record::builder().value(Bytes::from_static(b"abc").into()))
+            .attributes(BatchAttribute::default().transaction(true).into())
+            .producer_id(producer_id)
+            .producer_epoch(producer_epoch)
+            .base_sequence(base_sequence)
+            .build()
+            .and_then(TryInto::try_into)
+    };
+
+    let zombie = sc
+        .init_producer(
+            Some(transaction_id.as_str()),
+            transaction_timeout_ms,
+            Some(-1),
+            Some(-1),
+        )
+        .await?;
+    debug!(?zombie);
+
+    assert_eq!(
+        added(ErrorCode::None),
+        sc.txn_add_partitions(add_partitions(zombie.id, zombie.epoch))
+            .await?
+            .zero_to_three()
+    );
+
+    _ = sc
+        .produce(
+            Some(transaction_id.as_str()),
+            &topition,
+            batch(zombie.id, zombie.epoch, 0)?,
+        )
+        .await?;
+
+    // the producer restarts, bumping the epoch of the transactional id
+    //
+    let producer = sc
+        .init_producer(
+            Some(transaction_id.as_str()),
+            transaction_timeout_ms,
+            Some(-1),
+            Some(-1),
+        )
+        .await?;
+    debug!(?producer);
+
+    assert_eq!(zombie.id, producer.id);
+    assert_eq!(zombie.epoch + 1, producer.epoch);
+
+    // the zombie, still using the older epoch, is fenced
+    //
+    assert!(matches!(
+        sc.produce(
+            Some(transaction_id.as_str()),
+            &topition,
+            batch(zombie.id, zombie.epoch, 1)?,
+        )
+        .await,
+        Err(Error::Api(ErrorCode::ProducerFenced))
+    ));
+
+    assert_eq!(
+        added(ErrorCode::ProducerFenced),
+        sc.txn_add_partitions(add_partitions(zombie.id, zombie.epoch))
+            .await?
+            .zero_to_three()
+    );
+
+    assert_eq!(
+        ErrorCode::ProducerFenced,
+        sc.txn_add_offsets(
+            transaction_id.as_str(),
+            zombie.id,
+            zombie.epoch,
+            alphanumeric_string(10).as_str(),
+        )
+        .await?
+    );
+
+    assert!(matches!(
+        sc.txn_end(transaction_id.as_str(), zombie.id, zombie.epoch, true)
+            .await,
+        Err(Error::Api(ErrorCode::ProducerFenced))
+    ));
+
+    // whereas the restarted producer continues
+    //
+    assert_eq!(
+        added(ErrorCode::None),
+        sc.txn_add_partitions(add_partitions(producer.id, producer.epoch))
+            .await?
+            .zero_to_three()
+    );
+
+    _ = sc
+        .produce(
+            Some(transaction_id.as_str()),
+            &topition,
+            batch(producer.id, producer.epoch, 0)?,
+        )
+        .await?;
+
+    assert_eq!(
+        ErrorCode::None,
+        sc.txn_end(transaction_id.as_str(), producer.id, producer.epoch, true)
+            .await?
+    );
+
+    assert_eq!(
+        ErrorCode::None,
+        sc.delete_topic(&TopicId::from(topic_id)).await?
+    );
+
+    Ok(())
+}
+
+pub async fn stale_epoch_is_fenced(
+    cluster_id: Uuid,
+    broker_id: i32,
+    mut sc: StorageContainer,
+) -> Result<()> {
+    register_broker(&cluster_id, broker_id, &mut sc).await?;
+
+    let topic_name: String = alphanumeric_string(15);
+    debug!(?topic_name);
+
+    let topic_id = sc
+        .create_topic(
+            CreatableTopic {
+                name: topic_name.clone(),
+                num_partitions: 1,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+    debug!(?topic_id);
+
+    let transaction_timeout_ms = 10_000;
+    let transaction_id: String = alphanumeric_string(10);
+    let group_id: String = alphanumeric_string(15);
+    let partition_index = 0;
+
+    let add_partitions =
+        |producer_id, producer_epoch| TxnAddPartitionsRequest::VersionZeroToThree {
+            transaction_id: transaction_id.clone(),
+            producer_id,
+            producer_epoch,
+            topics: [AddPartitionsToTxnTopic {
+                name: topic_name.clone(),
+                partitions: Some([partition_index].into()),
+            }]
+            .into(),
+        };
+
+    let added = |error_code: ErrorCode| {
+        [AddPartitionsToTxnTopicResult {
+            name: topic_name.clone(),
+            results_by_partition: Some(
+                [AddPartitionsToTxnPartitionResult {
+                    partition_index,
+                    partition_error_code: error_code.into(),
+                }]
+                .into(),
+            ),
+        }]
+    };
+
+    let offset_commit = |producer_id, producer_epoch| TxnOffsetCommitRequest {
+        transaction_id: transaction_id.clone(),
+        group_id: group_id.clone(),
+        producer_id,
+        producer_epoch,
+        generation_id: None,
+        member_id: None,
+        group_instance_id: None,
+        topics: vec![TxnOffsetCommitRequestTopic {
+            name: topic_name.clone(),
+            partitions: Some(vec![TxnOffsetCommitRequestPartition {
+                partition_index,
+                committed_offset: 6,
+                committed_leader_epoch: None,
+                committed_metadata: None,
+            }]),
+        }],
+    };
+
+    let committed = |error_code: ErrorCode| {
+        vec![TxnOffsetCommitResponseTopic {
+            name: topic_name.clone(),
+            partitions: Some(vec![TxnOffsetCommitResponsePartition {
+                partition_index,
+                error_code: error_code.into(),
+            }]),
+        }]
+    };
+
+    let stale = sc
+        .init_producer(
+            Some(transaction_id.as_str()),
+            transaction_timeout_ms,
+            Some(-1),
+            Some(-1),
+        )
+        .await?;
+    debug!(?stale);
+
+    // an existing producer using the current epoch bumps the epoch
+    //
+    let current = sc
+        .init_producer(
+            Some(transaction_id.as_str()),
+            transaction_timeout_ms,
+            Some(stale.id),
+            Some(stale.epoch),
+        )
+        .await?;
+    debug!(?current);
+
+    assert_eq!(ErrorCode::None, current.error);
+    assert_eq!(stale.id, current.id);
+    assert_eq!(stale.epoch + 1, current.epoch);
+
+    // every method fences the stale epoch
+    //
+    assert_eq!(
+        ErrorCode::ProducerFenced,
+        sc.init_producer(
+            Some(transaction_id.as_str()),
+            transaction_timeout_ms,
+            Some(stale.id),
+            Some(stale.epoch),
+        )
+        .await?
+        .error
+    );
+
+    assert_eq!(
+        added(ErrorCode::ProducerFenced),
+        sc.txn_add_partitions(add_partitions(stale.id, stale.epoch))
+            .await?
+            .zero_to_three()
+    );
+
+    assert_eq!(
+        ErrorCode::ProducerFenced,
+        sc.txn_add_offsets(
+            transaction_id.as_str(),
+            stale.id,
+            stale.epoch,
+            group_id.as_str(),
+        )
+        .await?
+    );
+
+    assert_eq!(
+        committed(ErrorCode::ProducerFenced),
+        sc.txn_offset_commit(offset_commit(stale.id, stale.epoch))
+            .await?
+    );
+
+    assert!(matches!(
+        sc.txn_end(transaction_id.as_str(), stale.id, stale.epoch, true)
+            .await,
+        Err(Error::Api(ErrorCode::ProducerFenced))
+    ));
+
+    // an epoch that is ahead of the current epoch was never issued
+    //
+    let ahead = current.epoch + 1;
+
+    assert_eq!(
+        ErrorCode::InvalidProducerEpoch,
+        sc.init_producer(
+            Some(transaction_id.as_str()),
+            transaction_timeout_ms,
+            Some(current.id),
+            Some(ahead),
+        )
+        .await?
+        .error
+    );
+
+    assert_eq!(
+        added(ErrorCode::InvalidProducerEpoch),
+        sc.txn_add_partitions(add_partitions(current.id, ahead))
+            .await?
+            .zero_to_three()
+    );
+
+    assert_eq!(
+        ErrorCode::InvalidProducerEpoch,
+        sc.txn_add_offsets(
+            transaction_id.as_str(),
+            current.id,
+            ahead,
+            group_id.as_str(),
+        )
+        .await?
+    );
+
+    assert_eq!(
+        committed(ErrorCode::InvalidProducerEpoch),
+        sc.txn_offset_commit(offset_commit(current.id, ahead))
+            .await?
+    );
+
+    assert!(matches!(
+        sc.txn_end(transaction_id.as_str(), current.id, ahead, true)
+            .await,
+        Err(Error::Api(ErrorCode::InvalidProducerEpoch))
+    ));
+
+    // while the current epoch continues
+    //
+    assert_eq!(
+        added(ErrorCode::None),
+        sc.txn_add_partitions(add_partitions(current.id, current.epoch))
+            .await?
+            .zero_to_three()
+    );
+
+    assert_eq!(
+        ErrorCode::None,
+        sc.txn_add_offsets(
+            transaction_id.as_str(),
+            current.id,
+            current.epoch,
+            group_id.as_str(),
+        )
+        .await?
+    );
+
+    assert_eq!(
+        committed(ErrorCode::None),
+        sc.txn_offset_commit(offset_commit(current.id, current.epoch))
+            .await?
+    );
+
+    assert_eq!(
+        ErrorCode::None,
+        sc.txn_end(transaction_id.as_str(), current.id, current.epoch, true)
+            .await?
+    );
+
+    assert_eq!(
+        ErrorCode::None,
+        sc.delete_topic(&TopicId::from(topic_id)).await?
+    );
+
+    Ok(())
+}
+
 mod pg {
     use super::*;
 
@@ -1653,6 +2196,51 @@ mod pg {
         )
         .await
     }
+
+    #[tokio::test]
+    async fn txn_produce_gated_at_last_stable() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::txn_produce_gated_at_last_stable(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn producer_restart_fences_previous_epoch() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::producer_restart_fences_previous_epoch(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn stale_epoch_is_fenced() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::stale_epoch_is_fenced(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
 }
 
 mod in_memory {
@@ -1761,4 +2349,49 @@ mod in_memory {
         )
         .await
     }
+
+    #[tokio::test]
+    async fn txn_produce_gated_at_last_stable() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::txn_produce_gated_at_last_stable(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn producer_restart_fences_previous_epoch() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::producer_restart_fences_previous_epoch(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn stale_epoch_is_fenced() -> Result<()> {
+        let _guard = init_tracing()?;
+
+        let cluster_id = Uuid::now_v7();
+        let broker_id = rng().random_range(0..i32::MAX);
+
+        super::stale_epoch_is_fenced(
+            cluster_id,
+            broker_id,
+            storage_container(cluster_id, broker_id)?,
+        )
+        .await
+    }
 }
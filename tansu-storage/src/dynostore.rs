@@ -18,7 +18,10 @@ use std::{
     fmt::{Debug, Display},
     io::Cursor,
     str::FromStr,
-    sync::{Arc, Mutex},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
     time::{Duration, SystemTime},
 };
 
@@ -42,17 +45,19 @@ use opticon::OptiCon;
 use rand::{prelude::*, rng};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use tansu_kafka_sans_io::{
-    BatchAttribute, ConfigResource, ConfigSource, ConfigType, ControlBatch, Decoder, Encoder,
-    EndTransactionMarker, ErrorCode, IsolationLevel, NULL_TOPIC_ID, OpType,
+    BatchAttribute, ConfigResource, ControlBatch, Decoder, Encoder, EndTransactionMarker,
+    ErrorCode, IsolationLevel, NULL_TOPIC_ID, OpType, TimestampType,
+    add_partitions_to_txn_request::AddPartitionsToTxnTopic,
     add_partitions_to_txn_response::{
         AddPartitionsToTxnPartitionResult, AddPartitionsToTxnTopicResult,
     },
-    create_topics_request::{CreatableTopic, CreatableTopicConfig},
+    create_partitions_request::CreatePartitionsAssignment,
+    create_topics_request::{CreatableReplicaAssignment, CreatableTopic, CreatableTopicConfig},
     delete_groups_response::DeletableGroupResult,
     delete_records_request::DeleteRecordsTopic,
-    delete_records_response::DeleteRecordsTopicResult,
+    delete_records_response::{DeleteRecordsPartitionResult, DeleteRecordsTopicResult},
     describe_cluster_response::DescribeClusterBroker,
-    describe_configs_response::{DescribeConfigsResourceResult, DescribeConfigsResult},
+    describe_configs_response::DescribeConfigsResult,
     describe_topic_partitions_response::{
         DescribeTopicPartitionsResponsePartition, DescribeTopicPartitionsResponseTopic,
     },
@@ -61,6 +66,7 @@ use tansu_kafka_sans_io::{
     list_groups_response::ListedGroup,
     metadata_response::{MetadataResponseBroker, MetadataResponsePartition, MetadataResponseTopic},
     record::{Record, deflated, inflated},
+    to_system_time, to_timestamp,
     txn_offset_commit_response::{TxnOffsetCommitResponsePartition, TxnOffsetCommitResponseTopic},
 };
 use tansu_schema_registry::{
@@ -75,10 +81,15 @@ mod metadata;
 mod opticon;
 
 use crate::{
-    BrokerRegistrationRequest, Error, GroupDetail, ListOffsetRequest, ListOffsetResponse, METER,
-    MetadataResponse, NamedGroupDetail, OffsetCommitRequest, OffsetStage, ProducerIdResponse,
-    Result, Storage, TopicId, Topition, TxnAddPartitionsRequest, TxnAddPartitionsResponse,
-    TxnOffsetCommitRequest, TxnState, UpdateError, Version,
+    BrokerRegistrationRequest, CommittedOffset, DEFAULT_NUM_PARTITIONS, Error, GroupDetail,
+    ListOffsetRequest, ListOffsetResponse, LogDirDescription, METER, MetadataResponse,
+    NamedGroupDetail, OFFSET_METADATA_MAX_BYTES, OFFSETS_RETENTION, OffsetCommitRequest,
+    OffsetStage, PRODUCER_ID_SEQUENCE_WINDOW, ProducerIdResponse, ProducerState, Result, Storage,
+    TopicDetail, TopicId, Topition, TxnAddPartitionsRequest, TxnAddPartitionsResponse,
+    TxnOffsetCommitRequest, TxnState, UpdateError, Version, assign_replicas, broker_config,
+    export::Scan, idempotent_sequence, log_append_time, num_partitions, offset_expiry,
+    replication_factor, timestamp_type, topic_configs, unsupported_config, validate_batch,
+    validate_topic, verify_producer_epoch,
 };
 
 const APPLICATION_JSON: &str = "application/json";
@@ -90,11 +101,24 @@ pub struct DynoStore {
     advertised_listener: Url,
     schemas: Option<Registry>,
     lake: Option<House>,
+    sequence_window: usize,
+    offsets_retention: Duration,
+    offset_metadata_max_bytes: usize,
+    num_partitions: i32,
 
     watermarks: Arc<Mutex<BTreeMap<Topition, OptiCon<Watermark>>>>,
     meta: OptiCon<Meta>,
 
     object_store: Arc<DynObjectStore>,
+    closed: Arc<AtomicBool>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+struct CommittedOffsetDetail {
+    #[serde(flatten)]
+    commit: OffsetCommitRequest,
+    #[serde(default)]
+    expire_timestamp: Option<SystemTime>,
 }
 
 type Group = String;
@@ -253,6 +277,9 @@ impl Meta {
 #[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 struct ProducerDetail {
     sequences: BTreeMap<ProducerEpoch, BTreeMap<String, BTreeMap<i32, Sequence>>>,
+
+    #[serde(default)]
+    batches: BTreeMap<ProducerEpoch, BTreeMap<Topic, BTreeMap<Partition, Vec<Sequence>>>>,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
@@ -269,6 +296,16 @@ struct Txn {
     epochs: BTreeMap<ProducerEpoch, TxnDetail>,
 }
 
+impl Txn {
+    fn producer_check(&self, producer_id: ProducerId, producer_epoch: ProducerEpoch) -> ErrorCode {
+        self.epochs
+            .last_key_value()
+            .map_or(ErrorCode::ProducerFenced, |(current_epoch, _)| {
+                verify_producer_epoch((self.producer, *current_epoch), producer_id, producer_epoch)
+            })
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 struct TxnDetail {
     transaction_timeout_ms: i32,
@@ -322,6 +359,7 @@ struct TopicMetadata {
 struct Watermark {
     low: Option<i64>,
     high: Option<i64>,
+    record_count: Option<i64>,
 }
 
 impl OptiCon<Watermark> {
@@ -367,12 +405,17 @@ impl DynoStore {
             advertised_listener: Url::parse("tcp://127.0.0.1/").unwrap(),
             schemas: None,
             lake: None,
+            sequence_window: PRODUCER_ID_SEQUENCE_WINDOW,
+            offsets_retention: OFFSETS_RETENTION,
+            offset_metadata_max_bytes: OFFSET_METADATA_MAX_BYTES,
+            num_partitions: DEFAULT_NUM_PARTITIONS,
             watermarks: Arc::new(Mutex::new(BTreeMap::new())),
             meta: OptiCon::<Meta>::new(cluster),
             object_store: Arc::new(Cache::new(
                 Metron::new(object_store, cluster),
                 Duration::from_millis(5_000),
             )),
+            closed: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -391,6 +434,88 @@ impl DynoStore {
         Self { lake, ..self }
     }
 
+    /// The number of batches retained per producer and topition when
+    /// deduplicating idempotent produces.
+    pub fn sequence_window(self, sequence_window: usize) -> Self {
+        Self {
+            sequence_window,
+            ..self
+        }
+    }
+
+    /// The retention of committed offsets when a commit doesn't
+    /// specify one.
+    pub fn offsets_retention(self, offsets_retention: Duration) -> Self {
+        Self {
+            offsets_retention,
+            ..self
+        }
+    }
+
+    /// The maximum size of the metadata of a committed offset.
+    pub fn offset_metadata_max_bytes(self, offset_metadata_max_bytes: usize) -> Self {
+        Self {
+            offset_metadata_max_bytes,
+            ..self
+        }
+    }
+
+    /// The number of partitions of a topic created with `-1`.
+    pub fn num_partitions(self, num_partitions: i32) -> Self {
+        Self {
+            num_partitions,
+            ..self
+        }
+    }
+
+    async fn committed_topitions(&self, group_id: &str) -> Result<Vec<Topition>> {
+        let mut topitions = vec![];
+
+        let location = Path::from(format!(
+            "clusters/{}/groups/consumers/{}/offsets/",
+            self.cluster, group_id,
+        ));
+
+        let mut list_stream = self.object_store.list(Some(&location));
+
+        while let Some(meta) = list_stream
+            .next()
+            .await
+            .inspect(|meta| debug!(?meta))
+            .transpose()
+            .inspect_err(|error| error!(?error))
+            .map_err(|_| Error::Api(ErrorCode::UnknownServerError))?
+        {
+            debug!(?meta);
+            let Some(topic): Option<String> = meta
+                .location
+                .parts()
+                .nth(6)
+                .inspect(|topic| debug!(?topic))
+                .map(|topic| topic.as_ref().into())
+            else {
+                continue;
+            };
+
+            let Some(partition) = meta
+                .location
+                .parts()
+                .nth(8)
+                .inspect(|partition| debug!(?partition))
+                .map(|partition| i32::from_str(&partition.as_ref()[0..10]))
+                .transpose()?
+            else {
+                continue;
+            };
+
+            debug!(topic, partition);
+
+            topitions.push(Topition::new(topic, partition));
+        }
+
+        Ok(topitions)
+    }
+
     async fn topic_metadata(&self, topic: &TopicId) -> Result<Option<TopicMetadata>> {
         debug!(?topic);
 
@@ -410,6 +535,193 @@ impl DynoStore {
             .await
     }
 
+    /// Reset the watermarks of a new topition.
+    async fn reset_watermark(&self, topition: &Topition) -> Result<()> {
+        let watermark = self.watermarks.lock().map(|mut locked| {
+            locked
+                .entry(topition.to_owned())
+                .or_insert(OptiCon::<Watermark>::new(self.cluster.as_str(), topition))
+                .to_owned()
+        })?;
+
+        watermark
+            .with_mut(&self.object_store, |watermark| {
+                watermark.high.take();
+                watermark.low.take();
+
+                Ok(())
+            })
+            .await
+    }
+
+    async fn delete_batches_before(
+        &self,
+        topition: &Topition,
+        offset: i64,
+        high_watermark: i64,
+    ) -> Result<()> {
+        debug!(?topition, offset, high_watermark);
+
+        let location = Path::from(format!(
+            "clusters/{}/topics/{}/partitions/{:0>10}/records/",
+            self.cluster, topition.topic, topition.partition
+        ));
+
+        let mut offsets = self
+            .object_store
+            .list(Some(&location))
+            .try_collect::<Vec<_>>()
+            .await?
+            .into_iter()
+            .filter_map(|meta| {
+                meta.location
+                    .parts()
+                    .last()
+                    .and_then(|offset| i64::from_str(&offset.as_ref()[0..20]).ok())
+            })
+            .collect::<BTreeSet<_>>();
+
+        _ = offsets.insert(high_watermark);
+
+        let offsets = offsets.into_iter().collect::<Vec<_>>();
+
+        // a batch is only removed once every record within it is before the offset
+        //
+        for batch in offsets.windows(2) {
+            if batch[1] <= offset {
+                let location = Path::from(format!(
+                    "clusters/{}/topics/{}/partitions/{:0>10}/records/{:0>20}.batch",
+                    self.cluster, topition.topic, topition.partition, batch[0],
+                ));
+
+                self.object_store
+                    .delete(&location)
+                    .await
+                    .inspect(|outcome| debug!(?outcome, %location))
+                    .inspect_err(|error| error!(?error, %location))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove the batches at or after offset, which must not fall within
+    /// a batch as a batch is only ever removed whole.
+    async fn delete_batches_from(
+        &self,
+        topition: &Topition,
+        offset: i64,
+        high_watermark: i64,
+    ) -> Result<()> {
+        debug!(?topition, offset, high_watermark);
+
+        let location = Path::from(format!(
+            "clusters/{}/topics/{}/partitions/{:0>10}/records/",
+            self.cluster, topition.topic, topition.partition
+        ));
+
+        let offsets = self
+            .object_store
+            .list(Some(&location))
+            .try_collect::<Vec<_>>()
+            .await?
+            .into_iter()
+            .filter_map(|meta| {
+                meta.location
+                    .parts()
+                    .last()
+                    .and_then(|offset| i64::from_str(&offset.as_ref()[0..20]).ok())
+            })
+            .collect::<BTreeSet<_>>();
+
+        let end = offsets
+            .range(offset..)
+            .next()
+            .copied()
+            .unwrap_or(high_watermark);
+
+        if offsets.range(..offset).next_back().is_some() && end > offset {
+            debug!(?topition, offset, end);
+            return Err(Error::Api(ErrorCode::InvalidRequest));
+        }
+
+        for base_offset in offsets.range(offset..) {
+            let location = Path::from(format!(
+                "clusters/{}/topics/{}/partitions/{:0>10}/records/{:0>20}.batch",
+                self.cluster, topition.topic, topition.partition, base_offset,
+            ));
+
+            self.object_store
+                .delete(&location)
+                .await
+                .inspect(|outcome| debug!(?outcome, %location))
+                .inspect_err(|error| error!(?error, %location))?;
+        }
+
+        Ok(())
+    }
+
+    fn opened(&self) -> Result<()> {
+        if self.closed.load(Ordering::Relaxed) {
+            Err(Error::Closed)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Export the records of a committed transaction to the lake. The
+    /// transaction has already committed, so a failure is logged
+    /// rather than failing the request.
+    async fn lake_export(
+        &mut self,
+        topition: &Topition,
+        producer_id: ProducerId,
+        offset_range: TxnProduceOffset,
+    ) {
+        let (Some(registry), Some(lake)) = (self.schemas.clone(), self.lake.clone()) else {
+            return;
+        };
+
+        if let Err(err) = async {
+            let config = self
+                .describe_config(topition.topic(), ConfigResource::Topic, None)
+                .await?;
+
+            for batch in Scan::new(topition.to_owned())
+                .offset(offset_range.offset_start)
+                .end(offset_range.offset_end + 1)
+                .isolation(IsolationLevel::ReadUncommitted)
+                .batches(self)
+                .await?
+            {
+                if batch.producer_id != producer_id
+                    || !BatchAttribute::try_from(batch.attributes)?.transaction
+                {
+                    continue;
+                }
+
+                if let Some(record_batch) =
+                    registry.as_arrow(topition.topic(), topition.partition(), &batch)?
+                {
+                    lake.store(
+                        topition.topic(),
+                        topition.partition(),
+                        batch.base_offset,
+                        record_batch,
+                        config.clone(),
+                    )
+                    .await?;
+                }
+            }
+
+            Ok::<(), Error>(())
+        }
+        .await
+        {
+            error!(?err, ?topition, producer_id, ?offset_range);
+        }
+    }
+
     fn encode(&self, deflated: deflated::Batch) -> Result<PutPayload> {
         let mut encoded = Cursor::new(vec![]);
         let mut encoder = Encoder::new(&mut encoded);
@@ -425,6 +737,121 @@ impl DynoStore {
         deflated::Batch::deserialize(&mut decoder).map_err(Into::into)
     }
 
+    /// The record batches of a topition between the low and high
+    /// watermarks, in offset order.
+    async fn batches(&self, topition: &Topition) -> Result<(Watermark, Vec<inflated::Batch>)> {
+        debug!(?topition);
+
+        let watermark = self.watermarks.lock().map(|mut locked| {
+            locked
+                .entry(topition.to_owned())
+                .or_insert(OptiCon::<Watermark>::new(self.cluster.as_str(), topition))
+                .to_owned()
+        })?;
+
+        let watermark = watermark
+            .with(&self.object_store, |watermark| Ok(watermark.to_owned()))
+            .await?;
+
+        let low = watermark.low.unwrap_or_default();
+        let high = watermark.high.unwrap_or_default();
+
+        let location = Path::from(format!(
+            "clusters/{}/topics/{}/partitions/{:0>10}/records/",
+            self.cluster, topition.topic, topition.partition
+        ));
+
+        let mut offsets = BTreeSet::new();
+        let mut list_stream = self.object_store.list(Some(&location));
+
+        while let Some(meta) = list_stream
+            .next()
+            .await
+            .transpose()
+            .inspect_err(|error| error!(?error, ?topition))
+            .map_err(|_| Error::Api(ErrorCode::UnknownServerError))?
+        {
+            let Some(offset) = meta.location.parts().last() else {
+                continue;
+            };
+
+            let offset = i64::from_str(&offset.as_ref()[0..20])?;
+
+            if offset < high {
+                _ = offsets.insert(offset);
+            }
+        }
+
+        let mut batches = vec![];
+
+        for offset in offsets {
+            let location = Path::from(format!(
+                "clusters/{}/topics/{}/partitions/{:0>10}/records/{:0>20}.batch",
+                self.cluster, topition.topic, topition.partition, offset,
+            ));
+
+            let mut batch = self
+                .object_store
+                .get(&location)
+                .await
+                .inspect_err(|error| error!(?error, %location))
+                .map_err(|_| Error::Api(ErrorCode::UnknownServerError))?
+                .bytes()
+                .await
+                .inspect_err(|error| error!(?error, %location))
+                .map_err(|_| Error::Api(ErrorCode::UnknownServerError))
+                .and_then(|encoded| self.decode(encoded))?;
+            batch.base_offset = offset;
+
+            let batch = inflated::Batch::try_from(batch)?;
+
+            if batch.base_offset + i64::from(batch.last_offset_delta) >= low {
+                batches.push(batch);
+            }
+        }
+
+        Ok((watermark, batches))
+    }
+
+    /// The offset of the first record at or after a timestamp, or the
+    /// high watermark when there is no such record.
+    async fn offset_for_timestamp(
+        &self,
+        topition: &Topition,
+        timestamp: SystemTime,
+    ) -> Result<ListOffsetResponse> {
+        debug!(?topition, ?timestamp);
+
+        let timestamp = to_timestamp(timestamp)?;
+
+        let (watermark, batches) = self.batches(topition).await?;
+        let low = watermark.low.unwrap_or_default();
+        let high = watermark.high.unwrap_or_default();
+
+        for batch in batches {
+            for record in batch.records {
+                let offset = batch.base_offset + i64::from(record.offset_delta);
+                let record_timestamp = batch.base_timestamp + record.timestamp_delta;
+
+                if offset >= low && offset < high && record_timestamp >= timestamp {
+                    return to_system_time(record_timestamp)
+                        .map(|timestamp| ListOffsetResponse {
+                            error_code: ErrorCode::None,
+                            timestamp: Some(timestamp),
+                            offset: Some(offset),
+                        })
+                        .map_err(Into::into);
+                }
+            }
+        }
+
+        Ok(ListOffsetResponse {
+            error_code: ErrorCode::None,
+            timestamp: None,
+            offset: Some(high),
+        })
+    }
+
     async fn get<V>(&self, location: &Path) -> Result<(V, Version)>
     where
         V: DeserializeOwned,
@@ -487,6 +914,31 @@ impl DynoStore {
         }
     }
 
+    fn txn_add_partitions_response_error(
+        topics: &[AddPartitionsToTxnTopic],
+        error_code: ErrorCode,
+    ) -> Result<TxnAddPartitionsResponse> {
+        let mut results = vec![];
+
+        for topic in topics {
+            let mut results_by_partition = vec![];
+
+            for partition_index in topic.partitions.as_deref().unwrap_or(&[]) {
+                results_by_partition.push(AddPartitionsToTxnPartitionResult {
+                    partition_index: *partition_index,
+                    partition_error_code: error_code.into(),
+                });
+            }
+
+            results.push(AddPartitionsToTxnTopicResult {
+                name: topic.name.clone(),
+                results_by_partition: Some(results_by_partition),
+            })
+        }
+
+        Ok(TxnAddPartitionsResponse::VersionZeroToThree(results))
+    }
+
     fn txn_offset_commit_response_error(
         offsets: &TxnOffsetCommitRequest,
         error_code: ErrorCode,
@@ -521,6 +973,8 @@ impl Storage for DynoStore {
         &mut self,
         broker_registration: BrokerRegistrationRequest,
     ) -> Result<()> {
+        self.opened()?;
+
         debug!(?broker_registration);
         Ok(())
     }
@@ -529,6 +983,8 @@ impl Storage for DynoStore {
         &mut self,
         resource: AlterConfigsResource,
     ) -> Result<AlterConfigsResourceResponse> {
+        self.opened()?;
+
         let _ = resource;
 
         match ConfigResource::from(resource.resource_type) {
@@ -581,8 +1037,35 @@ impl Storage for DynoStore {
     }
 
     async fn create_topic(&mut self, topic: CreatableTopic, validate_only: bool) -> Result<Uuid> {
+        self.opened()?;
+
         debug!(?topic, ?validate_only);
 
+        let broker_ids = self
+            .brokers()
+            .await?
+            .into_iter()
+            .map(|broker| broker.broker_id)
+            .collect::<Vec<_>>();
+
+        let topic = assign_replicas(topic, &broker_ids)
+            .map(|topic| num_partitions(topic, self.num_partitions))
+            .and_then(|topic| replication_factor(topic, broker_ids.len()))?;
+
+        validate_topic(&topic, self.schemas.as_ref()).await?;
+
+        if validate_only {
+            return if self
+                .topic_metadata(&TopicId::Name(topic.name.clone()))
+                .await?
+                .is_some()
+            {
+                Err(Error::Api(ErrorCode::TopicAlreadyExists))
+            } else {
+                Ok(Uuid::nil())
+            };
+        }
+
         match self
             .meta
             .with_mut(&self.object_store, |meta| {
@@ -603,22 +1086,7 @@ impl Storage for DynoStore {
         {
             Ok(id) => {
                 for partition in 0..topic.num_partitions {
-                    let topition = Topition::new(topic.name.as_str(), partition);
-
-                    let watermark = self.watermarks.lock().map(|mut locked| {
-                        locked
-                            .entry(topition.to_owned())
-                            .or_insert(OptiCon::<Watermark>::new(self.cluster.as_str(), &topition))
-                            .to_owned()
-                    })?;
-
-                    watermark
-                        .with_mut(&self.object_store, |watermark| {
-                            watermark.high.take();
-                            watermark.low.take();
-
-                            Ok(())
-                        })
+                    self.reset_watermark(&Topition::new(topic.name.as_str(), partition))
                         .await?;
                 }
 
@@ -629,15 +1097,228 @@ impl Storage for DynoStore {
         }
     }
 
+    async fn create_partitions(
+        &mut self,
+        topic: &TopicId,
+        new_count: i32,
+        assignments: Option<&[CreatePartitionsAssignment]>,
+    ) -> Result<ErrorCode> {
+        self.opened()?;
+
+        debug!(?topic, new_count, ?assignments);
+
+        let Some(metadata) = self.topic_metadata(topic).await? else {
+            return Ok(ErrorCode::UnknownTopicOrPartition);
+        };
+
+        let partitions = metadata.topic.num_partitions;
+
+        // as with Kafka, the partition count must increase
+        //
+        if new_count <= partitions {
+            return Ok(ErrorCode::InvalidPartitions);
+        }
+
+        if assignments.is_some_and(|assignments| {
+            assignments.len() != (new_count - partitions) as usize
+                || assignments.iter().any(|assignment| {
+                    assignment
+                        .broker_ids
+                        .as_deref()
+                        .is_none_or(<[i32]>::is_empty)
+                })
+        }) {
+            return Ok(ErrorCode::InvalidReplicaAssignment);
+        }
+
+        for partition in partitions..new_count {
+            self.reset_watermark(&Topition::new(metadata.topic.name.as_str(), partition))
+                .await?;
+        }
+
+        self.meta
+            .with_mut(&self.object_store, |meta| {
+                let Some(topic_metadata) = meta.topics.get_mut(metadata.topic.name.as_str()) else {
+                    return Ok(ErrorCode::UnknownTopicOrPartition);
+                };
+
+                if topic_metadata.topic.num_partitions != partitions {
+                    return Ok(ErrorCode::InvalidPartitions);
+                }
+
+                if let Some(assignments) = assignments {
+                    topic_metadata
+                        .topic
+                        .assignments
+                        .get_or_insert_default()
+                        .extend((partitions..new_count).zip(assignments).map(
+                            |(partition_index, assignment)| CreatableReplicaAssignment {
+                                partition_index,
+                                broker_ids: assignment.broker_ids.clone(),
+                            },
+                        ));
+                }
+
+                topic_metadata.topic.num_partitions = new_count;
+
+                Ok(ErrorCode::None)
+            })
+            .await
+    }
+
     async fn delete_records(
         &mut self,
         topics: &[DeleteRecordsTopic],
     ) -> Result<Vec<DeleteRecordsTopicResult>> {
+        self.opened()?;
+
         debug!(?topics);
-        todo!()
+
+        let mut responses = vec![];
+
+        for topic in topics {
+            let num_partitions = self
+                .topic_metadata(&TopicId::Name(topic.name.clone()))
+                .await?
+                .map(|topic_metadata| topic_metadata.topic.num_partitions);
+
+            let mut partition_responses = vec![];
+
+            for partition in topic.partitions.as_deref().unwrap_or_default() {
+                if num_partitions.is_none_or(|num_partitions| {
+                    partition.partition_index < 0 || partition.partition_index >= num_partitions
+                }) {
+                    partition_responses.push(DeleteRecordsPartitionResult {
+                        partition_index: partition.partition_index,
+                        low_watermark: -1,
+                        error_code: ErrorCode::UnknownTopicOrPartition.into(),
+                    });
+
+                    continue;
+                }
+
+                let topition = Topition::new(topic.name.clone(), partition.partition_index);
+
+                let watermark = self.watermarks.lock().map(|mut locked| {
+                    locked
+                        .entry(topition.to_owned())
+                        .or_insert(OptiCon::<Watermark>::new(self.cluster.as_str(), &topition))
+                        .to_owned()
+                })?;
+
+                let (low_watermark, high_watermark, error_code) = watermark
+                    .with_mut(&self.object_store, |watermark| {
+                        debug!(?watermark);
+
+                        let low = watermark.low.unwrap_or_default();
+                        let high = watermark.high.unwrap_or_default();
+
+                        let offset = if partition.offset == -1 {
+                            high
+                        } else {
+                            partition.offset
+                        };
+
+                        if offset < 0 || offset > high {
+                            return Ok((low, high, ErrorCode::OffsetOutOfRange));
+                        }
+
+                        if offset > low {
+                            watermark.low = Some(offset);
+                            watermark.record_count =
+                                Some(watermark.record_count.unwrap_or_default() - (offset - low));
+                        }
+
+                        Ok((offset.max(low), high, ErrorCode::None))
+                    })
+                    .await
+                    .inspect(|outcome| debug!(?topition, ?outcome))
+                    .inspect_err(|err| error!(?err, ?topition))?;
+
+                if error_code == ErrorCode::None {
+                    self.delete_batches_before(&topition, low_watermark, high_watermark)
+                        .await?;
+                }
+
+                partition_responses.push(DeleteRecordsPartitionResult {
+                    partition_index: partition.partition_index,
+                    low_watermark,
+                    error_code: error_code.into(),
+                });
+            }
+
+            responses.push(DeleteRecordsTopicResult {
+                name: topic.name.clone(),
+                partitions: Some(partition_responses),
+            });
+        }
+
+        Ok(responses)
+    }
+
+    async fn truncate(&mut self, topition: &Topition, offset: i64) -> Result<i64> {
+        self.opened()?;
+
+        debug!(?topition, offset);
+
+        if self
+            .topic_metadata(&TopicId::Name(topition.topic.clone()))
+            .await?
+            .is_none_or(|topic_metadata| {
+                topition.partition < 0 || topition.partition >= topic_metadata.topic.num_partitions
+            })
+        {
+            return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
+        }
+
+        let watermark = self.watermarks.lock().map(|mut locked| {
+            locked
+                .entry(topition.to_owned())
+                .or_insert(OptiCon::<Watermark>::new(self.cluster.as_str(), topition))
+                .to_owned()
+        })?;
+
+        let (low, high) = watermark
+            .with(&self.object_store, |watermark| {
+                Ok((
+                    watermark.low.unwrap_or_default(),
+                    watermark.high.unwrap_or_default(),
+                ))
+            })
+            .await?;
+
+        debug!(?topition, low, high, offset);
+
+        if offset < low || offset > high {
+            return Err(Error::Api(ErrorCode::OffsetOutOfRange));
+        }
+
+        if offset == high {
+            return Ok(high);
+        }
+
+        self.delete_batches_from(topition, offset, high).await?;
+
+        watermark
+            .with_mut(&self.object_store, |watermark| {
+                let high = watermark.high.unwrap_or_default();
+
+                if offset < high {
+                    watermark.high = Some(offset);
+                    watermark.record_count =
+                        Some(watermark.record_count.unwrap_or_default() - (high - offset));
+                }
+
+                Ok(offset)
+            })
+            .await
+            .inspect(|high| debug!(?topition, high))
+            .inspect_err(|err| error!(?err, ?topition))
     }
 
     async fn delete_topic(&mut self, topic: &TopicId) -> Result<ErrorCode> {
+        self.opened()?;
+
         debug!(?topic);
 
         if let Some(metadata) = self.topic_metadata(topic).await? {
@@ -706,6 +1387,8 @@ impl Storage for DynoStore {
     }
 
     async fn brokers(&mut self) -> Result<Vec<DescribeClusterBroker>> {
+        self.opened()?;
+
         debug!(cluster = self.cluster);
 
         let broker_id = self.node;
@@ -731,13 +1414,21 @@ impl Storage for DynoStore {
         topition: &Topition,
         deflated: deflated::Batch,
     ) -> Result<i64> {
+        self.opened()?;
+
         debug!(?transaction_id, ?topition, ?deflated);
 
+        validate_batch(&deflated)?;
+
         let config = self
             .describe_config(topition.topic(), ConfigResource::Topic, None)
             .await?;
 
+        // records in a transaction are stored until it commits, even
+        // when the topic is only a sink for the lake
+        //
         if self.lake.is_some()
+            && !BatchAttribute::try_from(deflated.attributes)?.transaction
             && config
                 .configs
                 .as_ref()
@@ -797,7 +1488,19 @@ impl Storage for DynoStore {
 
             Ok(offset)
         } else {
+            let deflated = if timestamp_type(&config) == TimestampType::LogAppendTime {
+                inflated::Batch::try_from(deflated)
+                    .map_err(Into::into)
+                    .and_then(|inflated| log_append_time(inflated, SystemTime::now()))
+                    .and_then(|inflated| deflated::Batch::try_from(inflated).map_err(Into::into))
+                    .inspect(|deflated| debug!(?deflated))?
+            } else {
+                deflated
+            };
+
             if deflated.is_idempotent() {
+                let window = self.sequence_window;
+
                 self.meta
                 .with_mut(&self.object_store, |meta| {
                     let Some(pd) = meta.producers.get_mut(&deflated.producer_id) else {
@@ -815,34 +1518,32 @@ impl Storage for DynoStore {
                         return Err(Error::Api(ErrorCode::ProducerFenced));
                     }
 
-                    let sequences = current.get_mut();
-                    debug!(?sequences);
-
-                    match sequences
+                    let batches = pd
+                        .batches
+                        .entry(*current.key())
+                        .or_default()
                         .entry(topition.topic.clone())
                         .or_default()
                         .entry(topition.partition)
-                        .or_default()
-                    {
-                        sequence if *sequence < deflated.base_sequence => {
-                            debug!(?sequence, base_sequence = deflated.base_sequence);
+                        .or_default();
 
-                            Err(Error::Api(ErrorCode::OutOfOrderSequenceNumber))
-                        }
-
-                        sequence if *sequence > deflated.base_sequence => {
-                            debug!(?sequence, base_sequence = deflated.base_sequence);
+                    let sequences = current.get_mut();
+                    debug!(?sequences, ?batches);
 
-                            Err(Error::Api(ErrorCode::DuplicateSequenceNumber))
-                        }
+                    let sequence = sequences
+                        .entry(topition.topic.clone())
+                        .or_default()
+                        .entry(topition.partition)
+                        .or_default();
 
-                        sequence => {
+                    idempotent_sequence(window, *sequence, batches, deflated.base_sequence).map(
+                        |retained| {
                             debug!(?sequence, delta = deflated.last_offset_delta + 1);
 
                             *sequence += deflated.last_offset_delta + 1;
-                            Ok(())
-                        }
-                    }
+                            *batches = retained;
+                        },
+                    )
                 })
                 .await
                 .inspect(|outcome| debug!(transaction_id, ?topition, ?outcome))
@@ -876,6 +1577,10 @@ impl Storage for DynoStore {
                         .map_or(Some(deflated.last_offset_delta as i64 + 1i64), |high| {
                             Some(high + deflated.last_offset_delta as i64 + 1i64)
                         });
+                    watermark.record_count = Some(
+                        watermark.record_count.unwrap_or_default()
+                            + i64::from(deflated.record_count),
+                    );
 
                     debug!(?watermark);
 
@@ -885,10 +1590,12 @@ impl Storage for DynoStore {
                 .inspect(|offset| debug!(offset, transaction_id, ?topition))
                 .inspect_err(|err| error!(?err, transaction_id, ?topition))?;
 
+            // records in a transaction are exported once it commits
+            //
             if let Some(ref registry) = self.schemas {
                 let batch_attribute = BatchAttribute::try_from(deflated.attributes)?;
 
-                if !batch_attribute.control {
+                if !batch_attribute.control && !batch_attribute.transaction {
                     if let Some(ref lake) = self.lake {
                         let inflated = inflated::Batch::try_from(&deflated)?;
                         if let Some(record_batch) =
@@ -979,6 +1686,20 @@ impl Storage for DynoStore {
         }
     }
 
+    async fn produce_many(
+        &mut self,
+        writes: &[(Topition, deflated::Batch)],
+    ) -> Result<Vec<(Topition, i64)>> {
+        self.opened()?;
+
+        debug!(writes = writes.len());
+
+        // each batch and watermark is a separate object, a failure
+        // part way through cannot be rolled back
+        //
+        Err(Error::UnsupportedOperation("produce_many"))
+    }
+
     async fn fetch(
         &mut self,
         topition: &'_ Topition,
@@ -987,13 +1708,18 @@ impl Storage for DynoStore {
         max_bytes: u32,
         isolation_level: IsolationLevel,
     ) -> Result<Vec<deflated::Batch>> {
-        let high_watermark = self.offset_stage(topition).await.map(|offset_stage| {
-            if isolation_level == IsolationLevel::ReadCommitted {
-                offset_stage.last_stable
-            } else {
-                offset_stage.high_watermark
-            }
-        })?;
+        self.opened()?;
+
+        let offset_stage = self.offset_stage(topition).await?;
+
+        // records below the log start have been deleted
+        //
+        if offset < offset_stage.log_start {
+            debug!(?topition, offset, log_start = offset_stage.log_start);
+            return Err(Error::Api(ErrorCode::OffsetOutOfRange));
+        }
+
+        let high_watermark = offset_stage.visible(isolation_level);
 
         debug!(
             ?topition,
@@ -1070,74 +1796,229 @@ impl Storage for DynoStore {
             batches.push(batch);
         }
 
-        Ok(batches)
+        Ok(batches)
+    }
+
+    async fn offset_stage(&mut self, topition: &Topition) -> Result<OffsetStage> {
+        self.opened()?;
+
+        debug!(?topition);
+
+        let stable = self
+            .meta
+            .with(&self.object_store, |meta| {
+                Ok(meta
+                    .transactions
+                    .values()
+                    .flat_map(|txn| {
+                        debug!(?txn);
+
+                        txn.epochs
+                            .values()
+                            .filter(|detail| {
+                                detail.state.is_some_and(|state| {
+                                    state != TxnState::Committed && state != TxnState::Aborted
+                                })
+                            })
+                            .map(BTreeMap::<Topition, Offset>::from)
+                            .collect::<Vec<_>>()
+                    })
+                    .reduce(|mut acc, e| {
+                        debug!(?acc, ?e);
+
+                        for (topition, offset_start) in e.iter() {
+                            _ = acc
+                                .entry(topition.to_owned())
+                                .and_modify(|existing_offset_start| {
+                                    if *existing_offset_start > *offset_start {
+                                        *existing_offset_start = *offset_start
+                                    }
+                                })
+                                .or_insert(*offset_start);
+                        }
+
+                        acc
+                    })
+                    .unwrap_or(BTreeMap::new()))
+            })
+            .await?;
+
+        debug!(?stable);
+
+        let watermark = self.watermarks.lock().map(|mut locked| {
+            locked
+                .entry(topition.to_owned())
+                .or_insert(OptiCon::<Watermark>::new(self.cluster.as_str(), topition))
+                .to_owned()
+        })?;
+
+        watermark
+            .with(&self.object_store, |watermark| {
+                debug!(?watermark);
+                let high_watermark = watermark.high.unwrap_or(0);
+                let log_start = watermark.low.unwrap_or(0);
+
+                // an open transaction may have produced beyond a truncation
+                //
+                let last_stable = stable
+                    .get(topition)
+                    .copied()
+                    .map_or(high_watermark, |stable| stable.min(high_watermark));
+
+                Ok(OffsetStage {
+                    last_stable,
+                    high_watermark,
+                    log_start,
+                })
+            })
+            .await
+    }
+
+    async fn record_counts(&mut self, topics: &[TopicId]) -> Result<Vec<(Topition, i64)>> {
+        self.opened()?;
+
+        debug!(?topics);
+
+        let mut record_counts = vec![];
+
+        for topic in topics {
+            let Some(topic_metadata) = self.topic_metadata(topic).await? else {
+                continue;
+            };
+
+            for partition in 0..topic_metadata.topic.num_partitions {
+                let topition = Topition::new(topic_metadata.topic.name.clone(), partition);
+
+                let watermark = self.watermarks.lock().map(|mut locked| {
+                    locked
+                        .entry(topition.to_owned())
+                        .or_insert(OptiCon::<Watermark>::new(self.cluster.as_str(), &topition))
+                        .to_owned()
+                })?;
+
+                let record_count = watermark
+                    .with(&self.object_store, |watermark| {
+                        Ok(watermark.record_count.unwrap_or_default())
+                    })
+                    .await?;
+
+                record_counts.push((topition, record_count));
+            }
+        }
+
+        Ok(record_counts)
+    }
+
+    async fn log_dirs(&mut self) -> Result<Vec<LogDirDescription>> {
+        self.opened()?;
+
+        debug!(cluster = self.cluster);
+
+        let topics = self
+            .meta
+            .with(&self.object_store, |meta| {
+                Ok(meta
+                    .topics
+                    .iter()
+                    .map(|(name, metadata)| (name.to_owned(), metadata.topic.num_partitions))
+                    .collect::<Vec<_>>())
+            })
+            .await?;
+
+        let mut log_dirs = vec![];
+
+        for (name, num_partitions) in topics {
+            for partition in 0..num_partitions {
+                let topition = Topition::new(name.clone(), partition);
+
+                let (watermark, batches) = self.batches(&topition).await?;
+                let log_start = watermark.low.unwrap_or_default();
+                let log_end = watermark.high.unwrap_or_default();
+
+                let size = batches
+                    .iter()
+                    .flat_map(|batch| {
+                        batch.records.iter().filter(move |record| {
+                            let offset = batch.base_offset + i64::from(record.offset_delta);
+                            offset >= log_start && offset < log_end
+                        })
+                    })
+                    .map(|record| {
+                        record.key.as_ref().map_or(0, Bytes::len)
+                            + record.value.as_ref().map_or(0, Bytes::len)
+                    })
+                    .sum::<usize>();
+
+                log_dirs.push(LogDirDescription {
+                    topition,
+                    log_start,
+                    log_end,
+                    record_count: watermark.record_count.unwrap_or_default(),
+                    size: i64::try_from(size)?,
+                });
+            }
+        }
+
+        Ok(log_dirs)
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        debug!(cluster = self.cluster);
+
+        // every write is made directly to the object store, with
+        // nothing pending to flush
+        //
+        self.closed.store(true, Ordering::Relaxed);
+
+        Ok(())
     }
 
-    async fn offset_stage(&mut self, topition: &Topition) -> Result<OffsetStage> {
+    async fn describe_producers(&mut self, topition: &Topition) -> Result<Vec<ProducerState>> {
+        self.opened()?;
+
         debug!(?topition);
 
-        let stable = self
-            .meta
+        self.meta
             .with(&self.object_store, |meta| {
                 Ok(meta
-                    .transactions
-                    .values()
-                    .flat_map(|txn| {
-                        debug!(?txn);
-
-                        txn.epochs
-                            .values()
-                            .filter(|detail| {
-                                detail.state.is_some_and(|state| {
-                                    state != TxnState::Committed && state != TxnState::Aborted
-                                })
+                    .producers
+                    .iter()
+                    .filter_map(|(producer_id, detail)| {
+                        let (producer_epoch, sequences) = detail.sequences.last_key_value()?;
+
+                        let sequence = sequences
+                            .get(topition.topic())
+                            .and_then(|partitions| partitions.get(&topition.partition()))?;
+
+                        let transaction_id = meta
+                            .transactions
+                            .iter()
+                            .find(|(_, txn)| {
+                                txn.producer == *producer_id
+                                    && txn.epochs.get(producer_epoch).is_some_and(|detail| {
+                                        detail.state.is_some_and(|state| {
+                                            state != TxnState::Committed
+                                                && state != TxnState::Aborted
+                                        }) && detail.produces.get(topition.topic()).is_some_and(
+                                            |partitions| {
+                                                partitions.contains_key(&topition.partition())
+                                            },
+                                        )
+                                    })
                             })
-                            .map(BTreeMap::<Topition, Offset>::from)
-                            .collect::<Vec<_>>()
-                    })
-                    .reduce(|mut acc, e| {
-                        debug!(?acc, ?e);
-
-                        for (topition, offset_start) in e.iter() {
-                            _ = acc
-                                .entry(topition.to_owned())
-                                .and_modify(|existing_offset_start| {
-                                    if *existing_offset_start > *offset_start {
-                                        *existing_offset_start = *offset_start
-                                    }
-                                })
-                                .or_insert(*offset_start);
-                        }
+                            .map(|(transaction_id, _)| transaction_id.to_owned());
 
-                        acc
+                        Some(ProducerState {
+                            producer_id: *producer_id,
+                            producer_epoch: *producer_epoch,
+                            last_sequence: sequence - 1,
+                            transaction_id,
+                        })
                     })
-                    .unwrap_or(BTreeMap::new()))
-            })
-            .await?;
-
-        debug!(?stable);
-
-        let watermark = self.watermarks.lock().map(|mut locked| {
-            locked
-                .entry(topition.to_owned())
-                .or_insert(OptiCon::<Watermark>::new(self.cluster.as_str(), topition))
-                .to_owned()
-        })?;
-
-        watermark
-            .with(&self.object_store, |watermark| {
-                debug!(?watermark);
-                let high_watermark = watermark.high.unwrap_or(0);
-                let log_start = watermark.low.unwrap_or(0);
-                let last_stable = stable.get(topition).copied().unwrap_or(high_watermark);
-
-                Ok(OffsetStage {
-                    last_stable,
-                    high_watermark,
-                    log_start,
-                })
+                    .collect::<Vec<_>>())
             })
             .await
+            .inspect(|producers| debug!(?topition, ?producers))
     }
 
     async fn list_offsets(
@@ -1145,6 +2026,8 @@ impl Storage for DynoStore {
         isolation_level: IsolationLevel,
         offsets: &[(Topition, ListOffsetRequest)],
     ) -> Result<Vec<(Topition, ListOffsetResponse)>> {
+        self.opened()?;
+
         debug!(?offsets, ?isolation_level);
 
         let stable = if isolation_level == IsolationLevel::ReadCommitted {
@@ -1242,7 +2125,9 @@ impl Storage for DynoStore {
                                 .await?
                         }
                     }
-                    ListOffsetRequest::Timestamp(..) => todo!(),
+                    ListOffsetRequest::Timestamp(timestamp) => {
+                        self.offset_for_timestamp(topition, *timestamp).await?
+                    }
                 },
             ));
         }
@@ -1256,24 +2141,37 @@ impl Storage for DynoStore {
         retention_time_ms: Option<Duration>,
         offsets: &[(Topition, OffsetCommitRequest)],
     ) -> Result<Vec<(Topition, ErrorCode)>> {
+        self.opened()?;
+
         debug!(?retention_time_ms, ?group_id, ?offsets);
 
         let mut responses = vec![];
 
         for (topition, offset_commit) in offsets {
-            if self
+            if offset_commit.is_metadata_too_large(self.offset_metadata_max_bytes) {
+                responses.push((topition.to_owned(), ErrorCode::OffsetMetadataTooLarge));
+            } else if self
                 .topic_metadata(&TopicId::from(topition))
                 .await?
-                .is_some()
+                .is_some_and(|metadata| {
+                    (0..metadata.topic.num_partitions).contains(&topition.partition)
+                })
             {
                 let location = Path::from(format!(
                     "clusters/{}/groups/consumers/{}/offsets/{}/partitions/{:0>10}.json",
                     self.cluster, group_id, topition.topic, topition.partition,
                 ));
 
-                let payload = serde_json::to_vec(&offset_commit)
-                    .map(Bytes::from)
-                    .map(PutPayload::from)?;
+                let payload = serde_json::to_vec(&CommittedOffsetDetail {
+                    commit: offset_commit.to_owned(),
+                    expire_timestamp: Some(offset_expiry(
+                        offset_commit,
+                        retention_time_ms,
+                        self.offsets_retention,
+                    )),
+                })
+                .map(Bytes::from)
+                .map(PutPayload::from)?;
 
                 let options = PutOptions {
                     mode: PutMode::Overwrite,
@@ -1302,56 +2200,110 @@ impl Storage for DynoStore {
         &mut self,
         group_id: &str,
     ) -> Result<BTreeMap<Topition, i64>> {
+        self.opened()?;
+
         debug!(group_id);
 
-        let mut topitions = vec![];
+        let topitions = self.committed_topitions(group_id).await?;
 
-        {
+        self.offset_fetch(Some(group_id), topitions.as_ref(), Some(false))
+            .await
+    }
+
+    async fn committed_offsets(
+        &mut self,
+        group_id: &str,
+    ) -> Result<BTreeMap<Topition, CommittedOffset>> {
+        self.opened()?;
+
+        debug!(group_id);
+
+        let mut offsets = BTreeMap::new();
+
+        for topition in self.committed_topitions(group_id).await? {
             let location = Path::from(format!(
-                "clusters/{}/groups/consumers/{}/offsets/",
-                self.cluster, group_id,
+                "clusters/{}/groups/consumers/{}/offsets/{}/partitions/{:0>10}.json",
+                self.cluster, group_id, topition.topic, topition.partition,
             ));
 
-            let mut list_stream = self.object_store.list(Some(&location));
-
-            while let Some(meta) = list_stream
-                .next()
+            let detail = self
+                .object_store
+                .get(&location)
+                .await?
+                .bytes()
                 .await
-                .inspect(|meta| debug!(?meta))
-                .transpose()
-                .inspect_err(|error| error!(?error))
-                .map_err(|_| Error::Api(ErrorCode::UnknownServerError))?
-            {
-                debug!(?meta);
-                let Some(topic): Option<String> = meta
-                    .location
-                    .parts()
-                    .nth(6)
-                    .inspect(|topic| debug!(?topic))
-                    .map(|topic| topic.as_ref().into())
-                else {
+                .map_err(Error::from)
+                .and_then(|encoded| {
+                    serde_json::from_slice::<CommittedOffsetDetail>(&encoded[..])
+                        .map_err(Error::from)
+                })
+                .inspect_err(|error| error!(?error, ?group_id, ?topition))?;
+
+            _ = offsets.insert(
+                topition,
+                CommittedOffset {
+                    offset: detail.commit.offset,
+                    expire_timestamp: detail.expire_timestamp,
+                },
+            );
+        }
+
+        Ok(offsets)
+    }
+
+    async fn expire_offsets(&mut self) -> Result<u64> {
+        self.opened()?;
+
+        debug!(cluster = self.cluster);
+
+        let now = SystemTime::now();
+        let mut expired = 0;
+
+        for group in self.list_groups(None).await? {
+            let group_id = group.group_id;
+
+            let location = Path::from(format!(
+                "clusters/{}/groups/consumers/{}.json",
+                self.cluster, group_id,
+            ));
+
+            // retaining the offsets of a group with members
+            //
+            match self.get::<GroupDetail>(&location).await {
+                Ok((detail, _)) if !detail.members.is_empty() => {
+                    debug!(group_id, members = detail.members.len());
                     continue;
-                };
+                }
 
-                let Some(partition) = meta
-                    .location
-                    .parts()
-                    .nth(8)
-                    .inspect(|partition| debug!(?partition))
-                    .map(|partition| i32::from_str(&partition.as_ref()[0..10]))
-                    .transpose()?
-                else {
+                Ok(_) | Err(Error::ObjectStore(object_store::Error::NotFound { .. })) => (),
+
+                Err(otherwise) => return Err(otherwise),
+            }
+
+            for (topition, committed) in self.committed_offsets(&group_id).await? {
+                if committed
+                    .expire_timestamp
+                    .is_none_or(|expire_timestamp| expire_timestamp >= now)
+                {
                     continue;
-                };
+                }
+
+                let location = Path::from(format!(
+                    "clusters/{}/groups/consumers/{}/offsets/{}/partitions/{:0>10}.json",
+                    self.cluster, group_id, topition.topic, topition.partition,
+                ));
 
-                debug!(topic, partition);
+                self.object_store
+                    .delete(&location)
+                    .await
+                    .inspect(|_| debug!(group_id, ?topition, ?committed))
+                    .inspect_err(|error| error!(?error, group_id, ?topition))?;
 
-                topitions.push(Topition::new(topic, partition));
+                expired += 1;
             }
         }
 
-        self.offset_fetch(Some(group_id), topitions.as_ref(), Some(false))
-            .await
+        Ok(expired)
     }
 
     async fn offset_fetch(
@@ -1360,6 +2312,8 @@ impl Storage for DynoStore {
         topics: &[Topition],
         require_stable: Option<bool>,
     ) -> Result<BTreeMap<Topition, i64>> {
+        self.opened()?;
+
         debug!(?group_id, ?topics, ?require_stable);
         let mut responses = BTreeMap::new();
 
@@ -1399,6 +2353,8 @@ impl Storage for DynoStore {
     }
 
     async fn metadata(&mut self, topics: Option<&[TopicId]>) -> Result<MetadataResponse> {
+        self.opened()?;
+
         debug!(?topics);
 
         let brokers = vec![MetadataResponseBroker {
@@ -1597,12 +2553,61 @@ impl Storage for DynoStore {
         })
     }
 
+    async fn list_topics(&mut self) -> Result<Vec<TopicDetail>> {
+        self.opened()?;
+
+        debug!(cluster = self.cluster);
+
+        let node = self.node;
+
+        self.meta
+            .with(&self.object_store, |meta| {
+                Ok(meta
+                    .topics
+                    .values()
+                    .map(|topic_metadata| {
+                        let mut creatable_topic = topic_metadata.topic.clone();
+
+                        let assigned = creatable_topic.assignments.take().unwrap_or_default();
+
+                        creatable_topic.assignments = Some(
+                            (0..creatable_topic.num_partitions)
+                                .map(|partition_index| {
+                                    assigned
+                                        .iter()
+                                        .find(|assignment| {
+                                            assignment.partition_index == partition_index
+                                        })
+                                        .cloned()
+                                        .unwrap_or(CreatableReplicaAssignment {
+                                            partition_index,
+                                            broker_ids: Some(vec![
+                                                node;
+                                                creatable_topic.replication_factor.max(0)
+                                                    as usize
+                                            ]),
+                                        })
+                                })
+                                .collect(),
+                        );
+
+                        creatable_topic.configs.get_or_insert_default();
+
+                        TopicDetail::from((topic_metadata.id, creatable_topic))
+                    })
+                    .collect())
+            })
+            .await
+    }
+
     async fn describe_config(
         &self,
         name: &str,
         resource: ConfigResource,
         keys: Option<&[String]>,
     ) -> Result<DescribeConfigsResult> {
+        self.opened()?;
+
         debug!(?name, ?resource, ?keys);
 
         match resource {
@@ -1615,22 +2620,16 @@ impl Storage for DynoStore {
                         error_message: Some(error_code.to_string()),
                         resource_type: i8::from(resource),
                         resource_name: name.into(),
-                        configs: topic_metadata.topic.configs.map(|configs| {
-                            configs
-                                .iter()
-                                .map(|config| DescribeConfigsResourceResult {
-                                    name: config.name.clone(),
-                                    value: config.value.clone(),
-                                    read_only: false,
-                                    is_default: None,
-                                    config_source: Some(ConfigSource::DefaultConfig.into()),
-                                    is_sensitive: false,
-                                    synonyms: Some([].into()),
-                                    config_type: Some(ConfigType::String.into()),
-                                    documentation: Some("".into()),
-                                })
-                                .collect()
-                        }),
+                        configs: Some(topic_configs(
+                            topic_metadata
+                                .topic
+                                .configs
+                                .unwrap_or_default()
+                                .into_iter()
+                                .map(|config| (config.name, config.value))
+                                .collect(),
+                            keys,
+                        )),
                     })
                 }
 
@@ -1645,7 +2644,16 @@ impl Storage for DynoStore {
                 Err(_) => todo!(),
             },
 
-            _ => todo!(),
+            ConfigResource::Broker => Ok(broker_config(
+                name,
+                self.node,
+                self.offsets_retention,
+                self.offset_metadata_max_bytes,
+                self.sequence_window,
+                keys,
+            )),
+
+            _ => Ok(unsupported_config(name, resource)),
         }
     }
 
@@ -1655,6 +2663,8 @@ impl Storage for DynoStore {
         partition_limit: i32,
         cursor: Option<Topition>,
     ) -> Result<Vec<DescribeTopicPartitionsResponseTopic>> {
+        self.opened()?;
+
         let _ = (partition_limit, cursor);
 
         let mut responses =
@@ -1733,6 +2743,8 @@ impl Storage for DynoStore {
     }
 
     async fn list_groups(&mut self, states_filter: Option<&[String]>) -> Result<Vec<ListedGroup>> {
+        self.opened()?;
+
         debug!(?states_filter);
 
         let location = Path::from(format!("clusters/{}/groups/consumers/", self.cluster,));
@@ -1763,6 +2775,8 @@ impl Storage for DynoStore {
         &mut self,
         group_ids: Option<&[String]>,
     ) -> Result<Vec<DeletableGroupResult>> {
+        self.opened()?;
+
         debug!(?group_ids);
 
         let mut results = vec![];
@@ -1823,6 +2837,8 @@ impl Storage for DynoStore {
         group_ids: Option<&[String]>,
         include_authorized_operations: bool,
     ) -> Result<Vec<NamedGroupDetail>> {
+        self.opened()?;
+
         debug!(?group_ids, include_authorized_operations);
         let mut results = vec![];
         if let Some(group_ids) = group_ids {
@@ -1868,6 +2884,8 @@ impl Storage for DynoStore {
         detail: GroupDetail,
         version: Option<Version>,
     ) -> Result<Version, UpdateError<GroupDetail>> {
+        self.opened()?;
+
         debug!(?group_id, ?detail, ?version);
 
         let location = Path::from(format!(
@@ -1892,6 +2910,8 @@ impl Storage for DynoStore {
         producer_id: Option<i64>,
         producer_epoch: Option<i16>,
     ) -> Result<ProducerIdResponse> {
+        self.opened()?;
+
         debug!(
             ?transaction_id,
             ?transaction_timeout_ms,
@@ -1914,7 +2934,27 @@ impl Storage for DynoStore {
                 .with_mut(&self.object_store, |meta| {
                     debug!(?meta);
                     match (producer_id, producer_epoch) {
-                        (Some(-1), Some(-1)) => {
+                        (Some(producer_id), Some(producer_epoch)) => {
+                            // an existing producer may only bump the epoch of its
+                            // transaction when it is using the current epoch
+                            //
+                            if producer_id != -1 || producer_epoch != -1 {
+                                let error = meta.transactions.get(transaction_id).map_or(
+                                    ErrorCode::TransactionalIdNotFound,
+                                    |transaction| {
+                                        transaction.producer_check(producer_id, producer_epoch)
+                                    },
+                                );
+
+                                if error != ErrorCode::None {
+                                    return Ok(InitProducer::Completed(ProducerIdResponse {
+                                        id: -1,
+                                        epoch: -1,
+                                        error,
+                                    }));
+                                }
+                            }
+
                             match meta.transactions.entry(transaction_id.to_string()) {
                                 Entry::Vacant(vacant) => {
                                     let id = meta
@@ -2085,15 +3125,28 @@ impl Storage for DynoStore {
         producer_epoch: i16,
         group_id: &str,
     ) -> Result<ErrorCode> {
+        self.opened()?;
+
         debug!(transaction_id, producer_id, producer_epoch, group_id);
 
-        Ok(ErrorCode::None)
+        self.meta
+            .with(&self.object_store, |meta| {
+                Ok(meta
+                    .transactions
+                    .get(transaction_id)
+                    .map_or(ErrorCode::TransactionalIdNotFound, |transaction| {
+                        transaction.producer_check(producer_id, producer_epoch)
+                    }))
+            })
+            .await
     }
 
     async fn txn_add_partitions(
         &mut self,
         partitions: TxnAddPartitionsRequest,
     ) -> Result<TxnAddPartitionsResponse> {
+        self.opened()?;
+
         debug!(?partitions);
 
         match partitions {
@@ -2106,94 +3159,25 @@ impl Storage for DynoStore {
                 self.meta
                     .with_mut(&self.object_store, |meta| {
                         let Some(transaction) = meta.transactions.get_mut(&transaction_id) else {
-                            let mut results = vec![];
-
-                            for topic in topics {
-                                let mut results_by_partition = vec![];
-
-                                for partition_index in topic.partitions.as_deref().unwrap_or(&[]) {
-                                    results_by_partition.push(AddPartitionsToTxnPartitionResult {
-                                        partition_index: *partition_index,
-                                        partition_error_code: ErrorCode::TransactionalIdNotFound
-                                            .into(),
-                                    });
-                                }
-
-                                results.push(AddPartitionsToTxnTopicResult {
-                                    name: topic.name.clone(),
-                                    results_by_partition: Some(results_by_partition),
-                                })
-                            }
-
-                            return Ok(TxnAddPartitionsResponse::VersionZeroToThree(results));
+                            return Self::txn_add_partitions_response_error(
+                                topics,
+                                ErrorCode::TransactionalIdNotFound,
+                            );
                         };
 
-                        if transaction.producer != producer_id {
-                            let mut results = vec![];
-
-                            for topic in topics {
-                                let mut results_by_partition = vec![];
-
-                                for partition_index in topic.partitions.as_deref().unwrap_or(&[]) {
-                                    results_by_partition.push(AddPartitionsToTxnPartitionResult {
-                                        partition_index: *partition_index,
-                                        partition_error_code: ErrorCode::UnknownProducerId.into(),
-                                    });
-                                }
-
-                                results.push(AddPartitionsToTxnTopicResult {
-                                    name: topic.name.clone(),
-                                    results_by_partition: Some(results_by_partition),
-                                })
-                            }
+                        let error_code = transaction.producer_check(producer_id, producer_epoch);
 
-                            return Ok(TxnAddPartitionsResponse::VersionZeroToThree(results));
+                        if error_code != ErrorCode::None {
+                            return Self::txn_add_partitions_response_error(topics, error_code);
                         }
 
                         let Some(mut current_epoch) = transaction.epochs.last_entry() else {
-                            let mut results = vec![];
-
-                            for topic in topics {
-                                let mut results_by_partition = vec![];
-
-                                for partition_index in topic.partitions.as_deref().unwrap_or(&[]) {
-                                    results_by_partition.push(AddPartitionsToTxnPartitionResult {
-                                        partition_index: *partition_index,
-                                        partition_error_code: ErrorCode::ProducerFenced.into(),
-                                    });
-                                }
-
-                                results.push(AddPartitionsToTxnTopicResult {
-                                    name: topic.name.clone(),
-                                    results_by_partition: Some(results_by_partition),
-                                })
-                            }
-
-                            return Ok(TxnAddPartitionsResponse::VersionZeroToThree(results));
+                            return Self::txn_add_partitions_response_error(
+                                topics,
+                                ErrorCode::ProducerFenced,
+                            );
                         };
 
-                        if &producer_epoch != current_epoch.key() {
-                            let mut results = vec![];
-
-                            for topic in topics {
-                                let mut results_by_partition = vec![];
-
-                                for partition_index in topic.partitions.as_deref().unwrap_or(&[]) {
-                                    results_by_partition.push(AddPartitionsToTxnPartitionResult {
-                                        partition_index: *partition_index,
-                                        partition_error_code: ErrorCode::ProducerFenced.into(),
-                                    });
-                                }
-
-                                results.push(AddPartitionsToTxnTopicResult {
-                                    name: topic.name.clone(),
-                                    results_by_partition: Some(results_by_partition),
-                                })
-                            }
-
-                            return Ok(TxnAddPartitionsResponse::VersionZeroToThree(results));
-                        }
-
                         let txn_detail = current_epoch.get_mut();
 
                         let mut results = vec![];
@@ -2239,6 +3223,8 @@ impl Storage for DynoStore {
         &mut self,
         offsets: TxnOffsetCommitRequest,
     ) -> Result<Vec<TxnOffsetCommitResponseTopic>> {
+        self.opened()?;
+
         debug!(?offsets);
         self.meta
             .with_mut(&self.object_store, |meta| {
@@ -2249,11 +3235,11 @@ impl Storage for DynoStore {
                     );
                 };
 
-                if transaction.producer != offsets.producer_id {
-                    return Self::txn_offset_commit_response_error(
-                        &offsets,
-                        ErrorCode::UnknownProducerId,
-                    );
+                let error_code =
+                    transaction.producer_check(offsets.producer_id, offsets.producer_epoch);
+
+                if error_code != ErrorCode::None {
+                    return Self::txn_offset_commit_response_error(&offsets, error_code);
                 }
 
                 let Some(mut current_epoch) = transaction.epochs.last_entry() else {
@@ -2263,13 +3249,6 @@ impl Storage for DynoStore {
                     );
                 };
 
-                if &offsets.producer_epoch != current_epoch.key() {
-                    return Self::txn_offset_commit_response_error(
-                        &offsets,
-                        ErrorCode::ProducerFenced,
-                    );
-                }
-
                 let txn_detail = current_epoch.get_mut();
 
                 let mut responses = vec![];
@@ -2319,6 +3298,8 @@ impl Storage for DynoStore {
         producer_epoch: i16,
         committed: bool,
     ) -> Result<ErrorCode> {
+        self.opened()?;
+
         debug!(transaction_id, producer_id, producer_epoch, committed);
 
         let produced = self
@@ -2330,18 +3311,16 @@ impl Storage for DynoStore {
                     return Err(Error::Api(ErrorCode::TransactionalIdNotFound));
                 };
 
-                if transaction.producer != producer_id {
-                    return Err(Error::Api(ErrorCode::UnknownProducerId));
+                let error_code = transaction.producer_check(producer_id, producer_epoch);
+
+                if error_code != ErrorCode::None {
+                    return Err(Error::Api(error_code));
                 }
 
                 let Some(mut current_epoch) = transaction.epochs.last_entry() else {
                     return Err(Error::Api(ErrorCode::ProducerFenced));
                 };
 
-                if &producer_epoch != current_epoch.key() {
-                    return Err(Error::Api(ErrorCode::ProducerFenced));
-                }
-
                 let txn_detail = current_epoch.get_mut();
 
                 let mut produced = vec![];
@@ -2428,7 +3407,7 @@ impl Storage for DynoStore {
                 })?;
         }
 
-        let offsets_to_commit = self
+        let (offsets_to_commit, stable) = self
             .meta
             .with_mut(&self.object_store, |meta| {
                 debug!(transactions = ?meta.transactions);
@@ -2437,16 +3416,10 @@ impl Storage for DynoStore {
                     return Err(Error::Api(ErrorCode::TransactionalIdNotFound));
                 };
 
-                if transaction.producer != producer_id {
-                    return Err(Error::Api(ErrorCode::UnknownProducerId));
-                }
-
-                let Some(current_epoch) = transaction.epochs.last_entry() else {
-                    return Err(Error::Api(ErrorCode::ProducerFenced));
-                };
+                let error_code = transaction.producer_check(producer_id, producer_epoch);
 
-                if &producer_epoch != current_epoch.key() {
-                    return Err(Error::Api(ErrorCode::ProducerFenced));
+                if error_code != ErrorCode::None {
+                    return Err(Error::Api(error_code));
                 }
 
                 let mut overlaps =
@@ -2458,6 +3431,8 @@ impl Storage for DynoStore {
                     BTreeMap<Topic, BTreeMap<Partition, TxnCommitOffset>>,
                 > = BTreeMap::new();
 
+                let mut stable = vec![];
+
                 if overlaps.iter().all(|txn_id| txn_id.state.is_prepared()) {
                     let txn_ids = {
                         overlaps.push(TxnId {
@@ -2503,6 +3478,18 @@ impl Storage for DynoStore {
                                 }
 
                                 if txn_id.state == TxnState::PrepareCommit {
+                                    for (topic, partitions) in txn_detail.produces.iter() {
+                                        for (partition, offset_range) in partitions {
+                                            if let Some(offset_range) = offset_range {
+                                                stable.push((
+                                                    Topition::new(topic.to_owned(), *partition),
+                                                    txn_id.producer_id,
+                                                    offset_range.to_owned(),
+                                                ));
+                                            }
+                                        }
+                                    }
+
                                     for (group, topics) in txn_detail.offsets.iter() {
                                         for (topic, partitions) in topics.iter() {
                                             for (partition, committed_offset) in partitions {
@@ -2528,13 +3515,17 @@ impl Storage for DynoStore {
                     }
                 }
 
-                Ok(offsets_to_commit)
+                Ok((offsets_to_commit, stable))
             })
             .await
             .inspect(|outcome| debug!(?outcome))
             .inspect_err(|err| error!(?err))?;
 
-        debug!(?offsets_to_commit);
+        debug!(?offsets_to_commit, ?stable);
+
+        for (topition, producer_id, offset_range) in stable {
+            self.lake_export(&topition, producer_id, offset_range).await;
+        }
 
         for (group, topics) in offsets_to_commit.iter() {
             let mut offsets = vec![];
@@ -2559,7 +3550,25 @@ impl Storage for DynoStore {
         Ok(ErrorCode::None)
     }
 
+    async fn health(&mut self) -> Result<()> {
+        self.opened()?;
+
+        debug!(cluster = self.cluster);
+
+        let location = Path::from(format!("clusters/{}/", self.cluster));
+
+        self.object_store
+            .list_with_delimiter(Some(&location))
+            .await
+            .inspect(|list_result| debug!(?list_result))
+            .inspect_err(|error| error!(?error, cluster = self.cluster))
+            .and(Ok(()))
+            .map_err(Into::into)
+    }
+
     async fn maintain(&self) -> Result<()> {
+        self.opened()?;
+
         debug!(?self);
 
         if let Some(ref lake) = self.lake {
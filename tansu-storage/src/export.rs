@@ -0,0 +1,279 @@
+// Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use tansu_kafka_sans_io::{
+    BatchAttribute, ConfigResource, ControlBatch, IsolationLevel, record::inflated,
+};
+use tansu_schema_registry::{
+    Registry,
+    lake::{House, LakeHouse},
+};
+use tracing::debug;
+
+use crate::{Result, Storage, Topition};
+
+const MAX_BYTES: u32 = 1_048_576;
+
+/// A scan of a topition for analytics exports, which are read
+/// committed unless otherwise configured.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Scan {
+    topition: Topition,
+    offset: i64,
+    end: Option<i64>,
+    isolation: IsolationLevel,
+    max_bytes: u32,
+}
+
+impl Scan {
+    pub fn new(topition: Topition) -> Self {
+        Self {
+            topition,
+            offset: 0,
+            end: None,
+            isolation: IsolationLevel::ReadCommitted,
+            max_bytes: MAX_BYTES,
+        }
+    }
+
+    pub fn offset(self, offset: i64) -> Self {
+        Self { offset, ..self }
+    }
+
+    /// Stop reading before this offset, rather than the high watermark.
+    pub fn end(self, end: i64) -> Self {
+        Self {
+            end: Some(end),
+            ..self
+        }
+    }
+
+    pub fn isolation(self, isolation: IsolationLevel) -> Self {
+        Self { isolation, ..self }
+    }
+
+    pub fn max_bytes(self, max_bytes: u32) -> Self {
+        Self { max_bytes, ..self }
+    }
+
+    /// The data batches of this scan, excluding control batches.
+    pub async fn batches(&self, storage: &mut impl Storage) -> Result<Vec<inflated::Batch>> {
+        let offset_stage = storage.offset_stage(&self.topition).await?;
+        debug!(?self, ?offset_stage);
+
+        // transaction markers may lie beyond the last stable offset,
+        // so everything up to the high watermark is read
+        //
+        let mut batches = vec![];
+        let mut offset = self.offset;
+        let end = self.end.map_or(offset_stage.high_watermark(), |end| {
+            end.min(offset_stage.high_watermark())
+        });
+
+        while offset < end {
+            let fetched = storage
+                .fetch(
+                    &self.topition,
+                    offset,
+                    self.max_bytes,
+                    self.max_bytes,
+                    IsolationLevel::ReadUncommitted,
+                )
+                .await?;
+
+            let Some(last) = fetched.last() else {
+                break;
+            };
+
+            offset = last.base_offset + i64::from(last.last_offset_delta) + 1;
+
+            for deflated in fetched.into_iter().filter(|batch| batch.base_offset < end) {
+                batches.push(inflated::Batch::try_from(deflated)?);
+            }
+        }
+
+        match self.isolation {
+            IsolationLevel::ReadCommitted => committed(batches, offset_stage.last_stable()),
+            IsolationLevel::ReadUncommitted => data(batches),
+        }
+    }
+
+    /// Export this scan into the lake house, returning the next
+    /// offset to be exported.
+    pub async fn export(
+        &self,
+        storage: &mut impl Storage,
+        registry: &Registry,
+        lake: &House,
+    ) -> Result<i64> {
+        let mut offset = self.offset;
+
+        let config = storage
+            .describe_config(self.topition.topic(), ConfigResource::Topic, None)
+            .await?;
+
+        for batch in self.batches(storage).await? {
+            if let Some(record_batch) =
+                registry.as_arrow(self.topition.topic(), self.topition.partition(), &batch)?
+            {
+                lake.store(
+                    self.topition.topic(),
+                    self.topition.partition(),
+                    batch.base_offset,
+                    record_batch,
+                    config.clone(),
+                )
+                .await?;
+            }
+
+            offset = batch.base_offset + i64::from(batch.last_offset_delta) + 1;
+        }
+
+        debug!(?self, offset);
+
+        Ok(offset)
+    }
+}
+
+fn data(batches: Vec<inflated::Batch>) -> Result<Vec<inflated::Batch>> {
+    let mut data = vec![];
+
+    for batch in batches {
+        if !BatchAttribute::try_from(batch.attributes)?.control {
+            data.push(batch);
+        }
+    }
+
+    Ok(data)
+}
+
+/// Batches before the last stable offset, excluding those from
+/// aborted transactions.
+fn committed(batches: Vec<inflated::Batch>, last_stable: i64) -> Result<Vec<inflated::Batch>> {
+    let mut pending: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+    let mut aborted = vec![false; batches.len()];
+
+    for (index, batch) in batches.iter().enumerate() {
+        let attributes = BatchAttribute::try_from(batch.attributes)?;
+
+        if !attributes.transaction {
+            continue;
+        }
+
+        if attributes.control {
+            let control = batch
+                .records
+                .first()
+                .and_then(|record| record.key.clone())
+                .map(ControlBatch::try_from)
+                .transpose()?;
+
+            let ended = pending.remove(&batch.producer_id).unwrap_or_default();
+
+            if control.is_some_and(|control| control.is_abort()) {
+                for index in ended {
+                    aborted[index] = true;
+                }
+            }
+        } else {
+            pending.entry(batch.producer_id).or_default().push(index);
+        }
+    }
+
+    data(
+        batches
+            .into_iter()
+            .zip(aborted)
+            .filter_map(|(batch, aborted)| {
+                (!aborted && batch.base_offset < last_stable).then_some(batch)
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use tansu_kafka_sans_io::{EndTransactionMarker, record::Record};
+
+    fn batch(base_offset: i64, producer_id: i64, transaction: bool) -> Result<inflated::Batch> {
+        inflated::Batch::builder()
+            .base_offset(base_offset)
+            .record(Record::builder().value(Bytes::from_static(b"abc").into()))
+            .attributes(BatchAttribute::default().transaction(transaction).into())
+            .producer_id(producer_id)
+            .build()
+            .map_err(Into::into)
+    }
+
+    fn marker(
+        base_offset: i64,
+        producer_id: i64,
+        control_batch: ControlBatch,
+    ) -> Result<inflated::Batch> {
+        let key: Bytes = control_batch.try_into()?;
+        let value: Bytes = EndTransactionMarker::default().try_into()?;
+
+        inflated::Batch::builder()
+            .base_offset(base_offset)
+            .record(Record::builder().key(key.into()).value(value.into()))
+            .attributes(
+                BatchAttribute::default()
+                    .transaction(true)
+                    .control(true)
+                    .into(),
+            )
+            .producer_id(producer_id)
+            .build()
+            .map_err(Into::into)
+    }
+
+    fn offsets(batches: &[inflated::Batch]) -> Vec<i64> {
+        batches.iter().map(|batch| batch.base_offset).collect()
+    }
+
+    #[test]
+    fn committed_excludes_aborted() -> Result<()> {
+        let batches = vec![
+            batch(0, -1, false)?,
+            batch(1, 54321, true)?,
+            batch(2, 12321, true)?,
+            marker(3, 54321, ControlBatch::default().abort())?,
+            marker(4, 12321, ControlBatch::default().commit())?,
+            batch(5, -1, false)?,
+        ];
+
+        assert_eq!(vec![0, 2, 5], offsets(&committed(batches.clone(), 6)?));
+        assert_eq!(vec![0, 1, 2, 5], offsets(&data(batches)?));
+
+        Ok(())
+    }
+
+    #[test]
+    fn committed_limited_to_last_stable() -> Result<()> {
+        let batches = vec![
+            batch(0, -1, false)?,
+            batch(1, 54321, true)?,
+            batch(2, -1, false)?,
+        ];
+
+        assert_eq!(vec![0], offsets(&committed(batches, 1)?));
+
+        Ok(())
+    }
+}
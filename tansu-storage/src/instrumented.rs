@@ -0,0 +1,715 @@
+// Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    result,
+    sync::{Arc, LazyLock},
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use opentelemetry::{
+    KeyValue,
+    metrics::{Counter, Histogram},
+};
+use tansu_kafka_sans_io::{
+    ConfigResource, ErrorCode, IsolationLevel,
+    create_partitions_request::CreatePartitionsAssignment,
+    create_topics_request::CreatableTopic,
+    delete_groups_response::DeletableGroupResult,
+    delete_records_request::DeleteRecordsTopic,
+    delete_records_response::DeleteRecordsTopicResult,
+    describe_cluster_response::DescribeClusterBroker,
+    describe_configs_response::DescribeConfigsResult,
+    describe_topic_partitions_response::DescribeTopicPartitionsResponseTopic,
+    incremental_alter_configs_request::AlterConfigsResource,
+    incremental_alter_configs_response::AlterConfigsResourceResponse,
+    list_groups_response::ListedGroup,
+    list_partition_reassignments_request::ListPartitionReassignmentsTopics,
+    list_partition_reassignments_response::OngoingTopicReassignment,
+    record::{deflated, inflated},
+    txn_offset_commit_response::TxnOffsetCommitResponseTopic,
+};
+use uuid::Uuid;
+
+use crate::{
+    BrokerRegistrationRequest, CommittedOffset, GroupDetail, ListOffsetRequest, ListOffsetResponse,
+    LogDirDescription, METER, MetadataResponse, NamedGroupDetail, OffsetCommitRequest, OffsetStage,
+    ProduceWatch, ProducerIdResponse, ProducerState, Result, Storage, TopicDetail, TopicId,
+    Topition, TxnAddPartitionsRequest, TxnAddPartitionsResponse, TxnOffsetCommitRequest,
+    UpdateError, Version,
+};
+
+/// A measurement of a single storage request.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Measurement<'a> {
+    pub method: &'static str,
+    pub topic: Option<&'a str>,
+    pub elapsed: Duration,
+    pub error: bool,
+}
+
+/// Observes the storage requests made through [`Instrumented`].
+pub trait Recorder: Debug + Send + Sync + 'static {
+    fn record(&self, measurement: Measurement<'_>);
+}
+
+static STORAGE_REQUESTS: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("tansu_storage_requests")
+        .with_description("tansu storage requests")
+        .build()
+});
+
+static STORAGE_ERRORS: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("tansu_storage_errors")
+        .with_description("tansu storage errors")
+        .build()
+});
+
+static STORAGE_DURATION: LazyLock<Histogram<u64>> = LazyLock::new(|| {
+    METER
+        .u64_histogram("tansu_storage_duration")
+        .with_unit("ms")
+        .with_description("The storage request latencies in milliseconds")
+        .build()
+});
+
+/// A recorder using the OpenTelemetry meter of this crate.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Telemetry;
+
+impl Recorder for Telemetry {
+    fn record(&self, measurement: Measurement<'_>) {
+        let mut attributes = vec![KeyValue::new("method", measurement.method)];
+
+        if let Some(topic) = measurement.topic {
+            attributes.push(KeyValue::new("topic", topic.to_owned()));
+        }
+
+        STORAGE_DURATION.record(measurement.elapsed.as_millis() as u64, &attributes);
+        STORAGE_REQUESTS.add(1, &attributes);
+
+        if measurement.error {
+            STORAGE_ERRORS.add(1, &attributes);
+        }
+    }
+}
+
+/// Storage that reports the count, latency and errors of each
+/// request to a [`Recorder`].
+#[derive(Clone, Debug)]
+pub struct Instrumented<S> {
+    inner: S,
+    recorder: Arc<dyn Recorder>,
+}
+
+impl<S> Instrumented<S> {
+    pub fn new(inner: S, recorder: impl Recorder) -> Self {
+        Self {
+            inner,
+            recorder: Arc::new(recorder),
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+async fn measure<T, E>(
+    recorder: &dyn Recorder,
+    method: &'static str,
+    topic: Option<&str>,
+    request: impl Future<Output = result::Result<T, E>>,
+) -> result::Result<T, E> {
+    let start = SystemTime::now();
+    let outcome = request.await;
+
+    recorder.record(Measurement {
+        method,
+        topic,
+        elapsed: start.elapsed().unwrap_or_default(),
+        error: outcome.is_err(),
+    });
+
+    outcome
+}
+
+#[async_trait]
+impl<S> Storage for Instrumented<S>
+where
+    S: Storage,
+{
+    async fn register_broker(
+        &mut self,
+        broker_registration: BrokerRegistrationRequest,
+    ) -> Result<()> {
+        measure(
+            self.recorder.as_ref(),
+            "register_broker",
+            None,
+            self.inner.register_broker(broker_registration),
+        )
+        .await
+    }
+
+    async fn create_topic(&mut self, topic: CreatableTopic, validate_only: bool) -> Result<Uuid> {
+        let name = topic.name.clone();
+
+        measure(
+            self.recorder.as_ref(),
+            "create_topic",
+            Some(&name),
+            self.inner.create_topic(topic, validate_only),
+        )
+        .await
+    }
+
+    async fn create_partitions(
+        &mut self,
+        topic: &TopicId,
+        new_count: i32,
+        assignments: Option<&[CreatePartitionsAssignment]>,
+    ) -> Result<ErrorCode> {
+        measure(
+            self.recorder.as_ref(),
+            "create_partitions",
+            None,
+            self.inner.create_partitions(topic, new_count, assignments),
+        )
+        .await
+    }
+
+    async fn list_partition_reassignments(
+        &mut self,
+        topics: Option<&[ListPartitionReassignmentsTopics]>,
+    ) -> Result<Vec<OngoingTopicReassignment>> {
+        measure(
+            self.recorder.as_ref(),
+            "list_partition_reassignments",
+            None,
+            self.inner.list_partition_reassignments(topics),
+        )
+        .await
+    }
+
+    async fn incremental_alter_resource(
+        &mut self,
+        resource: AlterConfigsResource,
+    ) -> Result<AlterConfigsResourceResponse> {
+        measure(
+            self.recorder.as_ref(),
+            "incremental_alter_resource",
+            None,
+            self.inner.incremental_alter_resource(resource),
+        )
+        .await
+    }
+
+    async fn delete_records(
+        &mut self,
+        topics: &[DeleteRecordsTopic],
+    ) -> Result<Vec<DeleteRecordsTopicResult>> {
+        measure(
+            self.recorder.as_ref(),
+            "delete_records",
+            None,
+            self.inner.delete_records(topics),
+        )
+        .await
+    }
+
+    async fn truncate(&mut self, topition: &Topition, offset: i64) -> Result<i64> {
+        measure(
+            self.recorder.as_ref(),
+            "truncate",
+            Some(topition.topic()),
+            self.inner.truncate(topition, offset),
+        )
+        .await
+    }
+
+    async fn delete_topic(&mut self, topic: &TopicId) -> Result<ErrorCode> {
+        measure(
+            self.recorder.as_ref(),
+            "delete_topic",
+            None,
+            self.inner.delete_topic(topic),
+        )
+        .await
+    }
+
+    async fn delete_topics(&mut self, topics: &[TopicId]) -> Result<Vec<(TopicId, ErrorCode)>> {
+        measure(
+            self.recorder.as_ref(),
+            "delete_topics",
+            None,
+            self.inner.delete_topics(topics),
+        )
+        .await
+    }
+
+    async fn brokers(&mut self) -> Result<Vec<DescribeClusterBroker>> {
+        measure(
+            self.recorder.as_ref(),
+            "brokers",
+            None,
+            self.inner.brokers(),
+        )
+        .await
+    }
+
+    async fn produce(
+        &mut self,
+        transaction_id: Option<&str>,
+        topition: &Topition,
+        batch: deflated::Batch,
+    ) -> Result<i64> {
+        measure(
+            self.recorder.as_ref(),
+            "produce",
+            Some(topition.topic()),
+            self.inner.produce(transaction_id, topition, batch),
+        )
+        .await
+    }
+
+    async fn produce_many(
+        &mut self,
+        writes: &[(Topition, deflated::Batch)],
+    ) -> Result<Vec<(Topition, i64)>> {
+        measure(
+            self.recorder.as_ref(),
+            "produce_many",
+            None,
+            self.inner.produce_many(writes),
+        )
+        .await
+    }
+
+    async fn fetch(
+        &mut self,
+        topition: &'_ Topition,
+        offset: i64,
+        min_bytes: u32,
+        max_bytes: u32,
+        isolation: IsolationLevel,
+    ) -> Result<Vec<deflated::Batch>> {
+        measure(
+            self.recorder.as_ref(),
+            "fetch",
+            Some(topition.topic()),
+            self.inner
+                .fetch(topition, offset, min_bytes, max_bytes, isolation),
+        )
+        .await
+    }
+
+    async fn fetch_inflated(
+        &mut self,
+        topition: &'_ Topition,
+        offset: i64,
+        min_bytes: u32,
+        max_bytes: u32,
+        isolation: IsolationLevel,
+    ) -> Result<Vec<inflated::Batch>> {
+        measure(
+            self.recorder.as_ref(),
+            "fetch_inflated",
+            Some(topition.topic()),
+            self.inner
+                .fetch_inflated(topition, offset, min_bytes, max_bytes, isolation),
+        )
+        .await
+    }
+
+    async fn offset_stage(&mut self, topition: &Topition) -> Result<OffsetStage> {
+        measure(
+            self.recorder.as_ref(),
+            "offset_stage",
+            Some(topition.topic()),
+            self.inner.offset_stage(topition),
+        )
+        .await
+    }
+
+    async fn record_counts(&mut self, topics: &[TopicId]) -> Result<Vec<(Topition, i64)>> {
+        measure(
+            self.recorder.as_ref(),
+            "record_counts",
+            None,
+            self.inner.record_counts(topics),
+        )
+        .await
+    }
+
+    async fn log_dirs(&mut self) -> Result<Vec<LogDirDescription>> {
+        measure(
+            self.recorder.as_ref(),
+            "log_dirs",
+            None,
+            self.inner.log_dirs(),
+        )
+        .await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        measure(self.recorder.as_ref(), "close", None, self.inner.close()).await
+    }
+
+    async fn describe_producers(&mut self, topition: &Topition) -> Result<Vec<ProducerState>> {
+        measure(
+            self.recorder.as_ref(),
+            "describe_producers",
+            Some(topition.topic()),
+            self.inner.describe_producers(topition),
+        )
+        .await
+    }
+
+    async fn list_offsets(
+        &mut self,
+        isolation_level: IsolationLevel,
+        offsets: &[(Topition, ListOffsetRequest)],
+    ) -> Result<Vec<(Topition, ListOffsetResponse)>> {
+        measure(
+            self.recorder.as_ref(),
+            "list_offsets",
+            None,
+            self.inner.list_offsets(isolation_level, offsets),
+        )
+        .await
+    }
+
+    async fn list_offsets_batch(
+        &mut self,
+        isolation_level: IsolationLevel,
+        offsets: &[(Topition, ListOffsetRequest)],
+    ) -> Result<Vec<(Topition, ListOffsetResponse)>> {
+        measure(
+            self.recorder.as_ref(),
+            "list_offsets_batch",
+            None,
+            self.inner.list_offsets_batch(isolation_level, offsets),
+        )
+        .await
+    }
+
+    async fn fetch_from_timestamp(
+        &mut self,
+        topition: &Topition,
+        timestamp: SystemTime,
+        max_bytes: u32,
+    ) -> Result<deflated::Batch> {
+        measure(
+            self.recorder.as_ref(),
+            "fetch_from_timestamp",
+            Some(topition.topic()),
+            self.inner
+                .fetch_from_timestamp(topition, timestamp, max_bytes),
+        )
+        .await
+    }
+
+    async fn offset_commit(
+        &mut self,
+        group_id: &str,
+        retention_time_ms: Option<Duration>,
+        offsets: &[(Topition, OffsetCommitRequest)],
+    ) -> Result<Vec<(Topition, ErrorCode)>> {
+        measure(
+            self.recorder.as_ref(),
+            "offset_commit",
+            None,
+            self.inner
+                .offset_commit(group_id, retention_time_ms, offsets),
+        )
+        .await
+    }
+
+    async fn offset_fetch(
+        &mut self,
+        group_id: Option<&str>,
+        topics: &[Topition],
+        require_stable: Option<bool>,
+    ) -> Result<BTreeMap<Topition, i64>> {
+        measure(
+            self.recorder.as_ref(),
+            "offset_fetch",
+            None,
+            self.inner.offset_fetch(group_id, topics, require_stable),
+        )
+        .await
+    }
+
+    async fn committed_offset_topitions(
+        &mut self,
+        group_id: &str,
+    ) -> Result<BTreeMap<Topition, i64>> {
+        measure(
+            self.recorder.as_ref(),
+            "committed_offset_topitions",
+            None,
+            self.inner.committed_offset_topitions(group_id),
+        )
+        .await
+    }
+
+    async fn committed_offsets(
+        &mut self,
+        group_id: &str,
+    ) -> Result<BTreeMap<Topition, CommittedOffset>> {
+        measure(
+            self.recorder.as_ref(),
+            "committed_offsets",
+            None,
+            self.inner.committed_offsets(group_id),
+        )
+        .await
+    }
+
+    async fn expire_offsets(&mut self) -> Result<u64> {
+        measure(
+            self.recorder.as_ref(),
+            "expire_offsets",
+            None,
+            self.inner.expire_offsets(),
+        )
+        .await
+    }
+
+    async fn metadata(&mut self, topics: Option<&[TopicId]>) -> Result<MetadataResponse> {
+        measure(
+            self.recorder.as_ref(),
+            "metadata",
+            None,
+            self.inner.metadata(topics),
+        )
+        .await
+    }
+
+    async fn list_topics(&mut self) -> Result<Vec<TopicDetail>> {
+        measure(
+            self.recorder.as_ref(),
+            "list_topics",
+            None,
+            self.inner.list_topics(),
+        )
+        .await
+    }
+
+    async fn describe_config(
+        &self,
+        name: &str,
+        resource: ConfigResource,
+        keys: Option<&[String]>,
+    ) -> Result<DescribeConfigsResult> {
+        measure(
+            self.recorder.as_ref(),
+            "describe_config",
+            None,
+            self.inner.describe_config(name, resource, keys),
+        )
+        .await
+    }
+
+    async fn list_groups(&mut self, states_filter: Option<&[String]>) -> Result<Vec<ListedGroup>> {
+        measure(
+            self.recorder.as_ref(),
+            "list_groups",
+            None,
+            self.inner.list_groups(states_filter),
+        )
+        .await
+    }
+
+    async fn delete_groups(
+        &mut self,
+        group_ids: Option<&[String]>,
+    ) -> Result<Vec<DeletableGroupResult>> {
+        measure(
+            self.recorder.as_ref(),
+            "delete_groups",
+            None,
+            self.inner.delete_groups(group_ids),
+        )
+        .await
+    }
+
+    async fn describe_groups(
+        &mut self,
+        group_ids: Option<&[String]>,
+        include_authorized_operations: bool,
+    ) -> Result<Vec<NamedGroupDetail>> {
+        measure(
+            self.recorder.as_ref(),
+            "describe_groups",
+            None,
+            self.inner
+                .describe_groups(group_ids, include_authorized_operations),
+        )
+        .await
+    }
+
+    async fn describe_topic_partitions(
+        &mut self,
+        topics: Option<&[TopicId]>,
+        partition_limit: i32,
+        cursor: Option<Topition>,
+    ) -> Result<Vec<DescribeTopicPartitionsResponseTopic>> {
+        measure(
+            self.recorder.as_ref(),
+            "describe_topic_partitions",
+            None,
+            self.inner
+                .describe_topic_partitions(topics, partition_limit, cursor),
+        )
+        .await
+    }
+
+    async fn update_group(
+        &mut self,
+        group_id: &str,
+        detail: GroupDetail,
+        version: Option<Version>,
+    ) -> Result<Version, UpdateError<GroupDetail>> {
+        measure(
+            self.recorder.as_ref(),
+            "update_group",
+            None,
+            self.inner.update_group(group_id, detail, version),
+        )
+        .await
+    }
+
+    async fn init_producer(
+        &mut self,
+        transaction_id: Option<&str>,
+        transaction_timeout_ms: i32,
+        producer_id: Option<i64>,
+        producer_epoch: Option<i16>,
+    ) -> Result<ProducerIdResponse> {
+        measure(
+            self.recorder.as_ref(),
+            "init_producer",
+            None,
+            self.inner.init_producer(
+                transaction_id,
+                transaction_timeout_ms,
+                producer_id,
+                producer_epoch,
+            ),
+        )
+        .await
+    }
+
+    async fn txn_add_offsets(
+        &mut self,
+        transaction_id: &str,
+        producer_id: i64,
+        producer_epoch: i16,
+        group_id: &str,
+    ) -> Result<ErrorCode> {
+        measure(
+            self.recorder.as_ref(),
+            "txn_add_offsets",
+            None,
+            self.inner
+                .txn_add_offsets(transaction_id, producer_id, producer_epoch, group_id),
+        )
+        .await
+    }
+
+    async fn txn_add_partitions(
+        &mut self,
+        partitions: TxnAddPartitionsRequest,
+    ) -> Result<TxnAddPartitionsResponse> {
+        measure(
+            self.recorder.as_ref(),
+            "txn_add_partitions",
+            None,
+            self.inner.txn_add_partitions(partitions),
+        )
+        .await
+    }
+
+    async fn txn_offset_commit(
+        &mut self,
+        offsets: TxnOffsetCommitRequest,
+    ) -> Result<Vec<TxnOffsetCommitResponseTopic>> {
+        measure(
+            self.recorder.as_ref(),
+            "txn_offset_commit",
+            None,
+            self.inner.txn_offset_commit(offsets),
+        )
+        .await
+    }
+
+    async fn txn_end(
+        &mut self,
+        transaction_id: &str,
+        producer_id: i64,
+        producer_epoch: i16,
+        committed: bool,
+    ) -> Result<ErrorCode> {
+        measure(
+            self.recorder.as_ref(),
+            "txn_end",
+            None,
+            self.inner
+                .txn_end(transaction_id, producer_id, producer_epoch, committed),
+        )
+        .await
+    }
+
+    async fn compact(&mut self, topition: &Topition) -> Result<u64> {
+        measure(
+            self.recorder.as_ref(),
+            "compact",
+            Some(topition.topic()),
+            self.inner.compact(topition),
+        )
+        .await
+    }
+
+    async fn health(&mut self) -> Result<()> {
+        measure(self.recorder.as_ref(), "health", None, self.inner.health()).await
+    }
+
+    async fn maintain(&self) -> Result<()> {
+        measure(
+            self.recorder.as_ref(),
+            "maintain",
+            None,
+            self.inner.maintain(),
+        )
+        .await
+    }
+
+    // watching doesn't touch the storage, so isn't measured as a request
+    //
+    fn produce_watch(&self, topitions: &[Topition]) -> Result<ProduceWatch> {
+        self.inner.produce_watch(topitions)
+    }
+}
@@ -15,33 +15,43 @@
 
 use std::{
     cmp::Ordering,
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     hash::{DefaultHasher, Hash, Hasher},
+    io::Cursor,
     marker::PhantomData,
     str::FromStr,
-    sync::LazyLock,
+    sync::{
+        Arc, LazyLock, Mutex, Weak,
+        atomic::{self, AtomicU64},
+    },
     time::{Duration, SystemTime},
 };
 
 use async_trait::async_trait;
 use bytes::Bytes;
-use deadpool_postgres::{Manager, ManagerConfig, Object, Pool, RecyclingMethod};
+use cache::Cache;
+use deadpool::managed::TimeoutType;
+use deadpool_postgres::{Manager, ManagerConfig, Object, Pool, PoolError, RecyclingMethod};
+use futures::{StreamExt, TryStreamExt};
+use object_store::{DynObjectStore, ObjectStore, PutMode, PutOptions, PutPayload, path::Path};
 use opentelemetry::metrics::Histogram;
 use opentelemetry::{KeyValue, metrics::Counter};
 use rand::{prelude::*, rng};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tansu_kafka_sans_io::{
-    BatchAttribute, ConfigResource, ConfigSource, ConfigType, ControlBatch, EndTransactionMarker,
-    ErrorCode, IsolationLevel, NULL_TOPIC_ID, OpType,
+    BatchAttribute, ConfigResource, ControlBatch, Decoder, Encoder, EndTransactionMarker,
+    ErrorCode, IsolationLevel, NULL_TOPIC_ID, OpType, TimestampType,
     add_partitions_to_txn_response::{
         AddPartitionsToTxnPartitionResult, AddPartitionsToTxnTopicResult,
     },
-    create_topics_request::CreatableTopic,
+    create_partitions_request::CreatePartitionsAssignment,
+    create_topics_request::{CreatableReplicaAssignment, CreatableTopic, CreatableTopicConfig},
     delete_groups_response::DeletableGroupResult,
     delete_records_request::DeleteRecordsTopic,
     delete_records_response::{DeleteRecordsPartitionResult, DeleteRecordsTopicResult},
     describe_cluster_response::DescribeClusterBroker,
-    describe_configs_response::{DescribeConfigsResourceResult, DescribeConfigsResult},
+    describe_configs_response::DescribeConfigsResult,
     describe_topic_partitions_response::{
         DescribeTopicPartitionsResponsePartition, DescribeTopicPartitionsResponseTopic,
     },
@@ -57,18 +67,52 @@ use tansu_schema_registry::{
     Registry,
     lake::{House, LakeHouse},
 };
+use tokio::{
+    sync::Notify,
+    time::{sleep, timeout},
+};
 use tokio_postgres::{Config, NoTls, Row, Transaction, error::SqlState, types::ToSql};
-use tracing::{debug, error};
+use tracing::{debug, error, instrument, warn};
 use url::Url;
 use uuid::Uuid;
 
 use crate::{
-    BrokerRegistrationRequest, Error, GroupDetail, ListOffsetRequest, ListOffsetResponse, METER,
-    MetadataResponse, NamedGroupDetail, OffsetCommitRequest, OffsetStage, ProducerIdResponse,
-    Result, Storage, TopicId, Topition, TxnAddPartitionsRequest, TxnAddPartitionsResponse,
-    TxnOffsetCommitRequest, TxnState, UpdateError, Version,
+    BatchLimit, BrokerRegistrationRequest, CommittedOffset, DEFAULT_NUM_PARTITIONS, Error,
+    GroupDetail, ListOffsetRequest, ListOffsetResponse, LogDirDescription, MESSAGE_TIMESTAMP_TYPE,
+    METER, MetadataResponse, NamedGroupDetail, OFFSET_METADATA_MAX_BYTES, OFFSETS_RETENTION,
+    OffsetCommitRequest, OffsetStage, PRODUCER_ID_SEQUENCE_WINDOW, ProduceWatch,
+    ProducerIdResponse, ProducerState, Result, Storage, TopicDetail, TopicId, Topition,
+    TxnAddPartitionsRequest, TxnAddPartitionsResponse, TxnOffsetCommitRequest, TxnState,
+    UpdateError, Version, assign_replicas, broker_config, compaction,
+    export::Scan,
+    idempotent_sequence,
+    instrumented::{Instrumented, Recorder},
+    is_internal_topic, log_append_time, message_timestamp_type, num_partitions, offset_expiry,
+    redact_dsn, replication_factor, topic_configs, unsupported_config, validate_batch,
+    validate_topic, verify_producer_epoch,
 };
 
+/// The time allowed to obtain a connection when checking health.
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The time allowed for connections in use to be returned on close.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The segments sampled when training a topic dictionary on maintenance.
+const SEGMENT_DICTIONARY_SAMPLES: usize = 1_000;
+
+/// The size of a topic dictionary trained on maintenance.
+const SEGMENT_DICTIONARY_SIZE: usize = 112_640;
+
+/// The window of offsets sized by each query of a fetch.
+const FETCH_SCAN_ROWS: i64 = 1_000;
+
+mod cache;
+mod dictionary;
+mod retry;
+
+pub use retry::ConnectionRetry;
+
 macro_rules! include_sql {
     ($e: expr) => {
         remove_comments(include_str!($e))
@@ -83,6 +127,19 @@ pub struct Postgres {
     pool: Pool,
     schemas: Option<Registry>,
     lake: Option<House>,
+    segments: Option<Arc<DynObjectStore>>,
+    sequence_window: usize,
+    offsets_retention: Duration,
+    offset_metadata_max_bytes: usize,
+    segment_compression: Option<i32>,
+    connection_retry: ConnectionRetry,
+    auto_create_topics: bool,
+    num_partitions: i32,
+    fetch_batch_limit: Option<BatchLimit>,
+    dictionaries: Arc<Mutex<BTreeMap<u32, Arc<Vec<u8>>>>>,
+    cache: Option<Arc<Mutex<Cache>>>,
+    queries: Arc<AtomicU64>,
+    produced: Arc<Mutex<BTreeMap<Topition, Weak<Notify>>>>,
 }
 
 #[derive(Clone, Default, Debug)]
@@ -93,6 +150,16 @@ pub struct Builder<C, N, L, P> {
     pool: P,
     schemas: Option<Registry>,
     lake: Option<House>,
+    segments: Option<Arc<DynObjectStore>>,
+    sequence_window: usize,
+    offsets_retention: Duration,
+    offset_metadata_max_bytes: usize,
+    segment_compression: Option<i32>,
+    connection_retry: ConnectionRetry,
+    auto_create_topics: bool,
+    num_partitions: i32,
+    fetch_batch_limit: Option<BatchLimit>,
+    cache: Option<usize>,
 }
 
 impl<C, N, L, P> Builder<C, N, L, P> {
@@ -104,6 +171,16 @@ impl<C, N, L, P> Builder<C, N, L, P> {
             pool: self.pool,
             schemas: self.schemas,
             lake: self.lake,
+            segments: self.segments,
+            sequence_window: self.sequence_window,
+            offsets_retention: self.offsets_retention,
+            offset_metadata_max_bytes: self.offset_metadata_max_bytes,
+            segment_compression: self.segment_compression,
+            connection_retry: self.connection_retry,
+            auto_create_topics: self.auto_create_topics,
+            num_partitions: self.num_partitions,
+            fetch_batch_limit: self.fetch_batch_limit,
+            cache: self.cache,
         }
     }
 }
@@ -117,6 +194,16 @@ impl<C, N, L, P> Builder<C, N, L, P> {
             pool: self.pool,
             schemas: self.schemas,
             lake: self.lake,
+            segments: self.segments,
+            sequence_window: self.sequence_window,
+            offsets_retention: self.offsets_retention,
+            offset_metadata_max_bytes: self.offset_metadata_max_bytes,
+            segment_compression: self.segment_compression,
+            connection_retry: self.connection_retry,
+            auto_create_topics: self.auto_create_topics,
+            num_partitions: self.num_partitions,
+            fetch_batch_limit: self.fetch_batch_limit,
+            cache: self.cache,
         }
     }
 }
@@ -130,6 +217,16 @@ impl<C, N, L, P> Builder<C, N, L, P> {
             pool: self.pool,
             schemas: self.schemas,
             lake: self.lake,
+            segments: self.segments,
+            sequence_window: self.sequence_window,
+            offsets_retention: self.offsets_retention,
+            offset_metadata_max_bytes: self.offset_metadata_max_bytes,
+            segment_compression: self.segment_compression,
+            connection_retry: self.connection_retry,
+            auto_create_topics: self.auto_create_topics,
+            num_partitions: self.num_partitions,
+            fetch_batch_limit: self.fetch_batch_limit,
+            cache: self.cache,
         }
     }
 
@@ -140,6 +237,97 @@ impl<C, N, L, P> Builder<C, N, L, P> {
     pub fn lake(self, lake: Option<House>) -> Self {
         Self { lake, ..self }
     }
+
+    /// Store record batches as objects, keeping metadata, offsets
+    /// and watermarks in Postgres.
+    pub fn segments(self, segments: impl ObjectStore) -> Self {
+        Self {
+            segments: Some(Arc::new(segments)),
+            ..self
+        }
+    }
+
+    /// Compress segments at this zstd level with the dictionary
+    /// trained for their topic, see
+    /// [`Postgres::train_segment_dictionary`].
+    pub fn segment_compression(self, level: i32) -> Self {
+        Self {
+            segment_compression: Some(level),
+            ..self
+        }
+    }
+
+    /// The number of batches retained per producer and topition when
+    /// deduplicating idempotent produces.
+    pub fn sequence_window(self, sequence_window: usize) -> Self {
+        Self {
+            sequence_window,
+            ..self
+        }
+    }
+
+    /// The retention of committed offsets when a commit doesn't
+    /// specify one.
+    pub fn offsets_retention(self, offsets_retention: Duration) -> Self {
+        Self {
+            offsets_retention,
+            ..self
+        }
+    }
+
+    /// The maximum size of the metadata of a committed offset.
+    pub fn offset_metadata_max_bytes(self, offset_metadata_max_bytes: usize) -> Self {
+        Self {
+            offset_metadata_max_bytes,
+            ..self
+        }
+    }
+
+    /// Retry getting a connection while the database is transiently
+    /// unavailable.
+    pub fn connection_retry(self, connection_retry: ConnectionRetry) -> Self {
+        Self {
+            connection_retry,
+            ..self
+        }
+    }
+
+    /// Create a topic on its first produce, with the default number of
+    /// partitions and replication factor.
+    pub fn auto_create_topics(self, auto_create_topics: bool) -> Self {
+        Self {
+            auto_create_topics,
+            ..self
+        }
+    }
+
+    /// The number of partitions of a topic created with `-1`, or on its
+    /// first produce.
+    pub fn num_partitions(self, num_partitions: i32) -> Self {
+        Self {
+            num_partitions,
+            ..self
+        }
+    }
+
+    /// Split the records of a fetch into batches of at most this size,
+    /// rather than a single batch.
+    pub fn fetch_batch_limit(self, fetch_batch_limit: Option<BatchLimit>) -> Self {
+        Self {
+            fetch_batch_limit,
+            ..self
+        }
+    }
+
+    /// Cache up to capacity of the most recently used batches across
+    /// all topitions in memory, serving tail fetches without reading
+    /// records from Postgres.
+    pub fn cache(self, capacity: usize) -> Self {
+        Self {
+            cache: Some(capacity),
+            ..self
+        }
+    }
 }
 
 impl Builder<String, i32, Url, Pool> {
@@ -151,6 +339,21 @@ impl Builder<String, i32, Url, Pool> {
             pool: self.pool,
             schemas: self.schemas,
             lake: self.lake,
+            segments: self.segments,
+            sequence_window: self.sequence_window,
+            offsets_retention: self.offsets_retention,
+            offset_metadata_max_bytes: self.offset_metadata_max_bytes,
+            segment_compression: self.segment_compression,
+            connection_retry: self.connection_retry,
+            auto_create_topics: self.auto_create_topics,
+            num_partitions: self.num_partitions,
+            fetch_batch_limit: self.fetch_batch_limit,
+            dictionaries: Arc::new(Mutex::new(BTreeMap::new())),
+            cache: self
+                .cache
+                .map(|capacity| Arc::new(Mutex::new(Cache::new(capacity)))),
+            queries: Arc::new(AtomicU64::new(0)),
+            produced: Arc::new(Mutex::new(BTreeMap::new())),
         }
     }
 }
@@ -182,6 +385,16 @@ where
                 cluster: C::default(),
                 schemas: None,
                 lake: None,
+                segments: None,
+                sequence_window: PRODUCER_ID_SEQUENCE_WINDOW,
+                offsets_retention: OFFSETS_RETENTION,
+                offset_metadata_max_bytes: OFFSET_METADATA_MAX_BYTES,
+                segment_compression: None,
+                connection_retry: ConnectionRetry::default(),
+                auto_create_topics: false,
+                num_partitions: DEFAULT_NUM_PARTITIONS,
+                fetch_batch_limit: None,
+                cache: None,
             })
             .map_err(Into::into)
     }
@@ -219,186 +432,1384 @@ impl TryFrom<Row> for Txn {
     }
 }
 
+/// A record as it is stored, with the producer of a non-transactional
+/// record unset, from which fetched batches are built.
+#[derive(Clone, Debug)]
+struct Stored {
+    offset: i64,
+    attributes: i16,
+    producer_id: i64,
+    producer_epoch: i16,
+    timestamp: i64,
+    key: Option<Bytes>,
+    value: Option<Bytes>,
+    headers: Vec<Header>,
+}
+
+impl TryFrom<&Row> for Stored {
+    type Error = Error;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        let offset = row.try_get::<_, i64>(0).inspect_err(|err| error!(?err))?;
+        let attributes = row
+            .try_get::<_, Option<i16>>(1)
+            .map(|attributes| attributes.unwrap_or(0))
+            .inspect_err(|err| error!(?err))?;
+        let timestamp = row
+            .try_get::<_, SystemTime>(2)
+            .map_err(Error::from)
+            .and_then(|system_time| to_timestamp(system_time).map_err(Into::into))
+            .inspect_err(|err| error!(?err))?;
+        let key = row
+            .try_get::<_, Option<&[u8]>>(3)
+            .map(|o| o.map(Bytes::copy_from_slice))
+            .inspect_err(|err| error!(?err))?;
+        let value = row
+            .try_get::<_, Option<&[u8]>>(4)
+            .map(|o| o.map(Bytes::copy_from_slice))
+            .inspect_err(|err| error!(?err))?;
+        let producer_id = row
+            .try_get::<_, Option<i64>>(6)
+            .map(|producer_id| producer_id.unwrap_or(-1))
+            .inspect_err(|err| error!(?err))?;
+        let producer_epoch = row
+            .try_get::<_, Option<i16>>(7)
+            .map(|producer_epoch| producer_epoch.unwrap_or(-1))
+            .inspect_err(|err| error!(?err))?;
+
+        Ok(Self {
+            offset,
+            attributes,
+            producer_id,
+            producer_epoch,
+            timestamp,
+            key,
+            value,
+            headers: vec![],
+        })
+    }
+}
+
+/// A batch written by a produce, with the records to cache and the
+/// batch to export to the lake once committed.
+#[derive(Clone, Debug, Default)]
+struct Produced {
+    high: i64,
+    stored: Vec<Stored>,
+    export: Option<inflated::Batch>,
+}
+
+/// The offsets of the records of a committed transaction in a
+/// topition.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct TxnOffsets {
+    topition: Topition,
+    producer_id: i64,
+    offset_start: i64,
+    offset_end: i64,
+}
+
+/// The outcome of ending a transaction, with the topitions that
+/// were marked and the transactions that have committed as a result.
+#[derive(Clone, Debug)]
+struct TxnEnd {
+    error_code: ErrorCode,
+    marked: Vec<Topition>,
+    committed: Vec<TxnOffsets>,
+}
+
 impl Postgres {
     pub fn builder(
         connection: &str,
     ) -> Result<Builder<PhantomData<String>, PhantomData<i32>, Url, Pool>> {
-        debug!(connection);
+        debug!(connection = redact_dsn(connection));
         Builder::from_str(connection)
     }
 
     async fn connection(&self) -> Result<Object> {
-        self.pool.get().await.map_err(Into::into)
+        if self.pool.is_closed() {
+            return Err(Error::Closed);
+        }
+
+        self.connection_retry
+            .get(&self.pool, retry::is_transient)
+            .await
+            .map_err(Into::into)
     }
 
-    fn idempotent_sequence_check(
-        producer_epoch: &i16,
-        sequence: &i32,
-        deflated: &deflated::Batch,
-    ) -> Result<i32> {
-        debug!(?producer_epoch, ?sequence, ?deflated);
+    /// The number of SQL statements executed by this storage.
+    pub fn queries(&self) -> u64 {
+        self.queries.load(atomic::Ordering::Relaxed)
+    }
 
-        match producer_epoch.cmp(&deflated.producer_epoch) {
-            Ordering::Equal => match sequence.cmp(&deflated.base_sequence) {
-                Ordering::Equal => Ok(deflated.last_offset_delta + 1),
+    /// Report the count, latency and errors of each storage request
+    /// to a recorder.
+    pub fn with_metrics(self, recorder: impl Recorder) -> Instrumented<Self> {
+        Instrumented::new(self, recorder)
+    }
 
-                Ordering::Greater => {
-                    debug!(?sequence, ?deflated.base_sequence);
-                    Err(Error::Api(ErrorCode::DuplicateSequenceNumber))
-                }
+    fn cache_generation(&self, topition: &Topition) -> Result<Option<u64>> {
+        self.cache
+            .as_ref()
+            .map(|cache| {
+                cache
+                    .lock()
+                    .map(|cache| cache.generation(topition))
+                    .map_err(Into::into)
+            })
+            .transpose()
+    }
 
-                Ordering::Less => {
-                    debug!(?sequence, ?deflated.base_sequence);
-                    Err(Error::Api(ErrorCode::OutOfOrderSequenceNumber))
-                }
-            },
+    fn cache_put(
+        &self,
+        topition: &Topition,
+        generation: Option<u64>,
+        batches: &[deflated::Batch],
+    ) -> Result<()> {
+        if let (Some(cache), Some(generation)) = (self.cache.as_ref(), generation) {
+            cache.lock()?.put(topition, generation, batches);
+        }
 
-            Ordering::Greater => Err(Error::Api(ErrorCode::ProducerFenced)),
+        Ok(())
+    }
 
-            Ordering::Less => Err(Error::Api(ErrorCode::InvalidProducerEpoch)),
+    /// Cache committed records as they would be fetched.
+    fn cache_stored(
+        &self,
+        topition: &Topition,
+        generation: Option<u64>,
+        stored: Vec<Stored>,
+    ) -> Result<()> {
+        if generation.is_none() || stored.is_empty() {
+            return Ok(());
         }
+
+        self.stored_batches(stored)?
+            .into_iter()
+            .map(|batch| deflated::Batch::try_from(batch).map_err(Into::into))
+            .collect::<Result<Vec<_>>>()
+            .and_then(|batches| self.cache_put(topition, generation, &batches))
     }
 
-    async fn idempotent_message_check(
+    /// The high watermark visible to a fetch from an offset, which is
+    /// out of range when below the log start, such as after records
+    /// have been deleted.
+    async fn high_watermark(
         &mut self,
-        transaction_id: Option<&str>,
         topition: &Topition,
-        deflated: &deflated::Batch,
-        tx: &Transaction<'_>,
-    ) -> Result<()> {
-        debug!(transaction_id, ?deflated);
-        if let Some(row) = self
-            .tx_prepare_query_opt(
-                tx,
-                include_sql!("pg/producer_epoch_current_for_producer.sql").as_str(),
-                &[&self.cluster, &deflated.producer_id],
-                "idempotent_message_check",
-            )
-            .await
-            .inspect_err(|err| error!(?err))?
-        {
-            let current_epoch = row
-                .try_get::<_, i16>(0)
-                .inspect_err(|err| error!(self.cluster, deflated.producer_id, ?err))?;
+        offset: i64,
+        isolation_level: IsolationLevel,
+    ) -> Result<i64> {
+        let offset_stage = self.offset_stage(topition).await?;
 
-            let row = self
-                .tx_prepare_query_one(
-                    tx,
-                    include_sql!("pg/producer_select_for_update.sql").as_str(),
+        if offset < offset_stage.log_start {
+            debug!(?topition, offset, log_start = offset_stage.log_start);
+            return Err(Error::Api(ErrorCode::OffsetOutOfRange));
+        }
+
+        Ok(offset_stage.visible(isolation_level))
+    }
+
+    /// Fetch records from the database as inflated batches, returning
+    /// no batches when there are no records.
+    async fn record_fetch(
+        &self,
+        topition: &Topition,
+        offset: i64,
+        max_bytes: u32,
+        high_watermark: i64,
+    ) -> Result<Vec<inflated::Batch>> {
+        let c = self.connection().await?;
+
+        let mut records = vec![];
+        let mut from = offset;
+        let mut remaining = i64::from(max_bytes);
+
+        'scan: while from < high_watermark {
+            let rows = self
+                .prepare_query(
+                    &c,
+                    include_sql!("pg/record_fetch.sql").as_str(),
                     &[
                         &self.cluster,
                         &topition.topic(),
                         &topition.partition(),
-                        &deflated.producer_id,
-                        &deflated.producer_epoch,
+                        &from,
+                        &remaining,
+                        &high_watermark,
+                        &FETCH_SCAN_ROWS,
                     ],
-                    "idempotent_message_check",
+                    "fetch",
                 )
                 .await
-                .inspect_err(|err| {
-                    error!(
-                        self.cluster,
-                        ?topition,
-                        deflated.producer_id,
-                        deflated.producer_epoch,
-                        ?err
-                    )
-                })?;
+                .inspect_err(|err| error!(?err))?;
 
-            let sequence = row.try_get::<_, i32>(0).inspect_err(|err| error!(?err))?;
+            // fewer rows than scanned in the window once max bytes has
+            // been reached
+            //
+            let scanned = rows
+                .first()
+                .map_or(Ok(0), |row| row.try_get::<_, i64>(9))
+                .inspect_err(|err| error!(?err))?;
+            let exhausted = i64::try_from(rows.len())? < scanned;
+            let mut used = 0;
 
-            debug!(
-                self.cluster,
-                ?topition,
-                deflated.producer_id,
-                deflated.producer_epoch,
-                current_epoch,
-                sequence,
-            );
+            for row in rows {
+                let bytes = row.try_get::<_, i64>(5).inspect_err(|err| error!(?err))?;
 
-            let increment = Self::idempotent_sequence_check(&current_epoch, &sequence, deflated)?;
+                // only the first record of a fetch may exceed max bytes
+                //
+                if !records.is_empty() && bytes > remaining {
+                    break 'scan;
+                }
 
-            debug!(increment);
+                used = bytes;
+                records.push(row);
+            }
 
-            assert_eq!(
-                1,
-                self.tx_prepare_execute(
-                    tx,
-                    include_sql!("pg/producer_detail_insert.sql").as_str(),
+            from = high_watermark.min(from.saturating_add(FETCH_SCAN_ROWS));
+            remaining -= used;
+
+            debug!(from, remaining, records = records.len());
+
+            if exhausted || remaining <= 0 {
+                break;
+            }
+        }
+
+        let mut stored = Vec::with_capacity(records.len());
+
+        for row in records.iter() {
+            let mut record = Stored::try_from(row)?;
+
+            for header in self
+                .prepare_query(
+                    &c,
+                    include_sql!("pg/header_fetch.sql").as_str(),
                     &[
                         &self.cluster,
                         &topition.topic(),
                         &topition.partition(),
-                        &deflated.producer_id,
-                        &deflated.producer_epoch,
-                        &increment,
+                        &record.offset,
                     ],
-                    "idempotent_message_check",
+                    "fetch",
                 )
-                .await?
-            );
+                .await
+                .inspect(|row| debug!(?row))
+                .inspect_err(|err| error!(?err))?
+            {
+                record.headers.push(Header {
+                    key: header
+                        .try_get::<_, Option<&[u8]>>(0)
+                        .map(|o| o.map(Bytes::copy_from_slice))
+                        .inspect_err(|err| error!(?err))?,
+                    value: header
+                        .try_get::<_, Option<&[u8]>>(1)
+                        .map(|o| o.map(Bytes::copy_from_slice))
+                        .inspect_err(|err| error!(?err))?,
+                });
+            }
 
-            Ok(())
-        } else {
-            Err(Error::Api(ErrorCode::UnknownProducerId))
+            stored.push(record);
         }
-    }
 
-    async fn watermark_select_for_update(
-        &mut self,
-        topition: &Topition,
-        tx: &Transaction<'_>,
-    ) -> Result<(Option<i64>, Option<i64>)> {
-        if let Some(row) = self
-            .tx_prepare_query_opt(
-                tx,
-                include_sql!("pg/watermark_select_for_update.sql").as_str(),
-                &[&self.cluster, &topition.topic(), &topition.partition()],
-                "watermark_select_for_update",
-            )
-            .await
-            .inspect_err(|err| error!(?err, cluster = ?self.cluster, ?topition))?
-        {
-            Ok((
-                row.try_get::<_, Option<i64>>(0)
-                    .inspect_err(|err| error!(?err))?,
-                row.try_get::<_, Option<i64>>(1)
-                    .inspect_err(|err| error!(?err))?,
-            ))
-        } else {
-            Err(Error::Api(ErrorCode::UnknownTopicOrPartition))
-        }
+        self.stored_batches(stored)
     }
 
-    fn attributes_for_error(
-        &self,
-        nickname: &str,
-        error: &tokio_postgres::error::Error,
-    ) -> Vec<KeyValue> {
-        let mut attributes = vec![
-            KeyValue::new("sql", nickname.to_owned()),
-            KeyValue::new("cluster_id", self.cluster.clone()),
-        ];
+    /// Build batches from stored records, starting a new batch when the
+    /// attributes or producer change, or the fetch batch limit would be
+    /// exceeded.
+    fn stored_batches(&self, records: Vec<Stored>) -> Result<Vec<inflated::Batch>> {
+        let mut batches = vec![];
 
-        if let Some(db_error) = error.as_db_error() {
-            if let Some(schema) = db_error.schema() {
-                attributes.push(KeyValue::new("schema", schema.to_owned()));
-            }
+        let Some(first) = records.first() else {
+            return Ok(batches);
+        };
 
-            if let Some(table) = db_error.table() {
-                attributes.push(KeyValue::new("table", table.to_owned()));
+        let mut batch_builder = inflated::Batch::builder()
+            .base_offset(first.offset)
+            .attributes(first.attributes)
+            .base_timestamp(first.timestamp)
+            .max_timestamp(first.timestamp)
+            .producer_id(first.producer_id)
+            .producer_epoch(first.producer_epoch);
+
+        let mut batch_records = 0;
+        let mut batch_bytes = 0;
+
+        for record in records {
+            debug!(?record);
+
+            let record_bytes = record.key.as_ref().map_or(0, Bytes::len)
+                + record.value.as_ref().map_or(0, Bytes::len);
+
+            if batch_builder.attributes != record.attributes
+                || batch_builder.producer_id != record.producer_id
+                || batch_builder.producer_epoch != record.producer_epoch
+                || (batch_records > 0
+                    && self.fetch_batch_limit.is_some_and(|limit| {
+                        limit.exceeded_by(batch_records + 1, batch_bytes + record_bytes)
+                    }))
+            {
+                batches.push(batch_builder.build()?);
+                batch_records = 0;
+                batch_bytes = 0;
+
+                batch_builder = inflated::Batch::builder()
+                    .base_offset(record.offset)
+                    .base_timestamp(record.timestamp)
+                    .max_timestamp(record.timestamp)
+                    .attributes(record.attributes)
+                    .producer_id(record.producer_id)
+                    .producer_epoch(record.producer_epoch);
             }
 
-            if let Some(constraint) = db_error.constraint() {
-                attributes.push(KeyValue::new("constraint", constraint.to_owned()));
+            let offset_delta = i32::try_from(record.offset - batch_builder.base_offset)?;
+            let timestamp_delta = record.timestamp - batch_builder.base_timestamp;
+
+            let mut record_builder = Record::builder()
+                .offset_delta(offset_delta)
+                .timestamp_delta(timestamp_delta)
+                .key(record.key.into())
+                .value(record.value.into());
+
+            for header in record.headers {
+                record_builder = record_builder.header(header.into());
             }
+
+            let max_timestamp = batch_builder.max_timestamp.max(record.timestamp);
+
+            batch_builder = batch_builder
+                .record(record_builder)
+                .last_offset_delta(offset_delta)
+                .max_timestamp(max_timestamp);
+
+            batch_records += 1;
+            batch_bytes += record_bytes;
         }
 
-        if let Some(code) = error.code() {
-            attributes.push(KeyValue::new("code", format!("{code:?}")));
+        batches.push(batch_builder.build()?);
+
+        Ok(batches)
+    }
+
+    /// Wake any fetch waiting for a produce to this topition.
+    fn notify_produced(&self, topition: &Topition) -> Result<()> {
+        let mut produced = self.produced.lock()?;
+
+        if let Some(notify) = produced.get(topition).and_then(Weak::upgrade) {
+            notify.notify_waiters();
+        } else {
+            _ = produced.remove(topition);
         }
 
-        attributes
+        Ok(())
+    }
+
+    fn cache_invalidate(&self, topition: &Topition) -> Result<()> {
+        if let Some(ref cache) = self.cache {
+            cache.lock()?.invalidate(topition);
+        }
+
+        Ok(())
+    }
+
+    fn cache_invalidate_topic(&self, topic: &str) -> Result<()> {
+        if let Some(ref cache) = self.cache {
+            cache.lock()?.invalidate_topic(topic);
+        }
+
+        Ok(())
+    }
+
+    /// Delete a topic within a transaction, returning its name when it existed.
+    async fn delete_topic_in_tx(
+        &self,
+        topic: &TopicId,
+        tx: &Transaction<'_>,
+    ) -> Result<Option<String>> {
+        debug!(cluster = self.cluster, ?topic);
+
+        let row = match topic {
+            TopicId::Id(id) => {
+                self.tx_prepare_query_opt(
+                    tx,
+                    include_sql!("pg/topic_select_uuid.sql").as_str(),
+                    &[&self.cluster, &id],
+                    "delete_topic",
+                )
+                .await?
+            }
+
+            TopicId::Name(name) => {
+                self.tx_prepare_query_opt(
+                    tx,
+                    include_sql!("pg/topic_select_name.sql").as_str(),
+                    &[&self.cluster, name],
+                    "delete_topic",
+                )
+                .await?
+            }
+        };
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let topic_name = row.try_get::<_, String>(1)?;
+
+        for (description, sql, nickname) in [
+            (
+                "consumer_offsets",
+                include_sql!("pg/consumer_offset_delete_by_topic.sql"),
+                "delete_topic",
+            ),
+            (
+                "topic_configuration",
+                include_sql!("pg/topic_configuration_delete_by_topic.sql"),
+                "delete_topic",
+            ),
+            (
+                "watermarks",
+                include_sql!("pg/watermark_delete_by_topic.sql"),
+                "delete_topic",
+            ),
+            (
+                "headers",
+                include_sql!("pg/header_delete_by_topic.sql"),
+                "delete_topic",
+            ),
+            (
+                "records",
+                include_sql!("pg/record_delete_by_topic.sql"),
+                "delete_topic",
+            ),
+            (
+                "txn_offset_commit_tp",
+                include_sql!("pg/txn_offset_commit_tp_delete_by_topic.sql"),
+                "delete_topic",
+            ),
+            (
+                "txn_produce_offset_delete",
+                include_sql!("pg/txn_produce_offset_delete_by_topic.sql"),
+                "delete_topic",
+            ),
+            (
+                "txn_topition",
+                include_sql!("pg/txn_topition_delete_by_topic.sql"),
+                "delete_topic",
+            ),
+            (
+                "producer_detail",
+                include_sql!("pg/producer_detail_delete_by_topic.sql"),
+                "delete_topic",
+            ),
+            (
+                "topition_assignments",
+                include_sql!("pg/topition_assignment_delete_by_topic.sql"),
+                "delete_topic",
+            ),
+            (
+                "topitions",
+                include_sql!("pg/topition_delete_by_topic.sql"),
+                "delete_topic",
+            ),
+        ] {
+            let rows = self
+                .tx_prepare_execute(tx, sql.as_str(), &[&self.cluster, &topic_name], nickname)
+                .await
+                .inspect_err(|err| {
+                    debug!(?description, ?err);
+                })?;
+
+            debug!(?topic, ?rows, ?description);
+        }
+
+        _ = self
+            .tx_prepare_execute(
+                tx,
+                include_sql!("pg/topic_delete_by.sql").as_str(),
+                &[&self.cluster, &topic_name],
+                "delete_topic",
+            )
+            .await?;
+
+        Ok(Some(topic_name))
+    }
+
+    async fn segment_delete_topic(&self, topic_name: &str) -> Result<()> {
+        let Some(segments) = self.segments.as_deref() else {
+            return Ok(());
+        };
+
+        let prefix = Path::from(format!("clusters/{}/topics/{}/", self.cluster, topic_name));
+
+        let locations = segments
+            .list(Some(&prefix))
+            .map_ok(|meta| meta.location)
+            .boxed();
+
+        segments
+            .delete_stream(locations)
+            .try_collect::<Vec<_>>()
+            .await
+            .inspect(|deleted| debug!(%prefix, deleted = deleted.len()))
+            .inspect_err(|err| error!(?err, %prefix))
+            .and(Ok(()))
+            .map_err(Into::into)
+    }
+
+    fn segment_prefix(&self, topition: &Topition) -> Path {
+        Path::from(format!(
+            "clusters/{}/topics/{}/partitions/{:0>10}/records/",
+            self.cluster, topition.topic, topition.partition
+        ))
+    }
+
+    fn segment_location(&self, topition: &Topition, base_offset: i64) -> Path {
+        Path::from(format!(
+            "clusters/{}/topics/{}/partitions/{:0>10}/records/{:0>20}.batch",
+            self.cluster, topition.topic, topition.partition, base_offset
+        ))
+    }
+
+    fn segment_dictionary_location(&self, topic: &str, id: u32) -> Path {
+        Path::from(format!(
+            "clusters/{}/topics/{}/dictionaries/{:0>10}.zstd",
+            self.cluster, topic, id
+        ))
+    }
+
+    fn segment_current_dictionary_location(&self, topic: &str) -> Path {
+        Path::from(format!(
+            "clusters/{}/topics/{}/dictionary.json",
+            self.cluster, topic
+        ))
+    }
+
+    async fn segment_dictionary(
+        &self,
+        segments: &DynObjectStore,
+        topic: &str,
+        id: u32,
+    ) -> Result<Arc<Vec<u8>>> {
+        if let Some(dictionary) = self.dictionaries.lock()?.get(&id) {
+            return Ok(dictionary.clone());
+        }
+
+        let location = self.segment_dictionary_location(topic, id);
+
+        let dictionary = segments
+            .get(&location)
+            .await
+            .inspect_err(|err| error!(?err, %location))?
+            .bytes()
+            .await
+            .map(|dictionary| Arc::new(dictionary.to_vec()))?;
+
+        _ = self
+            .dictionaries
+            .lock()
+            .map(|mut dictionaries| dictionaries.insert(id, dictionary.clone()))?;
+
+        Ok(dictionary)
+    }
+
+    async fn segment_current_dictionary(
+        &self,
+        segments: &DynObjectStore,
+        topic: &str,
+    ) -> Result<Option<u32>> {
+        let location = self.segment_current_dictionary_location(topic);
+
+        match segments.get(&location).await {
+            Ok(get_result) => get_result
+                .bytes()
+                .await
+                .map_err(Error::from)
+                .and_then(|encoded| {
+                    serde_json::from_slice::<dictionary::Current>(&encoded).map_err(Into::into)
+                })
+                .map(|current| Some(current.id)),
+
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Compress an encoded segment with the current dictionary of its
+    /// topic, leaving it uncompressed until a dictionary is trained.
+    async fn segment_compress(
+        &self,
+        segments: &DynObjectStore,
+        topic: &str,
+        encoded: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let Some(level) = self.segment_compression else {
+            return Ok(encoded);
+        };
+
+        let Some(id) = self.segment_current_dictionary(segments, topic).await? else {
+            return Ok(encoded);
+        };
+
+        let dictionary = self.segment_dictionary(segments, topic, id).await?;
+        dictionary::compress(&encoded, level, &dictionary)
+    }
+
+    /// Decompress a segment with the dictionary that it was compressed
+    /// with, which may no longer be the current dictionary of its topic.
+    async fn segment_decompress(
+        &self,
+        segments: &DynObjectStore,
+        topic: &str,
+        encoded: Bytes,
+    ) -> Result<Bytes> {
+        if !dictionary::is_compressed(&encoded) {
+            return Ok(encoded);
+        }
+
+        let dictionary = if let Some(id) = dictionary::dictionary_id(&encoded) {
+            Some(self.segment_dictionary(segments, topic, id).await?)
+        } else {
+            None
+        };
+
+        dictionary::decompress(
+            &encoded,
+            dictionary.as_ref().map(|dictionary| &dictionary[..]),
+        )
+        .map(Bytes::from)
+    }
+
+    /// Train a zstd dictionary of at most `max_size` bytes from up to
+    /// `max_samples` segments of a topic, compressing new segments
+    /// of the topic with it when segment compression is enabled.
+    /// Returns the id of the trained dictionary, or none without any
+    /// segments to sample.
+    pub async fn train_segment_dictionary(
+        &self,
+        topic: &str,
+        max_samples: usize,
+        max_size: usize,
+    ) -> Result<Option<u32>> {
+        debug!(cluster = self.cluster, topic, max_samples, max_size);
+
+        let Some(segments) = self.segments.as_deref() else {
+            return Ok(None);
+        };
+
+        let prefix = Path::from(format!(
+            "clusters/{}/topics/{}/partitions/",
+            self.cluster, topic
+        ));
+
+        let locations = segments
+            .list(Some(&prefix))
+            .take(max_samples)
+            .map_ok(|meta| meta.location)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        let mut samples = vec![];
+
+        for location in locations {
+            let encoded = segments.get(&location).await?.bytes().await?;
+            samples.push(self.segment_decompress(segments, topic, encoded).await?);
+        }
+
+        if samples.is_empty() {
+            return Ok(None);
+        }
+
+        let (id, trained) = dictionary::train(&samples, max_size)?;
+        debug!(topic, id, samples = samples.len(), size = trained.len());
+
+        let trained = Arc::new(trained);
+
+        _ = segments
+            .put(
+                &self.segment_dictionary_location(topic, id),
+                PutPayload::from(Bytes::from(trained.to_vec())),
+            )
+            .await?;
+
+        _ = self
+            .dictionaries
+            .lock()
+            .map(|mut dictionaries| dictionaries.insert(id, trained))?;
+
+        _ = segments
+            .put(
+                &self.segment_current_dictionary_location(topic),
+                serde_json::to_vec(&dictionary::Current { id })
+                    .map(Bytes::from)
+                    .map(PutPayload::from)?,
+            )
+            .await?;
+
+        Ok(Some(id))
+    }
+
+    /// Train a dictionary for each topic with segments that has yet to
+    /// have one, when segment compression is enabled.
+    async fn segment_train_untrained(&self) -> Result<()> {
+        let (Some(segments), Some(_)) = (self.segments.as_deref(), self.segment_compression) else {
+            return Ok(());
+        };
+
+        let prefix = Path::from(format!("clusters/{}/topics/", self.cluster));
+
+        for location in segments
+            .list_with_delimiter(Some(&prefix))
+            .await?
+            .common_prefixes
+        {
+            let Some(topic) = location.parts().last().map(|part| part.as_ref().to_owned()) else {
+                continue;
+            };
+
+            if self
+                .segment_current_dictionary(segments, &topic)
+                .await?
+                .is_none()
+            {
+                _ = self
+                    .train_segment_dictionary(
+                        &topic,
+                        SEGMENT_DICTIONARY_SAMPLES,
+                        SEGMENT_DICTIONARY_SIZE,
+                    )
+                    .await
+                    .inspect(|id| debug!(topic, ?id))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn segment_offsets(
+        &self,
+        segments: &DynObjectStore,
+        topition: &Topition,
+    ) -> Result<BTreeSet<i64>> {
+        segments
+            .list(Some(&self.segment_prefix(topition)))
+            .try_collect::<Vec<_>>()
+            .await
+            .map(|metas| {
+                metas
+                    .into_iter()
+                    .filter_map(|meta| {
+                        meta.location
+                            .parts()
+                            .last()
+                            .and_then(|offset| i64::from_str(&offset.as_ref()[0..20]).ok())
+                    })
+                    .collect::<BTreeSet<_>>()
+            })
+            .inspect_err(|err| error!(?err, ?topition))
+            .map_err(Into::into)
+    }
+
+    async fn segment_put(
+        &self,
+        segments: &DynObjectStore,
+        topition: &Topition,
+        mut deflated: deflated::Batch,
+        base_offset: i64,
+    ) -> Result<()> {
+        deflated.base_offset = base_offset;
+
+        let mut encoded = Cursor::new(vec![]);
+        deflated.serialize(&mut Encoder::new(&mut encoded))?;
+
+        let encoded = self
+            .segment_compress(segments, &topition.topic, encoded.into_inner())
+            .await?;
+
+        let location = self.segment_location(topition, base_offset);
+        let payload = PutPayload::from(Bytes::from(encoded));
+
+        match segments
+            .put_opts(
+                &location,
+                payload.clone(),
+                PutOptions {
+                    mode: PutMode::Create,
+                    ..Default::default()
+                },
+            )
+            .await
+        {
+            Ok(put_result) => {
+                debug!(?put_result, %location);
+                Ok(())
+            }
+
+            // the watermark row lock is held until this produce ends, so
+            // a segment at an unallocated offset was left behind by a
+            // produce that rolled back
+            //
+            Err(object_store::Error::AlreadyExists { .. }) => {
+                warn!(%location, "replacing segment of a rolled back produce");
+
+                segments
+                    .put_opts(
+                        &location,
+                        payload,
+                        PutOptions {
+                            mode: PutMode::Overwrite,
+                            ..Default::default()
+                        },
+                    )
+                    .await
+                    .inspect(|put_result| debug!(?put_result, %location))
+                    .inspect_err(|err| error!(?err, %location))
+                    .and(Ok(()))
+                    .map_err(Into::into)
+            }
+
+            Err(err) => {
+                error!(?err, %location);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// The segments covering offset up to the high watermark, limited
+    /// by max bytes while always including the first covering segment.
+    async fn segment_fetch(
+        &self,
+        segments: &DynObjectStore,
+        topition: &Topition,
+        offset: i64,
+        max_bytes: u32,
+        high_watermark: i64,
+    ) -> Result<Vec<deflated::Batch>> {
+        if offset >= high_watermark {
+            return Ok(vec![]);
+        }
+
+        let offsets = self.segment_offsets(segments, topition).await?;
+
+        let start = offsets
+            .range(..=offset)
+            .next_back()
+            .copied()
+            .unwrap_or(offset);
+
+        let mut batches = vec![];
+        let mut bytes = u64::from(max_bytes);
+
+        for base_offset in offsets.range(start..high_watermark) {
+            let location = self.segment_location(topition, *base_offset);
+
+            let get_result = segments
+                .get(&location)
+                .await
+                .inspect_err(|err| error!(?err, %location))?;
+
+            if !batches.is_empty() && get_result.meta.size > bytes {
+                break;
+            }
+
+            bytes = bytes.saturating_sub(get_result.meta.size);
+
+            let encoded = self
+                .segment_decompress(segments, &topition.topic, get_result.bytes().await?)
+                .await?;
+            let mut c = Cursor::new(encoded);
+            let mut decoder = Decoder::new(&mut c);
+
+            let mut batch = deflated::Batch::deserialize(&mut decoder)?;
+            batch.base_offset = *base_offset;
+            batches.push(batch);
+        }
+
+        Ok(batches)
+    }
+
+    /// The base offsets of the segments with every record before offset,
+    /// as a segment is only ever removed whole.
+    async fn segments_before(
+        &self,
+        segments: &DynObjectStore,
+        topition: &Topition,
+        offset: i64,
+        high_watermark: i64,
+    ) -> Result<Vec<i64>> {
+        let mut offsets = self.segment_offsets(segments, topition).await?;
+        _ = offsets.insert(high_watermark);
+
+        Ok(offsets
+            .into_iter()
+            .collect::<Vec<_>>()
+            .windows(2)
+            .filter(|segment| segment[1] <= offset)
+            .map(|segment| segment[0])
+            .collect())
+    }
+
+    /// The base offsets of the segments at or after offset, which must not
+    /// fall within a segment as a segment is only ever removed whole.
+    async fn segments_from(
+        &self,
+        segments: &DynObjectStore,
+        topition: &Topition,
+        offset: i64,
+        high_watermark: i64,
+    ) -> Result<Vec<i64>> {
+        let offsets = self.segment_offsets(segments, topition).await?;
+
+        let end = offsets
+            .range(offset..)
+            .next()
+            .copied()
+            .unwrap_or(high_watermark);
+
+        if offsets.range(..offset).next_back().is_some() && end > offset {
+            debug!(?topition, offset, end);
+            return Err(Error::Api(ErrorCode::InvalidRequest));
+        }
+
+        Ok(offsets.range(offset..).copied().collect())
+    }
+
+    /// The number of records held by these segments, which may be fewer
+    /// than their span of offsets once compacted.
+    async fn segment_record_count(
+        &self,
+        segments: &DynObjectStore,
+        topition: &Topition,
+        base_offsets: &[i64],
+    ) -> Result<i64> {
+        let mut records = 0;
+
+        for base_offset in base_offsets {
+            records += i64::from(
+                self.segment_get(segments, topition, *base_offset)
+                    .await?
+                    .record_count,
+            );
+        }
+
+        Ok(records)
+    }
+
+    async fn segment_get(
+        &self,
+        segments: &DynObjectStore,
+        topition: &Topition,
+        base_offset: i64,
+    ) -> Result<deflated::Batch> {
+        let location = self.segment_location(topition, base_offset);
+
+        let get_result = segments
+            .get(&location)
+            .await
+            .inspect_err(|err| error!(?err, %location))?;
+
+        let encoded = self
+            .segment_decompress(segments, &topition.topic, get_result.bytes().await?)
+            .await?;
+        let mut c = Cursor::new(encoded);
+        let mut decoder = Decoder::new(&mut c);
+
+        let mut batch = deflated::Batch::deserialize(&mut decoder)?;
+        batch.base_offset = base_offset;
+        Ok(batch)
+    }
+
+    /// The offset of the first record at or after a timestamp within the
+    /// segments between the log start and high watermark, or the high
+    /// watermark when there is no such record.
+    async fn segment_offset_for_timestamp(
+        &self,
+        segments: &DynObjectStore,
+        topition: &Topition,
+        timestamp: SystemTime,
+        offset_stage: OffsetStage,
+    ) -> Result<ListOffsetResponse> {
+        debug!(?topition, ?timestamp, ?offset_stage);
+
+        let timestamp = to_timestamp(timestamp)?;
+        let low = offset_stage.log_start();
+        let high = offset_stage.high_watermark();
+
+        for base_offset in self
+            .segment_offsets(segments, topition)
+            .await?
+            .range(..high)
+        {
+            let batch = self.segment_get(segments, topition, *base_offset).await?;
+
+            if batch.max_timestamp < timestamp || batch.max_offset() < low {
+                continue;
+            }
+
+            let batch = inflated::Batch::try_from(batch)?;
+
+            for record in batch.records {
+                let offset = batch.base_offset + i64::from(record.offset_delta);
+                let record_timestamp = batch.base_timestamp + record.timestamp_delta;
+
+                if offset >= low && offset < high && record_timestamp >= timestamp {
+                    return to_system_time(record_timestamp)
+                        .map(|timestamp| ListOffsetResponse {
+                            timestamp: Some(timestamp),
+                            offset: Some(offset),
+                            ..Default::default()
+                        })
+                        .map_err(Into::into);
+                }
+            }
+        }
+
+        Ok(ListOffsetResponse {
+            offset: Some(high),
+            ..Default::default()
+        })
+    }
+
+    async fn segment_delete(
+        &self,
+        segments: &DynObjectStore,
+        topition: &Topition,
+        base_offsets: &[i64],
+    ) -> Result<()> {
+        for base_offset in base_offsets {
+            let location = self.segment_location(topition, *base_offset);
+
+            segments
+                .delete(&location)
+                .await
+                .inspect(|outcome| debug!(?outcome, %location))
+                .inspect_err(|err| error!(?err, %location))?;
+        }
+
+        Ok(())
+    }
+
+    fn idempotent_sequence_check(
+        &self,
+        producer_epoch: &i16,
+        sequence: &i32,
+        batches: &[i32],
+        deflated: &deflated::Batch,
+    ) -> Result<(i32, Vec<i32>)> {
+        debug!(?producer_epoch, ?sequence, ?batches, ?deflated);
+
+        match producer_epoch.cmp(&deflated.producer_epoch) {
+            Ordering::Equal => idempotent_sequence(
+                self.sequence_window,
+                *sequence,
+                batches,
+                deflated.base_sequence,
+            )
+            .map(|batches| (deflated.last_offset_delta + 1, batches)),
+
+            Ordering::Greater => Err(Error::Api(ErrorCode::ProducerFenced)),
+
+            Ordering::Less => Err(Error::Api(ErrorCode::InvalidProducerEpoch)),
+        }
+    }
+
+    async fn idempotent_message_check(
+        &mut self,
+        transaction_id: Option<&str>,
+        topition: &Topition,
+        deflated: &deflated::Batch,
+        tx: &Transaction<'_>,
+    ) -> Result<()> {
+        debug!(transaction_id, ?deflated);
+        if let Some(row) = self
+            .tx_prepare_query_opt(
+                tx,
+                include_sql!("pg/producer_epoch_current_for_producer.sql").as_str(),
+                &[&self.cluster, &deflated.producer_id],
+                "idempotent_message_check",
+            )
+            .await
+            .inspect_err(|err| error!(?err))?
+        {
+            let current_epoch = row
+                .try_get::<_, i16>(0)
+                .inspect_err(|err| error!(self.cluster, deflated.producer_id, ?err))?;
+
+            let Some(row) = self
+                .tx_prepare_query_opt(
+                    tx,
+                    include_sql!("pg/producer_select_for_update.sql").as_str(),
+                    &[
+                        &self.cluster,
+                        &topition.topic(),
+                        &topition.partition(),
+                        &deflated.producer_id,
+                        &deflated.producer_epoch,
+                    ],
+                    "idempotent_message_check",
+                )
+                .await
+                .inspect_err(|err| {
+                    error!(
+                        self.cluster,
+                        ?topition,
+                        deflated.producer_id,
+                        deflated.producer_epoch,
+                        ?err
+                    )
+                })?
+            else {
+                return Err(Error::Api(
+                    match current_epoch.cmp(&deflated.producer_epoch) {
+                        Ordering::Greater => ErrorCode::ProducerFenced,
+                        Ordering::Less => ErrorCode::InvalidProducerEpoch,
+
+                        // the epoch of the producer is current, so it
+                        // is the topition that is unknown
+                        //
+                        Ordering::Equal => ErrorCode::UnknownTopicOrPartition,
+                    },
+                ));
+            };
+
+            let sequence = row.try_get::<_, i32>(0).inspect_err(|err| error!(?err))?;
+            let batches = row
+                .try_get::<_, Vec<i32>>(1)
+                .inspect_err(|err| error!(?err))?;
+
+            debug!(
+                self.cluster,
+                ?topition,
+                deflated.producer_id,
+                deflated.producer_epoch,
+                current_epoch,
+                sequence,
+                ?batches,
+            );
+
+            let (increment, batches) =
+                self.idempotent_sequence_check(&current_epoch, &sequence, &batches, deflated)?;
+
+            debug!(increment, ?batches);
+
+            assert_eq!(
+                1,
+                self.tx_prepare_execute(
+                    tx,
+                    include_sql!("pg/producer_detail_insert.sql").as_str(),
+                    &[
+                        &self.cluster,
+                        &topition.topic(),
+                        &topition.partition(),
+                        &deflated.producer_id,
+                        &deflated.producer_epoch,
+                        &increment,
+                        &batches,
+                    ],
+                    "idempotent_message_check",
+                )
+                .await?
+            );
+
+            Ok(())
+        } else {
+            Err(Error::Api(ErrorCode::UnknownProducerId))
+        }
+    }
+
+    async fn timestamp_type_in_tx(
+        &self,
+        topic: &str,
+        tx: &Transaction<'_>,
+    ) -> Result<TimestampType> {
+        for row in self
+            .tx_prepare_query(
+                tx,
+                include_sql!("pg/topic_configuration_select.sql").as_str(),
+                &[&self.cluster, &topic],
+                "timestamp_type",
+            )
+            .await
+            .inspect_err(|err| error!(?err, topic))?
+        {
+            if row.try_get::<_, String>(0)? == MESSAGE_TIMESTAMP_TYPE {
+                return row
+                    .try_get::<_, Option<String>>(1)
+                    .map(|value| {
+                        value.map_or(TimestampType::CreateTime, |value| {
+                            message_timestamp_type(&value)
+                        })
+                    })
+                    .map_err(Into::into);
+            }
+        }
+
+        Ok(TimestampType::CreateTime)
+    }
+
+    async fn watermark_select_for_update(
+        &mut self,
+        topition: &Topition,
+        tx: &Transaction<'_>,
+    ) -> Result<(Option<i64>, Option<i64>)> {
+        if let Some(row) = self
+            .tx_prepare_query_opt(
+                tx,
+                include_sql!("pg/watermark_select_for_update.sql").as_str(),
+                &[&self.cluster, &topition.topic(), &topition.partition()],
+                "watermark_select_for_update",
+            )
+            .await
+            .inspect_err(|err| error!(?err, cluster = ?self.cluster, ?topition))?
+        {
+            Ok((
+                row.try_get::<_, Option<i64>>(0)
+                    .inspect_err(|err| error!(?err))?,
+                row.try_get::<_, Option<i64>>(1)
+                    .inspect_err(|err| error!(?err))?,
+            ))
+        } else {
+            Err(Error::Api(ErrorCode::UnknownTopicOrPartition))
+        }
+    }
+
+    /// Allocate the offsets of a batch stored as a segment by advancing
+    /// the high watermark, returning the base offset of the batch.
+    /// Offsets remain gapless because the watermark row stays locked
+    /// until the transaction ends, serializing produces to the same
+    /// topition. Records stored in Postgres are allocated and inserted
+    /// by a single statement instead.
+    async fn watermark_allocate(
+        &self,
+        topition: &Topition,
+        offsets: i64,
+        records: i64,
+        tx: &Transaction<'_>,
+    ) -> Result<i64> {
+        self.tx_prepare_query_opt(
+            tx,
+            include_sql!("pg/watermark_allocate.sql").as_str(),
+            &[
+                &self.cluster,
+                &topition.topic(),
+                &topition.partition(),
+                &offsets,
+                &records,
+            ],
+            "watermark_allocate",
+        )
+        .await
+        .inspect_err(|err| error!(?err, cluster = ?self.cluster, ?topition))?
+        .ok_or(Error::Api(ErrorCode::UnknownTopicOrPartition))
+        .and_then(|row| row.try_get::<_, i64>(0).map_err(Into::into))
+    }
+
+    /// The partition assignments of a topic, as stored when the topic
+    /// or partitions were created. Partitions without a current
+    /// assignment, because the brokers have since changed, are spread
+    /// from a start derived from the topic name so that every call
+    /// agrees without writing to the database.
+    async fn partition_assignments(
+        &self,
+        c: &Object,
+        topic: &str,
+        partitions: i32,
+        replication_factor: i32,
+        broker_ids: &[i32],
+    ) -> Result<Vec<MetadataResponsePartition>> {
+        debug!(
+            cluster = self.cluster,
+            topic,
+            partitions,
+            replication_factor,
+            ?broker_ids
+        );
+
+        let mut assignments = BTreeMap::new();
+
+        for row in self
+            .prepare_query(
+                c,
+                include_sql!("pg/topition_assignment_select.sql").as_str(),
+                &[&self.cluster, &topic],
+                "metadata",
+            )
+            .await
+            .inspect_err(|err| error!(?err, topic))?
+        {
+            let partition = row.try_get::<_, i32>(0)?;
+            let leader = row.try_get::<_, i32>(1)?;
+            let replicas = row.try_get::<_, Vec<i32>>(2)?;
+            let isr = row.try_get::<_, Vec<i32>>(3)?;
+
+            // only reassign when the brokers have changed
+            //
+            if broker_ids.contains(&leader)
+                && replicas.iter().all(|replica| broker_ids.contains(replica))
+            {
+                _ = assignments.insert(partition, (leader, replicas, isr));
+            }
+        }
+
+        let start = (default_hash(&topic).as_u128() % broker_ids.len().max(1) as u128) as usize;
+
+        let mut responses = vec![];
+
+        for partition_index in 0..partitions {
+            let (leader_id, replicas, isr) =
+                assignments.remove(&partition_index).unwrap_or_else(|| {
+                    let (leader, replicas) =
+                        round_robin(broker_ids, start, partition_index, replication_factor);
+                    let isr = replicas.clone();
+                    (leader, replicas, isr)
+                });
+
+            responses.push(MetadataResponsePartition {
+                error_code: ErrorCode::None.into(),
+                partition_index,
+                leader_id,
+                leader_epoch: Some(-1),
+                replica_nodes: Some(replicas),
+                isr_nodes: Some(isr),
+                offline_replicas: Some([].into()),
+            });
+        }
+
+        Ok(responses)
+    }
+
+    fn attributes_for_error(
+        &self,
+        nickname: &str,
+        error: &tokio_postgres::error::Error,
+    ) -> Vec<KeyValue> {
+        let mut attributes = vec![
+            KeyValue::new("sql", nickname.to_owned()),
+            KeyValue::new("cluster_id", self.cluster.clone()),
+        ];
+
+        if let Some(db_error) = error.as_db_error() {
+            if let Some(schema) = db_error.schema() {
+                attributes.push(KeyValue::new("schema", schema.to_owned()));
+            }
+
+            if let Some(table) = db_error.table() {
+                attributes.push(KeyValue::new("table", table.to_owned()));
+            }
+
+            if let Some(constraint) = db_error.constraint() {
+                attributes.push(KeyValue::new("constraint", constraint.to_owned()));
+            }
+        }
+
+        if let Some(code) = error.code() {
+            attributes.push(KeyValue::new("code", format!("{code:?}")));
+        }
+
+        attributes
     }
 
     async fn prepare_execute(
@@ -424,6 +1835,8 @@ impl Postgres {
                     ],
                 );
 
+                _ = self.queries.fetch_add(1, atomic::Ordering::Relaxed);
+
                 SQL_REQUESTS.add(
                     1,
                     &[
@@ -463,6 +1876,8 @@ impl Postgres {
                     ],
                 );
 
+                _ = self.queries.fetch_add(1, atomic::Ordering::Relaxed);
+
                 SQL_REQUESTS.add(
                     1,
                     &[
@@ -502,6 +1917,8 @@ impl Postgres {
                     ],
                 );
 
+                _ = self.queries.fetch_add(1, atomic::Ordering::Relaxed);
+
                 SQL_REQUESTS.add(
                     1,
                     &[
@@ -541,6 +1958,8 @@ impl Postgres {
                     ],
                 );
 
+                _ = self.queries.fetch_add(1, atomic::Ordering::Relaxed);
+
                 SQL_REQUESTS.add(
                     1,
                     &[
@@ -580,6 +1999,8 @@ impl Postgres {
                     ],
                 );
 
+                _ = self.queries.fetch_add(1, atomic::Ordering::Relaxed);
+
                 SQL_REQUESTS.add(
                     1,
                     &[
@@ -618,6 +2039,8 @@ impl Postgres {
                     ],
                 );
 
+                _ = self.queries.fetch_add(1, atomic::Ordering::Relaxed);
+
                 SQL_REQUESTS.add(
                     1,
                     &[
@@ -656,6 +2079,8 @@ impl Postgres {
                     ],
                 );
 
+                _ = self.queries.fetch_add(1, atomic::Ordering::Relaxed);
+
                 SQL_REQUESTS.add(
                     1,
                     &[
@@ -694,6 +2119,8 @@ impl Postgres {
                     ],
                 );
 
+                _ = self.queries.fetch_add(1, atomic::Ordering::Relaxed);
+
                 SQL_REQUESTS.add(
                     1,
                     &[
@@ -707,13 +2134,215 @@ impl Postgres {
             })
     }
 
+    /// Insert a validated topic with its partitions, their assignments
+    /// and any configs.
+    async fn create_topic_in_tx(
+        &self,
+        topic: &CreatableTopic,
+        broker_ids: &[i32],
+        tx: &Transaction<'_>,
+    ) -> Result<Uuid> {
+        let topic_uuid = self
+            .tx_prepare_query_one(
+                tx,
+                include_sql!("pg/topic_insert.sql").as_str(),
+                &[
+                    &self.cluster,
+                    &topic.name,
+                    &topic.num_partitions,
+                    &(topic.replication_factor as i32),
+                ],
+                "create_topic",
+            )
+            .await
+            .inspect_err(|err| error!(?err, ?topic))
+            .map(|row| row.get(0))
+            .map_err(|error| violation(error, ErrorCode::TopicAlreadyExists))?;
+
+        debug!(?topic_uuid, cluster = self.cluster, ?topic);
+
+        let start = rng().random_range(0..broker_ids.len());
+
+        for partition in 0..topic.num_partitions {
+            _ = self
+                .tx_prepare_query_one(
+                    tx,
+                    include_sql!("pg/topition_insert.sql").as_str(),
+                    &[&self.cluster, &topic.name, &partition],
+                    "create_topic",
+                )
+                .await?;
+
+            _ = self
+                .tx_prepare_query_one(
+                    tx,
+                    include_sql!("pg/watermark_insert.sql").as_str(),
+                    &[&self.cluster, &topic.name, &partition],
+                    "create_topic",
+                )
+                .await?;
+
+            let (leader, replicas) = topic
+                .assignments
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .find(|assignment| assignment.partition_index == partition)
+                .and_then(|assignment| assignment.broker_ids.clone())
+                .and_then(|replicas| replicas.first().copied().map(|leader| (leader, replicas)))
+                .unwrap_or_else(|| {
+                    round_robin(
+                        broker_ids,
+                        start,
+                        partition,
+                        topic.replication_factor.into(),
+                    )
+                });
+            let isr = replicas.clone();
+
+            _ = self
+                .tx_prepare_execute(
+                    tx,
+                    include_sql!("pg/topition_assignment_upsert.sql").as_str(),
+                    &[
+                        &self.cluster,
+                        &topic.name,
+                        &partition,
+                        &leader,
+                        &replicas,
+                        &isr,
+                    ],
+                    "create_topic",
+                )
+                .await?;
+        }
+
+        if let Some(ref configs) = topic.configs {
+            for config in configs {
+                debug!(?config);
+
+                _ = self
+                    .tx_prepare_execute(
+                        tx,
+                        include_sql!("pg/topic_configuration_upsert.sql").as_str(),
+                        &[
+                            &self.cluster,
+                            &topic.name,
+                            &config.name,
+                            &config.value.as_deref(),
+                        ],
+                        "create_topic",
+                    )
+                    .await
+                    .inspect_err(|err| error!(?err, ?config));
+            }
+        }
+
+        Ok(topic_uuid)
+    }
+
+    /// Produce a batch within the producing transaction, creating its
+    /// topic on the first produce to an unknown topic when topics are
+    /// automatically created.
+    async fn produce_or_create_in_tx(
+        &mut self,
+        transaction_id: Option<&str>,
+        topition: &Topition,
+        deflated: deflated::Batch,
+        tx: &mut Transaction<'_>,
+    ) -> Result<Produced> {
+        let retry = self.auto_create_topics.then(|| deflated.clone());
+
+        match (
+            self.produce_in_tx(transaction_id, topition, deflated, tx)
+                .await,
+            retry,
+        ) {
+            (Err(Error::Api(ErrorCode::UnknownTopicOrPartition)), Some(deflated)) => {
+                self.auto_create_topic(topition, tx).await?;
+
+                self.produce_in_tx(transaction_id, topition, deflated, tx)
+                    .await
+            }
+
+            (otherwise, _) => otherwise,
+        }
+    }
+
+    /// Create the unknown topic of a produce, within the producing
+    /// transaction.
+    async fn auto_create_topic(
+        &mut self,
+        topition: &Topition,
+        tx: &mut Transaction<'_>,
+    ) -> Result<()> {
+        // only a missing topic is created, not a missing partition
+        //
+        if self
+            .tx_prepare_query_opt(
+                tx,
+                include_sql!("pg/topic_select_name.sql").as_str(),
+                &[&self.cluster, &topition.topic()],
+                "produce",
+            )
+            .await?
+            .is_some()
+        {
+            return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
+        }
+
+        let broker_ids = self
+            .brokers()
+            .await?
+            .into_iter()
+            .map(|broker| broker.broker_id)
+            .collect::<Vec<_>>();
+
+        let topic = replication_factor(
+            CreatableTopic {
+                name: topition.topic().into(),
+                num_partitions: self.num_partitions,
+                replication_factor: -1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            broker_ids.len(),
+        )?;
+
+        validate_topic(&topic, self.schemas.as_ref()).await?;
+
+        if topition.partition() >= topic.num_partitions {
+            return Err(Error::Api(ErrorCode::UnknownTopicOrPartition));
+        }
+
+        // a concurrent produce may create the same topic
+        //
+        let savepoint = tx.savepoint("auto_create_topic").await?;
+
+        match self
+            .create_topic_in_tx(&topic, &broker_ids, &savepoint)
+            .await
+        {
+            Ok(topic_uuid) => {
+                debug!(?topic_uuid, ?topition, "auto created");
+                savepoint.commit().await.map_err(Into::into)
+            }
+
+            Err(Error::Api(ErrorCode::TopicAlreadyExists)) => {
+                savepoint.rollback().await.map_err(Into::into)
+            }
+
+            Err(err) => Err(err),
+        }
+    }
+
     async fn produce_in_tx(
         &mut self,
         transaction_id: Option<&str>,
         topition: &Topition,
         deflated: deflated::Batch,
         tx: &Transaction<'_>,
-    ) -> Result<i64> {
+    ) -> Result<Produced> {
         debug!(cluster = ?self.cluster, ?transaction_id, ?topition, ?deflated);
 
         let topic = topition.topic();
@@ -725,11 +2354,19 @@ impl Postgres {
                 .inspect_err(|err| error!(?err))?;
         }
 
-        let (low, high) = self.watermark_select_for_update(topition, tx).await?;
+        let timestamp_type = self.timestamp_type_in_tx(topic, tx).await?;
 
-        debug!(?low, ?high);
+        let mut segment = self.segments.as_ref().map(|_| deflated.clone());
 
-        let inflated = inflated::Batch::try_from(deflated).inspect_err(|err| error!(?err))?;
+        let mut inflated = inflated::Batch::try_from(deflated).inspect_err(|err| error!(?err))?;
+
+        if timestamp_type == TimestampType::LogAppendTime {
+            inflated = log_append_time(inflated, SystemTime::now())?;
+
+            if segment.is_some() {
+                segment = deflated::Batch::try_from(inflated.clone()).map(Some)?;
+            }
+        }
 
         let attributes = BatchAttribute::try_from(inflated.attributes)?;
 
@@ -741,83 +2378,111 @@ impl Postgres {
 
         let last_offset_delta = i64::from(inflated.last_offset_delta);
 
-        for (delta, record) in inflated.records.iter().enumerate() {
-            let delta = i64::try_from(delta)?;
-            let offset = high.unwrap_or_default() + delta;
-            let key = record.key.as_deref();
-            let value = record.value.as_deref();
+        let producer_id = transaction_id.map(|_| inflated.producer_id);
+        let producer_epoch = transaction_id.map(|_| inflated.producer_epoch);
 
-            debug!(?delta, ?record, ?offset);
+        let timestamps = inflated
+            .records
+            .iter()
+            .map(|record| {
+                if attributes.timestamp == TimestampType::LogAppendTime {
+                    to_system_time(inflated.max_timestamp)
+                } else {
+                    to_system_time(inflated.base_timestamp + record.timestamp_delta)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // the batch is fully prepared before allocating its offsets,
+        // holding the watermark row lock only while it is inserted
+        //
+        let high = if segment.is_none() {
+            let mut deltas = vec![];
+            let mut keys = vec![];
+            let mut values = vec![];
+            let mut header_deltas = vec![];
+            let mut header_keys = vec![];
+            let mut header_values = vec![];
+
+            for (delta, record) in inflated.records.iter().enumerate() {
+                let delta = i64::try_from(delta)?;
+
+                deltas.push(delta);
+                keys.push(record.key.as_deref());
+                values.push(record.value.as_deref());
+
+                for header in &record.headers {
+                    header_deltas.push(delta);
+                    header_keys.push(header.key.as_deref());
+                    header_values.push(header.value.as_deref());
+                }
+            }
 
-            _ = self
-                .tx_prepare_execute(
-                    tx,
-                    include_sql!("pg/record_insert.sql").as_str(),
-                    &[
-                        &self.cluster,
-                        &topic,
-                        &partition,
-                        &offset,
-                        &inflated.attributes,
-                        &if transaction_id.is_none() {
-                            None
-                        } else {
-                            Some(inflated.producer_id)
-                        },
-                        &if transaction_id.is_none() {
-                            None
-                        } else {
-                            Some(inflated.producer_epoch)
-                        },
-                        &(to_system_time(inflated.base_timestamp + record.timestamp_delta)?),
-                        &key,
-                        &value,
-                    ],
-                    "produce_in_tx",
-                )
-                .await
-                .inspect_err(|err| error!(?err, ?topic, ?partition, ?offset, ?key, ?value))
-                .map_err(|error| {
-                    if let Some(db_error) = error.as_db_error() {
-                        debug!(
-                            schema = db_error.schema(),
-                            table = db_error.table(),
-                            constraint = db_error.constraint()
-                        );
-                    }
+            self.tx_prepare_query_opt(
+                tx,
+                include_sql!("pg/record_produce.sql").as_str(),
+                &[
+                    &self.cluster,
+                    &topic,
+                    &partition,
+                    &(last_offset_delta + 1),
+                    &i64::try_from(inflated.records.len())?,
+                    &inflated.attributes,
+                    &producer_id,
+                    &producer_epoch,
+                    &deltas,
+                    &timestamps,
+                    &keys,
+                    &values,
+                    &header_deltas,
+                    &header_keys,
+                    &header_values,
+                ],
+                "produce_in_tx",
+            )
+            .await
+            .inspect_err(|err| error!(?err, ?topic, ?partition))
+            .map_err(|error| violation(error, ErrorCode::UnknownServerError))?
+            .ok_or(Error::Api(ErrorCode::UnknownTopicOrPartition))
+            .and_then(|row| row.try_get::<_, i64>(0).map_err(Into::into))?
+        } else {
+            // records are stored as segment objects when configured
+            //
+            self.watermark_allocate(
+                topition,
+                last_offset_delta + 1,
+                i64::try_from(inflated.records.len())?,
+                tx,
+            )
+            .await?
+        };
 
-                    if error
-                        .code()
-                        .is_some_and(|code| *code == SqlState::UNIQUE_VIOLATION)
-                    {
-                        Error::Api(ErrorCode::UnknownServerError)
-                    } else {
-                        error.into()
-                    }
-                })?;
+        debug!(high);
 
-            for header in record.headers.iter().as_ref() {
-                let key = header.key.as_deref();
-                let value = header.value.as_deref();
+        // the records as stored, which are cached once committed
+        //
+        let mut stored = vec![];
 
-                _ = self
-                    .tx_prepare_execute(
-                        tx,
-                        include_sql!("pg/header_insert.sql").as_str(),
-                        &[&self.cluster, &topic, &partition, &offset, &key, &value],
-                        "produce_in_tx",
-                    )
-                    .await
-                    .inspect_err(|err| {
-                        error!(?err, ?topic, ?partition, ?offset, ?key, ?value);
-                    });
+        if segment.is_none() && self.cache.is_some() {
+            for ((delta, record), timestamp) in inflated.records.iter().enumerate().zip(timestamps)
+            {
+                stored.push(Stored {
+                    offset: high + i64::try_from(delta)?,
+                    attributes: inflated.attributes,
+                    producer_id: producer_id.unwrap_or(-1),
+                    producer_epoch: producer_epoch.unwrap_or(-1),
+                    timestamp: to_timestamp(timestamp)?,
+                    key: record.key.clone(),
+                    value: record.value.clone(),
+                    headers: record.headers.clone(),
+                });
             }
         }
 
         if let Some(transaction_id) = transaction_id {
             if attributes.transaction {
-                let offset_start = high.unwrap_or_default();
-                let offset_end = high.map_or(last_offset_delta, |high| high + last_offset_delta);
+                let offset_start = high;
+                let offset_end = high + last_offset_delta;
 
                 _ = self
                     .tx_prepare_execute(tx,
@@ -840,46 +2505,63 @@ impl Postgres {
             }
         }
 
-        _ = self
-            .tx_prepare_execute(
-                tx,
-                include_sql!("pg/watermark_update.sql").as_str(),
-                &[
-                    &self.cluster,
-                    &topic,
-                    &partition,
-                    &low.unwrap_or_default(),
-                    &high.map_or(last_offset_delta + 1, |high| high + last_offset_delta + 1),
-                ],
-                "produce_in_tx",
+        if let (Some(segments), Some(segment)) = (self.segments.as_deref(), segment) {
+            self.segment_put(segments, topition, segment, high).await?;
+        }
+
+        // records in a transaction are exported once it commits
+        //
+        let export = (self.lake.is_some()
+            && self.schemas.is_some()
+            && !attributes.control
+            && !attributes.transaction)
+            .then(|| {
+                inflated.base_offset = high;
+                inflated
+            });
+
+        Ok(Produced {
+            high,
+            stored,
+            export,
+        })
+    }
+
+    /// Fence a transactional producer that isn't using the current
+    /// epoch of its transaction.
+    async fn txn_producer_check(
+        &self,
+        transaction_id: &str,
+        producer_id: i64,
+        producer_epoch: i16,
+        tx: &Transaction<'_>,
+    ) -> Result<ErrorCode> {
+        let Some(row) = self
+            .tx_prepare_query_opt(
+                tx,
+                include_sql!("pg/producer_epoch_for_current_txn.sql").as_str(),
+                &[&self.cluster, &transaction_id],
+                "txn_producer_check",
             )
             .await
-            .inspect(|n| debug!(?n))
-            .inspect_err(|err| error!(?err))?;
+            .inspect_err(|err| error!(?err))?
+        else {
+            return Ok(ErrorCode::TransactionalIdNotFound);
+        };
 
-        if !attributes.control {
-            if let Some(ref registry) = self.schemas {
-                if let Some(ref lake) = self.lake {
-                    if let Some(record_batch) =
-                        registry.as_arrow(topition.topic(), topition.partition(), &inflated)?
-                    {
-                        let config = self
-                            .describe_config(topition.topic(), ConfigResource::Topic, None)
-                            .await?;
+        let current_id = row.try_get::<_, i64>(0)?;
+        let current_epoch = row.try_get::<_, i16>(1)?;
 
-                        lake.store(
-                            topition.topic(),
-                            topition.partition(),
-                            high.unwrap_or_default(),
-                            record_batch,
-                            config,
-                        )
-                        .await?;
-                    }
-                }
-            }
-        }
-        Ok(high.unwrap_or_default())
+        debug!(
+            transaction_id,
+            producer_id, producer_epoch, current_id, current_epoch
+        );
+
+        Ok(verify_producer_epoch(
+            (current_id, current_epoch),
+            producer_id,
+            producer_epoch,
+        ))
     }
 
     async fn end_in_tx(
@@ -889,10 +2571,12 @@ impl Postgres {
         producer_epoch: i16,
         committed: bool,
         tx: &Transaction<'_>,
-    ) -> Result<ErrorCode> {
+    ) -> Result<TxnEnd> {
         debug!(cluster = ?self.cluster, ?transaction_id, ?producer_id, ?producer_epoch, ?committed);
 
         let mut overlaps = vec![];
+        let mut marked = vec![];
+        let mut stable = vec![];
 
         let rows = self
             .tx_prepare_query(
@@ -944,10 +2628,13 @@ impl Postgres {
 
             let offset = self
                 .produce_in_tx(Some(transaction_id), &topition, batch, tx)
-                .await?;
+                .await
+                .map(|produced| produced.high)?;
 
             debug!(offset, ?topition);
 
+            marked.push(topition);
+
             let row = self
                 .tx_prepare_query_one(
                     tx,
@@ -1015,6 +2702,36 @@ impl Postgres {
             for txn in txns {
                 debug!(?txn);
 
+                if txn.status == TxnState::PrepareCommit
+                    && self.lake.is_some()
+                    && self.schemas.is_some()
+                {
+                    for row in self
+                        .tx_prepare_query(
+                            tx,
+                            include_sql!("pg/txn_produce_offset_select_by_txn.sql").as_str(),
+                            &[
+                                &self.cluster,
+                                &txn.name,
+                                &txn.producer_id,
+                                &txn.producer_epoch,
+                            ],
+                            "end_in_tx",
+                        )
+                        .await?
+                    {
+                        stable.push(TxnOffsets {
+                            topition: Topition::new(
+                                row.try_get::<_, String>(0)?,
+                                row.try_get::<_, i32>(1)?,
+                            ),
+                            producer_id: txn.producer_id,
+                            offset_start: row.try_get::<_, i64>(2)?,
+                            offset_end: row.try_get::<_, i64>(3)?,
+                        });
+                    }
+                }
+
                 _ = self
                     .tx_prepare_execute(
                         tx,
@@ -1140,12 +2857,109 @@ impl Postgres {
             })?;
         }
 
-        Ok(ErrorCode::None)
+        Ok(TxnEnd {
+            error_code: ErrorCode::None,
+            marked,
+            committed: stable,
+        })
+    }
+
+    /// Wake any fetch waiting on a topition marked by the ended
+    /// transaction, exporting the records of transactions that have
+    /// committed to the lake.
+    async fn txn_ended(&mut self, ended: &TxnEnd) {
+        for topition in &ended.marked {
+            _ = self
+                .notify_produced(topition)
+                .inspect_err(|err| error!(?err, ?topition));
+        }
+
+        for txn in &ended.committed {
+            let batches = Scan::new(txn.topition.clone())
+                .offset(txn.offset_start)
+                .end(txn.offset_end + 1)
+                .isolation(IsolationLevel::ReadUncommitted)
+                .batches(self)
+                .await
+                .inspect_err(|err| error!(?err, ?txn));
+
+            if let Ok(batches) = batches {
+                let mut exported = vec![];
+
+                for batch in batches {
+                    if batch.producer_id == txn.producer_id
+                        && BatchAttribute::try_from(batch.attributes)
+                            .is_ok_and(|attributes| attributes.transaction)
+                    {
+                        exported.push(batch);
+                    }
+                }
+
+                self.lake_store(&txn.topition, exported).await;
+            }
+        }
+    }
+
+    /// Export a committed batch to the lake.
+    async fn lake_export(&mut self, topition: &Topition, batch: Option<inflated::Batch>) {
+        if let Some(batch) = batch {
+            self.lake_store(topition, vec![batch]).await;
+        }
+    }
+
+    /// Store committed batches in the lake. The records have already
+    /// been committed, so a failure is logged rather than failing the
+    /// request.
+    async fn lake_store(&mut self, topition: &Topition, batches: Vec<inflated::Batch>) {
+        let (Some(registry), Some(lake)) = (self.schemas.clone(), self.lake.clone()) else {
+            return;
+        };
+
+        if batches.is_empty() {
+            return;
+        }
+
+        let config = match self
+            .describe_config(topition.topic(), ConfigResource::Topic, None)
+            .await
+        {
+            Ok(config) => config,
+            Err(err) => {
+                error!(?err, ?topition);
+                return;
+            }
+        };
+
+        for batch in batches {
+            let base_offset = batch.base_offset;
+
+            if let Err(err) = async {
+                if let Some(record_batch) =
+                    registry.as_arrow(topition.topic(), topition.partition(), &batch)?
+                {
+                    lake.store(
+                        topition.topic(),
+                        topition.partition(),
+                        base_offset,
+                        record_batch,
+                        config.clone(),
+                    )
+                    .await?;
+                }
+
+                Ok::<(), Error>(())
+            }
+            .await
+            {
+                error!(?err, ?topition, base_offset);
+            }
+        }
     }
 }
 
 #[async_trait]
 impl Storage for Postgres {
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
     async fn register_broker(
         &mut self,
         broker_registration: BrokerRegistrationRequest,
@@ -1168,14 +2982,22 @@ impl Storage for Postgres {
                 "register_broker",
             )
             .await
-            .inspect(|n| debug!(cluster = self.cluster, n))?;
+            .inspect(|n| debug!(cluster = self.cluster, n))
+            .map_err(|error| violation(error, ErrorCode::DuplicateResource))?;
 
         Ok(())
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
     async fn brokers(&mut self) -> Result<Vec<DescribeClusterBroker>> {
         debug!(cluster = self.cluster);
 
+        // the brokers are from configuration rather than a connection
+        //
+        if self.pool.is_closed() {
+            return Err(Error::Closed);
+        }
+
         let broker_id = self.node;
         let host = self
             .advertised_listener
@@ -1193,55 +3015,137 @@ impl Storage for Postgres {
         }])
     }
 
+    #[instrument(
+        skip_all,
+        fields(
+            cluster = %self.cluster,
+            topic = %topic.name,
+            validate_only = validate_only,
+        )
+    )]
     async fn create_topic(&mut self, topic: CreatableTopic, validate_only: bool) -> Result<Uuid> {
         debug!(cluster = self.cluster, ?topic, validate_only);
 
+        let broker_ids = self
+            .brokers()
+            .await?
+            .into_iter()
+            .map(|broker| broker.broker_id)
+            .collect::<Vec<_>>();
+
+        let topic = assign_replicas(topic, &broker_ids)
+            .map(|topic| num_partitions(topic, self.num_partitions))
+            .and_then(|topic| replication_factor(topic, broker_ids.len()))?;
+
+        validate_topic(&topic, self.schemas.as_ref()).await?;
+
         let mut c = self.connection().await?;
+
+        if validate_only {
+            return if self
+                .prepare_query_opt(
+                    &c,
+                    include_sql!("pg/topic_select_name.sql").as_str(),
+                    &[&self.cluster, &topic.name],
+                    "create_topic",
+                )
+                .await
+                .inspect_err(|err| error!(?err, ?topic))?
+                .is_some()
+            {
+                Err(Error::Api(ErrorCode::TopicAlreadyExists))
+            } else {
+                // no topic id is assigned when only validating
+                //
+                Ok(Uuid::nil())
+            };
+        }
+
         let tx = c.transaction().await?;
 
-        let topic_uuid = self
-            .tx_prepare_query_one(
-                &tx,
-                include_sql!("pg/topic_insert.sql").as_str(),
-                &[
-                    &self.cluster,
-                    &topic.name,
-                    &topic.num_partitions,
-                    &(topic.replication_factor as i32),
-                ],
-                "create_topic",
-            )
-            .await
-            .inspect_err(|err| error!(?err, ?topic, ?validate_only))
-            .map(|row| row.get(0))
-            .map_err(|error| {
-                if let Some(db_error) = error.as_db_error() {
-                    debug!(
-                        schema = db_error.schema(),
-                        table = db_error.table(),
-                        constraint = db_error.constraint()
-                    );
-                }
+        let topic_uuid = self.create_topic_in_tx(&topic, &broker_ids, &tx).await?;
 
-                if error
-                    .code()
-                    .is_some_and(|code| *code == SqlState::UNIQUE_VIOLATION)
-                {
-                    Error::Api(ErrorCode::TopicAlreadyExists)
-                } else {
-                    error.into()
-                }
-            })?;
+        tx.commit().await.inspect_err(|err| error!(?err))?;
 
-        debug!(?topic_uuid, cluster = self.cluster, ?topic);
+        Ok(topic_uuid)
+    }
 
-        for partition in 0..topic.num_partitions {
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
+    async fn create_partitions(
+        &mut self,
+        topic: &TopicId,
+        new_count: i32,
+        assignments: Option<&[CreatePartitionsAssignment]>,
+    ) -> Result<ErrorCode> {
+        debug!(cluster = self.cluster, ?topic, new_count, ?assignments);
+
+        let broker_ids = self
+            .brokers()
+            .await?
+            .into_iter()
+            .map(|broker| broker.broker_id)
+            .collect::<Vec<_>>();
+
+        let mut c = self.connection().await?;
+        let tx = c.transaction().await?;
+
+        let row = match topic {
+            TopicId::Id(id) => {
+                self.tx_prepare_query_opt(
+                    &tx,
+                    include_sql!("pg/topic_select_uuid.sql").as_str(),
+                    &[&self.cluster, &id],
+                    "create_partitions",
+                )
+                .await?
+            }
+
+            TopicId::Name(name) => {
+                self.tx_prepare_query_opt(
+                    &tx,
+                    include_sql!("pg/topic_select_name.sql").as_str(),
+                    &[&self.cluster, name],
+                    "create_partitions",
+                )
+                .await?
+            }
+        };
+
+        let Some(row) = row else {
+            return Ok(ErrorCode::UnknownTopicOrPartition);
+        };
+
+        let topic_name = row.try_get::<_, String>(1)?;
+        let partitions = row.try_get::<_, i32>(3)?;
+        let replication_factor = row.try_get::<_, i32>(4)?;
+
+        // as with Kafka, the partition count must increase
+        //
+        if new_count <= partitions {
+            return Ok(ErrorCode::InvalidPartitions);
+        }
+
+        if assignments.is_some_and(|assignments| {
+            assignments.len() != (new_count - partitions) as usize
+                || assignments.iter().any(|assignment| {
+                    assignment
+                        .broker_ids
+                        .as_deref()
+                        .is_none_or(<[i32]>::is_empty)
+                })
+        }) {
+            return Ok(ErrorCode::InvalidReplicaAssignment);
+        }
+
+        let start = rng().random_range(0..broker_ids.len().max(1));
+
+        for partition in partitions..new_count {
             _ = self
                 .tx_prepare_query_one(
                     &tx,
                     include_sql!("pg/topition_insert.sql").as_str(),
-                    &[&self.cluster, &topic.name, &partition],
-                    "create_topic",
+                    &[&self.cluster, &topic_name, &partition],
+                    "create_partitions",
                 )
                 .await?;
 
@@ -1249,158 +3153,177 @@ impl Storage for Postgres {
                 .tx_prepare_query_one(
                     &tx,
                     include_sql!("pg/watermark_insert.sql").as_str(),
-                    &[&self.cluster, &topic.name, &partition],
-                    "create_topic",
+                    &[&self.cluster, &topic_name, &partition],
+                    "create_partitions",
                 )
                 .await?;
-        }
 
-        if let Some(configs) = topic.configs {
-            for config in configs {
-                debug!(?config);
+            let (leader, replicas) = assignments
+                .and_then(|assignments| assignments.get((partition - partitions) as usize))
+                .and_then(|assignment| assignment.broker_ids.clone())
+                .and_then(|replicas| replicas.first().copied().map(|leader| (leader, replicas)))
+                .unwrap_or_else(|| round_robin(&broker_ids, start, partition, replication_factor));
+            let isr = replicas.clone();
 
-                _ = self
-                    .tx_prepare_execute(
-                        &tx,
-                        include_sql!("pg/topic_configuration_upsert.sql").as_str(),
-                        &[
-                            &self.cluster,
-                            &topic.name,
-                            &config.name,
-                            &config.value.as_deref(),
-                        ],
-                        "create_topic",
-                    )
-                    .await
-                    .inspect_err(|err| error!(?err, ?config));
-            }
+            _ = self
+                .tx_prepare_execute(
+                    &tx,
+                    include_sql!("pg/topition_assignment_upsert.sql").as_str(),
+                    &[
+                        &self.cluster,
+                        &topic_name,
+                        &partition,
+                        &leader,
+                        &replicas,
+                        &isr,
+                    ],
+                    "create_partitions",
+                )
+                .await?;
         }
 
+        _ = self
+            .tx_prepare_execute(
+                &tx,
+                include_sql!("pg/topic_update_partitions.sql").as_str(),
+                &[&self.cluster, &topic_name, &new_count],
+                "create_partitions",
+            )
+            .await?;
+
         tx.commit().await.inspect_err(|err| error!(?err))?;
 
-        Ok(topic_uuid)
+        Ok(ErrorCode::None)
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
     async fn delete_records(
         &mut self,
         topics: &[DeleteRecordsTopic],
     ) -> Result<Vec<DeleteRecordsTopicResult>> {
         debug!(cluster = self.cluster, ?topics);
 
-        let c = self.connection().await?;
-
-        let delete_records = c
-            .prepare(concat!(
-                "delete from record",
-                " using topic, cluster",
-                " where",
-                " cluster.name=$1",
-                " and topic.name = $2",
-                " and record.partition = $3",
-                " and record.id >= $4",
-                " and topic.cluster = cluster.id",
-                " and record.topic = topic.id",
-            ))
-            .await
-            .inspect_err(|err| error!(?err, ?topics))?;
+        let mut c = self.connection().await?;
+        let tx = c.transaction().await?;
 
         let mut responses = vec![];
+        let mut truncated = vec![];
+        let mut removed = vec![];
 
         for topic in topics {
             let mut partition_responses = vec![];
 
-            if let Some(ref partitions) = topic.partitions {
-                for partition in partitions {
-                    _ = c
-                        .execute(
-                            &delete_records,
+            for partition in topic.partitions.as_deref().unwrap_or_default() {
+                let topition = Topition::new(topic.name.clone(), partition.partition_index);
+
+                let (low, high) = match self.watermark_select_for_update(&topition, &tx).await {
+                    Ok((low, high)) => (low.unwrap_or_default(), high.unwrap_or_default()),
+
+                    Err(Error::Api(error_code)) => {
+                        partition_responses.push(DeleteRecordsPartitionResult {
+                            partition_index: partition.partition_index,
+                            low_watermark: -1,
+                            error_code: error_code.into(),
+                        });
+
+                        continue;
+                    }
+
+                    Err(otherwise) => return Err(otherwise),
+                };
+
+                let offset = if partition.offset == -1 {
+                    high
+                } else {
+                    partition.offset
+                };
+
+                debug!(?topition, low, high, offset);
+
+                if offset < 0 || offset > high {
+                    partition_responses.push(DeleteRecordsPartitionResult {
+                        partition_index: partition.partition_index,
+                        low_watermark: low,
+                        error_code: ErrorCode::OffsetOutOfRange.into(),
+                    });
+
+                    continue;
+                }
+
+                if offset > low {
+                    _ = self
+                        .tx_prepare_execute(
+                            &tx,
+                            include_sql!("pg/header_delete_before.sql").as_str(),
                             &[
                                 &self.cluster,
                                 &topic.name,
                                 &partition.partition_index,
-                                &partition.offset,
+                                &offset,
                             ],
+                            "delete_records",
                         )
                         .await
-                        .inspect_err(|err| {
-                            let cluster = self.cluster.as_str();
-                            let topic = topic.name.as_str();
-                            let partition_index = partition.partition_index;
-                            let offset = partition.offset;
-
-                            error!(?err, ?cluster, ?topic, ?partition_index, ?offset)
-                        })?;
+                        .inspect_err(|err| error!(?err, ?topition, offset))?;
 
-                    let prepared = c
-                        .prepare(concat!(
-                            "select",
-                            " id as offset",
-                            " from",
-                            " record",
-                            " join (",
-                            " select",
-                            " coalesce(min(record.id), (select last_value from record_id_seq)) as offset",
-                            " from record, topic, cluster",
-                            " where",
-                            " topic.cluster = cluster.id",
-                            " and cluster.name = $1",
-                            " and topic.name = $2",
-                            " and record.partition = $3",
-                            " and record.topic = topic.id) as minimum",
-                            " on record.id = minimum.offset",
-                        ))
+                    let deleted = self
+                        .tx_prepare_execute(
+                            &tx,
+                            include_sql!("pg/record_delete_before.sql").as_str(),
+                            &[
+                                &self.cluster,
+                                &topic.name,
+                                &partition.partition_index,
+                                &offset,
+                            ],
+                            "delete_records",
+                        )
                         .await
-                        .inspect_err(|err| {
-                            let cluster = self.cluster.as_str();
-                            let topic = topic.name.as_str();
-                            let partition_index = partition.partition_index;
-                            let offset = partition.offset;
+                        .inspect(|deleted| debug!(?topition, offset, deleted))
+                        .inspect_err(|err| error!(?err, ?topition, offset))
+                        .map_err(Error::from)
+                        .and_then(|deleted| i64::try_from(deleted).map_err(Into::into))?;
 
-                            error!(?err, ?cluster, ?topic, ?partition_index, ?offset)
-                        })?;
+                    let deleted = if let Some(segments) = self.segments.as_deref() {
+                        let base_offsets = self
+                            .segments_before(segments, &topition, offset, high)
+                            .await?;
+
+                        let deleted = self
+                            .segment_record_count(segments, &topition, &base_offsets)
+                            .await?;
+
+                        removed.push((topition.clone(), base_offsets));
+
+                        deleted
+                    } else {
+                        deleted
+                    };
 
-                    let partition_result = c
-                        .query_opt(
-                            &prepared,
-                            &[&self.cluster, &topic.name, &partition.partition_index],
+                    _ = self
+                        .tx_prepare_execute(
+                            &tx,
+                            include_sql!("pg/watermark_update_low.sql").as_str(),
+                            &[
+                                &self.cluster,
+                                &topic.name,
+                                &partition.partition_index,
+                                &offset,
+                                &deleted,
+                            ],
+                            "delete_records",
                         )
                         .await
-                        .inspect_err(|err| {
-                            let cluster = self.cluster.as_str();
-                            let topic = topic.name.as_str();
-                            let partition_index = partition.partition_index;
-                            let offset = partition.offset;
+                        .inspect_err(|err| error!(?err, ?topition, offset))?;
+                }
 
-                            error!(?err, ?cluster, ?topic, ?partition_index, ?offset)
-                        })
-                        .map_or(
-                            Ok(DeleteRecordsPartitionResult {
-                                partition_index: partition.partition_index,
-                                low_watermark: 0,
-                                error_code: ErrorCode::UnknownServerError.into(),
-                            }),
-                            |row| {
-                                row.map_or(
-                                    Ok(DeleteRecordsPartitionResult {
-                                        partition_index: partition.partition_index,
-                                        low_watermark: 0,
-                                        error_code: ErrorCode::UnknownServerError.into(),
-                                    }),
-                                    |row| {
-                                        row.try_get::<_, i64>(0).map(|low_watermark| {
-                                            DeleteRecordsPartitionResult {
-                                                partition_index: partition.partition_index,
-                                                low_watermark,
-                                                error_code: ErrorCode::None.into(),
-                                            }
-                                        })
-                                    },
-                                )
-                            },
-                        )?;
+                partition_responses.push(DeleteRecordsPartitionResult {
+                    partition_index: partition.partition_index,
+                    low_watermark: offset.max(low),
+                    error_code: ErrorCode::None.into(),
+                });
 
-                    partition_responses.push(partition_result);
-                }
+                truncated.push(topition);
             }
 
             responses.push(DeleteRecordsTopicResult {
@@ -1408,119 +3331,182 @@ impl Storage for Postgres {
                 partitions: Some(partition_responses),
             });
         }
+
+        tx.commit().await?;
+
+        // segments are only removed once the deletion has committed
+        //
+        if let Some(segments) = self.segments.as_deref() {
+            for (topition, base_offsets) in removed {
+                self.segment_delete(segments, &topition, &base_offsets)
+                    .await?;
+            }
+        }
+
+        for topition in truncated {
+            self.cache_invalidate(&topition)?;
+        }
+
         Ok(responses)
     }
 
-    async fn delete_topic(&mut self, topic: &TopicId) -> Result<ErrorCode> {
-        debug!(cluster = self.cluster, ?topic);
+    #[instrument(
+        skip_all,
+        fields(
+            cluster = %self.cluster,
+            topic = topition.topic(),
+            partition = topition.partition(),
+            offset = offset,
+        )
+    )]
+    async fn truncate(&mut self, topition: &Topition, offset: i64) -> Result<i64> {
+        debug!(cluster = self.cluster, ?topition, offset);
 
         let mut c = self.connection().await?;
         let tx = c.transaction().await?;
 
-        let row = match topic {
-            TopicId::Id(id) => {
-                self.tx_prepare_query_opt(
-                    &tx,
-                    include_sql!("pg/topic_select_uuid.sql").as_str(),
-                    &[&self.cluster, &id],
-                    "delete_topic",
-                )
-                .await?
-            }
+        let (low, high) = self
+            .watermark_select_for_update(topition, &tx)
+            .await
+            .map(|(low, high)| (low.unwrap_or_default(), high.unwrap_or_default()))?;
 
-            TopicId::Name(name) => {
-                self.tx_prepare_query_opt(
-                    &tx,
-                    include_sql!("pg/topic_select_name.sql").as_str(),
-                    &[&self.cluster, name],
-                    "delete_topic",
-                )
-                .await?
-            }
-        };
+        debug!(?topition, low, high, offset);
 
-        let Some(row) = row else {
-            return Ok(ErrorCode::UnknownTopicOrPartition);
-        };
+        if offset < low || offset > high {
+            return Err(Error::Api(ErrorCode::OffsetOutOfRange));
+        }
 
-        let topic_name = row.try_get::<_, String>(1)?;
+        if offset == high {
+            return Ok(high);
+        }
 
-        for (description, sql, nickname) in [
-            (
-                "consumer_offsets",
-                include_sql!("pg/consumer_offset_delete_by_topic.sql"),
-                "delete_topic",
-            ),
-            (
-                "topic_configuration",
-                include_sql!("pg/topic_configuration_delete_by_topic.sql"),
-                "delete_topic",
-            ),
-            (
-                "watermarks",
-                include_sql!("pg/watermark_delete_by_topic.sql"),
-                "delete_topic",
-            ),
-            (
-                "headers",
-                include_sql!("pg/header_delete_by_topic.sql"),
-                "delete_topic",
-            ),
-            (
-                "records",
-                include_sql!("pg/record_delete_by_topic.sql"),
-                "delete_topic",
-            ),
-            (
-                "txn_offset_commit_tp",
-                include_sql!("pg/txn_offset_commit_tp_delete_by_topic.sql"),
-                "delete_topic",
-            ),
-            (
-                "txn_produce_offset_delete",
-                include_sql!("pg/txn_produce_offset_delete_by_topic.sql"),
-                "delete_topic",
-            ),
-            (
-                "txn_topition",
-                include_sql!("pg/txn_topition_delete_by_topic.sql"),
-                "delete_topic",
-            ),
-            (
-                "producer_detail",
-                include_sql!("pg/producer_detail_delete_by_topic.sql"),
-                "delete_topic",
-            ),
-            (
-                "topitions",
-                include_sql!("pg/topition_delete_by_topic.sql"),
-                "delete_topic",
-            ),
-        ] {
-            let rows = self
-                .tx_prepare_execute(&tx, sql.as_str(), &[&self.cluster, &topic_name], nickname)
-                .await
-                .inspect_err(|err| {
-                    debug!(?description, ?err);
-                })?;
+        _ = self
+            .tx_prepare_execute(
+                &tx,
+                include_sql!("pg/header_delete_from.sql").as_str(),
+                &[
+                    &self.cluster,
+                    &topition.topic(),
+                    &topition.partition(),
+                    &offset,
+                ],
+                "truncate",
+            )
+            .await
+            .inspect_err(|err| error!(?err, ?topition, offset))?;
 
-            debug!(?topic, ?rows, ?description);
-        }
+        let deleted = self
+            .tx_prepare_execute(
+                &tx,
+                include_sql!("pg/record_delete_from.sql").as_str(),
+                &[
+                    &self.cluster,
+                    &topition.topic(),
+                    &topition.partition(),
+                    &offset,
+                ],
+                "truncate",
+            )
+            .await
+            .inspect(|deleted| debug!(?topition, offset, deleted))
+            .inspect_err(|err| error!(?err, ?topition, offset))
+            .map_err(Error::from)
+            .and_then(|deleted| i64::try_from(deleted).map_err(Into::into))?;
+
+        let (deleted, base_offsets) = if let Some(segments) = self.segments.as_deref() {
+            let base_offsets = self.segments_from(segments, topition, offset, high).await?;
+
+            (
+                self.segment_record_count(segments, topition, &base_offsets)
+                    .await?,
+                base_offsets,
+            )
+        } else {
+            (deleted, vec![])
+        };
 
         _ = self
             .tx_prepare_execute(
                 &tx,
-                include_sql!("pg/topic_delete_by.sql").as_str(),
-                &[&self.cluster, &topic_name],
-                "delete_topic",
+                include_sql!("pg/watermark_update_high.sql").as_str(),
+                &[
+                    &self.cluster,
+                    &topition.topic(),
+                    &topition.partition(),
+                    &offset,
+                    &deleted,
+                ],
+                "truncate",
             )
-            .await?;
+            .await
+            .inspect_err(|err| error!(?err, ?topition, offset))?;
+
+        tx.commit().await?;
+
+        // segments are only removed once the truncation has committed
+        //
+        if let Some(segments) = self.segments.as_deref() {
+            self.segment_delete(segments, topition, &base_offsets)
+                .await?;
+        }
+
+        self.cache_invalidate(topition)?;
+
+        Ok(offset)
+    }
+
+    #[instrument(skip_all, fields(cluster = %self.cluster, ?topic))]
+    async fn delete_topic(&mut self, topic: &TopicId) -> Result<ErrorCode> {
+        debug!(cluster = self.cluster, ?topic);
+
+        let mut c = self.connection().await?;
+        let tx = c.transaction().await?;
+
+        let deleted = self.delete_topic_in_tx(topic, &tx).await?;
 
         tx.commit().await.inspect_err(|err| error!(?err))?;
 
-        Ok(ErrorCode::None)
+        if let Some(ref topic_name) = deleted {
+            self.cache_invalidate_topic(topic_name)?;
+            self.segment_delete_topic(topic_name).await?;
+        }
+
+        Ok(deleted.map_or(ErrorCode::UnknownTopicOrPartition, |_| ErrorCode::None))
+    }
+
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
+    async fn delete_topics(&mut self, topics: &[TopicId]) -> Result<Vec<(TopicId, ErrorCode)>> {
+        debug!(cluster = self.cluster, ?topics);
+
+        let mut c = self.connection().await?;
+        let tx = c.transaction().await?;
+
+        let mut deleted = vec![];
+
+        for topic in topics {
+            deleted.push((topic.to_owned(), self.delete_topic_in_tx(topic, &tx).await?));
+        }
+
+        tx.commit().await.inspect_err(|err| error!(?err))?;
+
+        let mut responses = vec![];
+
+        for (topic, topic_name) in deleted {
+            if let Some(ref topic_name) = topic_name {
+                self.cache_invalidate_topic(topic_name)?;
+                self.segment_delete_topic(topic_name).await?;
+            }
+
+            responses.push((
+                topic,
+                topic_name.map_or(ErrorCode::UnknownTopicOrPartition, |_| ErrorCode::None),
+            ));
+        }
+
+        Ok(responses)
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
     async fn incremental_alter_resource(
         &mut self,
         resource: AlterConfigsResource,
@@ -1617,6 +3603,15 @@ impl Storage for Postgres {
         }
     }
 
+    #[instrument(
+        skip_all,
+        fields(
+            cluster = %self.cluster,
+            topic = topition.topic(),
+            partition = topition.partition(),
+            transaction_id = transaction_id,
+        )
+    )]
     async fn produce(
         &mut self,
         transaction_id: Option<&str>,
@@ -1625,19 +3620,81 @@ impl Storage for Postgres {
     ) -> Result<i64> {
         debug!(cluster = self.cluster, transaction_id, ?topition, ?deflated);
 
+        validate_batch(&deflated)?;
+
+        let generation = self.cache_generation(topition)?;
+
         let mut c = self.connection().await?;
 
-        let tx = c.transaction().await?;
+        let mut tx = c.transaction().await?;
 
-        let high = self
-            .produce_in_tx(transaction_id, topition, deflated, &tx)
+        let produced = self
+            .produce_or_create_in_tx(transaction_id, topition, deflated, &mut tx)
             .await?;
 
         tx.commit().await?;
 
+        let high = produced.high;
+
+        self.cache_stored(topition, generation, produced.stored)?;
+        self.lake_export(topition, produced.export).await;
+
+        self.notify_produced(topition)?;
+
         Ok(high)
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
+    async fn produce_many(
+        &mut self,
+        writes: &[(Topition, deflated::Batch)],
+    ) -> Result<Vec<(Topition, i64)>> {
+        debug!(cluster = self.cluster, writes = writes.len());
+
+        let mut generations = Vec::with_capacity(writes.len());
+
+        for (topition, deflated) in writes {
+            validate_batch(deflated)?;
+            generations.push(self.cache_generation(topition)?);
+        }
+
+        let mut c = self.connection().await?;
+
+        let mut tx = c.transaction().await?;
+
+        let mut offsets = vec![];
+        let mut written = vec![];
+
+        for (topition, deflated) in writes {
+            let produced = self
+                .produce_or_create_in_tx(None, topition, deflated.to_owned(), &mut tx)
+                .await
+                .inspect_err(|err| error!(?err, ?topition))?;
+
+            offsets.push((topition.to_owned(), produced.high));
+            written.push(produced);
+        }
+
+        tx.commit().await?;
+
+        for (((topition, _), generation), produced) in writes.iter().zip(generations).zip(written) {
+            self.cache_stored(topition, generation, produced.stored)?;
+            self.lake_export(topition, produced.export).await;
+            self.notify_produced(topition)?;
+        }
+
+        Ok(offsets)
+    }
+
+    #[instrument(
+        skip_all,
+        fields(
+            cluster = %self.cluster,
+            topic = topition.topic(),
+            partition = topition.partition(),
+            offset = offset,
+        )
+    )]
     async fn fetch(
         &mut self,
         topition: &Topition,
@@ -1646,13 +3703,9 @@ impl Storage for Postgres {
         max_bytes: u32,
         isolation_level: IsolationLevel,
     ) -> Result<Vec<deflated::Batch>> {
-        let high_watermark = self.offset_stage(topition).await.map(|offset_stage| {
-            if isolation_level == IsolationLevel::ReadCommitted {
-                offset_stage.last_stable
-            } else {
-                offset_stage.high_watermark
-            }
-        })?;
+        let high_watermark = self
+            .high_watermark(topition, offset, isolation_level)
+            .await?;
 
         debug!(
             cluster = self.cluster,
@@ -1664,203 +3717,139 @@ impl Storage for Postgres {
             max_bytes
         );
 
-        let c = self.connection().await?;
-
-        let records = self
-            .prepare_query(
-                &c,
-                include_sql!("pg/record_fetch.sql").as_str(),
-                &[
-                    &self.cluster,
-                    &topition.topic(),
-                    &topition.partition(),
-                    &offset,
-                    &(max_bytes as i64),
-                    &high_watermark,
-                ],
-                "fetch",
-            )
-            .await
-            .inspect_err(|err| error!(?err))?;
-
-        let mut batches = vec![];
-
-        if let Some(first) = records.first() {
-            let mut batch_builder = inflated::Batch::builder()
-                .base_offset(
-                    first
-                        .try_get::<_, i64>(0)
-                        .inspect(|base_offset| debug!(base_offset))
-                        .inspect_err(|err| error!(?err))?,
-                )
-                .attributes(
-                    first
-                        .try_get::<_, Option<i16>>(1)
-                        .map(|attributes| attributes.unwrap_or(0))
-                        .inspect_err(|err| error!(?err))?,
-                )
-                .base_timestamp(
-                    first
-                        .try_get::<_, SystemTime>(2)
-                        .map_err(Error::from)
-                        .and_then(|system_time| to_timestamp(system_time).map_err(Into::into))
-                        .inspect_err(|err| error!(?err))?,
-                )
-                .producer_id(
-                    first
-                        .try_get::<_, Option<i64>>(6)
-                        .map(|producer_id| producer_id.unwrap_or(-1))
-                        .inspect_err(|err| error!(?err))?,
-                )
-                .producer_epoch(
-                    first
-                        .try_get::<_, Option<i16>>(7)
-                        .map(|producer_epoch| producer_epoch.unwrap_or(-1))
-                        .inspect_err(|err| error!(?err))?,
-                );
-
-            for record in records.iter() {
-                let attributes = record
-                    .try_get::<_, Option<i16>>(1)
-                    .map(|attributes| attributes.unwrap_or(0))
-                    .inspect_err(|err| error!(?err))?;
-
-                let producer_id = record
-                    .try_get::<_, Option<i64>>(6)
-                    .map(|producer_id| producer_id.unwrap_or(-1))
-                    .inspect_err(|err| error!(?err))?;
-                let producer_epoch = record
-                    .try_get::<_, Option<i16>>(7)
-                    .map(|producer_epoch| producer_epoch.unwrap_or(-1))
-                    .inspect_err(|err| error!(?err))?;
-
-                if batch_builder.attributes != attributes
-                    || batch_builder.producer_id != producer_id
-                    || batch_builder.producer_epoch != producer_epoch
-                {
-                    batches.push(batch_builder.build().and_then(TryInto::try_into)?);
-
-                    batch_builder = inflated::Batch::builder()
-                        .base_offset(
-                            record
-                                .try_get::<_, i64>(0)
-                                .inspect(|base_offset| debug!(base_offset))
-                                .inspect_err(|err| error!(?err))?,
-                        )
-                        .base_timestamp(
-                            record
-                                .try_get::<_, SystemTime>(2)
-                                .map_err(Error::from)
-                                .and_then(|system_time| {
-                                    to_timestamp(system_time).map_err(Into::into)
-                                })
-                                .inspect_err(|err| error!(?err))?,
-                        )
-                        .attributes(attributes)
-                        .producer_id(producer_id)
-                        .producer_epoch(producer_epoch);
-                }
+        if let Some(segments) = self.segments.as_deref() {
+            return self
+                .segment_fetch(segments, topition, offset, max_bytes, high_watermark)
+                .await;
+        }
 
-                let offset = record
-                    .try_get::<_, i64>(0)
-                    .inspect(|offset| debug!(offset))
-                    .inspect_err(|err| error!(?err))?;
-                let offset_delta = i32::try_from(offset - batch_builder.base_offset)?;
-
-                let timestamp_delta = first
-                    .try_get::<_, SystemTime>(2)
-                    .map_err(Error::from)
-                    .and_then(|system_time| {
-                        to_timestamp(system_time)
-                            .map(|timestamp| timestamp - batch_builder.base_timestamp)
-                            .map_err(Into::into)
-                    })
-                    .inspect(|timestamp| debug!(?timestamp))
-                    .inspect_err(|err| error!(?err))?;
+        let generation = if let Some(ref cache) = self.cache {
+            let mut cache = cache.lock()?;
 
-                let k = record
-                    .try_get::<_, Option<&[u8]>>(3)
-                    .map(|o| o.map(Bytes::copy_from_slice))
-                    .inspect(|k| debug!(?k))
-                    .inspect_err(|err| error!(?err))?;
+            if let Some(batches) = cache.fetch(topition, offset, max_bytes, high_watermark) {
+                debug!(?topition, offset, cached = batches.len());
 
-                let v = record
-                    .try_get::<_, Option<&[u8]>>(4)
-                    .map(|o| o.map(Bytes::copy_from_slice))
-                    .inspect(|v| debug!(?v))
-                    .inspect_err(|err| error!(?err))?;
+                return if batches.is_empty() {
+                    inflated::Batch::builder()
+                        .build()
+                        .and_then(TryInto::try_into)
+                        .map(|empty| vec![empty])
+                        .map_err(Into::into)
+                } else {
+                    Ok(batches)
+                };
+            }
 
-                let mut record_builder = Record::builder()
-                    .offset_delta(offset_delta)
-                    .timestamp_delta(timestamp_delta)
-                    .key(k.into())
-                    .value(v.into());
+            Some(cache.generation(topition))
+        } else {
+            None
+        };
 
-                for header in self
-                    .prepare_query(
-                        &c,
-                        include_sql!("pg/header_fetch.sql").as_str(),
-                        &[
-                            &self.cluster,
-                            &topition.topic(),
-                            &topition.partition(),
-                            &offset,
-                        ],
-                        "fetch",
-                    )
-                    .await
-                    .inspect(|row| debug!(?row))
-                    .inspect_err(|err| error!(?err))?
-                {
-                    let mut header_builder = Header::builder();
+        let batches = self
+            .record_fetch(topition, offset, max_bytes, high_watermark)
+            .await?;
 
-                    if let Some(k) = header
-                        .try_get::<_, Option<&[u8]>>(0)
-                        .inspect_err(|err| error!(?err))?
-                    {
-                        header_builder = header_builder.key(k.to_vec());
-                    }
+        if batches.is_empty() {
+            inflated::Batch::builder()
+                .build()
+                .and_then(TryInto::try_into)
+                .map(|empty| vec![empty])
+                .map_err(Into::into)
+        } else {
+            let batches = batches
+                .into_iter()
+                .map(|batch| deflated::Batch::try_from(batch).map_err(Into::into))
+                .collect::<Result<Vec<_>>>()?;
 
-                    if let Some(v) = header
-                        .try_get::<_, Option<&[u8]>>(1)
-                        .inspect_err(|err| error!(?err))?
-                    {
-                        header_builder = header_builder.value(v.to_vec());
-                    }
+            self.cache_put(topition, generation, &batches)?;
 
-                    record_builder = record_builder.header(header_builder);
-                }
+            Ok(batches)
+        }
+    }
 
-                batch_builder = batch_builder
-                    .record(record_builder)
-                    .last_offset_delta(offset_delta);
-            }
+    #[instrument(
+        skip_all,
+        fields(
+            cluster = %self.cluster,
+            topic = topition.topic(),
+            partition = topition.partition(),
+            offset = offset,
+        )
+    )]
+    async fn fetch_inflated(
+        &mut self,
+        topition: &Topition,
+        offset: i64,
+        min_bytes: u32,
+        max_bytes: u32,
+        isolation_level: IsolationLevel,
+    ) -> Result<Vec<inflated::Batch>> {
+        debug!(
+            cluster = self.cluster,
+            ?topition,
+            offset,
+            ?isolation_level,
+            min_bytes,
+            max_bytes
+        );
 
-            batches.push(batch_builder.build().and_then(TryInto::try_into)?);
-        } else {
-            batches.push(
-                inflated::Batch::builder()
-                    .build()
-                    .and_then(TryInto::try_into)?,
-            );
+        // segments and the cache hold deflated batches
+        //
+        if self.segments.is_some() || self.cache.is_some() {
+            return self
+                .fetch(topition, offset, min_bytes, max_bytes, isolation_level)
+                .await
+                .and_then(|batches| {
+                    batches
+                        .into_iter()
+                        .map(|batch| inflated::Batch::try_from(batch).map_err(Into::into))
+                        .collect()
+                });
         }
 
-        Ok(batches)
+        let high_watermark = self
+            .high_watermark(topition, offset, isolation_level)
+            .await?;
+
+        let batches = self
+            .record_fetch(topition, offset, max_bytes, high_watermark)
+            .await?;
+
+        if batches.is_empty() {
+            inflated::Batch::builder()
+                .build()
+                .map(|empty| vec![empty])
+                .map_err(Into::into)
+        } else {
+            Ok(batches)
+        }
     }
 
+    #[instrument(
+        skip_all,
+        fields(
+            cluster = %self.cluster,
+            topic = topition.topic(),
+            partition = topition.partition(),
+        )
+    )]
     async fn offset_stage(&mut self, topition: &Topition) -> Result<OffsetStage> {
         debug!(cluster = self.cluster, ?topition);
         let c = self.connection().await?;
 
+        // a topition without a watermark doesn't exist, rather than being empty
+        //
         let row = self
-            .prepare_query_one(
+            .prepare_query_opt(
                 &c,
                 include_sql!("pg/watermark_select.sql").as_str(),
                 &[&self.cluster, &topition.topic(), &topition.partition()],
                 "offset_stage",
             )
             .await
-            .inspect_err(|err| error!(?topition, ?err))?;
+            .inspect_err(|err| error!(?topition, ?err))?
+            .ok_or(Error::Api(ErrorCode::UnknownTopicOrPartition))
+            .inspect_err(|err| debug!(?topition, ?err))?;
 
         let log_start = row
             .try_get::<_, Option<i64>>(0)
@@ -1872,12 +3861,20 @@ impl Storage for Postgres {
             .inspect_err(|err| error!(?topition, ?err))?
             .unwrap_or_default();
 
+        // an open transaction may have produced beyond a truncation
+        //
         let last_stable = row
-            .try_get::<_, Option<i64>>(1)
+            .try_get::<_, Option<i64>>(2)
             .inspect_err(|err| error!(?topition, ?err))?
-            .unwrap_or(high_watermark);
+            .map_or(high_watermark, |stable| stable.min(high_watermark));
 
-        debug!(cluster = self.cluster, ?topition, log_start, high_watermark,);
+        debug!(
+            cluster = self.cluster,
+            ?topition,
+            log_start,
+            high_watermark,
+            last_stable
+        );
 
         Ok(OffsetStage {
             last_stable,
@@ -1886,6 +3883,129 @@ impl Storage for Postgres {
         })
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
+    async fn record_counts(&mut self, topics: &[TopicId]) -> Result<Vec<(Topition, i64)>> {
+        debug!(cluster = self.cluster, ?topics);
+
+        let (names, ids) =
+            topics
+                .iter()
+                .fold((Vec::new(), Vec::new()), |(mut names, mut ids), topic| {
+                    match topic {
+                        TopicId::Name(name) => names.push(name.to_owned()),
+                        TopicId::Id(id) => ids.push(*id),
+                    }
+
+                    (names, ids)
+                });
+
+        let c = self.connection().await?;
+
+        self.prepare_query(
+            &c,
+            include_sql!("pg/watermark_record_count.sql").as_str(),
+            &[&self.cluster, &names, &ids],
+            "record_counts",
+        )
+        .await
+        .inspect_err(|err| error!(?err, ?topics))?
+        .into_iter()
+        .map(|row| {
+            Ok((
+                Topition::new(row.try_get::<_, String>(0)?, row.try_get::<_, i32>(1)?),
+                row.try_get::<_, i64>(2)?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()
+        .inspect(|record_counts| debug!(cluster = self.cluster, ?record_counts))
+    }
+
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
+    async fn log_dirs(&mut self) -> Result<Vec<LogDirDescription>> {
+        debug!(cluster = self.cluster);
+
+        let c = self.connection().await?;
+
+        self.prepare_query(
+            &c,
+            include_sql!("pg/log_dirs.sql").as_str(),
+            &[&self.cluster],
+            "log_dirs",
+        )
+        .await
+        .inspect_err(|err| error!(?err))?
+        .into_iter()
+        .map(|row| {
+            Ok(LogDirDescription {
+                topition: Topition::new(row.try_get::<_, String>(0)?, row.try_get::<_, i32>(1)?),
+                log_start: row.try_get::<_, Option<i64>>(2)?.unwrap_or_default(),
+                log_end: row.try_get::<_, Option<i64>>(3)?.unwrap_or_default(),
+                record_count: row.try_get::<_, Option<i64>>(4)?.unwrap_or_default(),
+                size: row.try_get::<_, i64>(5)?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()
+        .inspect(|log_dirs| debug!(cluster = self.cluster, ?log_dirs))
+    }
+
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
+    async fn close(&mut self) -> Result<()> {
+        debug!(cluster = self.cluster, status = ?self.pool.status());
+
+        // idle connections are dropped on close, with those in use
+        // being dropped as they are returned to the pool
+        //
+        self.pool.close();
+
+        if timeout(CLOSE_TIMEOUT, async {
+            while self.pool.status().size > 0 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .is_err()
+        {
+            warn!(cluster = self.cluster, status = ?self.pool.status(), "in use on close");
+        }
+
+        Ok(())
+    }
+
+    #[instrument(
+        skip_all,
+        fields(
+            cluster = %self.cluster,
+            topic = topition.topic(),
+            partition = topition.partition(),
+        )
+    )]
+    async fn describe_producers(&mut self, topition: &Topition) -> Result<Vec<ProducerState>> {
+        debug!(cluster = self.cluster, ?topition);
+
+        let c = self.connection().await?;
+
+        self.prepare_query(
+            &c,
+            include_sql!("pg/producer_state_select.sql").as_str(),
+            &[&self.cluster, &topition.topic(), &topition.partition()],
+            "describe_producers",
+        )
+        .await
+        .inspect_err(|err| error!(?err, ?topition))?
+        .into_iter()
+        .map(|row| {
+            Ok(ProducerState {
+                producer_id: row.try_get::<_, i64>(0)?,
+                producer_epoch: row.try_get::<_, i16>(1)?,
+                last_sequence: row.try_get::<_, i32>(2)?,
+                transaction_id: row.try_get::<_, Option<String>>(3)?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()
+        .inspect(|producers| debug!(cluster = self.cluster, ?topition, ?producers))
+    }
+
+    #[instrument(skip_all, fields(cluster = %self.cluster, group = group))]
     async fn offset_commit(
         &mut self,
         group: &str,
@@ -1895,7 +4015,7 @@ impl Storage for Postgres {
         debug!(cluster = self.cluster, ?group, ?retention);
 
         let mut c = self.connection().await?;
-        let tx = c.transaction().await?;
+        let mut tx = c.transaction().await?;
 
         let mut cg_inserted = false;
 
@@ -1904,6 +4024,11 @@ impl Storage for Postgres {
         for (topition, offset) in offsets {
             debug!(?topition, ?offset);
 
+            if offset.is_metadata_too_large(self.offset_metadata_max_bytes) {
+                responses.push((topition.to_owned(), ErrorCode::OffsetMetadataTooLarge));
+                continue;
+            }
+
             if self
                 .tx_prepare_query_opt(
                     &tx,
@@ -1913,54 +4038,74 @@ impl Storage for Postgres {
                 )
                 .await
                 .inspect_err(|err| error!(?err))?
-                .is_some()
+                .is_none()
             {
-                if !cg_inserted {
-                    let rows = self
-                        .tx_prepare_execute(
-                            &tx,
-                            include_sql!("pg/consumer_group_insert.sql").as_str(),
-                            &[&self.cluster, &group],
-                            "offset_commit",
-                        )
-                        .await?;
-                    debug!(rows);
-
-                    cg_inserted = true;
-                }
+                responses.push((topition.to_owned(), ErrorCode::UnknownTopicOrPartition));
+                continue;
+            }
 
+            if !cg_inserted {
                 let rows = self
                     .tx_prepare_execute(
                         &tx,
-                        include_sql!("pg/consumer_offset_insert.sql").as_str(),
-                        &[
-                            &self.cluster,
-                            &topition.topic(),
-                            &topition.partition(),
-                            &group,
-                            &offset.offset,
-                            &offset.leader_epoch,
-                            &offset.timestamp,
-                            &offset.metadata,
-                        ],
+                        include_sql!("pg/consumer_group_insert.sql").as_str(),
+                        &[&self.cluster, &group],
                         "offset_commit",
                     )
                     .await
-                    .inspect_err(|err| error!(?err))?;
+                    .map_err(|error| violation(error, ErrorCode::DuplicateResource))?;
+                debug!(rows);
 
-                debug!(?rows);
+                cg_inserted = true;
+            }
 
-                responses.push((
-                    topition.to_owned(),
-                    if rows == 0 {
-                        ErrorCode::UnknownTopicOrPartition
-                    } else {
-                        ErrorCode::None
-                    },
-                ));
+            let expire_timestamp = offset_expiry(offset, retention, self.offsets_retention);
+
+            // each offset is committed within its own savepoint, so that
+            // a failure only rolls back that partition
+            //
+            let savepoint = tx.savepoint("offset_commit").await?;
+
+            let error_code = match self
+                .tx_prepare_execute(
+                    &savepoint,
+                    include_sql!("pg/consumer_offset_insert.sql").as_str(),
+                    &[
+                        &self.cluster,
+                        &topition.topic(),
+                        &topition.partition(),
+                        &group,
+                        &offset.offset,
+                        &offset.leader_epoch,
+                        &offset.timestamp,
+                        &offset.metadata,
+                        &expire_timestamp,
+                    ],
+                    "offset_commit",
+                )
+                .await
+                .inspect_err(|err| error!(?err))
+                .map_err(|error| violation(error, ErrorCode::DuplicateResource))
+            {
+                Ok(0) => ErrorCode::UnknownTopicOrPartition,
+                Ok(rows) => {
+                    debug!(rows);
+                    ErrorCode::None
+                }
+                Err(Error::Api(error_code)) => error_code,
+                Err(Error::TokioPostgres(ref error)) if error.as_db_error().is_some() => {
+                    ErrorCode::UnknownServerError
+                }
+                Err(otherwise) => return Err(otherwise),
+            };
+
+            if error_code == ErrorCode::None {
+                savepoint.commit().await?;
             } else {
-                responses.push((topition.to_owned(), ErrorCode::UnknownTopicOrPartition))
+                savepoint.rollback().await?;
             }
+
+            responses.push((topition.to_owned(), error_code));
         }
 
         tx.commit().await.inspect_err(|err| error!(?err))?;
@@ -1968,6 +4113,7 @@ impl Storage for Postgres {
         Ok(responses)
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster, group = group_id))]
     async fn committed_offset_topitions(
         &mut self,
         group_id: &str,
@@ -2003,6 +4149,56 @@ impl Storage for Postgres {
         Ok(results)
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster, group = group_id))]
+    async fn committed_offsets(
+        &mut self,
+        group_id: &str,
+    ) -> Result<BTreeMap<Topition, CommittedOffset>> {
+        debug!(group_id);
+
+        let c = self.connection().await?;
+
+        self.prepare_query(
+            &c,
+            include_sql!("pg/consumer_offset_expiry_select_by_group.sql").as_str(),
+            &[&self.cluster, &group_id],
+            "committed_offsets",
+        )
+        .await
+        .inspect_err(|err| error!(?err))?
+        .into_iter()
+        .map(|row| {
+            Ok((
+                Topition::new(row.try_get::<_, String>(0)?, row.try_get::<_, i32>(1)?),
+                CommittedOffset {
+                    offset: row.try_get::<_, i64>(2)?,
+                    expire_timestamp: row.try_get::<_, Option<SystemTime>>(3)?,
+                },
+            ))
+        })
+        .collect::<Result<BTreeMap<_, _>>>()
+        .inspect(|offsets| debug!(group_id, ?offsets))
+    }
+
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
+    async fn expire_offsets(&mut self) -> Result<u64> {
+        debug!(cluster = self.cluster);
+
+        let c = self.connection().await?;
+
+        self.prepare_execute(
+            &c,
+            include_sql!("pg/consumer_offset_expire.sql").as_str(),
+            &[&self.cluster, &SystemTime::now()],
+            "expire_offsets",
+        )
+        .await
+        .inspect(|expired| debug!(cluster = self.cluster, expired))
+        .inspect_err(|err| error!(?err))
+        .map_err(Into::into)
+    }
+
+    #[instrument(skip_all, fields(cluster = %self.cluster, group = group_id))]
     async fn offset_fetch(
         &mut self,
         group_id: Option<&str>,
@@ -2050,6 +4246,7 @@ impl Storage for Postgres {
         Ok(offsets)
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
     async fn list_offsets(
         &mut self,
         isolation_level: IsolationLevel,
@@ -2062,6 +4259,44 @@ impl Storage for Postgres {
         let mut responses = vec![];
 
         for (topition, offset_type) in offsets {
+            // without record rows, earliest and latest come from the
+            // watermark, with a timestamp searching the segments
+            //
+            if let Some(segments) = self.segments.clone() {
+                let offset_stage = self.offset_stage(topition).await?;
+
+                let list_offset = match (offset_type, isolation_level) {
+                    (ListOffsetRequest::Earliest, _) => ListOffsetResponse {
+                        offset: Some(offset_stage.log_start()),
+                        ..Default::default()
+                    },
+                    (ListOffsetRequest::Latest, IsolationLevel::ReadCommitted) => {
+                        ListOffsetResponse {
+                            offset: Some(offset_stage.last_stable()),
+                            ..Default::default()
+                        }
+                    }
+                    (ListOffsetRequest::Latest, IsolationLevel::ReadUncommitted) => {
+                        ListOffsetResponse {
+                            offset: Some(offset_stage.high_watermark()),
+                            ..Default::default()
+                        }
+                    }
+                    (ListOffsetRequest::Timestamp(timestamp), _) => {
+                        self.segment_offset_for_timestamp(
+                            segments.as_ref(),
+                            topition,
+                            *timestamp,
+                            offset_stage,
+                        )
+                        .await?
+                    }
+                };
+
+                responses.push((topition.clone(), list_offset));
+                continue;
+            }
+
             let query = match (offset_type, isolation_level) {
                 (ListOffsetRequest::Earliest, _) => include_sql!("pg/list_earliest_offset.sql"),
                 (ListOffsetRequest::Latest, IsolationLevel::ReadCommitted) => {
@@ -2129,7 +4364,7 @@ impl Storage for Postgres {
                     debug!(?row);
 
                     row.try_get::<_, i64>(0).map(Some).and_then(|offset| {
-                        row.try_get::<_, SystemTime>(1).map(Some).map(|timestamp| {
+                        row.try_get::<_, Option<SystemTime>>(1).map(|timestamp| {
                             debug!(
                                 cluster = self.cluster,
                                 ?topition,
@@ -2154,6 +4389,91 @@ impl Storage for Postgres {
         Ok(responses).inspect(|r| debug!(?r))
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
+    async fn list_offsets_batch(
+        &mut self,
+        isolation_level: IsolationLevel,
+        offsets: &[(Topition, ListOffsetRequest)],
+    ) -> Result<Vec<(Topition, ListOffsetResponse)>> {
+        debug!(cluster = self.cluster, ?isolation_level, ?offsets);
+
+        // segments are listed one topition at a time
+        //
+        if self.segments.is_some() {
+            return self.list_offsets(isolation_level, offsets).await;
+        }
+
+        let mut topics = Vec::with_capacity(offsets.len());
+        let mut partitions = Vec::with_capacity(offsets.len());
+        let mut specs = Vec::with_capacity(offsets.len());
+        let mut timestamps = Vec::with_capacity(offsets.len());
+
+        for (topition, offset_type) in offsets {
+            topics.push(topition.topic());
+            partitions.push(topition.partition());
+
+            let (spec, timestamp) = match (offset_type, isolation_level) {
+                (ListOffsetRequest::Earliest, _) => ("earliest", None),
+                (ListOffsetRequest::Latest, IsolationLevel::ReadCommitted) => {
+                    ("latest_committed", None)
+                }
+                (ListOffsetRequest::Latest, IsolationLevel::ReadUncommitted) => {
+                    ("latest_uncommitted", None)
+                }
+                (ListOffsetRequest::Timestamp(timestamp), _) => ("timestamp", Some(*timestamp)),
+            };
+
+            specs.push(spec);
+            timestamps.push(timestamp);
+        }
+
+        let c = self.connection().await?;
+
+        let mut listed = BTreeMap::new();
+
+        for row in self
+            .prepare_query(
+                &c,
+                include_sql!("pg/list_offsets_batch.sql").as_str(),
+                &[&self.cluster, &topics, &partitions, &specs, &timestamps],
+                "list_offsets_batch",
+            )
+            .await
+            .inspect_err(|err| error!(?err, cluster = self.cluster))?
+        {
+            let ordinality = row.try_get::<_, i64>(0)?;
+            let offset = row.try_get::<_, Option<i64>>(1)?;
+            let timestamp = row.try_get::<_, Option<SystemTime>>(2)?;
+
+            _ = listed.insert(ordinality, (offset, timestamp));
+        }
+
+        // as with list offsets, a topition without a matching record
+        // is at offset 0
+        //
+        Ok(offsets
+            .iter()
+            .zip(1..)
+            .map(|((topition, _), ordinality)| {
+                let (offset, timestamp) = listed
+                    .get(&ordinality)
+                    .and_then(|(offset, timestamp)| offset.map(|offset| (offset, *timestamp)))
+                    .unwrap_or((0, None));
+
+                (
+                    topition.clone(),
+                    ListOffsetResponse {
+                        offset: Some(offset),
+                        timestamp,
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect::<Vec<_>>())
+        .inspect(|r| debug!(?r))
+    }
+
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
     async fn metadata(&mut self, topics: Option<&[TopicId]>) -> Result<MetadataResponse> {
         debug!(cluster = self.cluster, ?topics);
 
@@ -2172,6 +4492,11 @@ impl Storage for Postgres {
 
         debug!(?brokers);
 
+        let broker_ids = brokers
+            .iter()
+            .map(|broker| broker.node_id)
+            .collect::<Vec<_>>();
+
         let responses = match topics {
             Some(topics) if !topics.is_empty() => {
                 let mut responses = vec![];
@@ -2195,7 +4520,7 @@ impl Storage for Postgres {
                                         .try_get::<_, Uuid>(0)
                                         .map(|uuid| uuid.into_bytes())
                                         .map(Some)?;
-                                    let name = row.try_get::<_, String>(1).map(Some)?;
+                                    let name = row.try_get::<_, String>(1)?;
                                     let is_internal = row.try_get::<_, bool>(2).map(Some)?;
                                     let partitions = row.try_get::<_, i32>(3)?;
                                     let replication_factor = row.try_get::<_, i32>(4)?;
@@ -2209,43 +4534,20 @@ impl Storage for Postgres {
                                         ?replication_factor
                                     );
 
-                                    let mut rng = rng();
-                                    let mut broker_ids: Vec<_> =
-                                        brokers.iter().map(|broker| broker.node_id).collect();
-                                    broker_ids.shuffle(&mut rng);
-
-                                    let mut brokers = broker_ids.into_iter().cycle();
-
                                     let partitions = Some(
-                                        (0..partitions)
-                                            .map(|partition_index| {
-                                                let leader_id = brokers.next().expect("cycling");
-
-                                                let replica_nodes = Some(
-                                                    (0..replication_factor)
-                                                        .map(|_replica| {
-                                                            brokers.next().expect("cycling")
-                                                        })
-                                                        .collect(),
-                                                );
-                                                let isr_nodes = replica_nodes.clone();
-
-                                                MetadataResponsePartition {
-                                                    error_code,
-                                                    partition_index,
-                                                    leader_id,
-                                                    leader_epoch: Some(-1),
-                                                    replica_nodes,
-                                                    isr_nodes,
-                                                    offline_replicas: Some([].into()),
-                                                }
-                                            })
-                                            .collect(),
+                                        self.partition_assignments(
+                                            &c,
+                                            &name,
+                                            partitions,
+                                            replication_factor,
+                                            &broker_ids,
+                                        )
+                                        .await?,
                                     );
 
                                     MetadataResponseTopic {
                                         error_code,
-                                        name,
+                                        name: Some(name),
                                         topic_id,
                                         is_internal,
                                         partitions,
@@ -2292,7 +4594,7 @@ impl Storage for Postgres {
                                         .try_get::<_, Uuid>(0)
                                         .map(|uuid| uuid.into_bytes())
                                         .map(Some)?;
-                                    let name = row.try_get::<_, String>(1).map(Some)?;
+                                    let name = row.try_get::<_, String>(1)?;
                                     let is_internal = row.try_get::<_, bool>(2).map(Some)?;
                                     let partitions = row.try_get::<_, i32>(3)?;
                                     let replication_factor = row.try_get::<_, i32>(4)?;
@@ -2306,43 +4608,20 @@ impl Storage for Postgres {
                                         ?replication_factor
                                     );
 
-                                    let mut rng = rng();
-                                    let mut broker_ids: Vec<_> =
-                                        brokers.iter().map(|broker| broker.node_id).collect();
-                                    broker_ids.shuffle(&mut rng);
-
-                                    let mut brokers = broker_ids.into_iter().cycle();
-
                                     let partitions = Some(
-                                        (0..partitions)
-                                            .map(|partition_index| {
-                                                let leader_id = brokers.next().expect("cycling");
-
-                                                let replica_nodes = Some(
-                                                    (0..replication_factor)
-                                                        .map(|_replica| {
-                                                            brokers.next().expect("cycling")
-                                                        })
-                                                        .collect(),
-                                                );
-                                                let isr_nodes = replica_nodes.clone();
-
-                                                MetadataResponsePartition {
-                                                    error_code,
-                                                    partition_index,
-                                                    leader_id,
-                                                    leader_epoch: Some(-1),
-                                                    replica_nodes,
-                                                    isr_nodes,
-                                                    offline_replicas: Some([].into()),
-                                                }
-                                            })
-                                            .collect(),
+                                        self.partition_assignments(
+                                            &c,
+                                            &name,
+                                            partitions,
+                                            replication_factor,
+                                            &broker_ids,
+                                        )
+                                        .await?,
                                     );
 
                                     MetadataResponseTopic {
                                         error_code,
-                                        name,
+                                        name: Some(name),
                                         topic_id,
                                         is_internal,
                                         partitions,
@@ -2388,7 +4667,7 @@ impl Storage for Postgres {
                                 .try_get::<_, Uuid>(0)
                                 .map(|uuid| uuid.into_bytes())
                                 .map(Some)?;
-                            let name = row.try_get::<_, String>(1).map(Some)?;
+                            let name = row.try_get::<_, String>(1)?;
                             let is_internal = row.try_get::<_, bool>(2).map(Some)?;
                             let partitions = row.try_get::<_, i32>(3)?;
                             let replication_factor = row.try_get::<_, i32>(4)?;
@@ -2402,41 +4681,20 @@ impl Storage for Postgres {
                                 ?replication_factor
                             );
 
-                            let mut rng = rng();
-                            let mut broker_ids: Vec<_> =
-                                brokers.iter().map(|broker| broker.node_id).collect();
-                            broker_ids.shuffle(&mut rng);
-
-                            let mut brokers = broker_ids.into_iter().cycle();
-
                             let partitions = Some(
-                                (0..partitions)
-                                    .map(|partition_index| {
-                                        let leader_id = brokers.next().expect("cycling");
-
-                                        let replica_nodes = Some(
-                                            (0..replication_factor)
-                                                .map(|_replica| brokers.next().expect("cycling"))
-                                                .collect(),
-                                        );
-                                        let isr_nodes = replica_nodes.clone();
-
-                                        MetadataResponsePartition {
-                                            error_code,
-                                            partition_index,
-                                            leader_id,
-                                            leader_epoch: Some(-1),
-                                            replica_nodes,
-                                            isr_nodes,
-                                            offline_replicas: Some([].into()),
-                                        }
-                                    })
-                                    .collect(),
+                                self.partition_assignments(
+                                    &c,
+                                    &name,
+                                    partitions,
+                                    replication_factor,
+                                    &broker_ids,
+                                )
+                                .await?,
                             );
 
                             responses.push(MetadataResponseTopic {
                                 error_code,
-                                name,
+                                name: Some(name),
                                 topic_id,
                                 is_internal,
                                 partitions,
@@ -2457,18 +4715,97 @@ impl Storage for Postgres {
                     }
                 }
 
-                responses
+                responses
+            }
+        };
+
+        Ok(MetadataResponse {
+            cluster: Some(self.cluster.clone()),
+            controller: Some(self.node),
+            brokers,
+            topics: responses,
+        })
+    }
+
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
+    async fn list_topics(&mut self) -> Result<Vec<TopicDetail>> {
+        debug!(cluster = self.cluster);
+
+        let c = self.connection().await.inspect_err(|err| error!(?err))?;
+
+        let mut topics = vec![];
+
+        for row in self
+            .prepare_query(
+                &c,
+                include_sql!("pg/topic_by_cluster.sql").as_str(),
+                &[&self.cluster],
+                "list_topics",
+            )
+            .await
+            .inspect_err(|err| error!(?err))?
+        {
+            let id = row.try_get::<_, Uuid>(0)?;
+            let name = row.try_get::<_, String>(1)?;
+            let is_internal = row.try_get::<_, bool>(2)? || is_internal_topic(&name);
+            let num_partitions = row.try_get::<_, i32>(3)?;
+            let replication_factor = row.try_get::<_, i32>(4)?;
+
+            let mut assignments = vec![];
+
+            for row in self
+                .prepare_query(
+                    &c,
+                    include_sql!("pg/topition_assignment_select.sql").as_str(),
+                    &[&self.cluster, &name],
+                    "list_topics",
+                )
+                .await
+                .inspect_err(|err| error!(?err, name))?
+            {
+                assignments.push(CreatableReplicaAssignment {
+                    partition_index: row.try_get::<_, i32>(0)?,
+                    broker_ids: row.try_get::<_, Option<Vec<i32>>>(2)?,
+                });
             }
-        };
 
-        Ok(MetadataResponse {
-            cluster: Some(self.cluster.clone()),
-            controller: Some(self.node),
-            brokers,
-            topics: responses,
-        })
+            let mut configs = vec![];
+
+            for row in self
+                .prepare_query(
+                    &c,
+                    include_sql!("pg/topic_configuration_select.sql").as_str(),
+                    &[&self.cluster, &name],
+                    "list_topics",
+                )
+                .await
+                .inspect_err(|err| error!(?err, name))?
+            {
+                configs.push(CreatableTopicConfig {
+                    name: row.try_get::<_, String>(0)?,
+                    value: row.try_get::<_, Option<String>>(1)?,
+                });
+            }
+
+            topics.push(
+                TopicDetail::from((
+                    id,
+                    CreatableTopic {
+                        name,
+                        num_partitions,
+                        replication_factor: i16::try_from(replication_factor)?,
+                        assignments: Some(assignments),
+                        configs: Some(configs),
+                    },
+                ))
+                .internal(is_internal),
+            );
+        }
+
+        Ok(topics)
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster, name = name))]
     async fn describe_config(
         &self,
         name: &str,
@@ -2477,6 +4814,23 @@ impl Storage for Postgres {
     ) -> Result<DescribeConfigsResult> {
         debug!(cluster = self.cluster, name, ?resource, ?keys);
 
+        match resource {
+            ConfigResource::Topic => (),
+
+            ConfigResource::Broker => {
+                return Ok(broker_config(
+                    name,
+                    self.node,
+                    self.offsets_retention,
+                    self.offset_metadata_max_bytes,
+                    self.sequence_window,
+                    keys,
+                ));
+            }
+
+            _ => return Ok(unsupported_config(name, resource)),
+        }
+
         let c = self.connection().await.inspect_err(|err| error!(?err))?;
 
         let prepared = c
@@ -2511,7 +4865,7 @@ impl Storage for Postgres {
                 .await
                 .inspect_err(|err| error!(?err))?;
 
-            let mut configs = vec![];
+            let mut overrides = vec![];
 
             for row in rows {
                 let name = row
@@ -2523,19 +4877,11 @@ impl Storage for Postgres {
                     .map(Some)
                     .inspect_err(|err| error!(?err))?;
 
-                configs.push(DescribeConfigsResourceResult {
-                    name,
-                    value,
-                    read_only: false,
-                    is_default: None,
-                    config_source: Some(ConfigSource::DefaultConfig.into()),
-                    is_sensitive: false,
-                    synonyms: Some([].into()),
-                    config_type: Some(ConfigType::String.into()),
-                    documentation: Some("".into()),
-                });
+                overrides.push((name, value));
             }
 
+            let configs = topic_configs(overrides, keys);
+
             let error_code = ErrorCode::None;
 
             Ok(DescribeConfigsResult {
@@ -2558,6 +4904,7 @@ impl Storage for Postgres {
         }
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
     async fn describe_topic_partitions(
         &mut self,
         topics: Option<&[TopicId]>,
@@ -2751,6 +5098,7 @@ impl Storage for Postgres {
         Ok(responses)
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
     async fn list_groups(&mut self, states_filter: Option<&[String]>) -> Result<Vec<ListedGroup>> {
         debug!(?states_filter);
 
@@ -2781,6 +5129,7 @@ impl Storage for Postgres {
         Ok(listed_groups)
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
     async fn delete_groups(
         &mut self,
         group_ids: Option<&[String]>,
@@ -2838,6 +5187,7 @@ impl Storage for Postgres {
         Ok(results)
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
     async fn describe_groups(
         &mut self,
         group_ids: Option<&[String]>,
@@ -2880,6 +5230,7 @@ impl Storage for Postgres {
         Ok(results)
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster, group = group_id))]
     async fn update_group(
         &mut self,
         group_id: &str,
@@ -2982,6 +5333,7 @@ impl Storage for Postgres {
         outcome
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster, transaction_id = transaction_id))]
     async fn init_producer(
         &mut self,
         transaction_id: Option<&str>,
@@ -2995,10 +5347,32 @@ impl Storage for Postgres {
         );
 
         match (producer_id, producer_epoch, transaction_id) {
-            (Some(-1), Some(-1), Some(transaction_id)) => {
+            (Some(producer_id), Some(producer_epoch), Some(transaction_id)) => {
                 let mut c = self.connection().await.inspect_err(|err| error!(?err))?;
                 let tx = c.transaction().await.inspect_err(|err| error!(?err))?;
 
+                // an existing producer may only bump the epoch of its
+                // transaction when it is using the current epoch
+                //
+                if producer_id != -1 || producer_epoch != -1 {
+                    let error = self
+                        .txn_producer_check(transaction_id, producer_id, producer_epoch, &tx)
+                        .await?;
+
+                    if error != ErrorCode::None {
+                        return Ok(ProducerIdResponse {
+                            error,
+                            id: -1,
+                            epoch: -1,
+                        });
+                    }
+                }
+
+                // aborting an open transaction may complete others that
+                // overlap it
+                //
+                let mut ended = None;
+
                 if let Some(row) = self
                     .tx_prepare_query_opt(
                         &tx,
@@ -3021,10 +5395,12 @@ impl Storage for Postgres {
                     debug!(transaction_id, id, epoch, ?status);
 
                     if let Some(TxnState::Begin) = status {
-                        let error = self
+                        let aborted = self
                             .end_in_tx(transaction_id, id, epoch, false, &tx)
                             .await?;
 
+                        let error = aborted.error_code;
+
                         if error != ErrorCode::None {
                             _ = tx
                                 .rollback()
@@ -3033,6 +5409,8 @@ impl Storage for Postgres {
 
                             return Ok(ProducerIdResponse { error, id, epoch });
                         }
+
+                        ended = Some(aborted);
                     }
                 }
 
@@ -3146,6 +5524,10 @@ impl Storage for Postgres {
                     Err(_) => ErrorCode::UnknownServerError,
                 };
 
+                if let (ErrorCode::None, Some(ended)) = (error, ended) {
+                    self.txn_ended(&ended).await;
+                }
+
                 Ok(ProducerIdResponse {
                     error,
                     id: producer,
@@ -3201,6 +5583,14 @@ impl Storage for Postgres {
         }
     }
 
+    #[instrument(
+        skip_all,
+        fields(
+            cluster = %self.cluster,
+            transaction_id = transaction_id,
+            group = group_id,
+        )
+    )]
     async fn txn_add_offsets(
         &mut self,
         transaction_id: &str,
@@ -3213,9 +5603,19 @@ impl Storage for Postgres {
             transaction_id, producer_id, producer_epoch, group_id
         );
 
-        Ok(ErrorCode::None)
+        let mut c = self.connection().await.inspect_err(|err| error!(?err))?;
+        let tx = c.transaction().await.inspect_err(|err| error!(?err))?;
+
+        let error_code = self
+            .txn_producer_check(transaction_id, producer_id, producer_epoch, &tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(error_code)
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
     async fn txn_add_partitions(
         &mut self,
         partitions: TxnAddPartitionsRequest,
@@ -3234,6 +5634,32 @@ impl Storage for Postgres {
                 let mut c = self.connection().await.inspect_err(|err| error!(?err))?;
                 let tx = c.transaction().await.inspect_err(|err| error!(?err))?;
 
+                let error_code = self
+                    .txn_producer_check(&transaction_id, producer_id, producer_epoch, &tx)
+                    .await?;
+
+                if error_code != ErrorCode::None {
+                    return Ok(TxnAddPartitionsResponse::VersionZeroToThree(
+                        topics
+                            .into_iter()
+                            .map(|topic| AddPartitionsToTxnTopicResult {
+                                name: topic.name,
+                                results_by_partition: Some(
+                                    topic
+                                        .partitions
+                                        .unwrap_or_default()
+                                        .into_iter()
+                                        .map(|partition_index| AddPartitionsToTxnPartitionResult {
+                                            partition_index,
+                                            partition_error_code: error_code.into(),
+                                        })
+                                        .collect(),
+                                ),
+                            })
+                            .collect(),
+                    ));
+                }
+
                 let mut results = vec![];
 
                 for topic in topics {
@@ -3259,11 +5685,12 @@ impl Storage for Postgres {
                                 error!(
                                     ?err,
                                     cluster = self.cluster,
-                                    topic = topic.name,
+                                    topic = %topic.name,
                                     partition_index,
                                     transaction_id
                                 )
-                            })?;
+                            })
+                            .map_err(|error| violation(error, ErrorCode::DuplicateResource))?;
 
                         results_by_partition.push(AddPartitionsToTxnPartitionResult {
                             partition_index,
@@ -3311,6 +5738,7 @@ impl Storage for Postgres {
         }
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
     async fn txn_offset_commit(
         &mut self,
         offsets: TxnOffsetCommitRequest,
@@ -3320,30 +5748,37 @@ impl Storage for Postgres {
         let mut c = self.connection().await.inspect_err(|err| error!(?err))?;
         let tx = c.transaction().await.inspect_err(|err| error!(?err))?;
 
-        let (producer_id, producer_epoch) = if let Some(row) = self
-            .tx_prepare_query_opt(
+        let error_code = self
+            .txn_producer_check(
+                &offsets.transaction_id,
+                offsets.producer_id,
+                offsets.producer_epoch,
                 &tx,
-                include_sql!("pg/producer_epoch_for_current_txn.sql").as_str(),
-                &[&self.cluster, &offsets.transaction_id],
-                "txn_offset_commit",
             )
-            .await
-            .inspect_err(|err| error!(?err))?
-        {
-            let producer_id = row
-                .try_get::<_, i64>(0)
-                .map(Some)
-                .inspect_err(|err| error!(?err))?;
-
-            let epoch = row
-                .try_get::<_, i16>(1)
-                .map(Some)
-                .inspect_err(|err| error!(?err))?;
+            .await?;
 
-            (producer_id, epoch)
-        } else {
-            (None, None)
-        };
+        if error_code != ErrorCode::None {
+            debug!(?error_code);
+
+            return Ok(offsets
+                .topics
+                .into_iter()
+                .map(|topic| TxnOffsetCommitResponseTopic {
+                    name: topic.name,
+                    partitions: Some(
+                        topic
+                            .partitions
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|partition| TxnOffsetCommitResponsePartition {
+                                partition_index: partition.partition_index,
+                                error_code: error_code.into(),
+                            })
+                            .collect(),
+                    ),
+                })
+                .collect());
+        }
 
         _ = self
             .tx_prepare_execute(
@@ -3352,9 +5787,8 @@ impl Storage for Postgres {
                 &[&self.cluster, &offsets.group_id],
                 "txn_offset_commit",
             )
-            .await?;
-
-        debug!(?producer_id, ?producer_epoch);
+            .await
+            .map_err(|error| violation(error, ErrorCode::DuplicateResource))?;
 
         _ = self
             .tx_prepare_execute(
@@ -3372,7 +5806,8 @@ impl Storage for Postgres {
                 "txn_offset_commit",
             )
             .await
-            .inspect_err(|err| error!(?err))?;
+            .inspect_err(|err| error!(?err))
+            .map_err(|error| violation(error, ErrorCode::DuplicateResource))?;
 
         let mut topics = vec![];
 
@@ -3380,47 +5815,31 @@ impl Storage for Postgres {
             let mut partitions = vec![];
 
             for partition in topic.partitions.unwrap_or(vec![]) {
-                if producer_id.is_some_and(|producer_id| producer_id == offsets.producer_id) {
-                    if producer_epoch
-                        .is_some_and(|producer_epoch| producer_epoch == offsets.producer_epoch)
-                    {
-                        _ = self
-                            .tx_prepare_execute(
-                                &tx,
-                                include_sql!("pg/txn_offset_commit_tp_insert.sql").as_str(),
-                                &[
-                                    &self.cluster,
-                                    &offsets.transaction_id,
-                                    &offsets.group_id,
-                                    &offsets.producer_id,
-                                    &offsets.producer_epoch,
-                                    &topic.name,
-                                    &partition.partition_index,
-                                    &partition.committed_offset,
-                                    &partition.committed_leader_epoch,
-                                    &partition.committed_metadata,
-                                ],
-                                "txn_offset_commit",
-                            )
-                            .await
-                            .inspect_err(|err| error!(?err))?;
+                _ = self
+                    .tx_prepare_execute(
+                        &tx,
+                        include_sql!("pg/txn_offset_commit_tp_insert.sql").as_str(),
+                        &[
+                            &self.cluster,
+                            &offsets.transaction_id,
+                            &offsets.group_id,
+                            &offsets.producer_id,
+                            &offsets.producer_epoch,
+                            &topic.name,
+                            &partition.partition_index,
+                            &partition.committed_offset,
+                            &partition.committed_leader_epoch,
+                            &partition.committed_metadata,
+                        ],
+                        "txn_offset_commit",
+                    )
+                    .await
+                    .inspect_err(|err| error!(?err))?;
 
-                        partitions.push(TxnOffsetCommitResponsePartition {
-                            partition_index: partition.partition_index,
-                            error_code: i16::from(ErrorCode::None),
-                        });
-                    } else {
-                        partitions.push(TxnOffsetCommitResponsePartition {
-                            partition_index: partition.partition_index,
-                            error_code: i16::from(ErrorCode::InvalidProducerEpoch),
-                        });
-                    }
-                } else {
-                    partitions.push(TxnOffsetCommitResponsePartition {
-                        partition_index: partition.partition_index,
-                        error_code: i16::from(ErrorCode::UnknownProducerId),
-                    });
-                }
+                partitions.push(TxnOffsetCommitResponsePartition {
+                    partition_index: partition.partition_index,
+                    error_code: i16::from(ErrorCode::None),
+                });
             }
 
             topics.push(TxnOffsetCommitResponseTopic {
@@ -3434,6 +5853,7 @@ impl Storage for Postgres {
         Ok(topics)
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster, transaction_id = transaction_id))]
     async fn txn_end(
         &mut self,
         transaction_id: &str,
@@ -3447,21 +5867,243 @@ impl Storage for Postgres {
         let tx = c.transaction().await.inspect_err(|err| error!(?err))?;
 
         let error_code = self
+            .txn_producer_check(transaction_id, producer_id, producer_epoch, &tx)
+            .await?;
+
+        if error_code != ErrorCode::None {
+            return Err(Error::Api(error_code));
+        }
+
+        let ended = self
             .end_in_tx(transaction_id, producer_id, producer_epoch, committed, &tx)
             .await?;
 
         tx.commit().await?;
 
-        Ok(error_code)
+        self.txn_ended(&ended).await;
+
+        Ok(ended.error_code)
+    }
+
+    #[instrument(
+        skip_all,
+        fields(
+            cluster = %self.cluster,
+            topic = topition.topic(),
+            partition = topition.partition(),
+        )
+    )]
+    async fn compact(&mut self, topition: &Topition) -> Result<u64> {
+        debug!(cluster = self.cluster, ?topition);
+
+        if self.segments.is_some() {
+            debug!(?topition, "compaction of segments is unsupported");
+            return Ok(0);
+        }
+
+        // only compact records that are stable, an open transaction
+        // may yet abort the latest record for a key
+        //
+        let stable = self.offset_stage(topition).await?.last_stable;
+
+        let mut c = self.connection().await?;
+        let tx = c.transaction().await?;
+
+        let (low, high) = self
+            .watermark_select_for_update(topition, &tx)
+            .await
+            .map(|(low, high)| (low.unwrap_or_default(), high.unwrap_or_default()))?;
+
+        let mut configs = vec![];
+
+        for row in self
+            .tx_prepare_query(
+                &tx,
+                include_sql!("pg/topic_configuration_select.sql").as_str(),
+                &[&self.cluster, &topition.topic()],
+                "compact",
+            )
+            .await
+            .inspect_err(|err| error!(?err, ?topition))?
+        {
+            configs.push(CreatableTopicConfig {
+                name: row.try_get::<_, String>(0)?,
+                value: row.try_get::<_, Option<String>>(1)?,
+            });
+        }
+
+        let Some(delete_retention) = compaction(&configs)? else {
+            return Ok(0);
+        };
+
+        let delete_retention = i64::try_from(delete_retention.as_millis())?;
+
+        debug!(?topition, low, high, stable, delete_retention);
+
+        _ = self
+            .tx_prepare_execute(
+                &tx,
+                include_sql!("pg/header_compact.sql").as_str(),
+                &[
+                    &self.cluster,
+                    &topition.topic(),
+                    &topition.partition(),
+                    &stable,
+                    &delete_retention,
+                ],
+                "compact",
+            )
+            .await
+            .inspect_err(|err| error!(?err, ?topition))?;
+
+        let deleted = self
+            .tx_prepare_execute(
+                &tx,
+                include_sql!("pg/record_compact.sql").as_str(),
+                &[
+                    &self.cluster,
+                    &topition.topic(),
+                    &topition.partition(),
+                    &stable,
+                    &delete_retention,
+                ],
+                "compact",
+            )
+            .await
+            .inspect(|deleted| debug!(?topition, deleted))
+            .inspect_err(|err| error!(?err, ?topition))?;
+
+        if deleted > 0 {
+            let earliest = self
+                .tx_prepare_query_one(
+                    &tx,
+                    include_sql!("pg/record_min_offset.sql").as_str(),
+                    &[&self.cluster, &topition.topic(), &topition.partition()],
+                    "compact",
+                )
+                .await
+                .inspect_err(|err| error!(?err, ?topition))
+                .and_then(|row| row.try_get::<_, Option<i64>>(0))?
+                .unwrap_or(high);
+
+            _ = self
+                .tx_prepare_execute(
+                    &tx,
+                    include_sql!("pg/watermark_update_low.sql").as_str(),
+                    &[
+                        &self.cluster,
+                        &topition.topic(),
+                        &topition.partition(),
+                        &earliest.max(low),
+                        &i64::try_from(deleted)?,
+                    ],
+                    "compact",
+                )
+                .await
+                .inspect_err(|err| error!(?err, ?topition))?;
+        }
+
+        tx.commit().await?;
+
+        if deleted > 0 {
+            self.cache_invalidate(topition)?;
+        }
+
+        Ok(deleted)
     }
 
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
     async fn maintain(&self) -> Result<()> {
+        self.segment_train_untrained().await?;
+
         if let Some(ref lake) = self.lake {
             lake.maintain().await.map_err(Into::into)
         } else {
             Ok(())
         }
     }
+
+    #[instrument(skip_all, fields(cluster = %self.cluster))]
+    async fn health(&mut self) -> Result<()> {
+        debug!(cluster = self.cluster);
+
+        let c = match timeout(HEALTH_TIMEOUT, self.connection()).await {
+            Ok(connection) => connection.inspect_err(|err| error!(?err))?,
+
+            // waiting for a connection that is in use, rather than one
+            // that can't be established
+            //
+            Err(_elapsed) => {
+                let status = self.pool.status();
+                error!(cluster = self.cluster, ?status);
+
+                return Err(if status.available == 0 && status.size >= status.max_size {
+                    Error::PoolExhausted
+                } else {
+                    Error::Pool(PoolError::Timeout(TimeoutType::Create))
+                });
+            }
+        };
+
+        self.prepare_query_one(&c, "select 1", &[], "health")
+            .await
+            .inspect_err(|err| error!(?err))
+            .and(Ok(()))
+            .map_err(Into::into)
+    }
+
+    fn produce_watch(&self, topitions: &[Topition]) -> Result<ProduceWatch> {
+        debug!(cluster = self.cluster, ?topitions);
+
+        let mut produced = self.produced.lock()?;
+
+        // forget topitions that are no longer watched by any fetch
+        produced.retain(|_, notify| notify.strong_count() > 0);
+
+        Ok(ProduceWatch::new(
+            topitions
+                .iter()
+                .map(|topition| {
+                    produced
+                        .get(topition)
+                        .and_then(Weak::upgrade)
+                        .unwrap_or_else(|| {
+                            let notify = Arc::new(Notify::new());
+                            _ = produced.insert(topition.to_owned(), Arc::downgrade(&notify));
+                            notify
+                        })
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// The leader and replicas of a partition, spread round robin
+/// across the brokers from a starting broker. A broker holds at most
+/// one replica of a partition.
+fn round_robin(
+    broker_ids: &[i32],
+    start: usize,
+    partition: i32,
+    replication_factor: i32,
+) -> (i32, Vec<i32>) {
+    let broker = |offset: i32| {
+        broker_ids
+            .get((start + offset as usize) % broker_ids.len().max(1))
+            .copied()
+            .unwrap_or(-1)
+    };
+
+    let replicas = usize::try_from(replication_factor)
+        .unwrap_or_default()
+        .min(broker_ids.len());
+
+    (
+        broker(partition),
+        (0..replicas as i32)
+            .map(|replica| broker(partition + replica))
+            .collect(),
+    )
 }
 
 fn default_hash<H>(h: &H) -> Uuid
@@ -3473,6 +6115,35 @@ where
     Uuid::from_u128(s.finish() as u128)
 }
 
+/// The Kafka error code for a constraint violation, with `unique`
+/// being the code used when a write collides with an existing row.
+fn violation_code(code: &SqlState, unique: ErrorCode) -> Option<ErrorCode> {
+    if *code == SqlState::UNIQUE_VIOLATION {
+        Some(unique)
+    } else if *code == SqlState::FOREIGN_KEY_VIOLATION {
+        Some(ErrorCode::UnknownTopicOrPartition)
+    } else if *code == SqlState::CHECK_VIOLATION || *code == SqlState::NOT_NULL_VIOLATION {
+        Some(ErrorCode::InvalidRequest)
+    } else {
+        None
+    }
+}
+
+fn violation(error: tokio_postgres::error::Error, unique: ErrorCode) -> Error {
+    if let Some(db_error) = error.as_db_error() {
+        debug!(
+            schema = db_error.schema(),
+            table = db_error.table(),
+            constraint = db_error.constraint()
+        );
+    }
+
+    error
+        .code()
+        .and_then(|code| violation_code(code, unique))
+        .map_or_else(|| error.into(), Error::Api)
+}
+
 fn remove_comments(commented: &str) -> String {
     commented.lines().fold(String::new(), |uncommented, line| {
         if let Some(position) = line.find("--") {
@@ -3549,6 +6220,38 @@ mod tests {
         ))
     }
 
+    #[test]
+    fn violation_code() {
+        use tansu_kafka_sans_io::ErrorCode;
+        use tokio_postgres::error::SqlState;
+
+        assert_eq!(
+            Some(ErrorCode::TopicAlreadyExists),
+            super::violation_code(&SqlState::UNIQUE_VIOLATION, ErrorCode::TopicAlreadyExists)
+        );
+
+        assert_eq!(
+            Some(ErrorCode::UnknownTopicOrPartition),
+            super::violation_code(
+                &SqlState::FOREIGN_KEY_VIOLATION,
+                ErrorCode::DuplicateResource
+            )
+        );
+
+        assert_eq!(
+            Some(ErrorCode::InvalidRequest),
+            super::violation_code(&SqlState::CHECK_VIOLATION, ErrorCode::DuplicateResource)
+        );
+
+        assert_eq!(
+            None,
+            super::violation_code(
+                &SqlState::T_R_SERIALIZATION_FAILURE,
+                ErrorCode::DuplicateResource
+            )
+        );
+    }
+
     #[test]
     fn remove_comments() -> Result<()> {
         let _guard = init_tracing()?;
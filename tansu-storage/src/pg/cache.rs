@@ -0,0 +1,294 @@
+// Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use tansu_kafka_sans_io::record::deflated;
+use tracing::debug;
+
+use crate::Topition;
+
+#[derive(Clone, Debug)]
+struct Entry {
+    batch: deflated::Batch,
+    used: u64,
+}
+
+/// The most recently used record batches across all topitions, keyed
+/// by base offset within each topition. Records are immutable once
+/// written, so entries only need invalidating when records are
+/// removed. A generation guards against repopulating a topition with
+/// batches read before it was invalidated.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Cache {
+    capacity: usize,
+    tick: u64,
+    generations: BTreeMap<Topition, u64>,
+    batches: BTreeMap<Topition, BTreeMap<i64, Entry>>,
+    recency: BTreeMap<u64, (Topition, i64)>,
+}
+
+impl Cache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn generation(&self, topition: &Topition) -> u64 {
+        self.generations.get(topition).copied().unwrap_or_default()
+    }
+
+    pub(crate) fn put(
+        &mut self,
+        topition: &Topition,
+        generation: u64,
+        batches: &[deflated::Batch],
+    ) {
+        if generation != self.generation(topition) {
+            debug!(?topition, generation, current = self.generation(topition));
+            return;
+        }
+
+        let entries = self.batches.entry(topition.to_owned()).or_default();
+
+        for batch in batches.iter().filter(|batch| batch.record_count > 0) {
+            self.tick += 1;
+
+            if let Some(replaced) = entries.insert(
+                batch.base_offset,
+                Entry {
+                    batch: batch.to_owned(),
+                    used: self.tick,
+                },
+            ) {
+                _ = self.recency.remove(&replaced.used);
+            }
+
+            _ = self
+                .recency
+                .insert(self.tick, (topition.to_owned(), batch.base_offset));
+        }
+
+        while self.recency.len() > self.capacity {
+            let Some((_, (topition, lru))) = self.recency.pop_first() else {
+                break;
+            };
+
+            debug!(?topition, lru);
+
+            if let Some(entries) = self.batches.get_mut(&topition) {
+                _ = entries.remove(&lru);
+
+                if entries.is_empty() {
+                    _ = self.batches.remove(&topition);
+                }
+            }
+        }
+    }
+
+    /// The contiguous batches covering offset up to the high
+    /// watermark, always including the first, stopping before max
+    /// bytes would be exceeded. None when a batch needed within max
+    /// bytes is not cached.
+    pub(crate) fn fetch(
+        &mut self,
+        topition: &Topition,
+        offset: i64,
+        max_bytes: u32,
+        high_watermark: i64,
+    ) -> Option<Vec<deflated::Batch>> {
+        if offset >= high_watermark {
+            return Some(vec![]);
+        }
+
+        let entries = self.batches.get_mut(topition)?;
+
+        let mut base_offset = entries
+            .range(..=offset)
+            .next_back()
+            .filter(|(_, entry)| entry.batch.max_offset() >= offset)
+            .map(|(base_offset, _)| *base_offset)?;
+
+        let mut batches = vec![];
+        let mut bytes = 0;
+
+        while base_offset < high_watermark {
+            if !batches.is_empty() && bytes >= u64::from(max_bytes) {
+                break;
+            }
+
+            let entry = entries.get(&base_offset)?;
+
+            if entry.batch.max_offset() >= high_watermark {
+                return None;
+            }
+
+            let size = entry.batch.record_data.len() as u64;
+
+            if !batches.is_empty() && bytes + size > u64::from(max_bytes) {
+                break;
+            }
+
+            bytes += size;
+            batches.push(entry.batch.to_owned());
+            base_offset = entry.batch.max_offset() + 1;
+        }
+
+        for batch in &batches {
+            self.tick += 1;
+
+            if let Some(entry) = entries.get_mut(&batch.base_offset) {
+                _ = self.recency.remove(&entry.used);
+                entry.used = self.tick;
+                _ = self
+                    .recency
+                    .insert(self.tick, (topition.to_owned(), batch.base_offset));
+            }
+        }
+
+        debug!(
+            ?topition,
+            offset,
+            high_watermark,
+            bytes,
+            batches = batches.len()
+        );
+
+        Some(batches)
+    }
+
+    pub(crate) fn invalidate(&mut self, topition: &Topition) {
+        if let Some(entries) = self.batches.remove(topition) {
+            for entry in entries.values() {
+                _ = self.recency.remove(&entry.used);
+            }
+        }
+
+        *self.generations.entry(topition.to_owned()).or_default() += 1;
+    }
+
+    pub(crate) fn invalidate_topic(&mut self, topic: &str) {
+        let topitions = self
+            .batches
+            .keys()
+            .chain(self.generations.keys())
+            .filter(|topition| topition.topic() == topic)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for topition in topitions {
+            self.invalidate(&topition);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Result;
+    use bytes::Bytes;
+    use tansu_kafka_sans_io::record::{Record, inflated};
+
+    fn batch(base_offset: i64, value: &'static [u8]) -> Result<deflated::Batch> {
+        inflated::Batch::builder()
+            .base_offset(base_offset)
+            .record(Record::builder().value(Bytes::from_static(value).into()))
+            .build()
+            .and_then(TryInto::try_into)
+            .map_err(Into::into)
+    }
+
+    fn offsets(batches: Option<Vec<deflated::Batch>>) -> Option<Vec<i64>> {
+        batches.map(|batches| batches.iter().map(|batch| batch.base_offset).collect())
+    }
+
+    #[test]
+    fn contiguous_to_high_watermark() -> Result<()> {
+        let topition = Topition::new("abc", 3);
+        let mut cache = Cache::new(5);
+
+        cache.put(
+            &topition,
+            0,
+            &[batch(0, b"a")?, batch(1, b"b")?, batch(3, b"d")?],
+        );
+
+        assert_eq!(
+            Some(vec![0, 1]),
+            offsets(cache.fetch(&topition, 0, 1024, 2))
+        );
+        assert_eq!(Some(vec![]), offsets(cache.fetch(&topition, 2, 1024, 2)));
+
+        // offset 2 is missing
+        //
+        assert_eq!(None, offsets(cache.fetch(&topition, 1, 1024, 4)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn least_recently_used_evicted() -> Result<()> {
+        let topition = Topition::new("abc", 3);
+        let mut cache = Cache::new(2);
+
+        cache.put(&topition, 0, &[batch(0, b"a")?, batch(1, b"b")?]);
+        assert_eq!(Some(vec![0]), offsets(cache.fetch(&topition, 0, 1, 2)));
+
+        cache.put(&topition, 0, &[batch(2, b"c")?]);
+
+        assert_eq!(Some(vec![0]), offsets(cache.fetch(&topition, 0, 1, 3)));
+        assert_eq!(None, offsets(cache.fetch(&topition, 1, 1, 3)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn capacity_shared_by_topitions() -> Result<()> {
+        let abc = Topition::new("abc", 3);
+        let pqr = Topition::new("pqr", 5);
+        let mut cache = Cache::new(2);
+
+        cache.put(&abc, 0, &[batch(0, b"a")?]);
+        cache.put(&pqr, 0, &[batch(0, b"p")?]);
+        assert_eq!(Some(vec![0]), offsets(cache.fetch(&abc, 0, 1, 1)));
+
+        cache.put(&pqr, 0, &[batch(1, b"q")?]);
+
+        assert_eq!(Some(vec![0]), offsets(cache.fetch(&abc, 0, 1, 1)));
+        assert_eq!(None, offsets(cache.fetch(&pqr, 0, 1, 2)));
+        assert_eq!(Some(vec![1]), offsets(cache.fetch(&pqr, 1, 1, 2)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn stale_generation_ignored() -> Result<()> {
+        let topition = Topition::new("abc", 3);
+        let mut cache = Cache::new(5);
+
+        let generation = cache.generation(&topition);
+        cache.put(&topition, generation, &[batch(0, b"a")?]);
+
+        cache.invalidate_topic("abc");
+        cache.put(&topition, generation, &[batch(1, b"b")?]);
+
+        assert_eq!(None, offsets(cache.fetch(&topition, 0, 1024, 2)));
+        assert_eq!(None, offsets(cache.fetch(&topition, 1, 1024, 2)));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+-- -*- mode: sql; sql-product: postgres; -*-
+-- Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- This program is free software: you can redistribute it and/or modify
+-- it under the terms of the GNU Affero General Public License as
+-- published by the Free Software Foundation, either version 3 of the
+-- License, or (at your option) any later version.
+--
+-- This program is distributed in the hope that it will be useful,
+-- but WITHOUT ANY WARRANTY; without even the implied warranty of
+-- MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+-- GNU Affero General Public License for more details.
+--
+-- You should have received a copy of the GNU Affero General Public License
+-- along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+-- prepare consumer_offset_expire (text, timestamp) as
+
+delete from consumer_offset co
+using cluster c, consumer_group cg
+where c.name = $1
+and cg.cluster = c.id
+and co.consumer_group = cg.id
+and co.expire_timestamp < $2
+
+-- retaining the offsets of a group with members
+--
+and not exists (
+    select 1
+    from consumer_group_detail cgd
+    where cgd.consumer_group = cg.id
+    and exists (select 1 from json_object_keys(cgd.detail -> 'members'))
+);
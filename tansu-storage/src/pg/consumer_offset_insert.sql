@@ -15,9 +15,9 @@
 -- along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 insert into consumer_offset
-(consumer_group, topition, committed_offset, leader_epoch, timestamp, metadata)
+(consumer_group, topition, committed_offset, leader_epoch, timestamp, metadata, expire_timestamp)
 
-select cg.id, tp.id, $5, $6, $7, $8
+select cg.id, tp.id, $5, $6, $7, $8, $9
 
 from cluster c
 join topic t on t.cluster = c.id
@@ -34,4 +34,5 @@ do update set
 committed_offset = excluded.committed_offset,
 leader_epoch = excluded.leader_epoch,
 timestamp = excluded.timestamp,
-metadata = excluded.metadata;
+metadata = excluded.metadata,
+expire_timestamp = excluded.expire_timestamp;
@@ -0,0 +1,98 @@
+// Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+use zstd::{bulk::Compressor, dict, stream::read::Decoder, zstd_safe};
+
+use crate::Result;
+
+/// The magic number starting every zstd frame.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// The dictionary used to compress new segments of a topic.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+pub(crate) struct Current {
+    pub(crate) id: u32,
+}
+
+/// Train a dictionary of at most `max_size` bytes from sample
+/// segments, returning the dictionary with its id.
+pub(crate) fn train<S: AsRef<[u8]>>(samples: &[S], max_size: usize) -> Result<(u32, Vec<u8>)> {
+    let dictionary = dict::from_samples(samples, max_size)?;
+    let id = zstd_safe::get_dict_id(&dictionary).map_or(0, |id| id.get());
+
+    Ok((id, dictionary))
+}
+
+pub(crate) fn compress(encoded: &[u8], level: i32, dictionary: &[u8]) -> Result<Vec<u8>> {
+    Compressor::with_dictionary(level, dictionary)
+        .and_then(|mut compressor| compressor.compress(encoded))
+        .map_err(Into::into)
+}
+
+/// Whether a segment is compressed, rather than an encoded batch
+/// which always starts with a (much smaller) base offset.
+pub(crate) fn is_compressed(encoded: &[u8]) -> bool {
+    encoded.starts_with(&ZSTD_MAGIC)
+}
+
+/// The id of the dictionary used to compress a segment.
+pub(crate) fn dictionary_id(compressed: &[u8]) -> Option<u32> {
+    zstd_safe::get_dict_id_from_frame(compressed).map(|id| id.get())
+}
+
+pub(crate) fn decompress(compressed: &[u8], dictionary: Option<&[u8]>) -> Result<Vec<u8>> {
+    let mut decompressed = vec![];
+
+    _ = Decoder::with_dictionary(compressed, dictionary.unwrap_or_default())
+        .and_then(|mut decoder| decoder.read_to_end(&mut decompressed))?;
+
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compress_with_dictionary() -> Result<()> {
+        let samples = (0..1_000)
+            .map(|i| {
+                format!(
+                    r#"{{"id": {i}, "name": "sensor-{}", "status": "active"}}"#,
+                    i % 7
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let (id, dictionary) = train(&samples, 4_096)?;
+        assert_ne!(0, id);
+
+        let encoded = samples[0].as_bytes();
+        let compressed = compress(encoded, 3, &dictionary)?;
+
+        assert!(is_compressed(&compressed));
+        assert!(!is_compressed(encoded));
+        assert_eq!(Some(id), dictionary_id(&compressed));
+
+        assert_eq!(encoded, decompress(&compressed, Some(&dictionary))?);
+
+        Ok(())
+    }
+}
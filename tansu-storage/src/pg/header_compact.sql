@@ -0,0 +1,59 @@
+-- -*- mode: sql; sql-product: postgres; -*-
+-- Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- This program is free software: you can redistribute it and/or modify
+-- it under the terms of the GNU Affero General Public License as
+-- published by the Free Software Foundation, either version 3 of the
+-- License, or (at your option) any later version.
+--
+-- This program is distributed in the hope that it will be useful,
+-- but WITHOUT ANY WARRANTY; without even the implied warranty of
+-- MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+-- GNU Affero General Public License for more details.
+--
+-- You should have received a copy of the GNU Affero General Public License
+-- along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+delete from header
+using cluster c, topic t, topition tp, record r
+where c.name = $1
+and t.name = $2
+and tp.partition = $3
+and t.cluster = c.id
+and tp.topic = t.id
+and r.topition = tp.id
+and header.topition = r.topition
+and header.offset_id = r.offset_id
+and r.offset_id < $4
+and r.k is not null
+and (r.attributes is null or r.attributes & 32 = 0)
+and (
+    exists (
+        select 1
+        from record later
+        where later.topition = r.topition
+        and later.k = r.k
+        and later.offset_id > r.offset_id
+        and later.offset_id < $4
+        -- only a non-transactional or committed record supersedes,
+        -- being committed when the first control record of its
+        -- producer that follows is a commit rather than an abort
+        --
+        and (
+            later.attributes is null
+            or later.attributes & 16 = 0
+            or (
+                select get_byte(marker.k, 3) = 1
+                from record marker
+                where marker.topition = later.topition
+                and marker.producer_id = later.producer_id
+                and marker.offset_id > later.offset_id
+                and marker.offset_id < $4
+                and marker.attributes & 32 = 32
+                order by marker.offset_id
+                limit 1
+            )
+        )
+    )
+    or (r.v is null and r.created_at < localtimestamp - $5::bigint * interval '1 millisecond')
+);
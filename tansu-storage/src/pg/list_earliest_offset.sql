@@ -14,10 +14,13 @@
 -- You should have received a copy of the GNU Affero General Public License
 -- along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+-- once every record has been deleted the earliest offset is the
+-- low watermark, which is then the log end offset
+--
 -- prepare list_earliest_offset (text, text, integer) as
 select
 
-r.offset_id,
+coalesce(r.offset_id, w.low, 0),
 r.timestamp
 
 from
@@ -26,7 +29,7 @@ cluster c
 join topic t on t.cluster = c.id
 join topition tp on tp.topic = t.id
 join watermark w on w.topition = tp.id
-join record r on r.topition = tp.id and r.offset_id = w.low
+left join record r on r.topition = tp.id and r.offset_id = w.low
 
 where
 
@@ -1,5 +1,5 @@
 -- -*- mode: sql; sql-product: postgres; -*-
--- Copyright ⓒ 2024 Peter Morgan <peter.james.morgan@gmail.com>
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
 --
 -- This program is free software: you can redistribute it and/or modify
 -- it under the terms of the GNU Affero General Public License as
@@ -14,19 +14,44 @@
 -- You should have received a copy of the GNU Affero General Public License
 -- along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+-- the earliest retained offset with a timestamp at or after $4,
+-- otherwise the high watermark when no such record exists
+--
+-- prepare list_latest_offset_timestamp (text, text, integer, timestamp) as
 select
-id as offset, timestamp
+
+coalesce(r.offset_id, w.high, 0),
+r.timestamp
+
 from
-record
-join (
-select
-coalesce(min(record.id), (select last_value from record_id_seq)) as offset
-from record, topic, cluster
+
+cluster c
+join topic t on t.cluster = c.id
+join topition tp on tp.topic = t.id
+join watermark w on w.topition = tp.id
+left join lateral (
+    select
+
+    r.offset_id,
+    r.timestamp
+
+    from
+
+    record r
+
+    where
+
+    r.topition = tp.id
+    and r.offset_id >= coalesce(w.low, 0)
+    and r.offset_id < coalesce(w.high, 0)
+    and r.timestamp >= $4
+
+    order by r.offset_id asc
+    limit 1
+) r on true
+
 where
-topic.cluster = cluster.id
-and cluster.name = $1
-and topic.name = $2
-and record.partition = $3
-and record.timestamp >= $4
-and record.topic = topic.id) as minimum
-on record.id = minimum.offset;
+
+c.name = $1
+and t.name = $2
+and tp.partition = $3;
@@ -14,10 +14,13 @@
 -- You should have received a copy of the GNU Affero General Public License
 -- along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+-- once every record has been deleted the latest offset is the
+-- high watermark
+--
 -- prepare list_latest_offset (text, text, integer) as
 select
 
-r.offset_id + 1, r.timestamp
+coalesce(r.offset_id + 1, w.high, 0), r.timestamp
 
 from
 
@@ -25,7 +28,7 @@ cluster c
 join topic t on t.cluster = c.id
 join topition tp on tp.topic = t.id
 join watermark w on w.topition = tp.id
-join record r on r.topition = tp.id and r.offset_id = w.high - 1
+left join record r on r.topition = tp.id and r.offset_id = w.high - 1
 
 where
 
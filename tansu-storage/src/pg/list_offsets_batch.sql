@@ -0,0 +1,151 @@
+-- -*- mode: sql; sql-product: postgres; -*-
+-- Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- This program is free software: you can redistribute it and/or modify
+-- it under the terms of the GNU Affero General Public License as
+-- published by the Free Software Foundation, either version 3 of the
+-- License, or (at your option) any later version.
+--
+-- This program is distributed in the hope that it will be useful,
+-- but WITHOUT ANY WARRANTY; without even the implied warranty of
+-- MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+-- GNU Affero General Public License for more details.
+--
+-- You should have received a copy of the GNU Affero General Public License
+-- along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+-- the offsets of many topitions in a single query, with each request
+-- being one of: earliest, latest_committed, latest_uncommitted or
+-- timestamp (the earliest offset at or after that time)
+--
+-- prepare list_offsets_batch (text, text[], integer[], text[], timestamp[]) as
+select
+
+req.ordinality,
+o.offset_id,
+o.timestamp
+
+from
+
+cluster c
+cross join unnest($2::text[], $3::integer[], $4::text[], $5::timestamp[])
+with ordinality as req (topic, partition, spec, at, ordinality)
+join topic t on t.cluster = c.id and t.name = req.topic
+join topition tp on tp.topic = t.id and tp.partition = req.partition
+join watermark w on w.topition = tp.id
+left join lateral (
+    select
+
+    coalesce(r.offset_id, w.low, 0) as offset_id,
+    r.timestamp as timestamp
+
+    from
+
+    (select 1) one
+    left join record r on r.topition = tp.id and r.offset_id = w.low
+
+    where
+
+    req.spec = 'earliest'
+
+    union all
+
+    select
+
+    coalesce(r.offset_id + 1, w.high, 0) as offset_id,
+    r.timestamp as timestamp
+
+    from
+
+    (select 1) one
+    left join record r on r.topition = tp.id and r.offset_id = w.high - 1
+
+    where
+
+    req.spec = 'latest_uncommitted'
+
+    union all
+
+    select
+
+    committed.offset_id as offset_id,
+    committed.timestamp as timestamp
+
+    from
+
+    (select
+
+    1 as o, r.offset_id, r.timestamp
+
+    from
+
+    txn
+    join txn_detail txn_d on txn_d.transaction = txn.id
+    join txn_topition txn_tp on txn_tp.txn_detail = txn_d.id and txn_tp.topition = tp.id
+    join txn_produce_offset txn_po on txn_po.txn_topition = txn_tp.id
+    join record r on r.topition = tp.id and r.offset_id = txn_po.offset_start
+
+    where
+
+    txn.cluster = c.id
+    and (txn_d.status = 'PREPARE_COMMIT' or txn_d.status = 'PREPARE_ABORT' or txn_d.status = 'BEGIN')
+
+    union
+
+    select
+
+    2 as o, coalesce(r.offset_id + 1, w.high, 0) as offset_id, r.timestamp
+
+    from
+
+    (select 1) one
+    left join record r on r.topition = tp.id and r.offset_id = w.high - 1
+
+    order by o, offset_id asc
+    limit 1) committed
+
+    where
+
+    req.spec = 'latest_committed'
+
+    union all
+
+    select
+
+    coalesce(ts.offset_id, w.high, 0) as offset_id,
+    ts.timestamp as timestamp
+
+    from
+
+    (select 1) one
+    left join lateral (
+        select
+
+        r.offset_id,
+        r.timestamp
+
+        from
+
+        record r
+
+        where
+
+        r.topition = tp.id
+        and r.offset_id >= coalesce(w.low, 0)
+        and r.offset_id < coalesce(w.high, 0)
+        and r.timestamp >= req.at
+
+        order by r.offset_id asc
+        limit 1
+    ) ts on true
+
+    where
+
+    req.spec = 'timestamp'
+) o on true
+
+where
+
+c.name = $1
+
+order by req.ordinality;
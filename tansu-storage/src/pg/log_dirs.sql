@@ -0,0 +1,40 @@
+-- -*- mode: sql; sql-product: postgres; -*-
+-- Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- This program is free software: you can redistribute it and/or modify
+-- it under the terms of the GNU Affero General Public License as
+-- published by the Free Software Foundation, either version 3 of the
+-- License, or (at your option) any later version.
+--
+-- This program is distributed in the hope that it will be useful,
+-- but WITHOUT ANY WARRANTY; without even the implied warranty of
+-- MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+-- GNU Affero General Public License for more details.
+--
+-- You should have received a copy of the GNU Affero General Public License
+-- along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+select
+
+t.name,
+tp.partition,
+w.low,
+w.high,
+w.record_count,
+coalesce(sum(coalesce(octet_length(r.k), 0) + coalesce(octet_length(r.v), 0)), 0)::bigint
+
+from
+
+cluster c
+join topic t on t.cluster = c.id
+join topition tp on tp.topic = t.id
+join watermark w on w.topition = tp.id
+left join record r on r.topition = tp.id
+
+where
+
+c.name = $1
+
+group by t.name, tp.partition, w.low, w.high, w.record_count
+
+order by t.name, tp.partition;
@@ -14,9 +14,9 @@
 -- You should have received a copy of the GNU Affero General Public License
 -- along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-insert into producer_detail (producer_epoch, topition, sequence)
+insert into producer_detail (producer_epoch, topition, sequence, batches)
 
-select pe.id, tp.id, $6
+select pe.id, tp.id, $6, $7
 
 from
 
@@ -39,4 +39,5 @@ on conflict (producer_epoch, topition)
 do update set
 
 sequence = producer_detail.sequence + $6,
+batches = excluded.batches,
 last_updated = excluded.last_updated
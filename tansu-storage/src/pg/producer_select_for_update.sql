@@ -16,7 +16,8 @@
 
 select
 
-coalesce(pd.sequence, 0)
+coalesce(pd.sequence, 0),
+coalesce(pd.batches, '{}')
 
 from
 
@@ -0,0 +1,50 @@
+-- -*- mode: sql; sql-product: postgres; -*-
+-- Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- This program is free software: you can redistribute it and/or modify
+-- it under the terms of the GNU Affero General Public License as
+-- published by the Free Software Foundation, either version 3 of the
+-- License, or (at your option) any later version.
+--
+-- This program is distributed in the hope that it will be useful,
+-- but WITHOUT ANY WARRANTY; without even the implied warranty of
+-- MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+-- GNU Affero General Public License for more details.
+--
+-- You should have received a copy of the GNU Affero General Public License
+-- along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+-- prepare producer_state_select (text, text, integer) as
+select distinct on (p.id)
+
+p.id,
+pe.epoch,
+pd.sequence - 1,
+txn.name
+
+from
+
+cluster c
+join producer p on p.cluster = c.id
+join producer_epoch pe on pe.producer = p.id
+join topic t on t.cluster = c.id
+join topition tp on tp.topic = t.id
+join producer_detail pd on pd.producer_epoch = pe.id and pd.topition = tp.id
+
+-- the transaction in progress on this topition, if any
+--
+left join (
+    txn_topition txn_tp
+    join txn_detail txn_d on txn_d.id = txn_tp.txn_detail
+    join txn on txn.id = txn_d.transaction
+) on txn_tp.topition = tp.id
+and txn_d.producer_epoch = pe.id
+and txn_d.status in ('BEGIN', 'PREPARE_COMMIT', 'PREPARE_ABORT')
+
+where
+
+c.name = $1
+and t.name = $2
+and tp.partition = $3
+
+order by p.id, pe.epoch desc;
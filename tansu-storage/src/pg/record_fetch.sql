@@ -14,7 +14,14 @@
 -- You should have received a copy of the GNU Affero General Public License
 -- along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
--- prepare record_fetch (text, text, integer, integer, integer, integer) as
+-- prepare record_fetch (text, text, integer, bigint, bigint, bigint, bigint) as
+--
+-- only a window of $7 offsets from the (topition, offset_id) primary key
+-- is sized, rather than every record of the partition up to the high
+-- watermark, with the caller continuing from the end of the window. The
+-- row comparisons keep the planner on the primary key, rather than
+-- filtering every record of the partition from the timestamp index.
+--
 with sized as (
 select
 
@@ -25,21 +32,36 @@ r.k,
 r.v,
 sum(coalesce(length(r.k), 0) + coalesce(length(r.v), 0)) over (order by r.offset_id) as bytes,
 r.producer_id,
-r.producer_epoch
+r.producer_epoch,
+row_number() over (order by r.offset_id) as n,
+count(*) over () as scanned
 
 from
 
 cluster c
 join topic t on t.cluster = c.id
 join topition tp on tp.topic = t.id
-join record r on r.topition = tp.id
+cross join lateral (
+    select
+
+    *
+
+    from
+
+    record r
+
+    where
+
+    (r.topition, r.offset_id) >= (tp.id, $4)
+    and (r.topition, r.offset_id) < (tp.id, least($6, $4 + $7))
+) r
 
 where
 
 c.name = $1
 and t.name = $2
-and tp.partition = $3
-and r.offset_id >= $4
-and r.offset_id < $6)
+and tp.partition = $3)
 
-select * from sized where bytes < $5;
+-- the first record is returned even when it exceeds max bytes
+--
+select * from sized where bytes <= $5 or n = 1 order by offset_id;
@@ -0,0 +1,79 @@
+-- -*- mode: sql; sql-product: postgres; -*-
+-- Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- This program is free software: you can redistribute it and/or modify
+-- it under the terms of the GNU Affero General Public License as
+-- published by the Free Software Foundation, either version 3 of the
+-- License, or (at your option) any later version.
+--
+-- This program is distributed in the hope that it will be useful,
+-- but WITHOUT ANY WARRANTY; without even the implied warranty of
+-- MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+-- GNU Affero General Public License for more details.
+--
+-- You should have received a copy of the GNU Affero General Public License
+-- along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+-- allocate the offsets of a batch by advancing the high watermark,
+-- inserting its records and headers in the same statement so that
+-- the watermark row is only locked for this statement and the commit
+-- that follows it
+--
+with allocated as (
+    update watermark w
+
+    set
+
+    low = coalesce(w.low, 0),
+    high = coalesce(w.high, 0) + $4,
+    record_count = w.record_count + $5
+
+    from
+
+    cluster c
+    join topic t on t.cluster = c.id
+    join topition tp on tp.topic = t.id
+
+    where
+
+    c.name = $1
+    and t.name = $2
+    and tp.partition = $3
+    and w.topition = tp.id
+
+    returning w.topition, w.high - $4 as base_offset
+),
+
+inserted as (
+    insert into record
+    (topition, offset_id, attributes, producer_id, producer_epoch, timestamp, k, v)
+
+    select
+
+    a.topition, a.base_offset + r.delta, $6, $7, $8, r.timestamp, r.k, r.v
+
+    from
+
+    allocated a,
+    unnest($9::bigint[], $10::timestamp[], $11::bytea[], $12::bytea[])
+    as r (delta, timestamp, k, v)
+
+    returning topition, offset_id
+),
+
+headers as (
+    insert into header
+    (topition, offset_id, k, v)
+
+    select
+
+    i.topition, i.offset_id, h.k, h.v
+
+    from
+
+    unnest($13::bigint[], $14::bytea[], $15::bytea[]) as h (delta, k, v)
+    join allocated a on true
+    join inserted i on i.offset_id = a.base_offset + h.delta
+)
+
+select base_offset from allocated;
@@ -0,0 +1,234 @@
+// Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::{fmt::Debug, time::Duration};
+
+use deadpool::managed::{Manager, Object, Pool, PoolError};
+use rand::{prelude::*, rng};
+use tokio::time::sleep;
+use tracing::{debug, warn};
+
+/// Retry getting a connection from the pool when the database is
+/// briefly unavailable, backing off exponentially with jitter between
+/// attempts.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ConnectionRetry {
+    attempts: u32,
+    backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Default for ConnectionRetry {
+    fn default() -> Self {
+        Self {
+            attempts: 5,
+            backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl ConnectionRetry {
+    /// The maximum number of attempts, including the first.
+    pub fn attempts(self, attempts: u32) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            ..self
+        }
+    }
+
+    /// The delay before the first retry, doubling on each subsequent retry.
+    pub fn backoff(self, backoff: Duration) -> Self {
+        Self { backoff, ..self }
+    }
+
+    /// The upper bound of the delay between attempts.
+    pub fn max_backoff(self, max_backoff: Duration) -> Self {
+        Self {
+            max_backoff,
+            ..self
+        }
+    }
+
+    /// The delay before retrying a failed attempt, which is between
+    /// half and all of the exponential backoff.
+    fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff);
+
+        let half = backoff / 2;
+
+        half + half.mul_f64(rng().random::<f64>())
+    }
+
+    pub(crate) async fn get<M>(
+        &self,
+        pool: &Pool<M>,
+        transient: impl Fn(&PoolError<M::Error>) -> bool,
+    ) -> Result<Object<M>, PoolError<M::Error>>
+    where
+        M: Manager,
+        M::Error: Debug,
+    {
+        let mut attempt = 0;
+
+        loop {
+            match pool.get().await {
+                Err(error) if attempt + 1 < self.attempts && transient(&error) => {
+                    let delay = self.delay(attempt);
+                    warn!(?error, attempt, ?delay);
+
+                    sleep(delay).await;
+                    attempt += 1;
+                }
+
+                otherwise => {
+                    debug!(attempt);
+                    return otherwise;
+                }
+            }
+        }
+    }
+}
+
+/// Whether getting a connection failed because the database couldn't
+/// be reached in time, rather than rejecting the connection.
+pub(crate) fn is_transient(error: &deadpool_postgres::PoolError) -> bool {
+    match error {
+        PoolError::Timeout(_) => true,
+        PoolError::Backend(error) => error.as_db_error().is_none(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    use deadpool::managed::{Metrics, RecycleResult};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct Flaky {
+        failures: AtomicU32,
+        created: AtomicU32,
+    }
+
+    impl Flaky {
+        fn new(failures: u32) -> Self {
+            Self {
+                failures: AtomicU32::new(failures),
+                created: AtomicU32::new(0),
+            }
+        }
+    }
+
+    impl Manager for Flaky {
+        type Type = ();
+        type Error = io::Error;
+
+        async fn create(&self) -> Result<Self::Type, Self::Error> {
+            _ = self.created.fetch_add(1, Ordering::Relaxed);
+
+            if self
+                .failures
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |failures| {
+                    failures.checked_sub(1)
+                })
+                .is_ok()
+            {
+                Err(io::Error::from(io::ErrorKind::ConnectionRefused))
+            } else {
+                Ok(())
+            }
+        }
+
+        async fn recycle(
+            &self,
+            _obj: &mut Self::Type,
+            _metrics: &Metrics,
+        ) -> RecycleResult<Self::Error> {
+            Ok(())
+        }
+    }
+
+    fn connection_refused(error: &PoolError<io::Error>) -> bool {
+        matches!(
+            error,
+            PoolError::Backend(error) if error.kind() == io::ErrorKind::ConnectionRefused
+        )
+    }
+
+    fn retry(attempts: u32) -> ConnectionRetry {
+        ConnectionRetry::default()
+            .attempts(attempts)
+            .backoff(Duration::from_millis(1))
+            .max_backoff(Duration::from_millis(4))
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried() {
+        let pool = Pool::builder(Flaky::new(2)).max_size(1).build().unwrap();
+
+        assert!(retry(3).get(&pool, connection_refused).await.is_ok());
+        assert_eq!(3, pool.manager().created.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let pool = Pool::builder(Flaky::new(5)).max_size(1).build().unwrap();
+
+        assert!(matches!(
+            retry(3).get(&pool, connection_refused).await,
+            Err(PoolError::Backend(_))
+        ));
+        assert_eq!(3, pool.manager().created.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn logical_errors_are_not_retried() {
+        let pool = Pool::builder(Flaky::new(2)).max_size(1).build().unwrap();
+
+        assert!(matches!(
+            retry(3).get(&pool, |_| false).await,
+            Err(PoolError::Backend(_))
+        ));
+        assert_eq!(1, pool.manager().created.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn delay_is_bounded() {
+        let retry = ConnectionRetry::default()
+            .backoff(Duration::from_millis(10))
+            .max_backoff(Duration::from_millis(100));
+
+        for attempt in 0..10 {
+            let delay = retry.delay(attempt);
+            let backoff =
+                Duration::from_millis(10 * 2u64.pow(attempt)).min(Duration::from_millis(100));
+
+            assert!(
+                delay >= backoff / 2 && delay <= backoff,
+                "{attempt}: {delay:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,41 @@
+-- -*- mode: sql; sql-product: postgres; -*-
+-- Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
+--
+-- This program is free software: you can redistribute it and/or modify
+-- it under the terms of the GNU Affero General Public License as
+-- published by the Free Software Foundation, either version 3 of the
+-- License, or (at your option) any later version.
+--
+-- This program is distributed in the hope that it will be useful,
+-- but WITHOUT ANY WARRANTY; without even the implied warranty of
+-- MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+-- GNU Affero General Public License for more details.
+--
+-- You should have received a copy of the GNU Affero General Public License
+-- along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+-- allocate offsets for a batch, returning the base offset, the row
+-- lock is held until the producing transaction ends
+--
+update watermark w
+
+set
+
+low = coalesce(w.low, 0),
+high = coalesce(w.high, 0) + $4,
+record_count = w.record_count + $5
+
+from
+
+cluster c
+join topic t on t.cluster = c.id
+join topition tp on tp.topic = t.id
+
+where
+
+c.name = $1
+and t.name = $2
+and tp.partition = $3
+and w.topition = tp.id
+
+returning w.high - $4;
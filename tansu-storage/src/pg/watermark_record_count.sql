@@ -14,23 +14,18 @@
 -- You should have received a copy of the GNU Affero General Public License
 -- along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-insert into header
-(topition, offset_id, k, v)
-
-select
-
-r.topition, r.offset_id, $5, $6
+select t.name, tp.partition, w.record_count
 
 from
 
 cluster c
 join topic t on t.cluster = c.id
 join topition tp on tp.topic = t.id
-join record r on r.topition = tp.id
+join watermark w on w.topition = tp.id
 
 where
 
 c.name = $1
-and t.name = $2
-and tp.partition = $3
-and r.offset_id = $4;
+and (t.name = any($2) or t.uuid = any($3))
+
+order by t.name, tp.partition;
@@ -1,5 +1,5 @@
 -- -*- mode: sql; sql-product: postgres; -*-
--- Copyright ⓒ 2024 Peter Morgan <peter.james.morgan@gmail.com>
+-- Copyright ⓒ 2025 Peter Morgan <peter.james.morgan@gmail.com>
 --
 -- This program is free software: you can redistribute it and/or modify
 -- it under the terms of the GNU Affero General Public License as
@@ -14,12 +14,12 @@
 -- You should have received a copy of the GNU Affero General Public License
 -- along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-insert into record
-(topition, offset_id, attributes, producer_id, producer_epoch, timestamp, k, v)
+update watermark w
 
-select
+set
 
-tp.id, $4, $5, $6, $7, $8, $9, $10
+high = $4,
+record_count = w.record_count - $5
 
 from
 
@@ -31,4 +31,5 @@ where
 
 c.name = $1
 and t.name = $2
-and tp.partition = $3;
+and tp.partition = $3
+and w.topition = tp.id;
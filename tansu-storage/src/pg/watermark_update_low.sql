@@ -1,5 +1,5 @@
 -- -*- mode: sql; sql-product: postgres; -*-
--- Copyright ⓒ 2024 Peter Morgan <peter.james.morgan@gmail.com>
+-- Copyright ⓒ 2024-2025 Peter Morgan <peter.james.morgan@gmail.com>
 --
 -- This program is free software: you can redistribute it and/or modify
 -- it under the terms of the GNU Affero General Public License as
@@ -19,7 +19,7 @@ update watermark w
 set
 
 low = $4,
-high = $5
+record_count = w.record_count - $5
 
 from
 
@@ -32,6 +32,4 @@ where
 c.name = $1
 and t.name = $2
 and tp.partition = $3
-and t.cluster = c.id
-and tp.topic = t.id
 and w.topition = tp.id;
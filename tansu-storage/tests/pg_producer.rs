@@ -18,12 +18,13 @@ use rand::{distr::Alphanumeric, prelude::*, rng};
 use tansu_kafka_sans_io::{
     ErrorCode, IsolationLevel,
     create_topics_request::CreatableTopic,
-    record::{Record, inflated},
+    record::{Record, deflated, inflated},
 };
 use tansu_storage::{
     BrokerRegistrationRequest, Error, ListOffsetRequest, Result, Storage, StorageContainer,
     TopicId, Topition, pg::Postgres,
 };
+use tokio::task::JoinSet;
 use tracing::{debug, subscriber::DefaultGuard};
 use uuid::Uuid;
 
@@ -90,7 +91,7 @@ async fn produce() -> Result<()> {
         .collect();
 
     let num_partitions = rng().random_range(1..64);
-    let replication_factor = rng().random_range(0..64);
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 
@@ -179,3 +180,216 @@ async fn produce() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn corrupt_batch_is_rejected() -> Result<()> {
+    let _guard = init_tracing()?;
+
+    let cluster_id = Uuid::now_v7();
+    let broker_id = rng().random_range(0..i32::MAX);
+
+    let mut storage_container = storage_container(cluster_id, broker_id)?;
+
+    storage_container
+        .register_broker(BrokerRegistrationRequest {
+            broker_id,
+            cluster_id: cluster_id.into(),
+            incarnation_id: Uuid::now_v7(),
+            rack: None,
+        })
+        .await?;
+
+    let name: String = rng()
+        .sample_iter(&Alphanumeric)
+        .take(15)
+        .map(char::from)
+        .collect();
+
+    _ = storage_container
+        .create_topic(
+            CreatableTopic {
+                name: name.clone(),
+                num_partitions: 1,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let topition = Topition::new(name, 0);
+
+    let batch = |last_offset_delta: i32| -> Result<deflated::Batch> {
+        (0..3)
+            .fold(inflated::Batch::builder(), |builder, offset_delta| {
+                builder.record(
+                    Record::builder()
+                        .offset_delta(offset_delta)
+                        .value(Bytes::from_static(b"Lorem ipsum dolor sit amet").into()),
+                )
+            })
+            .last_offset_delta(last_offset_delta)
+            .build()
+            .and_then(TryInto::try_into)
+            .map_err(Error::from)
+    };
+
+    let mut corrupt_crc = batch(2)?;
+    corrupt_crc.crc ^= 1;
+
+    assert!(matches!(
+        storage_container
+            .produce(None, &topition, corrupt_crc)
+            .await,
+        Err(Error::Api(ErrorCode::CorruptMessage))
+    ));
+
+    assert!(matches!(
+        storage_container.produce(None, &topition, batch(1)?).await,
+        Err(Error::Api(ErrorCode::CorruptMessage))
+    ));
+
+    // neither batch was stored
+    //
+    assert_eq!(
+        0,
+        storage_container
+            .offset_stage(&topition)
+            .await?
+            .high_watermark()
+    );
+
+    assert_eq!(
+        0,
+        storage_container
+            .produce(None, &topition, batch(2)?)
+            .await?
+    );
+
+    assert_eq!(
+        3,
+        storage_container
+            .offset_stage(&topition)
+            .await?
+            .high_watermark()
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn concurrent_produces_are_contiguous() -> Result<()> {
+    let _guard = init_tracing()?;
+
+    let cluster_id = Uuid::now_v7();
+    let broker_id = rng().random_range(0..i32::MAX);
+
+    let mut storage_container = storage_container(cluster_id, broker_id)?;
+
+    storage_container
+        .register_broker(BrokerRegistrationRequest {
+            broker_id,
+            cluster_id: cluster_id.into(),
+            incarnation_id: Uuid::now_v7(),
+            rack: None,
+        })
+        .await?;
+
+    let name: String = rng()
+        .sample_iter(&Alphanumeric)
+        .take(15)
+        .map(char::from)
+        .collect();
+
+    _ = storage_container
+        .create_topic(
+            CreatableTopic {
+                name: name.clone(),
+                num_partitions: 1,
+                replication_factor: 1,
+                assignments: Some([].into()),
+                configs: Some([].into()),
+            },
+            false,
+        )
+        .await?;
+
+    let topition = Topition::new(name, 0);
+
+    const PRODUCERS: i64 = 64;
+    const RECORDS: i64 = 3;
+
+    let mut set = JoinSet::new();
+
+    for _ in 0..PRODUCERS {
+        let mut storage_container = storage_container.clone();
+        let topition = topition.clone();
+
+        _ = set.spawn(async move {
+            let batch = (0..RECORDS)
+                .try_fold(inflated::Batch::builder(), |builder, offset_delta| {
+                    i32::try_from(offset_delta).map(|offset_delta| {
+                        builder.record(
+                            Record::builder()
+                                .offset_delta(offset_delta)
+                                .value(Bytes::from_static(b"Lorem ipsum dolor sit amet").into()),
+                        )
+                    })
+                })?
+                .last_offset_delta(i32::try_from(RECORDS - 1)?)
+                .build()
+                .and_then(TryInto::try_into)
+                .map_err(Error::from)?;
+
+            storage_container.produce(None, &topition, batch).await
+        });
+    }
+
+    let mut base_offsets = vec![];
+
+    while let Some(produced) = set.join_next().await {
+        base_offsets.push(produced.map_err(|err| Error::Message(err.to_string()))??);
+    }
+
+    // each batch is allocated a unique, gapless range of offsets
+    //
+    base_offsets.sort();
+
+    assert_eq!(
+        (0..PRODUCERS)
+            .map(|producer| producer * RECORDS)
+            .collect::<Vec<_>>(),
+        base_offsets
+    );
+
+    assert_eq!(
+        PRODUCERS * RECORDS,
+        storage_container
+            .offset_stage(&topition)
+            .await?
+            .high_watermark()
+    );
+
+    let offsets = storage_container
+        .fetch_inflated(
+            &topition,
+            0,
+            u32::MAX,
+            u32::MAX,
+            IsolationLevel::ReadUncommitted,
+        )
+        .await?
+        .into_iter()
+        .flat_map(|batch| {
+            batch
+                .records
+                .into_iter()
+                .map(move |record| batch.base_offset + i64::from(record.offset_delta))
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!((0..PRODUCERS * RECORDS).collect::<Vec<_>>(), offsets);
+
+    Ok(())
+}
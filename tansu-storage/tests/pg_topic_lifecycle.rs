@@ -84,7 +84,7 @@ async fn topic_lifecycle() -> Result<()> {
         .collect();
 
     let num_partitions = rng().random_range(1..64);
-    let replication_factor = rng().random_range(0..64);
+    let replication_factor = 1;
     let assignments = Some([].into());
     let configs = Some([].into());
 